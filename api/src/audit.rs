@@ -0,0 +1,56 @@
+use application::{AuditService, DefaultAuditService, ServiceError};
+use axum::{
+    extract::{Extension, Query},
+    routing::get,
+    Json, Router,
+};
+use domain::{AuditEvent, AuditEventFilter, Page};
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn audit_routers() -> Router {
+    Router::new().route("/admin/audit-events", get(list))
+}
+
+async fn list(
+    _: AdminUser,
+    Query(query): Query<AuditEventsQuery>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<AuditEvent>>, ApiError> {
+    let kind =
+        match query.kind {
+            Some(kind) => Some(kind.parse().map_err(|_| {
+                ServiceError::BadRequest(format!("Unknown audit event kind {}", kind))
+            })?),
+            None => None,
+        };
+    get_audit_service(db.clone())
+        .list(AuditEventFilter {
+            kind,
+            user_id: query.user_id,
+            page: query.page.unwrap_or(0),
+            page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        })
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+fn get_audit_service(db: Arc<DbConn>) -> DefaultAuditService {
+    DefaultAuditService::new(db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditEventsQuery {
+    kind: Option<String>,
+    user_id: Option<Uuid>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
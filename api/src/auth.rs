@@ -0,0 +1,307 @@
+use app_config::ApplicationConfig;
+use application::{
+    decode_access_token, AccountService, AuthError, AuthService, CaptchaService, Claims,
+    DefaultAccountService, DefaultAuthService, LoginResponse, TokenPair,
+};
+use async_trait::async_trait;
+use axum::{
+    extract::{ConnectInfo, Extension, FromRequest, Path, RequestParts},
+    headers::{authorization::Bearer, Authorization},
+    http::StatusCode,
+    routing::{delete, post},
+    Json, Router, TypedHeader,
+};
+use domain::Role;
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::captcha::get_captcha_service;
+use crate::error::ApiError;
+
+pub fn auth_routers() -> Router {
+    Router::new()
+        .route("/auth/login", post(login))
+        .route("/auth/login/2fa", post(login_two_factor))
+        .route("/auth/refresh", post(refresh))
+        .route("/auth/logout", post(logout))
+        .route("/auth/register", post(register))
+        .route("/auth/verify-email", post(verify_email))
+        .route("/auth/verify-email/resend", post(resend_verification))
+        .route("/auth/password-reset", post(request_password_reset))
+        .route("/auth/password-reset/confirm", post(reset_password))
+        .route("/admin/users/:id/sessions", delete(revoke_sessions))
+}
+
+async fn login(
+    ClientIp(client_ip): ClientIp,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    info!("Login attempt for user: {}", request.name);
+    let auth_service = get_auth_service(config, db.clone());
+    auth_service
+        .login(&request.name, &request.password, &client_ip)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn login_two_factor(
+    ClientIp(client_ip): ClientIp,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<TwoFactorLoginRequest>,
+) -> Result<Json<TokenPair>, ApiError> {
+    let auth_service = get_auth_service(config, db.clone());
+    auth_service
+        .login_two_factor(&request.challenge, &request.code, &client_ip)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn refresh(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<Json<TokenPair>, ApiError> {
+    let auth_service = get_auth_service(config, db.clone());
+    auth_service
+        .refresh(&request.refresh_token)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn logout(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<RefreshRequest>,
+) -> Result<StatusCode, ApiError> {
+    let auth_service = get_auth_service(config, db.clone());
+    auth_service.logout(&request.refresh_token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn revoke_sessions(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    info!("User {} revokes all sessions of user {}", admin.name, id);
+    let auth_service = get_auth_service(config, db.clone());
+    auth_service.revoke_sessions(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn register(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<RegisterRequest>,
+) -> Result<StatusCode, ApiError> {
+    info!("Registration of user: {}", request.name);
+    get_captcha_service(config, db.clone())
+        .verify(request.captcha_id, request.captcha_answer.as_deref())
+        .await?;
+    let account_service = get_account_service(config, db.clone())?;
+    account_service
+        .register(&request.name, &request.email, &request.password)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn verify_email(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<TokenRequest>,
+) -> Result<StatusCode, ApiError> {
+    let account_service = get_account_service(config, db.clone())?;
+    account_service.verify_email(&request.token).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn resend_verification(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<EmailRequest>,
+) -> Result<StatusCode, ApiError> {
+    let account_service = get_account_service(config, db.clone())?;
+    account_service.resend_verification(&request.email).await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn request_password_reset(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<EmailRequest>,
+) -> Result<StatusCode, ApiError> {
+    let account_service = get_account_service(config, db.clone())?;
+    account_service
+        .request_password_reset(&request.email)
+        .await?;
+    Ok(StatusCode::ACCEPTED)
+}
+
+async fn reset_password(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    let account_service = get_account_service(config, db.clone())?;
+    account_service
+        .reset_password(&request.token, &request.password)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn get_account_service(
+    config: &ApplicationConfig,
+    db: Arc<DbConn>,
+) -> Result<DefaultAccountService, ApiError> {
+    DefaultAccountService::new(config, db).map_err(ApiError::from)
+}
+
+fn get_auth_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultAuthService {
+    DefaultAuthService::new(config, db)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LoginRequest {
+    name: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    challenge: String,
+    code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RegisterRequest {
+    name: String,
+    email: String,
+    password: String,
+    captcha_id: Option<Uuid>,
+    captcha_answer: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenRequest {
+    token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailRequest {
+    email: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetPasswordRequest {
+    token: String,
+    password: String,
+}
+
+/// Claims of a valid bearer access token.
+pub struct AuthUser(pub Claims);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AuthUser {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let TypedHeader(Authorization(bearer)) =
+            TypedHeader::<Authorization<Bearer>>::from_request(req)
+                .await
+                .map_err(|_| AuthError::InvalidToken)?;
+        let Extension(config) = Extension::<Arc<ApplicationConfig>>::from_request(req)
+            .await
+            .map_err(|err| anyhow::Error::msg(err.to_string()))?;
+        let claims = decode_access_token(&config.auth, bearer.token())?;
+        Ok(AuthUser(claims))
+    }
+}
+
+/// Claims of a valid bearer access token issued to an admin.
+pub struct AdminUser(pub Claims);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for AdminUser {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let AuthUser(claims) = AuthUser::from_request(req).await?;
+        match claims.role {
+            Role::ADMIN => Ok(AdminUser(claims)),
+            _ => Err(AuthError::Forbidden.into()),
+        }
+    }
+}
+
+/// Address of the client, taken from `X-Forwarded-For` only when proxies are trusted.
+pub struct ClientIp(pub String);
+
+#[async_trait]
+impl<B: Send> FromRequest<B> for ClientIp {
+    type Rejection = ApiError;
+
+    async fn from_request(req: &mut RequestParts<B>) -> Result<Self, Self::Rejection> {
+        let Extension(config) = Extension::<Arc<ApplicationConfig>>::from_request(req)
+            .await
+            .map_err(|err| anyhow::Error::msg(err.to_string()))?;
+        let forwarded = req
+            .headers()
+            .get("x-forwarded-for")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| forwarded_client(value, config.lockout.trusted_proxies));
+        if let Some(ip) = forwarded {
+            return Ok(ClientIp(ip));
+        }
+        let ip = ConnectInfo::<SocketAddr>::from_request(req)
+            .await
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+            .unwrap_or_else(|_| "unknown".to_owned());
+        Ok(ClientIp(ip))
+    }
+}
+
+/// Entries before the ones appended by the trusted proxies are set by the client, the entry
+/// appended by the outermost trusted proxy is the address it saw.
+fn forwarded_client(header: &str, trusted_proxies: usize) -> Option<String> {
+    if trusted_proxies == 0 {
+        return None;
+    }
+    header
+        .split(',')
+        .map(str::trim)
+        .rev()
+        .nth(trusted_proxies - 1)
+        .filter(|value| !value.is_empty())
+        .map(str::to_owned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_forwarded_client() {
+        let header = "203.0.113.7, 198.51.100.1, 10.0.0.2";
+        assert_eq!(forwarded_client(header, 0), None);
+        assert_eq!(forwarded_client(header, 1).as_deref(), Some("10.0.0.2"));
+        assert_eq!(forwarded_client(header, 2).as_deref(), Some("198.51.100.1"));
+        assert_eq!(forwarded_client(header, 4), None);
+        assert_eq!(forwarded_client("", 1), None);
+    }
+}
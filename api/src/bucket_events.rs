@@ -0,0 +1,39 @@
+use app_config::ApplicationConfig;
+use application::{
+    BucketEventService, BucketNotification, DefaultBucketEventService, IngestReport,
+};
+use axum::{
+    extract::Extension,
+    http::{header::AUTHORIZATION, HeaderMap},
+    routing::post,
+    Json, Router,
+};
+use log::info;
+use sea_orm::DbConn;
+use std::sync::Arc;
+
+use crate::error::ApiError;
+
+pub fn bucket_events_routers() -> Router {
+    Router::new().route("/storage/events", post(events))
+}
+
+/// Webhook target of the bucket, authorized by the shared secret instead of a user token.
+async fn events(
+    headers: HeaderMap,
+    Json(notification): Json<BucketNotification>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<IngestReport>, ApiError> {
+    let service = DefaultBucketEventService::new(config, db.clone());
+    service.verify(
+        headers
+            .get(AUTHORIZATION)
+            .and_then(|value| value.to_str().ok()),
+    )?;
+    info!(
+        "Bucket notification with {} records",
+        notification.records.len()
+    );
+    Ok(Json(service.ingest(notification).await?))
+}
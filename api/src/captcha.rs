@@ -0,0 +1,32 @@
+use app_config::ApplicationConfig;
+use application::{CaptchaChallenge, CaptchaService, DefaultCaptchaService};
+use axum::{extract::Extension, routing::post, Json, Router};
+use sea_orm::DbConn;
+use std::sync::Arc;
+
+use crate::auth::ClientIp;
+use crate::error::ApiError;
+
+pub fn captcha_routers() -> Router {
+    Router::new().route("/captcha", post(create))
+}
+
+/// New challenge, its id and answer are sent along with the protected request.
+async fn create(
+    ClientIp(client_ip): ClientIp,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<CaptchaChallenge>, ApiError> {
+    get_captcha_service(config, db.clone())
+        .create(&client_ip)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+pub(crate) fn get_captcha_service(
+    config: &ApplicationConfig,
+    db: Arc<DbConn>,
+) -> DefaultCaptchaService {
+    DefaultCaptchaService::new(config, db)
+}
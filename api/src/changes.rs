@@ -0,0 +1,93 @@
+use app_config::ApplicationConfig;
+use application::ChangeFeed;
+use axum::{
+    extract::{Extension, Query},
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Json, Router,
+};
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_LIMIT: u64 = 100;
+/// Header of a reconnecting event source with the id of the last received event.
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+pub fn changes_routers() -> Router {
+    Router::new()
+        .route("/changes", get(changes))
+        .route("/changes/stream", get(stream))
+}
+
+/// Long-polling: without changes after `since`, waits up to `wait` seconds for new ones. The
+/// events carry the files of all users, so the feed is for admins.
+async fn changes(
+    _: AdminUser,
+    Query(query): Query<ChangesQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref feed): Extension<ChangeFeed>,
+) -> Result<Json<ChangesResponse>, ApiError> {
+    let since = query.since.unwrap_or(0);
+    let wait = Duration::from_secs(query.wait.unwrap_or(0).min(config.changes.max_wait));
+    let events = feed
+        .changes(since, query.limit.unwrap_or(DEFAULT_LIMIT), wait)
+        .await?;
+    Ok(Json(ChangesResponse {
+        next: events.last().and_then(|event| event.id).unwrap_or(since),
+        events: events.iter().map(|event| event.payload()).collect(),
+    }))
+}
+
+/// Server-sent events, the id of an event is its sequence number, so a reconnecting client
+/// resumes after the last received event.
+async fn stream(
+    _: AdminUser,
+    headers: HeaderMap,
+    Query(query): Query<StreamQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref feed): Extension<ChangeFeed>,
+) -> Sse<impl Stream<Item = Result<Event, anyhow::Error>>> {
+    let since = headers
+        .get(LAST_EVENT_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+        .or(query.since)
+        .unwrap_or(0);
+    let events = feed.stream(since).map(|event| {
+        let event = event?;
+        Ok(Event::default()
+            .id(event.id.unwrap_or_default().to_string())
+            .event(event.event_type.name())
+            .json_data(event.payload())?)
+    });
+    Sse::new(events)
+        .keep_alive(KeepAlive::new().interval(Duration::from_secs(config.changes.keep_alive)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangesQuery {
+    /// Sequence number of the last seen event, from the first event if missing.
+    since: Option<i64>,
+    limit: Option<u64>,
+    /// Seconds to wait for changes, up to the configured maximum.
+    wait: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StreamQuery {
+    since: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangesResponse {
+    events: Vec<Value>,
+    /// `since` of the next request.
+    next: i64,
+}
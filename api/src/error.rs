@@ -0,0 +1,62 @@
+use anyhow::Error;
+use application::{AuthError, ServiceError};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::json;
+
+pub enum ApiError {
+    InnerErr(Error),
+}
+
+impl From<Error> for ApiError {
+    fn from(inner: Error) -> Self {
+        ApiError::InnerErr(inner)
+    }
+}
+
+impl From<AuthError> for ApiError {
+    fn from(inner: AuthError) -> Self {
+        ApiError::InnerErr(inner.into())
+    }
+}
+
+impl From<ServiceError> for ApiError {
+    fn from(inner: ServiceError) -> Self {
+        ApiError::InnerErr(inner.into())
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let (status, error_message) = match self {
+            ApiError::InnerErr(err) => (status_code(&err), err.to_string()),
+        };
+
+        let body = Json(json!({
+            "error": "API error",
+            "message": error_message,
+        }));
+
+        (status, body).into_response()
+    }
+}
+
+fn status_code(err: &Error) -> StatusCode {
+    if let Some(err) = err.downcast_ref::<AuthError>() {
+        return match err {
+            AuthError::Forbidden => StatusCode::FORBIDDEN,
+            AuthError::TooManyAttempts => StatusCode::TOO_MANY_REQUESTS,
+            AuthError::InvalidCaptcha => StatusCode::BAD_REQUEST,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+    }
+    match err.downcast_ref::<ServiceError>() {
+        Some(ServiceError::NotFound(_)) => StatusCode::NOT_FOUND,
+        Some(ServiceError::Conflict(_)) => StatusCode::CONFLICT,
+        Some(ServiceError::BadRequest(_)) => StatusCode::BAD_REQUEST,
+        None => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
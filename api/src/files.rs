@@ -1,28 +1,47 @@
-use anyhow::Error;
 use app_config::ApplicationConfig;
-use application::{DefaultFileService, FileService};
+use application::{
+    parse_color, CaptchaService, DefaultFileService, DefaultThumbnailService, FileService,
+    FileUpdate, ServiceError, ThumbnailService, UploadOptions, DEFAULT_COLOR_DISTANCE,
+};
 use axum::{
-    extract::{Extension, Multipart, Path},
-    http::StatusCode,
+    body::StreamBody,
+    extract::{Extension, Multipart, Path, Query},
+    http::{
+        header::{CACHE_CONTROL, CONTENT_LENGTH, CONTENT_TYPE},
+        HeaderMap, HeaderValue, StatusCode,
+    },
     response::{IntoResponse, Response},
-    routing::{get, post},
+    routing::{get, post, put},
     Json, Router,
 };
 use bytes::Bytes;
-use domain::FileObject;
+use chrono::{DateTime, Utc};
+use domain::{FileObject, ObjectStream, Page, ResourceFilter};
 use log::info;
 use sea_orm::DbConn;
 use serde::{Deserialize, Serialize};
-use serde_json::{json, Value};
+use serde_json::Value;
 use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::captcha::get_captcha_service;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
 
 pub fn files_routers() -> Router {
     Router::new()
         .route("/download/:key", get(download))
         .route("/upload", post(upload))
+        .route("/files", get(list))
+        .route("/files/:key", put(update).delete(delete_file))
+        .route("/files/:key/thumbnail/:size", get(thumbnail))
 }
 
+/// Anonymous uploads have to answer a CAPTCHA, authenticated uploads are owned by the user.
 async fn upload(
+    user: Option<AuthUser>,
     Extension(ref config): Extension<Arc<ApplicationConfig>>,
     Extension(ref db): Extension<Arc<DbConn>>,
     mut multipart: Multipart,
@@ -33,6 +52,10 @@ async fn upload(
     let mut metadata: Option<Value> = None;
     let mut ignored_fields = vec![];
     let mut key = "Unknown".to_owned();
+    let mut scrub_metadata: Option<bool> = None;
+    let mut captcha_id: Option<Uuid> = None;
+    let mut captcha_answer: Option<String> = None;
+    let mut expires_at: Option<DateTime<Utc>> = None;
     let mut data = Bytes::new();
     while let Some(field) = multipart.next_field().await.unwrap() {
         match field.name().unwrap_or("no name") {
@@ -51,21 +74,54 @@ async fn upload(
                     .ok()
                     .and_then(|s| serde_json::from_str(s.as_str()).ok())
             }
+            "scrub_metadata" => {
+                scrub_metadata = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "captcha_id" => {
+                captcha_id = field.text().await.ok().and_then(|s| s.trim().parse().ok())
+            }
+            "captcha_answer" => captcha_answer = field.text().await.ok(),
+            "expires_at" => {
+                let value = field.text().await.unwrap_or_default();
+                expires_at = Some(
+                    DateTime::parse_from_rfc3339(value.trim())
+                        .map_err(|_| {
+                            ServiceError::BadRequest(format!("Invalid expiry time {}", value))
+                        })?
+                        .with_timezone(&Utc),
+                );
+            }
             "file" => {
                 data = field.bytes().await.unwrap();
             }
             name => ignored_fields.push(name.to_owned()),
         }
     }
+    let claims = user.map(|AuthUser(claims)| claims);
+    let user_id = claims.as_ref().map(|claims| claims.sub);
+    if user_id.is_none() {
+        get_captcha_service(config, db.clone())
+            .verify(captcha_id, captcha_answer.as_deref())
+            .await?;
+    }
     let url = file_service
-        .upload(Box::new(FileObject {
-            key: key.clone(),
-            url: None,
-            tags,
-            metadata,
-            user_id: None,
-            data: Some(data),
-        }))
+        .upload(
+            claims.as_ref(),
+            Box::new(FileObject {
+                key: key.clone(),
+                url: None,
+                tags,
+                metadata,
+                blurhash: None,
+                palette: None,
+                size: None,
+                etag: None,
+                expires_at,
+                user_id,
+                data: Some(data),
+            }),
+            UploadOptions { scrub_metadata },
+        )
         .await?;
 
     Ok(Json(UploadResponse {
@@ -88,6 +144,101 @@ async fn download(
         .map_err(ApiError::from)
 }
 
+/// Files of the user, admins see the files of all users.
+async fn list(
+    AuthUser(claims): AuthUser,
+    Query(query): Query<FilesQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<FileObject>>, ApiError> {
+    let color = match query.color {
+        Some(color) => Some(
+            parse_color(&color)
+                .ok_or_else(|| ServiceError::BadRequest(format!("Invalid color {}", color)))?,
+        ),
+        None => None,
+    };
+    get_file_service(config, db.clone())
+        .list(
+            &claims,
+            ResourceFilter {
+                color,
+                max_color_distance: query.max_distance.unwrap_or(DEFAULT_COLOR_DISTANCE),
+                user_id: None,
+                page: query.page.unwrap_or(0),
+                page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+            },
+        )
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn update(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<UpdateFileRequest>,
+) -> Result<Json<FileObject>, ApiError> {
+    get_file_service(config, db.clone())
+        .update(
+            &claims,
+            key,
+            FileUpdate {
+                tags: request.tags,
+                metadata: request.metadata,
+            },
+        )
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn delete_file(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    get_file_service(config, db.clone())
+        .delete(&claims, key)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn thumbnail(
+    Path((key, size)): Path<(String, u32)>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Response, ApiError> {
+    info!("Thumbnail {} of file with key: {}", size, key);
+    let object = DefaultThumbnailService::new(config, db.clone())
+        .thumbnail(&key, size)
+        .await?;
+    let mut response = stream_response(object);
+    response.headers_mut().insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=86400"),
+    );
+    Ok(response)
+}
+
+/// Streams the object body with its content type and length.
+pub(crate) fn stream_response(object: ObjectStream) -> Response {
+    let mut headers = HeaderMap::new();
+    let content_type = object
+        .content_type
+        .as_deref()
+        .and_then(|value| HeaderValue::from_str(value).ok())
+        .unwrap_or_else(|| HeaderValue::from_static("application/octet-stream"));
+    headers.insert(CONTENT_TYPE, content_type);
+    if let Some(length) = object.content_length {
+        headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+    }
+    (headers, StreamBody::new(object.body)).into_response()
+}
+
 fn get_file_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultFileService {
     DefaultFileService::new(config, db)
 }
@@ -98,27 +249,17 @@ pub struct UploadResponse {
     ignored_fields: Vec<String>,
 }
 
-enum ApiError {
-    InnerErr(Error),
-}
-
-impl From<Error> for ApiError {
-    fn from(inner: Error) -> Self {
-        ApiError::InnerErr(inner)
-    }
+#[derive(Debug, Deserialize)]
+pub struct UpdateFileRequest {
+    tags: Option<Value>,
+    metadata: Option<Value>,
 }
 
-impl IntoResponse for ApiError {
-    fn into_response(self) -> Response {
-        let (status, error_message) = match self {
-            ApiError::InnerErr(err) => (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()),
-        };
-
-        let body = Json(json!({
-            "error": "API error",
-            "message": error_message,
-        }));
-
-        (status, body).into_response()
-    }
+#[derive(Debug, Deserialize)]
+pub struct FilesQuery {
+    /// Nearest dominant color first, e.g. `#ff8800`.
+    color: Option<String>,
+    max_distance: Option<u32>,
+    page: Option<u64>,
+    page_size: Option<u64>,
 }
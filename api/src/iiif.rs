@@ -0,0 +1,77 @@
+use app_config::ApplicationConfig;
+use application::{encode_identifier, DefaultIiifService, IiifService, ImageRequest};
+use axum::{
+    extract::{Extension, Path},
+    http::{
+        header::{ACCESS_CONTROL_ALLOW_ORIGIN, CACHE_CONTROL, CONTENT_TYPE},
+        HeaderValue,
+    },
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use log::info;
+use sea_orm::DbConn;
+use std::sync::Arc;
+
+use crate::error::ApiError;
+use crate::files::stream_response;
+
+const INFO_CONTENT_TYPE: &str =
+    "application/ld+json;profile=\"http://iiif.io/api/image/3/context.json\"";
+
+pub fn iiif_routers() -> Router {
+    Router::new()
+        .route("/iiif/:key", get(base))
+        .route("/iiif/:key/info.json", get(info))
+        .route("/iiif/:key/:region/:size/:rotation/:file", get(image))
+}
+
+/// The base URI of an image redirects to its information document.
+async fn base(Path(key): Path<String>) -> Redirect {
+    Redirect::to(&format!("/iiif/{}/info.json", encode_identifier(&key)))
+}
+
+async fn info(
+    Path(key): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Response, ApiError> {
+    info!("IIIF info of file with key: {}", key);
+    let info = get_iiif_service(config, db.clone()).info(&key).await?;
+    Ok((
+        [
+            (CONTENT_TYPE, HeaderValue::from_static(INFO_CONTENT_TYPE)),
+            (ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*")),
+        ],
+        Json(info),
+    )
+        .into_response())
+}
+
+async fn image(
+    Path((key, region, size, rotation, file)): Path<(String, String, String, String, String)>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Response, ApiError> {
+    info!(
+        "IIIF image {}/{}/{}/{} of file with key: {}",
+        region, size, rotation, file, key
+    );
+    let request = ImageRequest::parse(&region, &size, &rotation, &file, config.iiif.quality)?;
+    let object = get_iiif_service(config, db.clone())
+        .image(&key, &request)
+        .await?;
+    let mut response = stream_response(object);
+    let headers = response.headers_mut();
+    headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+    headers.insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=86400"),
+    );
+    Ok(response)
+}
+
+fn get_iiif_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultIiifService {
+    DefaultIiifService::new(config, db)
+}
@@ -0,0 +1,138 @@
+use application::{DefaultJobService, JobService, ServiceError};
+use axum::{
+    extract::{Extension, Path, Query},
+    routing::{get, post},
+    Json, Router,
+};
+use domain::{Job, JobFilter, JobQueueStats, Page};
+use log::info;
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn jobs_routers() -> Router {
+    Router::new()
+        .route("/admin/jobs", get(list))
+        .route("/admin/jobs/:id", get(get_job))
+        .route("/admin/jobs/:id/retry", post(retry))
+        .route("/admin/jobs/:id/cancel", post(cancel))
+        .route("/admin/queues", get(stats))
+        .route("/admin/queues/:kind/pause", post(pause))
+        .route("/admin/queues/:kind/resume", post(resume))
+}
+
+async fn list(
+    _: AdminUser,
+    Query(query): Query<JobsQuery>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<Job>>, ApiError> {
+    let status = match query.status {
+        Some(status) => Some(
+            status
+                .parse()
+                .map_err(|_| ServiceError::BadRequest(format!("Unknown job status {}", status)))?,
+        ),
+        None => None,
+    };
+    get_job_service(db.clone())
+        .list(JobFilter {
+            kind: query.kind,
+            status,
+            page: query.page.unwrap_or(0),
+            page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        })
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn get_job(
+    _: AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Job>, ApiError> {
+    get_job_service(db.clone())
+        .get(id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn retry(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Job>, ApiError> {
+    info!("User {} retries job {}", admin.name, id);
+    get_job_service(db.clone())
+        .retry(id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn cancel(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Job>, ApiError> {
+    info!("User {} cancels job {}", admin.name, id);
+    get_job_service(db.clone())
+        .cancel(id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn stats(
+    _: AdminUser,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Vec<JobQueueStats>>, ApiError> {
+    get_job_service(db.clone())
+        .stats()
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn pause(
+    AdminUser(admin): AdminUser,
+    Path(kind): Path<String>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<(), ApiError> {
+    info!("User {} pauses {} jobs", admin.name, kind);
+    get_job_service(db.clone())
+        .pause(&kind)
+        .await
+        .map_err(ApiError::from)
+}
+
+async fn resume(
+    AdminUser(admin): AdminUser,
+    Path(kind): Path<String>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<(), ApiError> {
+    info!("User {} resumes {} jobs", admin.name, kind);
+    get_job_service(db.clone())
+        .resume(&kind)
+        .await
+        .map_err(ApiError::from)
+}
+
+fn get_job_service(db: Arc<DbConn>) -> DefaultJobService {
+    DefaultJobService::new(db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct JobsQuery {
+    kind: Option<String>,
+    status: Option<String>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
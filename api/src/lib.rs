@@ -1 +1,19 @@
+pub mod audit;
+pub mod auth;
+pub mod bucket_events;
+pub mod captcha;
+pub mod changes;
+pub mod error;
 pub mod files;
+pub mod iiif;
+pub mod jobs;
+pub mod lifecycle;
+pub mod oidc;
+pub mod publications;
+pub mod shares;
+pub mod similarity;
+pub mod transforms;
+pub mod trash;
+pub mod two_factor;
+pub mod users;
+pub mod webhooks;
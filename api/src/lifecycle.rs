@@ -0,0 +1,43 @@
+use app_config::ApplicationConfig;
+use application::{DefaultLifecycleService, LifecycleReport, LifecycleService};
+use axum::{
+    extract::{Extension, Query},
+    routing::post,
+    Json, Router,
+};
+use log::info;
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+pub fn lifecycle_routers() -> Router {
+    Router::new().route("/admin/lifecycle/run", post(run))
+}
+
+/// Evaluates the lifecycle rules right away, by default as a dry run that only reports the
+/// actions.
+async fn run(
+    AdminUser(claims): AdminUser,
+    Query(query): Query<RunQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<LifecycleReport>, ApiError> {
+    let dry_run = query.dry_run.unwrap_or(true);
+    info!(
+        "Admin {} runs the lifecycle rules, dry run: {}",
+        claims.name, dry_run
+    );
+    DefaultLifecycleService::new(config, db.clone())
+        .evaluate(dry_run)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[derive(Debug, Deserialize)]
+struct RunQuery {
+    dry_run: Option<bool>,
+}
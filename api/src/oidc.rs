@@ -0,0 +1,86 @@
+use app_config::ApplicationConfig;
+use application::{
+    AuthError, DefaultOidcService, LoginResponse, OidcService, OIDC_LOGIN_STATE_TTL,
+};
+use axum::{
+    extract::{Extension, Query},
+    headers::Cookie,
+    http::header::SET_COOKIE,
+    response::{IntoResponse, Redirect},
+    routing::get,
+    Json, Router, TypedHeader,
+};
+use log::{info, warn};
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::error::ApiError;
+
+const LOGIN_STATE_COOKIE: &str = "oidc_login";
+const LOGIN_STATE_COOKIE_PATH: &str = "/auth/oidc";
+
+pub fn oidc_routers() -> Router {
+    Router::new()
+        .route("/auth/oidc/login", get(login))
+        .route("/auth/oidc/callback", get(callback))
+}
+
+/// Redirects to the identity provider, the state of the login is kept in a sealed cookie.
+async fn login(
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let login = get_oidc_service(config, db.clone()).begin_login().await?;
+    let cookie = login_state_cookie(config, &login.login_state, OIDC_LOGIN_STATE_TTL);
+    Ok(([(SET_COOKIE, cookie)], Redirect::to(&login.url)))
+}
+
+async fn callback(
+    Query(query): Query<CallbackQuery>,
+    cookies: Option<TypedHeader<Cookie>>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<impl IntoResponse, ApiError> {
+    if let Some(error) = query.error {
+        warn!("Identity provider rejected the login: {}", error);
+        return Err(AuthError::InvalidCredentials.into());
+    }
+    let (code, state) = match (query.code, query.state) {
+        (Some(code), Some(state)) => (code, state),
+        _ => return Err(AuthError::InvalidToken.into()),
+    };
+    let login_state = cookies
+        .as_ref()
+        .and_then(|TypedHeader(cookies)| cookies.get(LOGIN_STATE_COOKIE))
+        .ok_or(AuthError::InvalidToken)?;
+    info!("Complete OIDC login");
+    let response: LoginResponse = get_oidc_service(config, db.clone())
+        .complete_login(&code, &state, login_state)
+        .await?;
+    let cookie = login_state_cookie(config, "", 0);
+    Ok(([(SET_COOKIE, cookie)], Json(response)))
+}
+
+fn login_state_cookie(config: &ApplicationConfig, value: &str, max_age: i64) -> String {
+    let secure = if config.oidc.redirect_url.starts_with("https://") {
+        "; Secure"
+    } else {
+        ""
+    };
+    format!(
+        "{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Lax{}",
+        LOGIN_STATE_COOKIE, value, LOGIN_STATE_COOKIE_PATH, max_age, secure
+    )
+}
+
+fn get_oidc_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultOidcService {
+    DefaultOidcService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+}
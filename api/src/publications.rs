@@ -0,0 +1,152 @@
+use app_config::ApplicationConfig;
+use application::{DefaultPublicationService, PublicationService, ServiceError};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    routing::{get, post, put},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use domain::{Page, Publication, PublicationFilter};
+use log::info;
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn publications_routers() -> Router {
+    Router::new()
+        .route("/admin/publications", get(list).post(enqueue))
+        .route("/admin/publications/targets", get(targets))
+        .route("/admin/publications/:id", get(get_publication))
+        .route("/admin/publications/:id/schedule", put(reschedule))
+        .route("/admin/publications/:id/cancel", post(cancel))
+}
+
+async fn enqueue(
+    AdminUser(admin): AdminUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<EnqueueRequest>,
+) -> Result<(StatusCode, Json<Publication>), ApiError> {
+    info!(
+        "User {} queues {} for {}",
+        admin.name, request.key, request.target
+    );
+    let publication = get_publication_service(config, db.clone())
+        .enqueue(&request.key, &request.target, request.scheduled_at)
+        .await?;
+    Ok((StatusCode::CREATED, Json(publication)))
+}
+
+async fn list(
+    _: AdminUser,
+    Query(query): Query<PublicationsQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<Publication>>, ApiError> {
+    let status = match query.status {
+        Some(status) => Some(status.parse().map_err(|_| {
+            ServiceError::BadRequest(format!("Unknown publication status {}", status))
+        })?),
+        None => None,
+    };
+    get_publication_service(config, db.clone())
+        .list(PublicationFilter {
+            target: query.target,
+            status,
+            resource_id: query.resource_id,
+            page: query.page.unwrap_or(0),
+            page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        })
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn targets(
+    _: AdminUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Json<Vec<&'static str>> {
+    Json(get_publication_service(config, db.clone()).targets())
+}
+
+async fn get_publication(
+    _: AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Publication>, ApiError> {
+    get_publication_service(config, db.clone())
+        .get(id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn reschedule(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<RescheduleRequest>,
+) -> Result<Json<Publication>, ApiError> {
+    info!(
+        "User {} reschedules publication {} to {}",
+        admin.name, id, request.scheduled_at
+    );
+    get_publication_service(config, db.clone())
+        .reschedule(id, request.scheduled_at)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn cancel(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Publication>, ApiError> {
+    info!("User {} cancels publication {}", admin.name, id);
+    get_publication_service(config, db.clone())
+        .cancel(id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+fn get_publication_service(
+    config: &ApplicationConfig,
+    db: Arc<DbConn>,
+) -> DefaultPublicationService {
+    DefaultPublicationService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EnqueueRequest {
+    key: String,
+    target: String,
+    /// Right away if missing.
+    scheduled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RescheduleRequest {
+    scheduled_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PublicationsQuery {
+    target: Option<String>,
+    status: Option<String>,
+    resource_id: Option<Uuid>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
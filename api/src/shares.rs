@@ -0,0 +1,183 @@
+use app_config::ApplicationConfig;
+use application::{DefaultShareService, ShareOptions, ShareService, SharedObject};
+use axum::{
+    extract::{Extension, Form, Path},
+    http::{header::CONTENT_DISPOSITION, HeaderMap, HeaderValue, StatusCode},
+    response::Response,
+    routing::{delete, get},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use domain::Share;
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{AuthUser, ClientIp};
+use crate::error::ApiError;
+use crate::files::stream_response;
+
+const SHARE_PASSWORD_HEADER: &str = "x-share-password";
+
+pub fn shares_routers() -> Router {
+    Router::new()
+        .route("/files/:key/shares", get(list).post(create))
+        .route("/shares/:id", delete(revoke))
+        .route("/s/:token", get(open).post(open_with_password))
+}
+
+async fn create(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<CreateShareRequest>,
+) -> Result<(StatusCode, Json<ShareResponse>), ApiError> {
+    let created = get_share_service(config, db.clone())
+        .create(
+            &claims,
+            &key,
+            ShareOptions {
+                expires_in: request.expires_in,
+                password: request.password,
+                max_downloads: request.max_downloads,
+            },
+        )
+        .await?;
+    let mut response = ShareResponse::from(created.share);
+    response.url = Some(format!("/s/{}", created.token));
+    response.token = Some(created.token);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+async fn list(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Vec<ShareResponse>>, ApiError> {
+    let shares = get_share_service(config, db.clone())
+        .list(&claims, &key)
+        .await?;
+    Ok(Json(shares.into_iter().map(ShareResponse::from).collect()))
+}
+
+async fn revoke(
+    AuthUser(claims): AuthUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    get_share_service(config, db.clone())
+        .revoke(&claims, id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Public download, the password of a protected link is sent in the `X-Share-Password` header.
+async fn open(
+    ClientIp(client_ip): ClientIp,
+    Path(token): Path<String>,
+    headers: HeaderMap,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Response, ApiError> {
+    let password = headers
+        .get(SHARE_PASSWORD_HEADER)
+        .and_then(|value| value.to_str().ok());
+    info!("Open share link");
+    let shared = get_share_service(config, db.clone())
+        .open(&token, password, &client_ip)
+        .await?;
+    Ok(download_response(shared))
+}
+
+/// Public download of a protected link submitted by a form.
+async fn open_with_password(
+    ClientIp(client_ip): ClientIp,
+    Path(token): Path<String>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Form(request): Form<SharePasswordRequest>,
+) -> Result<Response, ApiError> {
+    info!("Open share link with password");
+    let shared = get_share_service(config, db.clone())
+        .open(&token, Some(request.password.as_str()), &client_ip)
+        .await?;
+    Ok(download_response(shared))
+}
+
+fn download_response(shared: SharedObject) -> Response {
+    let mut response = stream_response(shared.object);
+    if let Ok(disposition) = HeaderValue::from_str(&format!(
+        "attachment; filename=\"{}\"",
+        file_name(&shared.key)
+    )) {
+        response
+            .headers_mut()
+            .insert(CONTENT_DISPOSITION, disposition);
+    }
+    response
+}
+
+/// Last segment of the key without characters that would break the header.
+fn file_name(key: &str) -> String {
+    key.rsplit('/')
+        .next()
+        .unwrap_or(key)
+        .chars()
+        .filter(|c| c.is_ascii() && !c.is_ascii_control() && *c != '"' && *c != '\\')
+        .collect()
+}
+
+fn get_share_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultShareService {
+    DefaultShareService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateShareRequest {
+    expires_in: Option<i64>,
+    password: Option<String>,
+    max_downloads: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SharePasswordRequest {
+    password: String,
+}
+
+/// Share without the hashes, the token and url are only returned when the link is created.
+#[derive(Debug, Serialize)]
+pub struct ShareResponse {
+    id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    password_protected: bool,
+    max_downloads: Option<i32>,
+    download_count: i32,
+    revoked_at: Option<DateTime<Utc>>,
+    active: bool,
+    created_at: DateTime<Utc>,
+}
+
+impl From<Share> for ShareResponse {
+    fn from(share: Share) -> Self {
+        Self {
+            id: share.id,
+            token: None,
+            url: None,
+            expires_at: share.expires_at,
+            password_protected: share.password_hash.is_some(),
+            max_downloads: share.max_downloads,
+            download_count: share.download_count,
+            revoked_at: share.revoked_at,
+            active: share.is_active(),
+            created_at: share.created_at,
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use app_config::ApplicationConfig;
+use application::{
+    DefaultSimilarityService, DuplicateCluster, SimilarityService, DEFAULT_SIMILARITY_DISTANCE,
+};
+use axum::{
+    extract::{Extension, Path, Query},
+    routing::get,
+    Json, Router,
+};
+use domain::SimilarResource;
+use log::info;
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::auth::{AdminUser, AuthUser};
+use crate::error::ApiError;
+
+const DEFAULT_SIMILAR_LIMIT: u64 = 20;
+
+pub fn similarity_routers() -> Router {
+    Router::new()
+        .route("/files/:key/similar", get(similar))
+        .route("/admin/duplicates", get(duplicates))
+}
+
+async fn similar(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Query(query): Query<SimilarQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Vec<SimilarResource>>, ApiError> {
+    info!("Similar files of key: {}", key);
+    get_similarity_service(config, db.clone())
+        .similar(
+            &claims,
+            key,
+            query.max_distance.unwrap_or(DEFAULT_SIMILARITY_DISTANCE),
+            query.limit.unwrap_or(DEFAULT_SIMILAR_LIMIT),
+        )
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn duplicates(
+    _: AdminUser,
+    Query(query): Query<DuplicatesQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Vec<DuplicateCluster>>, ApiError> {
+    get_similarity_service(config, db.clone())
+        .duplicates(query.max_distance.unwrap_or(DEFAULT_SIMILARITY_DISTANCE))
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+fn get_similarity_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultSimilarityService {
+    DefaultSimilarityService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimilarQuery {
+    max_distance: Option<u32>,
+    limit: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicatesQuery {
+    max_distance: Option<u32>,
+}
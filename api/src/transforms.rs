@@ -0,0 +1,92 @@
+use app_config::ApplicationConfig;
+use application::{transform_path, DefaultTransformService, TransformParams, TransformService};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::{header::CACHE_CONTROL, HeaderValue},
+    response::Response,
+    routing::get,
+    Json, Router,
+};
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+use crate::files::stream_response;
+
+pub fn transform_routers() -> Router {
+    Router::new()
+        .route("/img/:key", get(transform))
+        .route("/img/:key/sign", get(sign))
+}
+
+/// Transformed image, the result never changes for a signed URL.
+async fn transform(
+    Path(key): Path<String>,
+    Query(query): Query<TransformQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Response, ApiError> {
+    info!("Transform image with key: {}", key);
+    let signature = query.s.clone().unwrap_or_default();
+    let object = get_transform_service(config, db.clone())
+        .transform(&key, &query.into(), &signature)
+        .await?;
+    let mut response = stream_response(object);
+    response.headers_mut().insert(
+        CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=31536000, immutable"),
+    );
+    Ok(response)
+}
+
+async fn sign(
+    AuthUser(claims): AuthUser,
+    Path(key): Path<String>,
+    Query(query): Query<TransformQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<SignedUrlResponse>, ApiError> {
+    info!("User {} signs transform of {}", claims.name, key);
+    let query = get_transform_service(config, db.clone())
+        .sign(&key, &query.into())
+        .await?;
+    Ok(Json(SignedUrlResponse {
+        url: format!("{}?{}", transform_path(&key), query),
+    }))
+}
+
+fn get_transform_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultTransformService {
+    DefaultTransformService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransformQuery {
+    w: Option<u32>,
+    h: Option<u32>,
+    fit: Option<String>,
+    format: Option<String>,
+    quality: Option<u8>,
+    v: Option<String>,
+    s: Option<String>,
+}
+
+impl From<TransformQuery> for TransformParams {
+    fn from(query: TransformQuery) -> Self {
+        Self {
+            width: query.w,
+            height: query.h,
+            fit: query.fit,
+            format: query.format,
+            quality: query.quality,
+            version: query.v,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignedUrlResponse {
+    url: String,
+}
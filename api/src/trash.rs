@@ -0,0 +1,60 @@
+use app_config::ApplicationConfig;
+use application::{DefaultTrashService, TrashService, TrashedFile};
+use axum::{
+    extract::{Extension, Path, Query},
+    routing::{get, post},
+    Json, Router,
+};
+use domain::{FileObject, Page};
+use sea_orm::DbConn;
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AuthUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn trash_routers() -> Router {
+    Router::new()
+        .route("/trash", get(list))
+        .route("/trash/:id/restore", post(restore))
+}
+
+/// Trashed files of the user, admins see the files of all users.
+async fn list(
+    AuthUser(claims): AuthUser,
+    Query(query): Query<TrashQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<TrashedFile>>, ApiError> {
+    DefaultTrashService::new(config, db.clone())
+        .list(
+            &claims,
+            query.page.unwrap_or(0),
+            query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        )
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn restore(
+    AuthUser(claims): AuthUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<FileObject>, ApiError> {
+    DefaultTrashService::new(config, db.clone())
+        .restore(&claims, id)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+#[derive(Debug, Deserialize)]
+struct TrashQuery {
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
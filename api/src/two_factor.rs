@@ -0,0 +1,101 @@
+use app_config::ApplicationConfig;
+use application::{DefaultTwoFactorService, TwoFactorService, TwoFactorSetup};
+use axum::{
+    extract::{Extension, Path},
+    http::StatusCode,
+    routing::{delete, post},
+    Json, Router,
+};
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{AdminUser, AuthUser};
+use crate::error::ApiError;
+
+pub fn two_factor_routers() -> Router {
+    Router::new()
+        .route("/auth/2fa/setup", post(setup))
+        .route("/auth/2fa/enable", post(enable))
+        .route("/auth/2fa/disable", post(disable))
+        .route("/auth/2fa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/admin/users/:id/2fa", delete(reset))
+}
+
+async fn setup(
+    AuthUser(user): AuthUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<TwoFactorSetup>, ApiError> {
+    get_two_factor_service(config, db.clone())
+        .setup(user.sub)
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn enable(
+    AuthUser(user): AuthUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let recovery_codes = get_two_factor_service(config, db.clone())
+        .enable(user.sub, &request.code)
+        .await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+async fn disable(
+    AuthUser(user): AuthUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<StatusCode, ApiError> {
+    get_two_factor_service(config, db.clone())
+        .disable(user.sub, &request.code)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn regenerate_recovery_codes(
+    AuthUser(user): AuthUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<CodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, ApiError> {
+    let recovery_codes = get_two_factor_service(config, db.clone())
+        .regenerate_recovery_codes(user.sub, &request.code)
+        .await?;
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+async fn reset(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    info!(
+        "User {} resets two-factor authentication of user {}",
+        admin.name, id
+    );
+    get_two_factor_service(config, db.clone()).reset(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn get_two_factor_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultTwoFactorService {
+    DefaultTwoFactorService::new(config, db)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CodeRequest {
+    code: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecoveryCodesResponse {
+    recovery_codes: Vec<String>,
+}
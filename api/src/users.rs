@@ -0,0 +1,183 @@
+use application::{DefaultUserService, ServiceError, UserService};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    routing::{delete, get, put},
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use domain::{Page, Role, User, UserFilter};
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn users_routers() -> Router {
+    Router::new()
+        .route("/admin/users", get(list).post(create))
+        .route("/admin/users/:id", delete(delete_user))
+        .route("/admin/users/:id/role", put(update_role))
+        .route("/admin/users/:id/enabled", put(set_enabled))
+}
+
+async fn list(
+    AdminUser(admin): AdminUser,
+    Query(query): Query<UsersQuery>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<UserResponse>>, ApiError> {
+    let role = match query.role {
+        Some(role) => Some(parse_role(&role)?),
+        None => None,
+    };
+    let page = get_user_service(db.clone())
+        .list(
+            &admin,
+            UserFilter {
+                role,
+                enabled: query.enabled,
+                page: query.page.unwrap_or(0),
+                page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+            },
+        )
+        .await?;
+    Ok(Json(page.map(UserResponse::from)))
+}
+
+async fn create(
+    AdminUser(admin): AdminUser,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<UserResponse>), ApiError> {
+    info!("User {} creates user {}", admin.name, request.name);
+    let role = match request.role {
+        Some(role) => parse_role(&role)?,
+        None => Role::USER,
+    };
+    let user = get_user_service(db.clone())
+        .create(
+            &admin,
+            User::default()
+                .with_name(&request.name)
+                .with_email(&request.email)
+                .with_password(&request.password)
+                .with_role(role)
+                .enable(request.enabled.unwrap_or(false)),
+        )
+        .await?;
+    Ok((StatusCode::CREATED, Json(user.into())))
+}
+
+async fn update_role(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<UpdateRoleRequest>,
+) -> Result<Json<UserResponse>, ApiError> {
+    info!(
+        "User {} sets role {} for user {}",
+        admin.name, request.role, id
+    );
+    let role = parse_role(&request.role)?;
+    get_user_service(db.clone())
+        .update_role(&admin, id, role)
+        .await
+        .map(|user| Json(user.into()))
+        .map_err(ApiError::from)
+}
+
+async fn set_enabled(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<SetEnabledRequest>,
+) -> Result<Json<UserResponse>, ApiError> {
+    info!(
+        "User {} sets enabled={} for user {}",
+        admin.name, request.enabled, id
+    );
+    get_user_service(db.clone())
+        .set_enabled(&admin, id, request.enabled)
+        .await
+        .map(|user| Json(user.into()))
+        .map_err(ApiError::from)
+}
+
+async fn delete_user(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    info!("User {} deletes user {}", admin.name, id);
+    get_user_service(db.clone()).delete(&admin, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn get_user_service(db: Arc<DbConn>) -> DefaultUserService {
+    DefaultUserService::new(db)
+}
+
+fn parse_role(role: &str) -> Result<Role, ApiError> {
+    role.parse()
+        .map_err(|_| ServiceError::BadRequest(format!("Unknown role {}", role)).into())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UsersQuery {
+    role: Option<String>,
+    enabled: Option<bool>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateUserRequest {
+    name: String,
+    email: String,
+    password: String,
+    role: Option<String>,
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateRoleRequest {
+    role: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetEnabledRequest {
+    enabled: bool,
+}
+
+/// User as exposed by the API, without the password.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserResponse {
+    id: Option<Uuid>,
+    name: String,
+    email: String,
+    enabled: bool,
+    email_verified_at: Option<DateTime<Utc>>,
+    role: Role,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+impl From<User> for UserResponse {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            enabled: user.enabled,
+            email_verified_at: user.email_verified_at,
+            role: user.role,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use app_config::ApplicationConfig;
+use application::{DefaultWebhookService, ServiceError, WebhookService};
+use axum::{
+    extract::{Extension, Path, Query},
+    http::StatusCode,
+    routing::{delete, get},
+    Json, Router,
+};
+use domain::{Page, WebhookDelivery, WebhookDeliveryFilter, WebhookSubscription};
+use log::info;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::AdminUser;
+use crate::error::ApiError;
+
+const DEFAULT_PAGE_SIZE: u64 = 20;
+
+pub fn webhooks_routers() -> Router {
+    Router::new()
+        .route("/admin/webhooks", get(list).post(subscribe))
+        .route("/admin/webhooks/deliveries", get(deliveries))
+        .route("/admin/webhooks/:id", delete(unsubscribe))
+}
+
+/// The secret is only returned here.
+async fn subscribe(
+    AdminUser(admin): AdminUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+    Json(request): Json<SubscribeRequest>,
+) -> Result<(StatusCode, Json<SubscribeResponse>), ApiError> {
+    info!("User {} subscribes {} to webhooks", admin.name, request.url);
+    let created = get_webhook_service(config, db.clone())
+        .subscribe(
+            &request.url,
+            request.secret,
+            &request.event_types.unwrap_or_default(),
+        )
+        .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(SubscribeResponse {
+            secret: created.secret,
+            subscription: created.subscription,
+        }),
+    ))
+}
+
+async fn list(
+    _: AdminUser,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Vec<WebhookSubscription>>, ApiError> {
+    get_webhook_service(config, db.clone())
+        .subscriptions()
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+async fn unsubscribe(
+    AdminUser(admin): AdminUser,
+    Path(id): Path<Uuid>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<StatusCode, ApiError> {
+    info!("User {} removes webhook {}", admin.name, id);
+    get_webhook_service(config, db.clone())
+        .unsubscribe(id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Delivery log, latest events first.
+async fn deliveries(
+    _: AdminUser,
+    Query(query): Query<DeliveriesQuery>,
+    Extension(ref config): Extension<Arc<ApplicationConfig>>,
+    Extension(ref db): Extension<Arc<DbConn>>,
+) -> Result<Json<Page<WebhookDelivery>>, ApiError> {
+    let status = match query.status {
+        Some(status) => Some(status.parse().map_err(|_| {
+            ServiceError::BadRequest(format!("Unknown delivery status {}", status))
+        })?),
+        None => None,
+    };
+    get_webhook_service(config, db.clone())
+        .deliveries(WebhookDeliveryFilter {
+            subscription_id: query.subscription_id,
+            status,
+            page: query.page.unwrap_or(0),
+            page_size: query.page_size.unwrap_or(DEFAULT_PAGE_SIZE),
+        })
+        .await
+        .map(Json)
+        .map_err(ApiError::from)
+}
+
+fn get_webhook_service(config: &ApplicationConfig, db: Arc<DbConn>) -> DefaultWebhookService {
+    DefaultWebhookService::new(config, db)
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SubscribeRequest {
+    url: String,
+    /// Generated if missing.
+    secret: Option<String>,
+    /// E.g. `resource.created`, all types if missing or empty.
+    event_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SubscribeResponse {
+    secret: String,
+    #[serde(flatten)]
+    subscription: WebhookSubscription,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeliveriesQuery {
+    subscription_id: Option<Uuid>,
+    status: Option<String>,
+    page: Option<u64>,
+    page_size: Option<u64>,
+}
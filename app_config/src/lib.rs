@@ -1,5 +1,6 @@
 use config::{Config, Environment, File};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
 
 const CONFIG_FILE_PATH: &str = "./Default.toml";
@@ -27,11 +28,328 @@ pub struct AwsConfig {
     pub bucket: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthConfig {
+    /// Signing key of the tokens, has to be set, e.g. with `P_AUTH_JWT_SECRET`.
+    pub jwt_secret: String,
+    pub access_token_ttl: i64,
+    pub refresh_token_ttl: i64,
+    pub verification_token_ttl: i64,
+    pub password_reset_token_ttl: i64,
+    pub mfa_challenge_ttl: i64,
+    pub totp_issuer: String,
+    /// Encrypts the TOTP secrets of two-factor authentication, has to be set to a random
+    /// secret. Secrets encrypted with a previous key can't be decrypted.
+    pub totp_encryption_key: String,
+}
+
+/// Throttling of failed logins, durations are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LockoutConfig {
+    pub max_failures: i32,
+    pub client_max_failures: i32,
+    pub failure_window: i64,
+    pub lockout_duration: i64,
+    pub backoff_base: i64,
+    pub backoff_max: i64,
+    /// Number of proxies appending to `X-Forwarded-For`, the client address is the entry the
+    /// outermost of them appended. The header is ignored with none.
+    pub trusted_proxies: usize,
+}
+
+/// OpenID Connect login against the company identity provider.
+#[derive(Debug, Deserialize, Clone)]
+pub struct OidcConfig {
+    pub enabled: bool,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub redirect_url: String,
+    /// Space separated, `openid` is always requested.
+    pub scopes: String,
+    /// Claim of the ID token holding a value or a list of values mapped to roles.
+    pub role_claim: String,
+    /// Claim value to the name of a `Role`.
+    pub role_mapping: HashMap<String, String>,
+    pub default_role: String,
+    /// Key sealing the state of a login in a cookie, independent of the JWT secret.
+    pub login_state_secret: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MailConfig {
+    pub transport: String,
+    pub from: String,
+    pub base_url: String,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub smtp_password: String,
+    pub smtp_tls: bool,
+}
+
+/// Durable background job queue and its workers.
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobsConfig {
+    /// Whether this process runs workers, jobs are enqueued either way.
+    pub enabled: bool,
+    /// Milliseconds between polls of a job type without due jobs.
+    pub poll_interval: u64,
+    /// Seconds a claimed job stays locked, a job that runs longer is failed and retried.
+    pub lock_timeout: i64,
+    pub max_attempts: i32,
+    /// Seconds before the first retry, doubled with every further attempt.
+    pub backoff_base: i64,
+    pub backoff_max: i64,
+    /// Jobs of one type run at the same time, unless overridden per type.
+    pub concurrency: usize,
+    pub kind_concurrency: HashMap<String, usize>,
+}
+
+/// Scheduled tasks, each one runs on a single replica per fire time.
+#[derive(Debug, Deserialize, Clone)]
+pub struct SchedulerConfig {
+    /// Whether this process runs scheduled tasks.
+    pub enabled: bool,
+    /// IANA name of the timezone of the schedules, e.g. `Europe/Berlin`.
+    pub timezone: String,
+}
+
+/// Publishing to the VK community, once a day at `hour`:`minute` in the scheduler timezone.
+#[derive(Debug, Deserialize, Clone)]
+pub struct VkConfig {
+    pub enabled: bool,
+    pub client_id: String,
+    pub secret_key: String,
+    /// Community access token with the `photos` and `wall` scopes.
+    pub access_token: String,
+    pub group_id: String,
+    pub hour: u32,
+    pub minute: u32,
+    pub api_url: String,
+    pub api_version: String,
+    /// Images posted per day.
+    pub batch_size: u64,
+}
+
+/// Publications to external targets, published by jobs of the job queue with its backoff.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PublishingConfig {
+    /// Attempts of the job of a publication.
+    pub max_attempts: i32,
+    pub webhook: WebhookPublisherConfig,
+    pub telegram: TelegramConfig,
+    pub s3: S3PublisherConfig,
+}
+
+/// Publishing to an HTTP endpoint, see `remote::WebhookClient`.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookPublisherConfig {
+    pub enabled: bool,
+    pub url: String,
+    /// Bearer token, sent if not empty.
+    pub token: String,
+}
+
+/// Publishing to a chat through the Telegram Bot API.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TelegramConfig {
+    pub enabled: bool,
+    pub api_url: String,
+    pub bot_token: String,
+    /// Chat id or `@channelusername`.
+    pub chat_id: String,
+}
+
+/// Copying to another bucket of the configured storage.
+#[derive(Debug, Deserialize, Clone)]
+pub struct S3PublisherConfig {
+    pub enabled: bool,
+    pub bucket: String,
+}
+
+/// Notifications of resource events to the subscribed URLs, durations are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhooksConfig {
+    /// Attempts of the job of a delivery, kept on the subscription when it is created.
+    pub max_attempts: i32,
+    /// Timeout of a single request.
+    pub timeout: u64,
+}
+
+/// Feed of the changes of resources, durations are in seconds.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ChangesConfig {
+    /// Longest wait of a long-polling request for new changes.
+    pub max_wait: u64,
+    /// Interval of the keep-alive comments of the event stream.
+    pub keep_alive: u64,
+}
+
+/// Registration of objects put into the bucket directly, from its event notifications.
+#[derive(Debug, Deserialize, Clone)]
+pub struct BucketEventsConfig {
+    pub enabled: bool,
+    /// Shared secret the bucket sends as the `Authorization` header.
+    pub secret: String,
+}
+
+/// Deleted resources are kept in the trash before they are purged.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TrashConfig {
+    /// Key prefix of the objects of trashed resources.
+    pub prefix: String,
+    /// Days a resource stays in the trash.
+    pub retention: i64,
+    /// Resources purged per run.
+    pub batch_size: u64,
+}
+
+/// Expiry and lifecycle rules of resources, evaluated every hour.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LifecycleConfig {
+    pub enabled: bool,
+    /// Only reports the actions of the scheduled runs without taking them.
+    pub dry_run: bool,
+    /// Resources handled per rule and run.
+    pub batch_size: u64,
+    /// Bucket of archived objects unless the rule names another one.
+    pub archive_bucket: String,
+    #[serde(default)]
+    pub rules: Vec<LifecycleRule>,
+}
+
+/// Applies the action to the resources matching all of the given conditions.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LifecycleRule {
+    pub name: String,
+    pub prefix: Option<String>,
+    /// `name` for resources with the tag, `name=value` for the tag with the value.
+    pub tag: Option<String>,
+    /// Days since the creation of the resource.
+    pub older_than: Option<i64>,
+    pub action: LifecycleAction,
+    /// Bucket of archived objects or key prefix of moved ones.
+    pub destination: Option<String>,
+}
+
+impl LifecycleRule {
+    /// A rule without conditions would match every resource, a move into its own prefix
+    /// would match the moved resources again.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.prefix.is_none() && self.tag.is_none() && self.older_than.is_none() {
+            return Err(format!("Lifecycle rule {} has no conditions", self.name));
+        }
+        if self.action != LifecycleAction::Move {
+            return Ok(());
+        }
+        let destination = match self.destination.as_deref() {
+            Some(destination) if !destination.is_empty() => destination,
+            _ => {
+                return Err(format!(
+                    "Lifecycle rule {} moves resources without a destination",
+                    self.name
+                ))
+            }
+        };
+        if matches!(&self.prefix, Some(prefix) if destination.starts_with(prefix.as_str())) {
+            return Err(format!(
+                "Lifecycle rule {} moves resources into its own prefix",
+                self.name
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleAction {
+    /// Moves the resource with its object to the trash.
+    Delete,
+    /// Copies the object to the archive bucket, then deletes the resource with its object.
+    Archive,
+    /// Moves the object under the destination prefix, keeping the rest of the key.
+    Move,
+}
+
+/// Image CAPTCHA of registrations and anonymous uploads.
+#[derive(Debug, Deserialize, Clone)]
+pub struct CaptchaConfig {
+    pub enabled: bool,
+    /// Seconds a challenge can be answered.
+    pub ttl: i64,
+    /// Number of characters of a challenge.
+    pub length: usize,
+}
+
+/// Derivatives generated in the background for uploaded images.
+#[derive(Debug, Deserialize, Clone)]
+pub struct ThumbnailConfig {
+    pub enabled: bool,
+    /// Bounding boxes of the thumbnails in pixels.
+    pub sizes: Vec<u32>,
+    /// `jpeg` or `png`.
+    pub format: String,
+    /// JPEG quality from 1 to 100.
+    pub quality: u8,
+}
+
+/// Removal of embedded metadata from uploaded images.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PrivacyConfig {
+    /// Policy of the bucket, an upload can override it.
+    pub scrub_metadata: bool,
+    /// Quality of re-encoded JPEG images from 1 to 100.
+    pub jpeg_quality: u8,
+}
+
+/// On-the-fly image transformations, the URLs are signed with the signing key.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TransformConfig {
+    /// Signs the URLs, has to be set to a random secret.
+    pub signing_key: String,
+    /// Largest width or height that can be requested.
+    pub max_dimension: u32,
+    /// Default JPEG quality from 1 to 100.
+    pub quality: u8,
+}
+
+/// IIIF Image API service of the stored images.
+#[derive(Debug, Deserialize, Clone)]
+pub struct IiifConfig {
+    /// Public URL of the application, the image ids are built from it.
+    pub base_url: String,
+    pub tile_size: u32,
+    /// Largest width or height of a returned image.
+    pub max_dimension: u32,
+    /// JPEG quality from 1 to 100.
+    pub quality: u8,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ApplicationConfig {
     pub app: App,
     pub db: DbConnection,
     pub aws: AwsConfig,
+    pub auth: AuthConfig,
+    pub lockout: LockoutConfig,
+    pub oidc: OidcConfig,
+    pub mail: MailConfig,
+    pub jobs: JobsConfig,
+    pub scheduler: SchedulerConfig,
+    pub vk: VkConfig,
+    pub publishing: PublishingConfig,
+    pub webhooks: WebhooksConfig,
+    pub changes: ChangesConfig,
+    pub bucket_events: BucketEventsConfig,
+    pub lifecycle: LifecycleConfig,
+    pub trash: TrashConfig,
+    pub captcha: CaptchaConfig,
+    pub thumbnails: ThumbnailConfig,
+    pub privacy: PrivacyConfig,
+    pub transform: TransformConfig,
+    pub iiif: IiifConfig,
 }
 
 impl Default for ApplicationConfig {
@@ -54,6 +372,41 @@ impl Default for ApplicationConfig {
     }
 }
 
+/// Well-known values that must not sign tokens.
+const WEAK_SECRETS: [&str; 2] = ["", "secret"];
+
+impl ApplicationConfig {
+    /// Checks values the server can't run with, the error names the first invalid one.
+    pub fn validate(&self) -> Result<(), String> {
+        if WEAK_SECRETS.contains(&self.auth.jwt_secret.trim()) {
+            return Err("auth.jwt_secret has to be set to a random secret".to_owned());
+        }
+        if WEAK_SECRETS.contains(&self.auth.totp_encryption_key.trim()) {
+            return Err("auth.totp_encryption_key has to be set to a random secret".to_owned());
+        }
+        if self.oidc.enabled && WEAK_SECRETS.contains(&self.oidc.login_state_secret.trim()) {
+            return Err("oidc.login_state_secret has to be set to a random secret".to_owned());
+        }
+        if WEAK_SECRETS.contains(&self.transform.signing_key.trim()) {
+            return Err("transform.signing_key has to be set to a random secret".to_owned());
+        }
+        if self.iiif.tile_size == 0 {
+            return Err("iiif.tile_size has to be greater than 0".to_owned());
+        }
+        for rule in &self.lifecycle.rules {
+            rule.validate()?;
+            let destination = rule.destination.as_deref().unwrap_or_default();
+            if rule.action == LifecycleAction::Move && destination.starts_with(&self.trash.prefix) {
+                return Err(format!(
+                    "Lifecycle rule {} moves resources into the trash",
+                    rule.name
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -86,4 +439,162 @@ mod test {
         assert_eq!(config.aws.bucket, String::from("files"));
         assert_eq!(config.aws.region, String::from("eu-central-1"));
     }
+
+    #[test]
+    fn test_auth_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.auth.access_token_ttl < config.auth.refresh_token_ttl);
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = ApplicationConfig::default();
+        config.auth.jwt_secret = "secret".to_owned();
+        assert!(config.validate().is_err());
+        config.auth.jwt_secret = "".to_owned();
+        assert!(config.validate().is_err());
+        config.auth.jwt_secret = "9b0c1f3e6a".to_owned();
+        config.auth.totp_encryption_key = "secret".to_owned();
+        assert!(config.validate().is_err());
+        config.auth.totp_encryption_key = "70c3d9a1f8".to_owned();
+        config.transform.signing_key = "secret".to_owned();
+        assert!(config.validate().is_err());
+        config.transform.signing_key = "e5f1a0c27b".to_owned();
+        assert!(config.validate().is_ok());
+        config.oidc.enabled = true;
+        assert!(config.validate().is_err());
+        config.oidc.login_state_secret = "4d2a7e91c0".to_owned();
+        assert!(config.validate().is_ok());
+        config.lifecycle.rules = vec![LifecycleRule {
+            name: "everything".to_owned(),
+            prefix: None,
+            tag: None,
+            older_than: None,
+            action: LifecycleAction::Delete,
+            destination: None,
+        }];
+        assert!(config.validate().is_err());
+        config.lifecycle.rules = vec![LifecycleRule {
+            name: "exports".to_owned(),
+            prefix: Some("exports/".to_owned()),
+            tag: None,
+            older_than: None,
+            action: LifecycleAction::Move,
+            destination: Some("archive/".to_owned()),
+        }];
+        assert!(config.validate().is_ok());
+        config.lifecycle.rules[0].destination = Some("exports/archive/".to_owned());
+        assert!(config.validate().is_err());
+        config.lifecycle.rules[0].destination = Some("".to_owned());
+        assert!(config.validate().is_err());
+        config.lifecycle.rules[0].destination = Some(format!("{}exports/", config.trash.prefix));
+        assert!(config.validate().is_err());
+        config.lifecycle.rules.clear();
+        config.iiif.tile_size = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_lockout_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.lockout.max_failures < config.lockout.client_max_failures);
+        assert!(config.lockout.backoff_base <= config.lockout.backoff_max);
+    }
+
+    #[test]
+    fn test_oidc_config() {
+        let config = ApplicationConfig::default();
+        assert!(!config.oidc.enabled);
+        assert_eq!(config.oidc.default_role, String::from("USER"));
+    }
+
+    #[test]
+    fn test_thumbnail_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.thumbnails.enabled);
+        assert_eq!(config.thumbnails.sizes, vec![128, 512, 1024]);
+    }
+
+    #[test]
+    fn test_jobs_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.jobs.backoff_base <= config.jobs.backoff_max);
+        assert!(config.jobs.concurrency > 0);
+        assert_eq!(
+            config.jobs.kind_concurrency.get("generate_thumbnails"),
+            Some(&2)
+        );
+    }
+
+    #[test]
+    fn test_scheduler_config() {
+        let config = ApplicationConfig::default();
+        assert_eq!(config.scheduler.timezone, String::from("UTC"));
+        assert!(!config.vk.enabled);
+        assert!(config.vk.hour < 24);
+        assert!(config.vk.minute < 60);
+    }
+
+    #[test]
+    fn test_publishing_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.publishing.max_attempts > 0);
+        assert!(!config.publishing.webhook.enabled);
+        assert!(!config.publishing.telegram.enabled);
+        assert!(!config.publishing.s3.enabled);
+    }
+
+    #[test]
+    fn test_webhooks_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.webhooks.max_attempts > 0);
+        assert!(config.webhooks.timeout > 0);
+    }
+
+    #[test]
+    fn test_changes_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.changes.max_wait > 0);
+        assert!(config.changes.keep_alive > 0);
+        assert!(!config.bucket_events.enabled);
+    }
+
+    #[test]
+    fn test_lifecycle_config() {
+        let config = ApplicationConfig::default();
+        assert!(!config.lifecycle.enabled);
+        assert!(config.lifecycle.batch_size > 0);
+        assert!(config.lifecycle.rules.is_empty());
+    }
+
+    #[test]
+    fn test_trash_config() {
+        let config = ApplicationConfig::default();
+        assert!(config.trash.prefix.ends_with('/'));
+        assert!(config.trash.retention > 0);
+        assert!(config.trash.batch_size > 0);
+    }
+
+    #[test]
+    fn test_captcha_config() {
+        let config = ApplicationConfig::default();
+        assert!(!config.captcha.enabled);
+        assert!(config.captcha.ttl > 0);
+        assert!(config.captcha.length > 0);
+    }
+
+    #[test]
+    fn test_privacy_config() {
+        let config = ApplicationConfig::default();
+        assert!(!config.privacy.scrub_metadata);
+        assert!(config.privacy.jpeg_quality <= 100);
+    }
+
+    #[test]
+    fn test_mail_config() {
+        env::set_var("P_MAIL_TRANSPORT", String::from("smtp"));
+
+        let config = ApplicationConfig::default();
+        assert_eq!(config.mail.transport, String::from("smtp"));
+    }
 }
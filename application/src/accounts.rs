@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, AuthConfig, MailConfig};
+use async_trait::async_trait;
+use chrono::Duration;
+use domain::*;
+use log::info;
+use once_cell::sync::Lazy;
+use remote::mailer_from_config;
+use repository::{RefreshTokenRepository, UserRepository, UserTokenRepository};
+use sea_orm::DbConn;
+use tera::{Context, Tera};
+use util::PasswordEncoder;
+
+use crate::{generate_token, hash_token, AuthError, DefaultUserService, ServiceError};
+
+const EMAIL_VERIFICATION_TEMPLATE: &str = "email_verification.txt";
+const PASSWORD_RESET_TEMPLATE: &str = "password_reset.txt";
+
+static TEMPLATES: Lazy<Tera> = Lazy::new(|| {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(vec![
+        (
+            EMAIL_VERIFICATION_TEMPLATE,
+            include_str!("../templates/email_verification.txt"),
+        ),
+        (
+            PASSWORD_RESET_TEMPLATE,
+            include_str!("../templates/password_reset.txt"),
+        ),
+    ])
+    .expect("Failed to parse mail templates");
+    tera
+});
+
+/// Self-service flows of a user account: registration, email verification and password reset.
+#[async_trait]
+pub trait AccountService {
+    async fn register(&self, name: &str, email: &str, password: &str) -> Result<User>;
+    async fn verify_email(&self, token: &str) -> Result<User>;
+    async fn resend_verification(&self, email: &str) -> Result<()>;
+    async fn request_password_reset(&self, email: &str) -> Result<()>;
+    async fn reset_password(&self, token: &str, password: &str) -> Result<()>;
+}
+
+pub struct DefaultAccountService {
+    users: Box<dyn UserDirectory + Send + Sync>,
+    user_service: DefaultUserService,
+    user_tokens: Box<dyn UserTokens + Send + Sync>,
+    refresh_tokens: Box<dyn TokenRepository + Send + Sync>,
+    mailer: Box<dyn Mailer + Send + Sync>,
+    auth: AuthConfig,
+    mail: MailConfig,
+}
+
+impl DefaultAccountService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Result<Self> {
+        Ok(Self {
+            users: Box::new(UserRepository::new(db.clone())),
+            user_service: DefaultUserService::new(db.clone()),
+            user_tokens: Box::new(UserTokenRepository::new(db.clone())),
+            refresh_tokens: Box::new(RefreshTokenRepository::new(db)),
+            mailer: mailer_from_config(config.mail.clone())?,
+            auth: config.auth.clone(),
+            mail: config.mail.clone(),
+        })
+    }
+
+    async fn send_token(&self, user: &User, kind: UserTokenKind) -> Result<()> {
+        let user_id = user
+            .id
+            .ok_or_else(|| ServiceError::NotFound(format!("User {} is not saved", user.name)))?;
+        let (ttl, template, subject, path) = match kind {
+            UserTokenKind::EmailVerification => (
+                self.auth.verification_token_ttl,
+                EMAIL_VERIFICATION_TEMPLATE,
+                "Confirm your email address",
+                "verify-email",
+            ),
+            UserTokenKind::PasswordReset => (
+                self.auth.password_reset_token_ttl,
+                PASSWORD_RESET_TEMPLATE,
+                "Reset your password",
+                "reset-password",
+            ),
+        };
+        self.user_tokens
+            .invalidate_all(user_id, kind.clone())
+            .await?;
+        let (token, token_hash) = generate_token();
+        self.user_tokens
+            .create(UserToken::new(
+                user_id,
+                kind,
+                &token_hash,
+                Duration::seconds(ttl),
+            ))
+            .await?;
+
+        let mut context = Context::new();
+        context.insert("name", &user.name);
+        context.insert(
+            "link",
+            &format!("{}/{}?token={}", self.mail.base_url, path, token),
+        );
+        context.insert("expires_in_minutes", &(ttl / 60));
+        self.mailer
+            .send(Email {
+                to: user.email.clone(),
+                subject: subject.to_owned(),
+                body: TEMPLATES.render(template, &context)?,
+            })
+            .await
+    }
+
+    /// Validates and consumes a token, returns the user it was issued to.
+    async fn consume_token(&self, token: &str, kind: UserTokenKind) -> Result<User> {
+        let user_token = self
+            .user_tokens
+            .get_by_key(hash_token(token))
+            .await
+            .map_err(|_| AuthError::InvalidToken)?;
+        if !user_token.is_valid(&kind) {
+            return Err(AuthError::InvalidToken.into());
+        }
+        let token_id = user_token.id.ok_or(AuthError::InvalidToken)?;
+        if !self.user_tokens.consume(token_id).await? {
+            return Err(AuthError::InvalidToken.into());
+        }
+        self.users
+            .get_by_id(user_token.user_id)
+            .await
+            .map_err(|_| AuthError::InvalidToken.into())
+    }
+}
+
+#[async_trait]
+impl AccountService for DefaultAccountService {
+    async fn register(&self, name: &str, email: &str, password: &str) -> Result<User> {
+        info!("Register user {}", name);
+        let user = self
+            .user_service
+            .create_user(
+                User::default()
+                    .with_name(name)
+                    .with_email(email)
+                    .with_password(password)
+                    .with_role(Role::USER)
+                    .pending_verification(),
+            )
+            .await?;
+        self.send_token(&user, UserTokenKind::EmailVerification)
+            .await?;
+        Ok(user)
+    }
+
+    async fn verify_email(&self, token: &str) -> Result<User> {
+        let user = self
+            .consume_token(token, UserTokenKind::EmailVerification)
+            .await?;
+        if user.email_verified_at.is_some() {
+            return Ok(user);
+        }
+        let user_id = user.id.ok_or(AuthError::InvalidToken)?;
+        info!("Email of user {} is verified", user.name);
+        self.users.update(user_id, user.verified()).await
+    }
+
+    /// Silently ignores unknown and already verified emails to not disclose registered accounts.
+    async fn resend_verification(&self, email: &str) -> Result<()> {
+        match self.users.get_by_email(email.to_owned()).await {
+            Ok(user) if user.email_verified_at.is_none() => {
+                self.send_token(&user, UserTokenKind::EmailVerification)
+                    .await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Silently ignores unknown emails to not disclose registered accounts.
+    async fn request_password_reset(&self, email: &str) -> Result<()> {
+        match self.users.get_by_email(email.to_owned()).await {
+            Ok(user) => self.send_token(&user, UserTokenKind::PasswordReset).await,
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Sets the new password and revokes all sessions of the user.
+    async fn reset_password(&self, token: &str, password: &str) -> Result<()> {
+        if password.is_empty() {
+            return Err(ServiceError::BadRequest("Password is required".to_owned()).into());
+        }
+        let user = self
+            .consume_token(token, UserTokenKind::PasswordReset)
+            .await?;
+        let user_id = user.id.ok_or(AuthError::InvalidToken)?;
+        info!("Reset password of user {}", user.name);
+        let password = PasswordEncoder::encode(password);
+        self.users
+            .update(user_id, user.with_password(&password))
+            .await?;
+        self.user_tokens
+            .invalidate_all(user_id, UserTokenKind::PasswordReset)
+            .await?;
+        self.refresh_tokens.revoke_all_for_user(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render_templates() {
+        let mut context = Context::new();
+        context.insert("name", "user");
+        context.insert("link", "http://localhost/verify-email?token=token");
+        context.insert("expires_in_minutes", &60);
+
+        for template in [EMAIL_VERIFICATION_TEMPLATE, PASSWORD_RESET_TEMPLATE] {
+            let body = TEMPLATES.render(template, &context).unwrap();
+            assert!(body.contains("Hello user"));
+            assert!(body.contains("http://localhost/verify-email?token=token"));
+            assert!(body.contains("60 minutes"));
+        }
+    }
+}
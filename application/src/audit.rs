@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::*;
+use log::warn;
+use repository::AuditEventRepository;
+use sea_orm::DbConn;
+
+use crate::users::MAX_PAGE_SIZE;
+
+#[async_trait]
+pub trait AuditService {
+    async fn record(&self, event: AuditEvent) -> Result<AuditEvent>;
+    async fn list(&self, filter: AuditEventFilter) -> Result<Page<AuditEvent>>;
+}
+
+pub struct DefaultAuditService {
+    events: Box<dyn AuditLog + Send + Sync>,
+}
+
+impl DefaultAuditService {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self {
+            events: Box::new(AuditEventRepository::new(db)),
+        }
+    }
+}
+
+#[async_trait]
+impl AuditService for DefaultAuditService {
+    /// Stores the event, it is logged as well to reach log based alerting.
+    async fn record(&self, event: AuditEvent) -> Result<AuditEvent> {
+        warn!("Audit event {}: {}", event.kind, event.message);
+        self.events.create(event).await
+    }
+
+    async fn list(&self, filter: AuditEventFilter) -> Result<Page<AuditEvent>> {
+        self.events
+            .find(AuditEventFilter {
+                page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+                ..filter
+            })
+            .await
+    }
+}
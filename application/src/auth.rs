@@ -0,0 +1,418 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, AuthConfig};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use derive_more::Display;
+use domain::*;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use log::{info, warn};
+use rand::RngCore;
+use repository::{RefreshTokenRepository, UserRepository};
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use util::PasswordEncoder;
+use uuid::Uuid;
+
+use crate::{
+    DefaultLoginThrottleService, DefaultTwoFactorService, LoginThrottleService, TwoFactorService,
+};
+
+#[derive(Debug, Display)]
+pub enum AuthError {
+    #[display(fmt = "Invalid credentials")]
+    InvalidCredentials,
+    #[display(fmt = "Invalid or expired token")]
+    InvalidToken,
+    #[display(fmt = "Refresh token reuse detected, session is revoked")]
+    TokenReused,
+    #[display(fmt = "Account is disabled")]
+    AccountDisabled,
+    #[display(fmt = "Email is not verified")]
+    EmailNotVerified,
+    #[display(fmt = "Access denied")]
+    Forbidden,
+    #[display(fmt = "Invalid two-factor code")]
+    InvalidCode,
+    #[display(fmt = "Too many failed login attempts, try again later")]
+    TooManyAttempts,
+    #[display(fmt = "Invalid or expired CAPTCHA")]
+    InvalidCaptcha,
+}
+
+impl std::error::Error for AuthError {}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Claims {
+    pub sub: Uuid,
+    pub name: String,
+    pub role: Role,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub access_token: String,
+    pub refresh_token: String,
+    pub token_type: String,
+    pub expires_in: i64,
+}
+
+/// Claims of the short-lived token that proves the password step of a two-factor login.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ChallengeClaims {
+    pub sub: Uuid,
+    pub typ: String,
+    pub iat: i64,
+    pub exp: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub challenge: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Tokens(TokenPair),
+    /// The user has 2FA enabled, the challenge must be completed with a code.
+    Challenge(MfaChallenge),
+}
+
+const CHALLENGE_TYPE: &str = "mfa";
+
+#[async_trait]
+pub trait AuthService {
+    async fn login(&self, name: &str, password: &str, client_ip: &str) -> Result<LoginResponse>;
+    async fn login_two_factor(
+        &self,
+        challenge: &str,
+        code: &str,
+        client_ip: &str,
+    ) -> Result<TokenPair>;
+    /// Starts a session of a user authenticated by other means, e.g. single sign-on. Users
+    /// with 2FA enabled get a challenge instead of the tokens.
+    async fn create_session(&self, user: &User) -> Result<LoginResponse>;
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenPair>;
+    async fn logout(&self, refresh_token: &str) -> Result<()>;
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<()>;
+}
+
+pub struct DefaultAuthService {
+    users: Box<dyn Repository<Type = User> + Send + Sync>,
+    tokens: Box<dyn TokenRepository + Send + Sync>,
+    two_factor: Box<dyn TwoFactorService + Send + Sync>,
+    throttle: Box<dyn LoginThrottleService + Send + Sync>,
+    config: AuthConfig,
+}
+
+impl DefaultAuthService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            users: Box::new(UserRepository::new(db.clone())),
+            tokens: Box::new(RefreshTokenRepository::new(db.clone())),
+            two_factor: Box::new(DefaultTwoFactorService::new(config, db.clone())),
+            throttle: Box::new(DefaultLoginThrottleService::new(config, db)),
+            config: config.auth.clone(),
+        }
+    }
+
+    fn token_pair(&self, user: &User, refresh_token: String) -> Result<TokenPair> {
+        Ok(TokenPair {
+            access_token: encode_access_token(&self.config, user)?,
+            refresh_token,
+            token_type: "Bearer".to_owned(),
+            expires_in: self.config.access_token_ttl,
+        })
+    }
+
+    fn refresh_ttl(&self) -> Duration {
+        Duration::seconds(self.config.refresh_token_ttl)
+    }
+
+    /// Starts a new session of the user.
+    async fn issue_tokens(&self, user: &User) -> Result<TokenPair> {
+        let user_id = user.id.ok_or(AuthError::InvalidCredentials)?;
+        let (token, token_hash) = generate_token();
+        self.tokens
+            .create(RefreshToken::new(user_id, &token_hash, self.refresh_ttl()))
+            .await?;
+        self.token_pair(user, token)
+    }
+}
+
+#[async_trait]
+impl AuthService for DefaultAuthService {
+    async fn login(&self, name: &str, password: &str, client_ip: &str) -> Result<LoginResponse> {
+        self.throttle.check(name, client_ip).await?;
+        let user = match self.users.get_by_key(name.to_owned()).await {
+            Ok(user) if PasswordEncoder::verify(&user.password, password) => user,
+            user => {
+                let user_id = user.ok().and_then(|user| user.id);
+                self.throttle
+                    .record_failure(name, user_id, client_ip)
+                    .await?;
+                return Err(AuthError::InvalidCredentials.into());
+            }
+        };
+        if !user.enabled && user.verification_pending {
+            return Err(AuthError::EmailNotVerified.into());
+        }
+        if !user.enabled {
+            return Err(AuthError::AccountDisabled.into());
+        }
+        let user_id = user.id.ok_or(AuthError::InvalidCredentials)?;
+        let user = if PasswordEncoder::needs_rehash(&user.password) {
+            info!("Rehash the legacy password of user {}", user.name);
+            let password = PasswordEncoder::encode(password);
+            self.users
+                .update(user_id, user.with_password(&password))
+                .await?
+        } else {
+            user
+        };
+        if self.two_factor.is_enabled(user_id).await? {
+            return Ok(LoginResponse::Challenge(MfaChallenge {
+                mfa_required: true,
+                challenge: encode_challenge_token(&self.config, user_id)?,
+                expires_in: self.config.mfa_challenge_ttl,
+            }));
+        }
+        // With 2FA enabled failures are kept until the second step succeeds.
+        self.throttle.record_success(name).await?;
+        self.issue_tokens(&user).await.map(LoginResponse::Tokens)
+    }
+
+    /// Failed codes count towards the lockout of the user like failed passwords.
+    async fn login_two_factor(
+        &self,
+        challenge: &str,
+        code: &str,
+        client_ip: &str,
+    ) -> Result<TokenPair> {
+        let claims = decode_challenge_token(&self.config, challenge)?;
+        let user = self
+            .users
+            .get_by_id(claims.sub)
+            .await
+            .map_err(|_| AuthError::InvalidToken)?;
+        if !user.enabled {
+            return Err(AuthError::AccountDisabled.into());
+        }
+        self.throttle.check(&user.name, client_ip).await?;
+        if !self.two_factor.verify(claims.sub, code).await? {
+            warn!("Invalid two-factor code for user {}", user.name);
+            self.throttle
+                .record_failure(&user.name, user.id, client_ip)
+                .await?;
+            return Err(AuthError::InvalidCode.into());
+        }
+        self.throttle.record_success(&user.name).await?;
+        self.issue_tokens(&user).await
+    }
+
+    async fn create_session(&self, user: &User) -> Result<LoginResponse> {
+        if !user.enabled {
+            return Err(AuthError::AccountDisabled.into());
+        }
+        let user_id = user.id.ok_or(AuthError::InvalidCredentials)?;
+        if self.two_factor.is_enabled(user_id).await? {
+            return Ok(LoginResponse::Challenge(MfaChallenge {
+                mfa_required: true,
+                challenge: encode_challenge_token(&self.config, user_id)?,
+                expires_in: self.config.mfa_challenge_ttl,
+            }));
+        }
+        self.issue_tokens(user).await.map(LoginResponse::Tokens)
+    }
+
+    async fn refresh(&self, refresh_token: &str) -> Result<TokenPair> {
+        let current = self
+            .tokens
+            .get_by_key(hash_token(refresh_token))
+            .await
+            .map_err(|_| AuthError::InvalidToken)?;
+        let current_id = current.id.ok_or(AuthError::InvalidToken)?;
+        let (token, token_hash) = generate_token();
+        let next = current.rotate(&token_hash, self.refresh_ttl());
+
+        if !self.tokens.revoke(current_id, next.id).await? {
+            warn!(
+                "Refresh token reuse detected, revoking token family {}",
+                current.family_id
+            );
+            self.tokens.revoke_family(current.family_id).await?;
+            return Err(AuthError::TokenReused.into());
+        }
+        if current.is_expired() {
+            return Err(AuthError::InvalidToken.into());
+        }
+        let user = self
+            .users
+            .get_by_id(current.user_id)
+            .await
+            .map_err(|_| AuthError::InvalidToken)?;
+        if !user.enabled {
+            self.tokens.revoke_family(current.family_id).await?;
+            return Err(AuthError::AccountDisabled.into());
+        }
+
+        self.tokens.create(next).await?;
+        self.token_pair(&user, token)
+    }
+
+    async fn logout(&self, refresh_token: &str) -> Result<()> {
+        if let Ok(current) = self.tokens.get_by_key(hash_token(refresh_token)).await {
+            self.tokens.revoke_family(current.family_id).await?;
+        }
+        Ok(())
+    }
+
+    async fn revoke_sessions(&self, user_id: Uuid) -> Result<()> {
+        self.tokens.revoke_all_for_user(user_id).await
+    }
+}
+
+pub fn encode_access_token(config: &AuthConfig, user: &User) -> Result<String> {
+    let now = Utc::now();
+    let claims = Claims {
+        sub: user.id.ok_or(AuthError::InvalidCredentials)?,
+        name: user.name.clone(),
+        role: user.role.clone(),
+        iat: now.timestamp(),
+        exp: (now + Duration::seconds(config.access_token_ttl)).timestamp(),
+    };
+    Ok(encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )?)
+}
+
+pub fn decode_access_token(config: &AuthConfig, token: &str) -> Result<Claims> {
+    decode::<Claims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .map(|data| data.claims)
+    .map_err(|_| AuthError::InvalidToken.into())
+}
+
+pub fn encode_challenge_token(config: &AuthConfig, user_id: Uuid) -> Result<String> {
+    let now = Utc::now();
+    let claims = ChallengeClaims {
+        sub: user_id,
+        typ: CHALLENGE_TYPE.to_owned(),
+        iat: now.timestamp(),
+        exp: (now + Duration::seconds(config.mfa_challenge_ttl)).timestamp(),
+    };
+    Ok(encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(config.jwt_secret.as_bytes()),
+    )?)
+}
+
+/// Access tokens are rejected here because they have no `typ` claim and vice versa.
+pub fn decode_challenge_token(config: &AuthConfig, token: &str) -> Result<ChallengeClaims> {
+    decode::<ChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(config.jwt_secret.as_bytes()),
+        &Validation::default(),
+    )
+    .ok()
+    .map(|data| data.claims)
+    .filter(|claims| claims.typ == CHALLENGE_TYPE)
+    .ok_or_else(|| AuthError::InvalidToken.into())
+}
+
+/// Returns a random token and its hash, only the hash is stored.
+pub(crate) fn generate_token() -> (String, String) {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    let token = base64::encode_config(bytes, base64::URL_SAFE_NO_PAD);
+    let hash = hash_token(&token);
+    (token, hash)
+}
+
+pub(crate) fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            jwt_secret: "secret".to_owned(),
+            access_token_ttl: 60,
+            refresh_token_ttl: 120,
+            verification_token_ttl: 120,
+            password_reset_token_ttl: 120,
+            mfa_challenge_ttl: 60,
+            totp_issuer: "assets".to_owned(),
+            totp_encryption_key: "key".to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_access_token_roundtrip() {
+        let user = User {
+            id: Some(Uuid::new_v4()),
+            ..User::default().with_name("admin").with_role(Role::ADMIN)
+        };
+        let token = encode_access_token(&config(), &user).unwrap();
+        let claims = decode_access_token(&config(), &token).unwrap();
+
+        assert_eq!(claims.sub, user.id.unwrap());
+        assert_eq!(claims.role, Role::ADMIN);
+    }
+
+    #[test]
+    fn test_access_token_wrong_secret() {
+        let user = User {
+            id: Some(Uuid::new_v4()),
+            ..User::default()
+        };
+        let token = encode_access_token(&config(), &user).unwrap();
+        let other = AuthConfig {
+            jwt_secret: "other".to_owned(),
+            ..config()
+        };
+
+        assert!(decode_access_token(&other, &token).is_err());
+    }
+
+    #[test]
+    fn test_challenge_token_is_not_access_token() {
+        let user = User {
+            id: Some(Uuid::new_v4()),
+            ..User::default()
+        };
+        let challenge = encode_challenge_token(&config(), user.id.unwrap()).unwrap();
+        let access_token = encode_access_token(&config(), &user).unwrap();
+
+        assert_eq!(
+            decode_challenge_token(&config(), &challenge).unwrap().sub,
+            user.id.unwrap()
+        );
+        assert!(decode_access_token(&config(), &challenge).is_err());
+        assert!(decode_challenge_token(&config(), &access_token).is_err());
+    }
+
+    #[test]
+    fn test_refresh_token_hash() {
+        let (token, hash) = generate_token();
+
+        assert_eq!(hash, hash_token(&token));
+        assert_ne!(token, generate_token().0);
+    }
+}
@@ -0,0 +1,284 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, BucketEventsConfig};
+use async_trait::async_trait;
+use chrono::Utc;
+use domain::*;
+use log::info;
+use percent_encoding::percent_decode_str;
+use repository::{is_unique_violation, ResourceRepository};
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tasks::JobQueue;
+
+use crate::{hash_token, set_system_metadata, AuthError, GenerateThumbnails, ServiceError};
+
+/// Event notification of a bucket, as sent by MinIO and S3.
+#[derive(Debug, Deserialize)]
+pub struct BucketNotification {
+    #[serde(rename = "Records", default)]
+    pub records: Vec<BucketEventRecord>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketEventRecord {
+    /// E.g. `s3:ObjectCreated:Put` or `ObjectRemoved:Delete`.
+    pub event_name: String,
+    pub s3: BucketEventEntity,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BucketEventEntity {
+    pub bucket: BucketEventBucket,
+    pub object: BucketEventObject,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BucketEventBucket {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BucketEventObject {
+    /// URL encoded key.
+    pub key: String,
+    pub size: Option<i64>,
+    #[serde(rename = "eTag")]
+    pub etag: Option<String>,
+    pub content_type: Option<String>,
+}
+
+/// Outcome of the records of a notification.
+#[derive(Debug, Default, PartialEq, Eq, Serialize)]
+pub struct IngestReport {
+    pub created: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Records of other buckets or events, and objects already registered as they are.
+    pub ignored: usize,
+}
+
+#[async_trait]
+pub trait BucketEventService {
+    /// Checks the shared secret sent with the notification.
+    fn verify(&self, authorization: Option<&str>) -> Result<()>;
+    /// Registers created objects and removes the resources of removed ones.
+    async fn ingest(&self, notification: BucketNotification) -> Result<IngestReport>;
+}
+
+pub struct DefaultBucketEventService {
+    resources: Box<dyn Resources + Send + Sync>,
+    queue: JobQueue,
+    thumbnails: bool,
+    bucket: String,
+    hostname: String,
+    /// Objects of trashed resources are moved by the application.
+    trash_prefix: String,
+    config: BucketEventsConfig,
+}
+
+impl DefaultBucketEventService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            queue: JobQueue::new(config, db),
+            thumbnails: config.thumbnails.enabled,
+            bucket: config.aws.bucket.clone(),
+            hostname: config.aws.endpoint.clone(),
+            trash_prefix: config.trash.prefix.clone(),
+            config: config.bucket_events.clone(),
+        }
+    }
+
+    async fn created(
+        &self,
+        key: String,
+        object: &BucketEventObject,
+        report: &mut IngestReport,
+    ) -> Result<()> {
+        let etag = object.etag.as_deref().map(normalize_etag);
+        if let Ok(resource) = self.resources.get_by_key(key.clone()).await {
+            // Uploads through the application are notified as well.
+            if resource.etag == etag && (object.size.is_none() || resource.size == object.size) {
+                report.ignored += 1;
+                return Ok(());
+            }
+            let id = resource
+                .id
+                .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+            info!("Update resource {} from a bucket notification", key);
+            self.resources
+                .update_with_event(
+                    id,
+                    Resource {
+                        size: object.size,
+                        etag,
+                        ..resource
+                    },
+                )
+                .await?;
+            report.updated += 1;
+            return Ok(());
+        }
+        info!("Register object {} from a bucket notification", key);
+        let metadata = set_system_metadata(
+            None,
+            "ingest",
+            json!({ "source": "bucket_notification", "ingested_at": Utc::now() }),
+        );
+        let created = self
+            .resources
+            .create_with_event(Resource {
+                key: key.clone(),
+                url: Some(format!("{}/{}/{}", self.hostname, self.bucket, key)),
+                size: object.size,
+                etag,
+                metadata,
+                ..Resource::default()
+            })
+            .await;
+        match created {
+            // Registered meanwhile by an upload of the application.
+            Err(err) if is_unique_violation(&err) => {
+                info!("Object {} was registered meanwhile", key);
+                report.ignored += 1;
+                return Ok(());
+            }
+            result => result?,
+        };
+        let image = matches!(
+            object.content_type.as_deref(),
+            Some(content_type) if content_type.starts_with("image/")
+        );
+        if self.thumbnails && image {
+            self.queue.enqueue(&GenerateThumbnails { key }).await?;
+        }
+        report.created += 1;
+        Ok(())
+    }
+
+    async fn removed(&self, key: String) -> Result<bool> {
+        let resource = match self.resources.get_by_key(key.clone()).await {
+            Ok(resource) => resource,
+            Err(_) => return Ok(false),
+        };
+        let id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        info!("Remove resource {} from a bucket notification", key);
+        self.resources.delete_with_event(id).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl BucketEventService for DefaultBucketEventService {
+    fn verify(&self, authorization: Option<&str>) -> Result<()> {
+        if !self.config.enabled {
+            return Err(
+                ServiceError::NotFound("Bucket notifications are disabled".to_owned()).into(),
+            );
+        }
+        let token = authorization
+            .map(|value| value.strip_prefix("Bearer ").unwrap_or(value))
+            .unwrap_or_default();
+        // Digests are compared, so the time doesn't depend on the matching prefix.
+        if self.config.secret.is_empty() || hash_token(token) != hash_token(&self.config.secret) {
+            return Err(AuthError::InvalidToken.into());
+        }
+        Ok(())
+    }
+
+    async fn ingest(&self, notification: BucketNotification) -> Result<IngestReport> {
+        let mut report = IngestReport::default();
+        for record in notification.records {
+            if record.s3.bucket.name != self.bucket {
+                report.ignored += 1;
+                continue;
+            }
+            let key = decode_key(&record.s3.object.key);
+            // Thumbnails and other objects derived by the application aren't files of their own.
+            if key.starts_with(&self.trash_prefix) || is_derivative_key(&key) {
+                report.ignored += 1;
+                continue;
+            }
+            let event = record.event_name.trim_start_matches("s3:");
+            if event.starts_with("ObjectCreated") {
+                self.created(key, &record.s3.object, &mut report).await?;
+            } else if event.starts_with("ObjectRemoved") {
+                match self.removed(key).await? {
+                    true => report.deleted += 1,
+                    false => report.ignored += 1,
+                }
+            } else {
+                report.ignored += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+/// Keys of notifications are URL encoded, with `+` for spaces.
+fn decode_key(key: &str) -> String {
+    percent_decode_str(&key.replace('+', " "))
+        .decode_utf8_lossy()
+        .into_owned()
+}
+
+/// Some senders quote the entity tag.
+fn normalize_etag(etag: &str) -> String {
+    etag.trim_matches('"').to_owned()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_decode_key() {
+        assert_eq!(
+            decode_key("albums/my+cat%281%29.png"),
+            "albums/my cat(1).png"
+        );
+        assert_eq!(decode_key("a%2Bb.png"), "a+b.png");
+        assert_eq!(
+            normalize_etag("\"d41d8cd98f00b204e9800998ecf8427e\""),
+            "d41d8cd98f00b204e9800998ecf8427e"
+        );
+    }
+
+    #[test]
+    fn test_parse_notification() {
+        let notification: BucketNotification = serde_json::from_value(json!({
+            "EventName": "s3:ObjectCreated:Put",
+            "Key": "assets/cat.png",
+            "Records": [{
+                "eventVersion": "2.0",
+                "eventSource": "minio:s3",
+                "eventName": "s3:ObjectCreated:Put",
+                "s3": {
+                    "bucket": { "name": "assets" },
+                    "object": {
+                        "key": "cat.png",
+                        "size": 3,
+                        "eTag": "5289df737df57326fcdd22597afb1fac",
+                        "contentType": "image/png"
+                    }
+                }
+            }]
+        }))
+        .unwrap();
+        let record = &notification.records[0];
+        assert_eq!(record.event_name, "s3:ObjectCreated:Put");
+        assert_eq!(record.s3.bucket.name, "assets");
+        assert_eq!(record.s3.object.size, Some(3));
+        assert_eq!(
+            record.s3.object.etag.as_deref(),
+            Some("5289df737df57326fcdd22597afb1fac")
+        );
+    }
+}
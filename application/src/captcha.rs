@@ -0,0 +1,324 @@
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, CaptchaConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use domain::*;
+use image::{DynamicImage, Rgb, RgbImage};
+use log::info;
+use rand::Rng;
+use repository::CaptchaRepository;
+use sea_orm::DbConn;
+use serde::Serialize;
+use tasks::ScheduledTask;
+use uuid::Uuid;
+
+use crate::{
+    encode, hash_token, AuthError, DefaultLoginThrottleService, LoginThrottleService, OutputFormat,
+    ServiceError,
+};
+
+const CAPTCHA_WIDTH: u32 = 200;
+const CAPTCHA_HEIGHT: u32 = 70;
+const CAPTCHA_MARGIN: u32 = 10;
+/// Characters that are hard to tell apart when distorted are left out.
+const CAPTCHA_ALPHABET: &[u8] = b"ABCDEFGHJKLMNPRSTUVWXYZ23456789";
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+
+/// Challenge shown to the client, the image is a PNG data URL.
+#[derive(Debug, Serialize)]
+pub struct CaptchaChallenge {
+    pub id: Uuid,
+    pub image: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Distorted text images that keep bots from registering and uploading anonymously.
+#[async_trait]
+pub trait CaptchaService {
+    /// Requests of a client are throttled like its failed logins.
+    async fn create(&self, client_ip: &str) -> Result<CaptchaChallenge>;
+    /// A challenge can be answered once, a wrong answer requires a new challenge.
+    /// Always passes when CAPTCHAs are disabled.
+    async fn verify(&self, id: Option<Uuid>, answer: Option<&str>) -> Result<()>;
+}
+
+pub struct DefaultCaptchaService {
+    captchas: Box<dyn Captchas + Send + Sync>,
+    throttle: Box<dyn LoginThrottleService + Send + Sync>,
+    config: CaptchaConfig,
+}
+
+impl DefaultCaptchaService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            captchas: Box::new(CaptchaRepository::new(db.clone())),
+            throttle: Box::new(DefaultLoginThrottleService::new(config, db)),
+            config: config.captcha.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl CaptchaService for DefaultCaptchaService {
+    async fn create(&self, client_ip: &str) -> Result<CaptchaChallenge> {
+        if !self.config.enabled {
+            return Err(ServiceError::NotFound("CAPTCHA is disabled".to_owned()).into());
+        }
+        self.throttle.record_captcha(client_ip).await?;
+        let expired = self.captchas.delete_expired().await?;
+        if expired > 0 {
+            info!("Deleted {} expired captchas", expired);
+        }
+        let answer = generate_answer(self.config.length.max(1));
+        let image = encode(&render_captcha(&answer), OutputFormat::Png)?;
+        let captcha = self
+            .captchas
+            .create(Captcha::new(
+                &hash_token(&answer),
+                Utc::now() + Duration::seconds(self.config.ttl),
+            ))
+            .await?;
+        Ok(CaptchaChallenge {
+            id: captcha.id.ok_or(AuthError::InvalidCaptcha)?,
+            image: format!("data:image/png;base64,{}", base64::encode(image)),
+            expires_at: captcha.expires_at,
+        })
+    }
+
+    async fn verify(&self, id: Option<Uuid>, answer: Option<&str>) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let (id, answer) = match (id, answer) {
+            (Some(id), Some(answer)) => (id, answer),
+            _ => return Err(AuthError::InvalidCaptcha.into()),
+        };
+        match self.captchas.take(id).await? {
+            Some(captcha)
+                if !captcha.is_expired()
+                    && captcha.answer_hash == hash_token(&normalize_answer(answer)) =>
+            {
+                Ok(())
+            }
+            _ => Err(AuthError::InvalidCaptcha.into()),
+        }
+    }
+}
+
+fn generate_answer(length: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..length)
+        .map(|_| CAPTCHA_ALPHABET[rng.gen_range(0..CAPTCHA_ALPHABET.len())] as char)
+        .collect()
+}
+
+/// Answers are case-insensitive and may be surrounded by whitespace.
+fn normalize_answer(answer: &str) -> String {
+    answer.trim().to_uppercase()
+}
+
+/// Draws the sheared characters at jittered positions, warps the image with sine waves and
+/// covers it with lines and dots.
+pub fn render_captcha(text: &str) -> DynamicImage {
+    let mut rng = rand::thread_rng();
+    let background = Rgb([
+        rng.gen_range(225..=255),
+        rng.gen_range(225..=255),
+        rng.gen_range(225..=255),
+    ]);
+    let mut canvas = RgbImage::from_pixel(CAPTCHA_WIDTH, CAPTCHA_HEIGHT, background);
+
+    let length = text.chars().count().max(1) as u32;
+    let advance = (CAPTCHA_WIDTH - 2 * CAPTCHA_MARGIN) / length;
+    let scale = (advance / (GLYPH_WIDTH + 1)).clamp(1, 5);
+    for (index, character) in text.chars().enumerate() {
+        let rows = match glyph(character) {
+            Some(rows) => rows,
+            None => continue,
+        };
+        let color = Rgb([
+            rng.gen_range(0..110),
+            rng.gen_range(0..110),
+            rng.gen_range(0..110),
+        ]);
+        let shear: f32 = rng.gen_range(-0.25..0.25);
+        let left = (CAPTCHA_MARGIN + index as u32 * advance) as i32 + rng.gen_range(-2..=2);
+        let top = ((CAPTCHA_HEIGHT - GLYPH_HEIGHT * scale) / 2) as i32 + rng.gen_range(-8..=8);
+        for (row, bits) in rows.iter().enumerate() {
+            let offset = (shear * (row as f32 - GLYPH_HEIGHT as f32 / 2.0) * scale as f32) as i32;
+            for column in 0..GLYPH_WIDTH {
+                if (bits >> (GLYPH_WIDTH - 1 - column)) & 1 == 0 {
+                    continue;
+                }
+                let x = left + offset + (column * scale) as i32;
+                let y = top + row as i32 * scale as i32;
+                fill_square(&mut canvas, x, y, scale, color);
+            }
+        }
+    }
+
+    let (amplitude_x, amplitude_y): (f32, f32) = (rng.gen_range(1.5..3.0), rng.gen_range(2.0..4.0));
+    let (period_x, period_y): (f32, f32) = (rng.gen_range(50.0..80.0), rng.gen_range(80.0..140.0));
+    let (phase_x, phase_y): (f32, f32) =
+        (rng.gen_range(0.0..2.0 * PI), rng.gen_range(0.0..2.0 * PI));
+    let mut image = RgbImage::from_fn(CAPTCHA_WIDTH, CAPTCHA_HEIGHT, |x, y| {
+        let source_x = x as f32 + amplitude_x * (2.0 * PI * y as f32 / period_x + phase_x).sin();
+        let source_y = y as f32 + amplitude_y * (2.0 * PI * x as f32 / period_y + phase_y).sin();
+        if source_x < 0.0 || source_y < 0.0 {
+            return background;
+        }
+        canvas
+            .get_pixel_checked(source_x as u32, source_y as u32)
+            .copied()
+            .unwrap_or(background)
+    });
+
+    for _ in 0..4 {
+        let color = Rgb([
+            rng.gen_range(0..160),
+            rng.gen_range(0..160),
+            rng.gen_range(0..160),
+        ]);
+        let start = (0, rng.gen_range(0..CAPTCHA_HEIGHT as i32));
+        let end = (
+            CAPTCHA_WIDTH as i32 - 1,
+            rng.gen_range(0..CAPTCHA_HEIGHT as i32),
+        );
+        draw_line(&mut image, start, end, color);
+    }
+    for _ in 0..(CAPTCHA_WIDTH * CAPTCHA_HEIGHT / 40) {
+        let (x, y) = (
+            rng.gen_range(0..CAPTCHA_WIDTH),
+            rng.gen_range(0..CAPTCHA_HEIGHT),
+        );
+        let shade = rng.gen_range(0..200);
+        image.put_pixel(x, y, Rgb([shade, shade, shade]));
+    }
+    DynamicImage::ImageRgb8(image)
+}
+
+fn fill_square(image: &mut RgbImage, x: i32, y: i32, size: u32, color: Rgb<u8>) {
+    for dy in 0..size as i32 {
+        for dx in 0..size as i32 {
+            put_pixel(image, x + dx, y + dy, color);
+        }
+    }
+}
+
+/// Bresenham line, two pixels thick.
+fn draw_line(image: &mut RgbImage, start: (i32, i32), end: (i32, i32), color: Rgb<u8>) {
+    let (mut x, mut y) = start;
+    let (dx, dy) = ((end.0 - x).abs(), -(end.1 - y).abs());
+    let (step_x, step_y) = (
+        if x < end.0 { 1 } else { -1 },
+        if y < end.1 { 1 } else { -1 },
+    );
+    let mut error = dx + dy;
+    loop {
+        put_pixel(image, x, y, color);
+        put_pixel(image, x, y + 1, color);
+        if (x, y) == end {
+            break;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+fn put_pixel(image: &mut RgbImage, x: i32, y: i32, color: Rgb<u8>) {
+    if x >= 0 && y >= 0 && (x as u32) < image.width() && (y as u32) < image.height() {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+/// Rows of the 5x7 bitmap of a character of the alphabet, the leftmost pixel is the highest bit.
+fn glyph(character: char) -> Option<[u8; 7]> {
+    let rows = match character {
+        'A' => [0x0e, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'B' => [0x1e, 0x11, 0x11, 0x1e, 0x11, 0x11, 0x1e],
+        'C' => [0x0e, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0e],
+        'D' => [0x1e, 0x11, 0x11, 0x11, 0x11, 0x11, 0x1e],
+        'E' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x1f],
+        'F' => [0x1f, 0x10, 0x10, 0x1e, 0x10, 0x10, 0x10],
+        'G' => [0x0e, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0f],
+        'H' => [0x11, 0x11, 0x11, 0x1f, 0x11, 0x11, 0x11],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0c],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1f],
+        'M' => [0x11, 0x1b, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'P' => [0x1e, 0x11, 0x11, 0x1e, 0x10, 0x10, 0x10],
+        'R' => [0x1e, 0x11, 0x11, 0x1e, 0x14, 0x12, 0x11],
+        'S' => [0x0f, 0x10, 0x10, 0x0e, 0x01, 0x01, 0x1e],
+        'T' => [0x1f, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0e],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0a, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0a],
+        'X' => [0x11, 0x11, 0x0a, 0x04, 0x0a, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x0a, 0x04, 0x04, 0x04, 0x04],
+        'Z' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1f],
+        '2' => [0x0e, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1f],
+        '3' => [0x1f, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0e],
+        '4' => [0x02, 0x06, 0x0a, 0x12, 0x1f, 0x02, 0x02],
+        '5' => [0x1f, 0x10, 0x1e, 0x01, 0x01, 0x11, 0x0e],
+        '6' => [0x06, 0x08, 0x10, 0x1e, 0x11, 0x11, 0x0e],
+        '7' => [0x1f, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0e, 0x11, 0x11, 0x0e, 0x11, 0x11, 0x0e],
+        '9' => [0x0e, 0x11, 0x11, 0x0f, 0x01, 0x02, 0x0c],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+/// Deletes the challenges that were never answered.
+#[async_trait]
+impl ScheduledTask for DefaultCaptchaService {
+    async fn run(&self) -> Result<()> {
+        let expired = self.captchas.delete_expired().await?;
+        info!("Deleted {} expired captchas", expired);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_answer() {
+        let answer = generate_answer(6);
+
+        assert_eq!(answer.len(), 6);
+        assert!(answer.bytes().all(|c| CAPTCHA_ALPHABET.contains(&c)));
+        assert!(CAPTCHA_ALPHABET.iter().all(|c| glyph(*c as char).is_some()));
+        assert_eq!(
+            normalize_answer(&format!(" {} ", answer.to_lowercase())),
+            answer
+        );
+    }
+
+    #[test]
+    fn test_render_captcha() {
+        let image = render_captcha("AB3XY9").to_rgb8();
+
+        assert_eq!(image.dimensions(), (CAPTCHA_WIDTH, CAPTCHA_HEIGHT));
+        let dark = image
+            .pixels()
+            .filter(|pixel| pixel.0.iter().all(|c| *c < 110))
+            .count();
+        assert!(dark > 500);
+        let png = encode(&DynamicImage::ImageRgb8(image), OutputFormat::Png).unwrap();
+        assert!(image::load_from_memory(&png).is_ok());
+    }
+}
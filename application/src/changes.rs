@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use domain::*;
+use futures::stream::{self, Stream};
+use log::{info, warn};
+use repository::{ResourceEventListener, ResourceEventRepository};
+use sea_orm::DbConn;
+use tokio::sync::broadcast;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::task::JoinHandle;
+
+use crate::users::MAX_PAGE_SIZE;
+
+/// Pause before the listener connects again after losing its connection.
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Ordered, resumable feed of resource events. Readers resume from the sequence number of the
+/// last event they saw, new events are pushed by the notifications of the database, so all
+/// replicas see the events of each other.
+#[derive(Clone)]
+pub struct ChangeFeed {
+    events: Arc<dyn ResourceEvents + Send + Sync>,
+    notifications: broadcast::Sender<i64>,
+}
+
+impl ChangeFeed {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        let (notifications, _) = broadcast::channel(1024);
+        Self {
+            events: Arc::new(ResourceEventRepository::new(db)),
+            notifications,
+        }
+    }
+
+    /// Spawns the listener of the notifications, it connects again after losing the
+    /// connection.
+    pub fn listen(&self, url: &str) -> JoinHandle<()> {
+        let (url, notifications) = (url.to_owned(), self.notifications.clone());
+        tokio::spawn(async move {
+            loop {
+                match ResourceEventListener::connect(&url).await {
+                    Ok(mut listener) => {
+                        // Notifications may have been missed while disconnected, wake up the
+                        // readers to look for new events.
+                        let _ = notifications.send(0);
+                        loop {
+                            match listener.recv().await {
+                                Ok(id) => {
+                                    let _ = notifications.send(id);
+                                }
+                                Err(err) => {
+                                    warn!("Lost the listener of resource events: {}", err);
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    Err(err) => warn!("Failed to listen to resource events: {}", err),
+                }
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        })
+    }
+
+    /// Events after `since`, oldest first. Without any, waits up to `wait` for new ones.
+    pub async fn changes(
+        &self,
+        since: i64,
+        limit: u64,
+        wait: Duration,
+    ) -> Result<Vec<ResourceEvent>> {
+        let limit = limit.clamp(1, MAX_PAGE_SIZE);
+        // Subscribe before reading, an event recorded in between isn't missed.
+        let mut receiver = self.notifications.subscribe();
+        let events = self.events.find_since(since, limit).await?;
+        if !events.is_empty() || wait.is_zero() {
+            return Ok(events);
+        }
+        if tokio::time::timeout(wait, receiver.recv()).await.is_err() {
+            return Ok(vec![]);
+        }
+        self.events.find_since(since, limit).await
+    }
+
+    /// Endless stream of the events after `since`.
+    pub fn stream(&self, since: i64) -> impl Stream<Item = Result<ResourceEvent>> {
+        info!("Stream resource events after {}", since);
+        let receiver = self.notifications.subscribe();
+        stream::unfold(
+            (self.events.clone(), receiver, since, VecDeque::new()),
+            |(events, mut receiver, mut since, mut pending)| async move {
+                loop {
+                    if let Some(event) = pending.pop_front() {
+                        return Some((Ok(event), (events, receiver, since, pending)));
+                    }
+                    match events.find_since(since, MAX_PAGE_SIZE).await {
+                        Ok(found) if !found.is_empty() => {
+                            since = found.last().and_then(|event| event.id).unwrap_or(since);
+                            pending.extend(found);
+                            continue;
+                        }
+                        Ok(_) => {}
+                        Err(err) => return Some((Err(err), (events, receiver, since, pending))),
+                    }
+                    match receiver.recv().await {
+                        Ok(_) | Err(RecvError::Lagged(_)) => {}
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    #[derive(Default)]
+    struct MemoryEvents(Mutex<Vec<ResourceEvent>>);
+
+    impl MemoryEvents {
+        fn record(&self, key: &str) {
+            let mut events = self.0.lock().unwrap();
+            let mut event = ResourceEvent::new(
+                ResourceEventType::Created,
+                Uuid::new_v4(),
+                &Resource::default().with_key(key),
+            );
+            event.id = Some(events.len() as i64 + 1);
+            events.push(event);
+        }
+    }
+
+    #[async_trait]
+    impl ResourceEvents for MemoryEvents {
+        async fn find_since(&self, since: i64, limit: u64) -> Result<Vec<ResourceEvent>> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|event| event.id > Some(since))
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+    }
+
+    fn feed(events: Arc<MemoryEvents>) -> ChangeFeed {
+        let (notifications, _) = broadcast::channel(16);
+        ChangeFeed {
+            events,
+            notifications,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_changes() {
+        let events = Arc::new(MemoryEvents::default());
+        let feed = feed(events.clone());
+        events.record("a.png");
+        events.record("b.png");
+
+        let changes = feed.changes(0, 10, Duration::ZERO).await.unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[1].key, "b.png");
+        assert_eq!(feed.changes(1, 10, Duration::ZERO).await.unwrap().len(), 1);
+        assert!(feed
+            .changes(2, 10, Duration::from_millis(10))
+            .await
+            .unwrap()
+            .is_empty());
+
+        let waiting = tokio::spawn({
+            let feed = feed.clone();
+            async move { feed.changes(2, 10, Duration::from_secs(5)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        events.record("c.png");
+        feed.notifications.send(3).unwrap();
+        let changes = waiting.await.unwrap().unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].key, "c.png");
+    }
+
+    #[tokio::test]
+    async fn test_stream() {
+        let events = Arc::new(MemoryEvents::default());
+        let feed = feed(events.clone());
+        events.record("a.png");
+        events.record("b.png");
+
+        let mut stream = Box::pin(feed.stream(1));
+        assert_eq!(stream.next().await.unwrap().unwrap().key, "b.png");
+
+        let next = tokio::spawn(async move { stream.next().await.unwrap().unwrap() });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        events.record("c.png");
+        feed.notifications.send(3).unwrap();
+        let event = next.await.unwrap();
+        assert_eq!(event.id, Some(3));
+        assert_eq!(event.key, "c.png");
+    }
+}
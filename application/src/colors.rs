@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use image::{imageops::FilterType, DynamicImage};
+
+/// Distance within which a dominant color matches a searched color.
+pub const DEFAULT_COLOR_DISTANCE: u32 = 60;
+/// Upper bound of the palette length.
+pub const PALETTE_SIZE: usize = 5;
+/// RGB distance under which a color counts as a shade of a color already in the palette.
+const MIN_PALETTE_DISTANCE: i32 = 48;
+const BLURHASH_COMPONENTS: (u32, u32) = (4, 3);
+const SAMPLE_DIMENSION: u32 = 64;
+
+/// Loading placeholder and dominant colors of an image.
+#[derive(Debug)]
+pub struct ImageColors {
+    pub blurhash: String,
+    /// `0xRRGGBB` colors, most frequent first.
+    pub palette: Vec<i32>,
+}
+
+/// Both are computed on a small sample of the image, the details don't matter.
+pub fn image_colors(image: &DynamicImage) -> Result<ImageColors> {
+    let sample = image
+        .resize(SAMPLE_DIMENSION, SAMPLE_DIMENSION, FilterType::Triangle)
+        .to_rgba8();
+    let (x, y) = BLURHASH_COMPONENTS;
+    let blurhash = blurhash::encode(x, y, sample.width(), sample.height(), sample.as_raw())?;
+    Ok(ImageColors {
+        blurhash,
+        palette: palette(sample.as_raw()),
+    })
+}
+
+/// Pixels are grouped into buckets of 16 levels per channel, the palette holds the average
+/// colors of the most populated buckets. Transparent pixels are ignored.
+fn palette(rgba: &[u8]) -> Vec<i32> {
+    let mut buckets: HashMap<u16, (u32, [u32; 3])> = HashMap::new();
+    for pixel in rgba.chunks_exact(4).filter(|pixel| pixel[3] >= 128) {
+        let bucket =
+            (pixel[0] as u16 >> 4) << 8 | (pixel[1] as u16 >> 4) << 4 | pixel[2] as u16 >> 4;
+        let (count, sum) = buckets.entry(bucket).or_insert((0, [0; 3]));
+        *count += 1;
+        for channel in 0..3 {
+            sum[channel] += pixel[channel] as u32;
+        }
+    }
+    let mut buckets: Vec<(u32, [u32; 3])> = buckets.into_values().collect();
+    buckets.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+
+    let mut palette: Vec<i32> = vec![];
+    for (count, sum) in buckets {
+        let color = sum.map(|channel| (channel / count) as i32);
+        let color = color[0] << 16 | color[1] << 8 | color[2];
+        if palette
+            .iter()
+            .all(|other| color_distance(*other, color) >= MIN_PALETTE_DISTANCE)
+        {
+            palette.push(color);
+        }
+        if palette.len() == PALETTE_SIZE {
+            break;
+        }
+    }
+    palette
+}
+
+/// Euclidean distance of two `0xRRGGBB` colors.
+pub fn color_distance(a: i32, b: i32) -> i32 {
+    let squared: i32 = [16, 8, 0]
+        .iter()
+        .map(|shift| ((a >> shift) & 255) - ((b >> shift) & 255))
+        .map(|difference| difference * difference)
+        .sum();
+    (squared as f64).sqrt() as i32
+}
+
+/// Parses `#rrggbb`, `rrggbb` or `#rgb`.
+pub fn parse_color(color: &str) -> Option<i32> {
+    let hex = color.trim().trim_start_matches('#');
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect(),
+        6 => hex.to_owned(),
+        _ => return None,
+    };
+    i32::from_str_radix(&hex, 16).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn test_image_colors() {
+        // Mostly red, some blue and a transparent stripe.
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(200, 100, |x, _| match x {
+            0..=119 => Rgba([220, 20, 30, 255]),
+            120..=159 => Rgba([10, 40, 200, 255]),
+            _ => Rgba([0, 255, 0, 0]),
+        }));
+        let colors = image_colors(&image).unwrap();
+
+        assert_eq!(colors.blurhash.len(), 4 + 2 * 4 * 3);
+        assert!(colors.palette.len() >= 2);
+        assert!(color_distance(colors.palette[0], 0xdc141e) < 8);
+        assert!(color_distance(colors.palette[1], 0x0a28c8) < 8);
+        assert!(colors
+            .palette
+            .iter()
+            .all(|color| color_distance(*color, 0x00ff00) > 100));
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#FF8800"), Some(0xff8800));
+        assert_eq!(parse_color("0a0b0c"), Some(0x0a0b0c));
+        assert_eq!(parse_color("#f80"), Some(0xff8800));
+        assert_eq!(parse_color("orange"), None);
+        assert_eq!(color_distance(0x000000, 0x000304), 5);
+    }
+}
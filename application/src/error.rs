@@ -0,0 +1,14 @@
+use derive_more::Display;
+
+/// Errors of the application services that callers are expected to handle.
+#[derive(Debug, Display)]
+pub enum ServiceError {
+    #[display(fmt = "{}", _0)]
+    NotFound(String),
+    #[display(fmt = "{}", _0)]
+    Conflict(String),
+    #[display(fmt = "{}", _0)]
+    BadRequest(String),
+}
+
+impl std::error::Error for ServiceError {}
@@ -1,23 +1,68 @@
 use std::sync::Arc;
 
 use anyhow::Result;
-use app_config::ApplicationConfig;
+use app_config::{ApplicationConfig, PrivacyConfig};
 use async_trait::async_trait;
 use bytes::Bytes;
+use chrono::Utc;
 use domain::*;
+use log::{info, warn};
 use remote::DefaultStorage;
-use repository::ResourceRepository;
+use repository::{is_unique_violation, ResourceRepository};
 use sea_orm::DbConn;
+use serde_json::{Map, Value};
+use tasks::JobQueue;
 
+use crate::users::MAX_PAGE_SIZE;
+use crate::{
+    check_owner, dhash, extract_metadata, image_colors, is_image, merge_metadata, record_scrubbing,
+    record_unscrubbed, scrub_image, Claims, DefaultTrashService, GenerateThumbnails, ServiceError,
+    TrashService, RESERVED_METADATA_KEY,
+};
+
+/// Options of a single upload.
+#[derive(Debug, Default)]
+pub struct UploadOptions {
+    /// Overrides the metadata scrubbing policy of the bucket.
+    pub scrub_metadata: Option<bool>,
+}
+
+/// Changes of the description of a file, missing fields are kept.
+#[derive(Debug, Default)]
+pub struct FileUpdate {
+    pub tags: Option<Value>,
+    /// Replaces the metadata of the client, the metadata of the application is kept.
+    pub metadata: Option<Value>,
+}
+
+/// Uploads, updates and deletes are recorded as resource events for the webhooks.
 #[async_trait]
 pub trait FileService {
-    async fn upload(self, object: Box<FileObject>) -> Result<String>;
+    /// Only the owner or an admin can replace a file, a taken key can't be uploaded to
+    /// anonymously.
+    async fn upload(
+        self,
+        claims: Option<&Claims>,
+        object: Box<FileObject>,
+        options: UploadOptions,
+    ) -> Result<String>;
     async fn download(self, key: String) -> Result<FileObject>;
+    /// Files of the user, admins see the files of all users.
+    async fn list(self, claims: &Claims, filter: ResourceFilter) -> Result<Page<FileObject>>;
+    /// Only the owner or an admin can update a file.
+    async fn update(self, claims: &Claims, key: String, update: FileUpdate) -> Result<FileObject>;
+    /// Moves the file with its object to the trash, only the owner or an admin can delete a
+    /// file.
+    async fn delete(self, claims: &Claims, key: String) -> Result<()>;
 }
 
 pub struct DefaultFileService {
-    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    resources: Box<dyn Resources + Send + Sync>,
     storage: Box<dyn Storage + Send + Sync>,
+    queue: JobQueue,
+    trash: DefaultTrashService,
+    thumbnails: bool,
+    privacy: PrivacyConfig,
     bucket: String,
     hostname: String,
 }
@@ -25,8 +70,12 @@ pub struct DefaultFileService {
 impl DefaultFileService {
     pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
         Self {
-            resources: Box::new(ResourceRepository::new(db)),
+            resources: Box::new(ResourceRepository::new(db.clone())),
             storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            queue: JobQueue::new(config, db.clone()),
+            trash: DefaultTrashService::new(config, db),
+            thumbnails: config.thumbnails.enabled,
+            privacy: config.privacy.clone(),
             bucket: config.aws.bucket.clone(),
             hostname: config.aws.endpoint.clone(),
         }
@@ -35,30 +84,205 @@ impl DefaultFileService {
 
 #[async_trait]
 impl FileService for DefaultFileService {
-    async fn upload(self, object: Box<FileObject>) -> Result<String> {
-        let empty = Bytes::new();
-        let data = object.data.as_ref().unwrap_or(&empty);
+    async fn upload(
+        self,
+        claims: Option<&Claims>,
+        object: Box<FileObject>,
+        options: UploadOptions,
+    ) -> Result<String> {
+        if matches!(object.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+            return Err(ServiceError::BadRequest("The expiry time has passed".to_owned()).into());
+        }
+        if is_derivative_key(&object.key) {
+            return Err(ServiceError::BadRequest(format!(
+                "Keys can't contain {}, it separates derived objects",
+                DERIVATIVE_SEPARATOR
+            ))
+            .into());
+        }
+        // Checked before the object is written, the upload would replace it.
+        let replaced = match self.resources.get_by_key(object.key.clone()).await {
+            Ok(existing) => {
+                match claims {
+                    Some(claims) => check_owner(claims, &existing)?,
+                    None => {
+                        return Err(ServiceError::Conflict(format!(
+                            "File {} already exists",
+                            object.key
+                        ))
+                        .into())
+                    }
+                }
+                Some(existing)
+            }
+            Err(_) => None,
+        };
+        let data = object.data.clone().unwrap_or_default();
         let resource = from_file_object(&object);
+        let scrub = options
+            .scrub_metadata
+            .unwrap_or(self.privacy.scrub_metadata);
+        let jpeg_quality = self.privacy.jpeg_quality;
+        // Parsing and re-encoding is CPU bound, keep it off the async workers.
+        let (data, resource) = tokio::task::spawn_blocking(move || -> Result<_> {
+            let mut extracted = extract_metadata(&data);
+            let scrubbed = if scrub {
+                scrub_image(&data, jpeg_quality)?
+            } else {
+                None
+            };
+            let data = match scrubbed {
+                Some(scrubbed) => {
+                    record_scrubbing(&mut extracted, &scrubbed);
+                    Bytes::from(scrubbed.data)
+                }
+                None => {
+                    if scrub && record_unscrubbed(&mut extracted, &data) {
+                        warn!("Metadata of {} can't be scrubbed, it is kept", resource.key);
+                    }
+                    data
+                }
+            };
+            let mut resource = Resource {
+                metadata: merge_metadata(resource.metadata, extracted),
+                ..resource
+            };
+            if let Ok(image) = image::load_from_memory(&data) {
+                let colors = image_colors(&image)?;
+                resource = resource
+                    .with_perceptual_hash(Some(dhash(&image)))
+                    .with_blurhash(&colors.blurhash)
+                    .with_palette(&colors.palette);
+            }
+            Ok((data, resource))
+        })
+        .await??;
+        let resource = resource.with_object(data.len() as i64, &etag(&data));
         let key = resource.key.clone();
         let url = format!("{}/{}/{}", self.hostname, self.bucket, key);
         self.storage
-            .upload_object(self.bucket.as_str(), data, key.as_str())
-            .await?;
-        self.resources
-            .create(resource.with_url(url.clone()))
+            .upload_object(self.bucket.as_str(), &data, key.as_str())
             .await?;
+        let resource = resource.with_url(url.clone());
+        match replaced {
+            Some(existing) => self.replace(existing, resource).await?,
+            None => {
+                if let Err(err) = self.resources.create_with_event(resource.clone()).await {
+                    if !is_unique_violation(&err) {
+                        return Err(err);
+                    }
+                    // The notification of the bucket may have registered the object first.
+                    match self.resources.get_by_key(key.clone()).await {
+                        Ok(existing) if existing.etag == resource.etag => {
+                            self.replace(existing, resource).await?
+                        }
+                        _ => return Err(err),
+                    }
+                }
+            }
+        }
+        if self.thumbnails && is_image(&data) {
+            self.queue.enqueue(&GenerateThumbnails { key }).await?;
+        }
         Ok(url)
     }
 
     async fn download(self, key: String) -> Result<FileObject> {
         let resource = self.resources.get_by_key(key.to_owned()).await?;
-        Ok(FileObject {
-            url: resource.url,
-            key: resource.key,
-            tags: resource.tags,
-            user_id: resource.user_id,
-            metadata: resource.metadata,
-            data: None,
-        })
+        // Until the lifecycle task deletes it.
+        if matches!(resource.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+            return Err(ServiceError::NotFound(format!("File {} doesn't exist", key)).into());
+        }
+        Ok(to_file_object(resource))
+    }
+
+    async fn list(self, claims: &Claims, filter: ResourceFilter) -> Result<Page<FileObject>> {
+        let user_id = match claims.role {
+            Role::ADMIN => None,
+            _ => Some(claims.sub),
+        };
+        let filter = ResourceFilter {
+            user_id,
+            page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+            ..filter
+        };
+        Ok(self.resources.find(filter).await?.map(to_file_object))
     }
+
+    async fn update(self, claims: &Claims, key: String, update: FileUpdate) -> Result<FileObject> {
+        let resource = self.find(&key).await?;
+        check_owner(claims, &resource)?;
+        let id = resource
+            .id
+            .ok_or_else(|| ServiceError::NotFound(format!("File {} doesn't exist", key)))?;
+        let metadata = match update.metadata {
+            Some(metadata) => merge_metadata(Some(metadata), system_metadata(&resource)),
+            None => resource.metadata.clone(),
+        };
+        info!("User {} updates file {}", claims.name, key);
+        let updated = self
+            .resources
+            .update_with_event(
+                id,
+                Resource {
+                    tags: update.tags.or_else(|| resource.tags.clone()),
+                    metadata,
+                    ..resource
+                },
+            )
+            .await?;
+        Ok(to_file_object(updated))
+    }
+
+    async fn delete(self, claims: &Claims, key: String) -> Result<()> {
+        let resource = self.find(&key).await?;
+        check_owner(claims, &resource)?;
+        info!("User {} deletes file {}", claims.name, key);
+        self.trash.trash(resource, Some(claims.sub)).await
+    }
+}
+
+impl DefaultFileService {
+    /// Describes the new object by the registered resource, an owner of it stays its owner.
+    async fn replace(&self, existing: Resource, resource: Resource) -> Result<()> {
+        let id = existing
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        self.resources
+            .update_with_event(
+                id,
+                Resource {
+                    id: existing.id,
+                    user_id: existing.user_id.or(resource.user_id),
+                    created_at: existing.created_at,
+                    ..resource
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn find(&self, key: &str) -> Result<Resource> {
+        Ok(self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("File {} doesn't exist", key)))?)
+    }
+}
+
+/// Entity tag S3 gives an object uploaded in one part.
+pub fn etag(data: &[u8]) -> String {
+    format!("{:x}", md5::compute(data))
+}
+
+/// Values the application keeps under the reserved metadata key.
+fn system_metadata(resource: &Resource) -> Map<String, Value> {
+    resource
+        .metadata
+        .as_ref()
+        .and_then(|metadata| metadata.get(RESERVED_METADATA_KEY))
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default()
 }
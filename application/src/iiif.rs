@@ -0,0 +1,746 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, IiifConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::*;
+use image::{imageops::FilterType, DynamicImage, GenericImageView, GrayImage, Luma};
+use log::info;
+use remote::DefaultStorage;
+use repository::ResourceRepository;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{encode, OutputFormat, ServiceError};
+
+const IIIF_CONTEXT: &str = "http://iiif.io/api/image/3/context.json";
+const BITONAL_THRESHOLD: u8 = 128;
+
+/// Rectangular portion of the full image.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    Full,
+    Square,
+    Pixels(u32, u32, u32, u32),
+    Percent(f64, f64, f64, f64),
+}
+
+impl Region {
+    pub fn parse(region: &str) -> Result<Self, ServiceError> {
+        match region {
+            "full" => Ok(Region::Full),
+            "square" => Ok(Region::Square),
+            _ => match region.strip_prefix("pct:") {
+                Some(region) => {
+                    let [x, y, w, h] = parse_numbers::<f64>(region)?;
+                    Ok(Region::Percent(x, y, w, h))
+                }
+                None => {
+                    let [x, y, w, h] = parse_numbers::<u32>(region)?;
+                    Ok(Region::Pixels(x, y, w, h))
+                }
+            },
+        }
+    }
+
+    /// `x, y, width, height` of the region clipped to the image.
+    pub fn resolve(&self, width: u32, height: u32) -> Result<(u32, u32, u32, u32), ServiceError> {
+        let (x, y, w, h) = match *self {
+            Region::Full => return Ok((0, 0, width, height)),
+            Region::Square => {
+                let side = width.min(height);
+                return Ok(((width - side) / 2, (height - side) / 2, side, side));
+            }
+            Region::Pixels(x, y, w, h) => (x, y, w, h),
+            Region::Percent(x, y, w, h) => (
+                percent_of(x, width),
+                percent_of(y, height),
+                percent_of(w, width),
+                percent_of(h, height),
+            ),
+        };
+        if x >= width || y >= height || w == 0 || h == 0 {
+            return Err(ServiceError::BadRequest(
+                "Region is outside of the image".to_owned(),
+            ));
+        }
+        Ok((x, y, w.min(width - x), h.min(height - y)))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SizeKind {
+    Max,
+    Width(u32),
+    Height(u32),
+    Percent(f64),
+    Exact(u32, u32),
+    /// Largest size that fits into the box, keeping the aspect ratio.
+    Confined(u32, u32),
+}
+
+/// Dimensions of the returned image, scaling above the region size requires `^`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Size {
+    pub upscale: bool,
+    pub kind: SizeKind,
+}
+
+impl Size {
+    pub fn parse(size: &str) -> Result<Self, ServiceError> {
+        let (upscale, size) = match size.strip_prefix('^') {
+            Some(size) => (true, size),
+            None => (false, size),
+        };
+        let invalid = || ServiceError::BadRequest(format!("Invalid size {}", size));
+        let kind = if size == "max" {
+            SizeKind::Max
+        } else if let Some(percent) = size.strip_prefix("pct:") {
+            SizeKind::Percent(percent.parse().map_err(|_| invalid())?)
+        } else {
+            let (confined, size) = match size.strip_prefix('!') {
+                Some(size) => (true, size),
+                None => (false, size),
+            };
+            let (w, h) = size.split_once(',').ok_or_else(invalid)?;
+            let w: Option<u32> = match w {
+                "" => None,
+                w => Some(w.parse().map_err(|_| invalid())?),
+            };
+            let h: Option<u32> = match h {
+                "" => None,
+                h => Some(h.parse().map_err(|_| invalid())?),
+            };
+            match (confined, w, h) {
+                (true, Some(w), Some(h)) => SizeKind::Confined(w, h),
+                (false, Some(w), Some(h)) => SizeKind::Exact(w, h),
+                (false, Some(w), None) => SizeKind::Width(w),
+                (false, None, Some(h)) => SizeKind::Height(h),
+                _ => return Err(invalid()),
+            }
+        };
+        Ok(Self { upscale, kind })
+    }
+
+    /// Width and height of the image scaled from the region.
+    pub fn resolve(
+        &self,
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    ) -> Result<(u32, u32), ServiceError> {
+        let (w, h) = (width as f64, height as f64);
+        let (target_w, target_h) = match self.kind {
+            SizeKind::Max => {
+                let scale = (max_dimension as f64 / w.max(h)).min(1.0);
+                (w * scale, h * scale)
+            }
+            SizeKind::Width(target) => (target as f64, h * target as f64 / w),
+            SizeKind::Height(target) => (w * target as f64 / h, target as f64),
+            SizeKind::Percent(percent) => (w * percent / 100.0, h * percent / 100.0),
+            SizeKind::Exact(target_w, target_h) => (target_w as f64, target_h as f64),
+            SizeKind::Confined(target_w, target_h) => {
+                let scale = (target_w as f64 / w).min(target_h as f64 / h);
+                (w * scale, h * scale)
+            }
+        };
+        let (target_w, target_h) = (target_w.round() as u32, target_h.round() as u32);
+        if target_w == 0 || target_h == 0 {
+            return Err(ServiceError::BadRequest("Size is empty".to_owned()));
+        }
+        if !self.upscale && (target_w > width || target_h > height) {
+            return Err(ServiceError::BadRequest(
+                "Size is larger than the region, use ^ to upscale".to_owned(),
+            ));
+        }
+        if target_w > max_dimension || target_h > max_dimension {
+            return Err(ServiceError::BadRequest(format!(
+                "Size is larger than {}",
+                max_dimension
+            )));
+        }
+        Ok((target_w, target_h))
+    }
+}
+
+/// Mirroring followed by a clockwise rotation by a multiple of 90 degrees.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    pub mirror: bool,
+    pub degrees: u32,
+}
+
+impl Rotation {
+    pub fn parse(rotation: &str) -> Result<Self, ServiceError> {
+        let (mirror, rotation) = match rotation.strip_prefix('!') {
+            Some(rotation) => (true, rotation),
+            None => (false, rotation),
+        };
+        let degrees: f64 = rotation
+            .parse()
+            .map_err(|_| ServiceError::BadRequest(format!("Invalid rotation {}", rotation)))?;
+        if !(0.0..=360.0).contains(&degrees) || degrees % 90.0 != 0.0 {
+            return Err(ServiceError::BadRequest(
+                "Only rotations by multiples of 90 degrees are supported".to_owned(),
+            ));
+        }
+        Ok(Self {
+            mirror,
+            degrees: degrees as u32 % 360,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quality {
+    Default,
+    Color,
+    Gray,
+    Bitonal,
+}
+
+/// Parsed `{region}/{size}/{rotation}/{quality}.{format}` of an image request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImageRequest {
+    pub region: Region,
+    pub size: Size,
+    pub rotation: Rotation,
+    pub quality: Quality,
+    pub format: OutputFormat,
+    /// Path of the request as it was received.
+    pub path: String,
+}
+
+impl ImageRequest {
+    pub fn parse(
+        region: &str,
+        size: &str,
+        rotation: &str,
+        file: &str,
+        jpeg_quality: u8,
+    ) -> Result<Self, ServiceError> {
+        let (quality, format) = file
+            .rsplit_once('.')
+            .ok_or_else(|| ServiceError::BadRequest(format!("Invalid file name {}", file)))?;
+        let quality = match quality {
+            "default" => Quality::Default,
+            "color" => Quality::Color,
+            "gray" => Quality::Gray,
+            "bitonal" => Quality::Bitonal,
+            quality => {
+                return Err(ServiceError::BadRequest(format!(
+                    "Unknown quality {}",
+                    quality
+                )))
+            }
+        };
+        Ok(Self {
+            region: Region::parse(region)?,
+            size: Size::parse(size)?,
+            rotation: Rotation::parse(rotation)?,
+            quality,
+            format: OutputFormat::parse(format, jpeg_quality)?,
+            path: format!("{}/{}/{}/{}", region, size, rotation, file),
+        })
+    }
+
+    /// Canonical form of the request for an image of the given dimensions, equivalent
+    /// requests resolve to the same one.
+    fn canonical(
+        &self,
+        width: u32,
+        height: u32,
+        max_dimension: u32,
+    ) -> Result<CanonicalRequest, ServiceError> {
+        let region = self.region.resolve(width, height)?;
+        let (_, _, w, h) = region;
+        let size = self.size.resolve(w, h, max_dimension)?;
+        let max = Size {
+            upscale: false,
+            kind: SizeKind::Max,
+        }
+        .resolve(w, h, max_dimension)?;
+        Ok(CanonicalRequest {
+            region,
+            size,
+            full: region == (0, 0, width, height),
+            max: size == max,
+            rotation: self.rotation,
+            quality: match self.quality {
+                Quality::Color => Quality::Default,
+                quality => quality,
+            },
+            format: self.format,
+        })
+    }
+
+    /// Region, size, rotation and quality in the order of the specification.
+    pub fn apply(
+        &self,
+        image: &DynamicImage,
+        max_dimension: u32,
+    ) -> Result<DynamicImage, ServiceError> {
+        let (width, height) = image.dimensions();
+        let (x, y, w, h) = self.region.resolve(width, height)?;
+        let mut result = image.crop_imm(x, y, w, h);
+        let (target_w, target_h) = self.size.resolve(w, h, max_dimension)?;
+        if (target_w, target_h) != (w, h) {
+            result = result.resize_exact(target_w, target_h, FilterType::Lanczos3);
+        }
+        if self.rotation.mirror {
+            result = result.fliph();
+        }
+        result = match self.rotation.degrees {
+            90 => result.rotate90(),
+            180 => result.rotate180(),
+            270 => result.rotate270(),
+            _ => result,
+        };
+        Ok(match self.quality {
+            Quality::Default | Quality::Color => result,
+            Quality::Gray => DynamicImage::ImageLuma8(result.to_luma8()),
+            Quality::Bitonal => {
+                let gray = result.to_luma8();
+                DynamicImage::ImageLuma8(GrayImage::from_fn(gray.width(), gray.height(), |x, y| {
+                    if gray.get_pixel(x, y)[0] >= BITONAL_THRESHOLD {
+                        Luma([255])
+                    } else {
+                        Luma([0])
+                    }
+                }))
+            }
+        })
+    }
+}
+
+/// Image request with the region and size resolved to pixels.
+#[derive(Debug, PartialEq)]
+struct CanonicalRequest {
+    region: (u32, u32, u32, u32),
+    size: (u32, u32),
+    full: bool,
+    max: bool,
+    rotation: Rotation,
+    quality: Quality,
+    format: OutputFormat,
+}
+
+impl CanonicalRequest {
+    /// `{region}/{size}/{rotation}/{quality}.{format}` in the canonical syntax.
+    fn path(&self) -> String {
+        let region = match self.region {
+            _ if self.full => "full".to_owned(),
+            (x, y, w, h) => format!("{},{},{},{}", x, y, w, h),
+        };
+        let (_, _, region_w, region_h) = self.region;
+        let (w, h) = self.size;
+        let upscale = if w > region_w || h > region_h {
+            "^"
+        } else {
+            ""
+        };
+        let size = match self.size {
+            _ if self.max => format!("{}max", upscale),
+            (w, h) => format!("{}{},{}", upscale, w, h),
+        };
+        let mirror = if self.rotation.mirror { "!" } else { "" };
+        let quality = match self.quality {
+            Quality::Default | Quality::Color => "default",
+            Quality::Gray => "gray",
+            Quality::Bitonal => "bitonal",
+        };
+        let format = match self.format {
+            OutputFormat::Jpeg(_) => "jpg",
+            OutputFormat::Png => "png",
+        };
+        format!(
+            "{}/{}/{}{}/{}.{}",
+            region, size, mirror, self.rotation.degrees, quality, format
+        )
+    }
+
+    /// Only the full image and the tiles advertised in `info.json` are cached, which
+    /// bounds the number of images stored for a resource.
+    fn is_cacheable(&self, dimensions: &Dimensions, tile_size: u32) -> bool {
+        if self.rotation.degrees != 0 || self.rotation.mirror || self.quality != Quality::Default {
+            return false;
+        }
+        if self.full && self.max {
+            return true;
+        }
+        let (x, y, w, h) = self.region;
+        scale_factors(dimensions, tile_size)
+            .into_iter()
+            .any(|factor| match tile_size.checked_mul(factor) {
+                Some(side) => {
+                    x % side == 0
+                        && y % side == 0
+                        && w == side.min(dimensions.width - x)
+                        && h == side.min(dimensions.height - y)
+                        && self.size == (w.div_ceil(factor), h.div_ceil(factor))
+                }
+                None => false,
+            })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Dimensions {
+    width: u32,
+    height: u32,
+}
+
+/// IIIF Image API 3.0 at compliance level 2, generated images are cached in the bucket.
+#[async_trait]
+pub trait IiifService {
+    /// Image information document `info.json`.
+    async fn info(&self, key: &str) -> Result<Value>;
+    async fn image(&self, key: &str, request: &ImageRequest) -> Result<ObjectStream>;
+}
+
+pub struct DefaultIiifService {
+    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    bucket: String,
+    config: IiifConfig,
+}
+
+impl DefaultIiifService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db)),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            bucket: config.aws.bucket.clone(),
+            config: config.iiif.clone(),
+        }
+    }
+
+    async fn resource(&self, key: &str) -> Result<Resource> {
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        Ok(resource)
+    }
+
+    async fn original(&self, resource: &Resource) -> Result<Bytes> {
+        self.storage
+            .download_object(self.bucket.as_str(), resource.key.as_str())
+            .await
+    }
+
+    /// Dimensions of the original, cached to avoid decoding it for every viewer.
+    async fn dimensions(&self, resource: &Resource) -> Result<Dimensions> {
+        let cache_key = derivative_key(
+            &resource.key,
+            &format!("iiif/{}/dimensions.json", version(resource)),
+        );
+        if let Ok(cached) = self
+            .storage
+            .download_object(self.bucket.as_str(), cache_key.as_str())
+            .await
+        {
+            if let Ok(dimensions) = serde_json::from_slice(&cached) {
+                return Ok(dimensions);
+            }
+        }
+        let original = self.original(resource).await?;
+        let (width, height) = tokio::task::spawn_blocking(move || {
+            image::io::Reader::new(std::io::Cursor::new(original))
+                .with_guessed_format()?
+                .into_dimensions()
+                .map_err(anyhow::Error::from)
+        })
+        .await??;
+        let dimensions = Dimensions { width, height };
+        self.storage
+            .upload_object(
+                self.bucket.as_str(),
+                &serde_json::to_vec(&dimensions)?,
+                cache_key.as_str(),
+            )
+            .await?;
+        Ok(dimensions)
+    }
+}
+
+#[async_trait]
+impl IiifService for DefaultIiifService {
+    async fn info(&self, key: &str) -> Result<Value> {
+        let resource = self.resource(key).await?;
+        let dimensions = self.dimensions(&resource).await?;
+        Ok(image_info(&self.config, key, &dimensions))
+    }
+
+    async fn image(&self, key: &str, request: &ImageRequest) -> Result<ObjectStream> {
+        let resource = self.resource(key).await?;
+        let dimensions = self.dimensions(&resource).await?;
+        let canonical = request.canonical(
+            dimensions.width,
+            dimensions.height,
+            self.config.max_dimension,
+        )?;
+        let cache_key = derivative_key(
+            &resource.key,
+            &format!("iiif/{}/{}", version(&resource), canonical.path()),
+        );
+        let cacheable = canonical.is_cacheable(&dimensions, self.config.tile_size);
+        let content_type = Some(request.format.content_type().to_owned());
+        if cacheable {
+            if let Ok(object) = self
+                .storage
+                .stream_object(self.bucket.as_str(), cache_key.as_str())
+                .await
+            {
+                return Ok(ObjectStream {
+                    content_type,
+                    ..object
+                });
+            }
+        }
+
+        let original = self.original(&resource).await?;
+        info!("Render IIIF image {}", cache_key);
+        let request = request.clone();
+        let max_dimension = self.config.max_dimension;
+        // Decoding and resizing is CPU bound, keep it off the async workers.
+        let data = tokio::task::spawn_blocking(move || -> Result<Vec<u8>> {
+            let image = image::load_from_memory(&original)?;
+            encode(&request.apply(&image, max_dimension)?, request.format)
+        })
+        .await??;
+        if cacheable {
+            self.storage
+                .upload_object(self.bucket.as_str(), &data, cache_key.as_str())
+                .await?;
+        }
+        let data = Bytes::from(data);
+        Ok(ObjectStream {
+            content_type,
+            content_length: Some(data.len() as i64),
+            body: Box::pin(futures::stream::once(async move { Ok(data) })),
+        })
+    }
+}
+
+/// Cached images are keyed by the etag of the original, so a replaced original isn't served
+/// from the cache of the previous one.
+fn version(resource: &Resource) -> &str {
+    resource.etag.as_deref().unwrap_or_default()
+}
+
+fn image_info(config: &IiifConfig, key: &str, dimensions: &Dimensions) -> Value {
+    json!({
+        "@context": IIIF_CONTEXT,
+        "id": format!("{}/iiif/{}", config.base_url.trim_end_matches('/'), encode_identifier(key)),
+        "type": "ImageService3",
+        "protocol": "http://iiif.io/api/image",
+        "profile": "level2",
+        "width": dimensions.width,
+        "height": dimensions.height,
+        "maxWidth": config.max_dimension,
+        "maxHeight": config.max_dimension,
+        "tiles": [{
+            "width": config.tile_size,
+            "scaleFactors": scale_factors(dimensions, config.tile_size),
+        }],
+        "extraQualities": ["color", "gray", "bitonal"],
+        "extraFeatures": [
+            "mirroring",
+            "regionByPct",
+            "regionByPx",
+            "regionSquare",
+            "rotationBy90s",
+            "sizeByConfinedWh",
+            "sizeByH",
+            "sizeByPct",
+            "sizeByW",
+            "sizeByWh",
+            "sizeUpscaling",
+        ],
+    })
+}
+
+/// Powers of two until a single tile covers the whole image.
+fn scale_factors(dimensions: &Dimensions, tile_size: u32) -> Vec<u32> {
+    let longest = dimensions.width.max(dimensions.height);
+    let mut factors = vec![1];
+    let mut factor: u32 = 1;
+    while tile_size > 0 && tile_size.saturating_mul(factor) < longest {
+        match factor.checked_mul(2) {
+            Some(next) => {
+                factor = next;
+                factors.push(factor);
+            }
+            None => break,
+        }
+    }
+    factors
+}
+
+/// Keys may contain slashes, they have to be escaped in the identifier.
+pub fn encode_identifier(key: &str) -> String {
+    key.replace('%', "%25").replace('/', "%2F")
+}
+
+fn parse_numbers<T: std::str::FromStr>(value: &str) -> Result<[T; 4], ServiceError> {
+    let invalid = || ServiceError::BadRequest(format!("Invalid region {}", value));
+    let numbers = value
+        .split(',')
+        .map(|number| number.parse::<T>().map_err(|_| invalid()))
+        .collect::<Result<Vec<T>, ServiceError>>()?;
+    numbers.try_into().map_err(|_| invalid())
+}
+
+fn percent_of(percent: f64, value: u32) -> u32 {
+    (percent.max(0.0) * value as f64 / 100.0).round() as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn request(region: &str, size: &str, rotation: &str, file: &str) -> ImageRequest {
+        ImageRequest::parse(region, size, rotation, file, 80).unwrap()
+    }
+
+    #[test]
+    fn test_parse_image_request() {
+        let request = request("pct:10,20,50,50", "^!300,200", "!90", "gray.png");
+        assert_eq!(request.region, Region::Percent(10.0, 20.0, 50.0, 50.0));
+        assert_eq!(
+            request.size,
+            Size {
+                upscale: true,
+                kind: SizeKind::Confined(300, 200)
+            }
+        );
+        assert_eq!(
+            request.rotation,
+            Rotation {
+                mirror: true,
+                degrees: 90
+            }
+        );
+        assert_eq!(request.quality, Quality::Gray);
+        assert_eq!(request.format, OutputFormat::Png);
+        assert_eq!(request.path, "pct:10,20,50,50/^!300,200/!90/gray.png");
+
+        assert!(ImageRequest::parse("1,2,3", "max", "0", "default.jpg", 80).is_err());
+        assert!(ImageRequest::parse("full", "!300,", "0", "default.jpg", 80).is_err());
+        assert!(ImageRequest::parse("full", "max", "45", "default.jpg", 80).is_err());
+        assert!(ImageRequest::parse("full", "max", "0", "sepia.jpg", 80).is_err());
+        assert!(ImageRequest::parse("full", "max", "0", "default.webp", 80).is_err());
+    }
+
+    #[test]
+    fn test_resolve_region_and_size() {
+        assert_eq!(
+            Region::Square.resolve(400, 200).unwrap(),
+            (100, 0, 200, 200)
+        );
+        assert_eq!(
+            Region::Pixels(300, 100, 200, 200)
+                .resolve(400, 200)
+                .unwrap(),
+            (300, 100, 100, 100)
+        );
+        assert!(Region::Pixels(400, 0, 10, 10).resolve(400, 200).is_err());
+
+        let size = |size: &str| Size::parse(size).unwrap().resolve(400, 200, 1000);
+        assert_eq!(size("max").unwrap(), (400, 200));
+        assert_eq!(size("200,").unwrap(), (200, 100));
+        assert_eq!(size(",50").unwrap(), (100, 50));
+        assert_eq!(size("pct:25").unwrap(), (100, 50));
+        assert_eq!(size("!100,100").unwrap(), (100, 50));
+        assert!(size("800,").is_err());
+        assert_eq!(size("^800,").unwrap(), (800, 400));
+        assert!(size("^2000,").is_err());
+        assert_eq!(
+            Size::parse("max")
+                .unwrap()
+                .resolve(4000, 2000, 1000)
+                .unwrap(),
+            (1000, 500)
+        );
+    }
+
+    #[test]
+    fn test_apply_image_request() {
+        let image =
+            DynamicImage::ImageRgb8(ImageBuffer::from_pixel(400, 200, Rgb([200u8, 10, 10])));
+
+        let result = request("0,0,200,100", "100,", "90", "bitonal.png")
+            .apply(&image, 1000)
+            .unwrap();
+        assert_eq!(result.dimensions(), (50, 100));
+        assert_eq!(result.to_luma8().get_pixel(0, 0)[0], 0);
+
+        let result = request("full", "max", "0", "gray.jpg")
+            .apply(&image, 1000)
+            .unwrap();
+        assert_eq!(result.dimensions(), (400, 200));
+        assert!(matches!(result, DynamicImage::ImageLuma8(_)));
+    }
+
+    #[test]
+    fn test_image_info() {
+        let config = IiifConfig {
+            base_url: "http://localhost:3000/".to_owned(),
+            tile_size: 512,
+            max_dimension: 4096,
+            quality: 80,
+        };
+        let info = image_info(
+            &config,
+            "scans/page-1.tif",
+            &Dimensions {
+                width: 3000,
+                height: 2000,
+            },
+        );
+        assert_eq!(info["id"], "http://localhost:3000/iiif/scans%2Fpage-1.tif");
+        assert_eq!(info["width"], 3000);
+        assert_eq!(info["tiles"][0]["scaleFactors"], json!([1, 2, 4, 8]));
+
+        let dimensions = Dimensions {
+            width: 10,
+            height: u32::MAX,
+        };
+        assert_eq!(scale_factors(&dimensions, 0), vec![1]);
+        assert_eq!(scale_factors(&dimensions, 1).len(), 32);
+    }
+
+    #[test]
+    fn test_canonical_request() {
+        let canonical = |request: ImageRequest| request.canonical(3000, 2000, 4096).unwrap();
+        let full = canonical(request("full", "max", "0", "default.jpg"));
+        assert_eq!(full.path(), "full/max/0/default.jpg");
+        assert_eq!(
+            canonical(request("0,0,3000,2000", "3000,", "0", "color.jpg")),
+            full
+        );
+        assert_eq!(
+            canonical(request("pct:0,0,50,50", "!750,1000", "!90", "gray.png")).path(),
+            "0,0,1500,1000/750,500/!90/gray.png"
+        );
+
+        let dimensions = Dimensions {
+            width: 3000,
+            height: 2000,
+        };
+        let cacheable = |region: &str, size: &str, rotation: &str| {
+            canonical(request(region, size, rotation, "default.jpg")).is_cacheable(&dimensions, 512)
+        };
+        assert!(cacheable("full", "max", "0"));
+        assert!(cacheable("512,1024,512,512", "512,", "0"));
+        assert!(cacheable("2048,0,952,2000", "238,", "0"));
+        assert!(!cacheable("512,1024,512,512", "512,", "90"));
+        assert!(!cacheable("1,0,512,512", "512,", "0"));
+        assert!(!cacheable("0,0,512,512", "100,", "0"));
+    }
+}
@@ -0,0 +1,82 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use image::{DynamicImage, ImageOutputFormat};
+
+use crate::ServiceError;
+
+/// Encoding of generated images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    Jpeg(u8),
+    Png,
+}
+
+impl OutputFormat {
+    /// Format by name, the quality only applies to JPEG and is clamped to 1..=100.
+    pub fn parse(name: &str, quality: u8) -> Result<Self, ServiceError> {
+        match name.to_lowercase().as_str() {
+            "jpeg" | "jpg" => Ok(OutputFormat::Jpeg(quality.clamp(1, 100))),
+            "png" => Ok(OutputFormat::Png),
+            name => Err(ServiceError::BadRequest(format!(
+                "Unsupported image format {}",
+                name
+            ))),
+        }
+    }
+
+    /// PNG for keys with a `.png` extension, JPEG otherwise.
+    pub fn of_key(key: &str, quality: u8) -> Self {
+        match key.rsplit('.').next() {
+            Some(extension) if extension.eq_ignore_ascii_case("png") => OutputFormat::Png,
+            _ => OutputFormat::Jpeg(quality.clamp(1, 100)),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg(_) => "image/jpeg",
+            OutputFormat::Png => "image/png",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Jpeg(_) => "jpg",
+            OutputFormat::Png => "png",
+        }
+    }
+}
+
+/// Whether the data is an image the `image` crate can decode.
+pub fn is_image(data: &[u8]) -> bool {
+    image::guess_format(data).is_ok()
+}
+
+pub fn encode(image: &DynamicImage, format: OutputFormat) -> Result<Vec<u8>> {
+    let mut data = Cursor::new(Vec::new());
+    match format {
+        // JPEG has no alpha channel.
+        OutputFormat::Jpeg(quality) => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut data, ImageOutputFormat::Jpeg(quality))?,
+        OutputFormat::Png => image.write_to(&mut data, ImageOutputFormat::Png)?,
+    }
+    Ok(data.into_inner())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_output_format() {
+        assert_eq!(
+            OutputFormat::parse("JPEG", 0).unwrap(),
+            OutputFormat::Jpeg(1)
+        );
+        assert_eq!(OutputFormat::parse("png", 80).unwrap(), OutputFormat::Png);
+        assert!(OutputFormat::parse("webp", 80).is_err());
+        assert_eq!(OutputFormat::of_key("cats/cat.PNG", 80), OutputFormat::Png);
+        assert_eq!(OutputFormat::of_key("cats/cat", 80), OutputFormat::Jpeg(80));
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::ApplicationConfig;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use domain::*;
+use log::info;
+use repository::JobRepository;
+use sea_orm::DbConn;
+use tasks::{Schedule, Scheduler, Worker};
+use uuid::Uuid;
+
+use crate::users::MAX_PAGE_SIZE;
+use crate::{
+    DefaultCaptchaService, DefaultLifecycleService, DefaultPublicationService,
+    DefaultSimilarityService, DefaultThumbnailService, DefaultTrashService, DefaultVkService,
+    DefaultWebhookService, ServiceError,
+};
+
+/// Minutes of the window of the throughput and latency statistics.
+const STATS_WINDOW: i64 = 60;
+
+/// Worker with the handlers of all background jobs of the application.
+pub fn job_worker(config: &ApplicationConfig, db: Arc<DbConn>) -> Worker {
+    Worker::new(config, db.clone())
+        .register(DefaultThumbnailService::new(config, db.clone()))
+        .register(DefaultPublicationService::new(config, db.clone()))
+        .register(DefaultWebhookService::new(config, db.clone()))
+        .register(DefaultSimilarityService::new(config, db))
+}
+
+/// Scheduler with all periodic tasks of the application.
+pub fn scheduler(config: &ApplicationConfig, db: Arc<DbConn>) -> Result<Scheduler> {
+    let mut scheduler = Scheduler::new(config, db.clone())?
+        .schedule(
+            "delete_expired_captchas",
+            Schedule::cron("0 * * * *")?,
+            DefaultCaptchaService::new(config, db.clone()),
+        )
+        .schedule(
+            "purge_trash",
+            Schedule::cron("30 * * * *")?,
+            DefaultTrashService::new(config, db.clone()),
+        );
+    if config.lifecycle.enabled {
+        scheduler = scheduler.schedule(
+            "apply_lifecycle_rules",
+            Schedule::cron("0 * * * *")?,
+            DefaultLifecycleService::new(config, db.clone()),
+        );
+    }
+    if config.vk.enabled {
+        scheduler = scheduler.schedule(
+            "publish_vk",
+            Schedule::daily(config.vk.hour, config.vk.minute)?,
+            DefaultVkService::new(config, db),
+        );
+    }
+    Ok(scheduler)
+}
+
+/// Administration of the job queue.
+#[async_trait]
+pub trait JobService {
+    async fn list(&self, filter: JobFilter) -> Result<Page<Job>>;
+    async fn get(&self, id: Uuid) -> Result<Job>;
+    /// Runs a dead or cancelled job again.
+    async fn retry(&self, id: Uuid) -> Result<Job>;
+    /// Cancels a pending or dead job.
+    async fn cancel(&self, id: Uuid) -> Result<Job>;
+    async fn pause(&self, kind: &str) -> Result<()>;
+    async fn resume(&self, kind: &str) -> Result<()>;
+    /// Counts by status per kind, with the throughput and latencies of the last hour.
+    async fn stats(&self) -> Result<Vec<JobQueueStats>>;
+}
+
+pub struct DefaultJobService {
+    jobs: Box<dyn Jobs + Send + Sync>,
+}
+
+impl DefaultJobService {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self {
+            jobs: Box::new(JobRepository::new(db)),
+        }
+    }
+}
+
+#[async_trait]
+impl JobService for DefaultJobService {
+    async fn list(&self, filter: JobFilter) -> Result<Page<Job>> {
+        self.jobs
+            .find(JobFilter {
+                page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+                ..filter
+            })
+            .await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Job> {
+        Ok(self
+            .jobs
+            .get_by_id(id)
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Job {} doesn't exist", id)))?)
+    }
+
+    async fn retry(&self, id: Uuid) -> Result<Job> {
+        let job = self.get(id).await?;
+        if !self.jobs.requeue(id).await? {
+            return Err(ServiceError::Conflict(format!(
+                "Job {} is {} and can't be retried",
+                id, job.status
+            ))
+            .into());
+        }
+        info!("Retry {} job {}", job.kind, id);
+        self.get(id).await
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Job> {
+        let job = self.get(id).await?;
+        if !self.jobs.cancel(id).await? {
+            return Err(ServiceError::Conflict(format!(
+                "Job {} is {} and can't be cancelled",
+                id, job.status
+            ))
+            .into());
+        }
+        info!("Cancelled {} job {}", job.kind, id);
+        self.get(id).await
+    }
+
+    async fn pause(&self, kind: &str) -> Result<()> {
+        info!("Pause {} jobs", kind);
+        self.jobs.set_paused(kind, true).await
+    }
+
+    async fn resume(&self, kind: &str) -> Result<()> {
+        info!("Resume {} jobs", kind);
+        self.jobs.set_paused(kind, false).await
+    }
+
+    async fn stats(&self) -> Result<Vec<JobQueueStats>> {
+        self.jobs
+            .stats(Utc::now() - Duration::minutes(STATS_WINDOW))
+            .await
+    }
+}
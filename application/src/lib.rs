@@ -1,2 +1,54 @@
+mod accounts;
+mod audit;
+mod auth;
+mod bucket_events;
+mod captcha;
+mod changes;
+mod colors;
+mod error;
 mod files;
+mod iiif;
+mod images;
+mod jobs;
+mod lifecycle;
+mod login_throttle;
+mod metadata;
+mod oidc;
+mod privacy;
+mod publishers;
+mod shares;
+mod similarity;
+mod thumbnails;
+mod transforms;
+mod trash;
+mod two_factor;
+mod users;
+mod vk;
+mod webhooks;
+pub use accounts::*;
+pub use audit::*;
+pub use auth::*;
+pub use bucket_events::*;
+pub use captcha::*;
+pub use changes::*;
+pub use colors::*;
+pub use error::*;
 pub use files::*;
+pub use iiif::*;
+pub use images::*;
+pub use jobs::*;
+pub use lifecycle::*;
+pub use login_throttle::*;
+pub use metadata::*;
+pub use oidc::*;
+pub use privacy::*;
+pub use publishers::*;
+pub use shares::*;
+pub use similarity::*;
+pub use thumbnails::*;
+pub use transforms::*;
+pub use trash::*;
+pub use two_factor::*;
+pub use users::*;
+pub use vk::*;
+pub use webhooks::*;
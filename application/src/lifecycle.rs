@@ -0,0 +1,434 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, LifecycleAction, LifecycleConfig, LifecycleRule};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use domain::*;
+use log::{info, warn};
+use remote::DefaultStorage;
+use repository::ResourceRepository;
+use sea_orm::DbConn;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tasks::ScheduledTask;
+
+use crate::{set_system_metadata, DefaultTrashService, ServiceError, TrashService};
+
+/// Name of the built-in rule moving expired resources to the trash.
+pub const EXPIRED_RULE: &str = "expired";
+
+/// Action on a resource, only planned in a dry run.
+#[derive(Debug, Serialize)]
+pub struct LifecycleEntry {
+    pub key: String,
+    pub rule: String,
+    pub action: LifecycleAction,
+    /// Archive bucket or key of the moved object.
+    pub destination: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LifecycleReport {
+    pub dry_run: bool,
+    pub entries: Vec<LifecycleEntry>,
+    pub failed: usize,
+}
+
+#[async_trait]
+pub trait LifecycleService {
+    /// Moves expired resources to the trash and applies the rules in their order, a resource is
+    /// handled by the first matching one. A dry run only reports the actions.
+    async fn evaluate(&self, dry_run: bool) -> Result<LifecycleReport>;
+}
+
+pub struct DefaultLifecycleService {
+    resources: Box<dyn Resources + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    trash: DefaultTrashService,
+    bucket: String,
+    hostname: String,
+    config: LifecycleConfig,
+}
+
+impl DefaultLifecycleService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            trash: DefaultTrashService::new(config, db),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            bucket: config.aws.bucket.clone(),
+            hostname: config.aws.endpoint.clone(),
+            config: config.lifecycle.clone(),
+        }
+    }
+
+    async fn apply(&self, resource: Resource, entry: &LifecycleEntry) -> Result<()> {
+        let id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        let destination = entry.destination.as_deref().unwrap_or_default();
+        match entry.action {
+            LifecycleAction::Delete => self.trash.trash(resource, None).await,
+            LifecycleAction::Archive => {
+                self.storage
+                    .copy_object(&self.bucket, &resource.key, destination, &resource.key)
+                    .await?;
+                self.delete(id, &resource.key).await
+            }
+            LifecycleAction::Move => {
+                let metadata = set_system_metadata(
+                    resource.metadata.clone(),
+                    "lifecycle",
+                    json!({
+                        "rule": entry.rule,
+                        "moved_from": resource.key,
+                        "moved_at": Utc::now(),
+                    }),
+                );
+                let moved = Resource {
+                    key: destination.to_owned(),
+                    url: Some(format!("{}/{}/{}", self.hostname, self.bucket, destination)),
+                    metadata,
+                    ..resource.clone()
+                };
+                // Moved before the copy, so the bucket notification of the copy finds the
+                // resource instead of registering a new one. Fails when the destination is taken.
+                self.resources.update_with_event(id, moved).await?;
+                if let Err(err) = self
+                    .storage
+                    .copy_object(&self.bucket, &resource.key, &self.bucket, destination)
+                    .await
+                {
+                    // Back where its object still is.
+                    self.resources
+                        .update_with_event(id, resource.clone())
+                        .await?;
+                    return Err(err);
+                }
+                self.delete_object(&resource.key).await;
+                Ok(())
+            }
+        }
+    }
+
+    async fn delete(&self, id: uuid::Uuid, key: &str) -> Result<()> {
+        self.resources.delete_with_event(id).await?;
+        self.delete_object(key).await;
+        Ok(())
+    }
+
+    /// The resource is changed already, a leftover object isn't served anymore.
+    async fn delete_object(&self, key: &str) {
+        if let Err(err) = self.storage.delete_object(&self.bucket, key).await {
+            warn!("Failed to delete the object {}: {}", key, err);
+        }
+    }
+
+    fn entry(&self, rule: &LifecycleRule, resource: &Resource) -> LifecycleEntry {
+        let destination = match rule.action {
+            LifecycleAction::Delete => None,
+            LifecycleAction::Archive => Some(
+                rule.destination
+                    .clone()
+                    .unwrap_or_else(|| self.config.archive_bucket.clone()),
+            ),
+            LifecycleAction::Move => Some(moved_key(rule, &resource.key)),
+        };
+        LifecycleEntry {
+            key: resource.key.clone(),
+            rule: rule.name.clone(),
+            action: rule.action,
+            destination,
+            error: None,
+        }
+    }
+}
+
+#[async_trait]
+impl LifecycleService for DefaultLifecycleService {
+    async fn evaluate(&self, dry_run: bool) -> Result<LifecycleReport> {
+        let now = Utc::now();
+        let expired = LifecycleRule {
+            name: EXPIRED_RULE.to_owned(),
+            prefix: None,
+            tag: None,
+            older_than: None,
+            action: LifecycleAction::Delete,
+            destination: None,
+        };
+        let mut planned = vec![];
+        for resource in self
+            .resources
+            .find_expired(now, self.config.batch_size)
+            .await?
+        {
+            planned.push((self.entry(&expired, &resource), resource));
+        }
+        let mut rules: Vec<(&LifecycleRule, LifecycleFilter)> = vec![];
+        for rule in &self.config.rules {
+            match lifecycle_filter(rule, now) {
+                Ok(filter) => rules.push((rule, filter)),
+                Err(err) => warn!("Skip lifecycle rule {}: {}", rule.name, err),
+            }
+        }
+        for (index, (rule, filter)) in rules.iter().enumerate() {
+            for resource in self
+                .resources
+                .find_lifecycle(filter.clone(), self.config.batch_size)
+                .await?
+            {
+                // Left to expiry or an earlier rule, even when outside of their batch.
+                let expired = matches!(resource.expires_at, Some(expires_at) if expires_at <= now);
+                if expired
+                    || rules[..index]
+                        .iter()
+                        .any(|(_, earlier)| matches_filter(earlier, &resource))
+                {
+                    continue;
+                }
+                planned.push((self.entry(rule, &resource), resource));
+            }
+        }
+
+        let mut handled = HashSet::new();
+        let mut report = LifecycleReport {
+            dry_run,
+            entries: vec![],
+            failed: 0,
+        };
+        for (mut entry, resource) in planned {
+            if !handled.insert(resource.id) {
+                continue;
+            }
+            if !dry_run {
+                info!(
+                    "Lifecycle rule {} applies {:?} to {}",
+                    entry.rule, entry.action, entry.key
+                );
+                if let Err(err) = self.apply(resource, &entry).await {
+                    warn!(
+                        "Lifecycle rule {} failed on {}: {}",
+                        entry.rule, entry.key, err
+                    );
+                    entry.error = Some(err.to_string());
+                    report.failed += 1;
+                }
+            }
+            report.entries.push(entry);
+        }
+        Ok(report)
+    }
+}
+
+/// Runs every hour, in a dry run only logs the planned actions.
+#[async_trait]
+impl ScheduledTask for DefaultLifecycleService {
+    async fn run(&self) -> Result<()> {
+        let report = self.evaluate(self.config.dry_run).await?;
+        if report.dry_run {
+            for entry in &report.entries {
+                info!(
+                    "Lifecycle rule {} would apply {:?} to {}",
+                    entry.rule, entry.action, entry.key
+                );
+            }
+        } else if !report.entries.is_empty() {
+            info!(
+                "Lifecycle rules handled {} resources, {} failed",
+                report.entries.len(),
+                report.failed
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Conditions of the rule, a rule without any would match every resource.
+fn lifecycle_filter(rule: &LifecycleRule, now: DateTime<Utc>) -> Result<LifecycleFilter> {
+    rule.validate().map_err(ServiceError::BadRequest)?;
+    let (tag, tag_value) = match rule.tag.as_deref().map(|tag| tag.split_once('=')) {
+        Some(Some((tag, value))) => (Some(tag.to_owned()), Some(value.to_owned())),
+        Some(None) => (rule.tag.clone(), None),
+        None => (None, None),
+    };
+    Ok(LifecycleFilter {
+        prefix: rule.prefix.clone(),
+        tag,
+        tag_value,
+        created_before: rule.older_than.map(|days| now - Duration::days(days)),
+        exclude_prefix: match rule.action {
+            LifecycleAction::Move => rule.destination.clone(),
+            _ => None,
+        },
+    })
+}
+
+/// Whether the resource matches the filter, like `Resources::find_lifecycle` does.
+fn matches_filter(filter: &LifecycleFilter, resource: &Resource) -> bool {
+    let tag = filter.tag.as_ref().map(|tag| {
+        let value = resource.tags.as_ref().and_then(|tags| tags.get(tag));
+        match (value, &filter.tag_value) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(Value::String(value)), Some(expected)) => value == expected,
+            (Some(value), Some(expected)) => &value.to_string() == expected,
+        }
+    });
+    let prefix = filter
+        .prefix
+        .as_ref()
+        .map(|prefix| resource.key.starts_with(prefix.as_str()));
+    let excluded = matches!(
+        &filter.exclude_prefix,
+        Some(prefix) if resource.key.starts_with(prefix.as_str())
+    );
+    let created = filter
+        .created_before
+        .map(|created_before| resource.created_at < created_before);
+    tag != Some(false) && prefix != Some(false) && created != Some(false) && !excluded
+}
+
+/// The prefix of the rule is replaced by the destination, without a prefix the destination
+/// is prepended.
+fn moved_key(rule: &LifecycleRule, key: &str) -> String {
+    let destination = rule.destination.as_deref().unwrap_or_default();
+    let rest = rule
+        .prefix
+        .as_deref()
+        .and_then(|prefix| key.strip_prefix(prefix))
+        .unwrap_or(key);
+    format!("{}{}", destination, rest)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(action: LifecycleAction) -> LifecycleRule {
+        LifecycleRule {
+            name: "exports".to_owned(),
+            prefix: Some("exports/".to_owned()),
+            tag: Some("kind=report".to_owned()),
+            older_than: Some(7),
+            action,
+            destination: Some("cold/".to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_lifecycle_filter() {
+        let now = Utc::now();
+        let filter = lifecycle_filter(&rule(LifecycleAction::Move), now).unwrap();
+        assert_eq!(filter.prefix.as_deref(), Some("exports/"));
+        assert_eq!(filter.tag.as_deref(), Some("kind"));
+        assert_eq!(filter.tag_value.as_deref(), Some("report"));
+        assert_eq!(filter.created_before, Some(now - Duration::days(7)));
+        assert_eq!(filter.exclude_prefix.as_deref(), Some("cold/"));
+
+        let delete = LifecycleRule {
+            tag: Some("temporary".to_owned()),
+            ..rule(LifecycleAction::Delete)
+        };
+        let filter = lifecycle_filter(&delete, now).unwrap();
+        assert_eq!(filter.tag.as_deref(), Some("temporary"));
+        assert_eq!(filter.tag_value, None);
+        assert_eq!(filter.exclude_prefix, None);
+
+        let unconditional = LifecycleRule {
+            prefix: None,
+            tag: None,
+            older_than: None,
+            ..rule(LifecycleAction::Delete)
+        };
+        assert!(lifecycle_filter(&unconditional, now).is_err());
+        let nowhere = LifecycleRule {
+            destination: None,
+            ..rule(LifecycleAction::Move)
+        };
+        assert!(lifecycle_filter(&nowhere, now).is_err());
+    }
+
+    #[test]
+    fn test_matches_filter() {
+        let now = Utc::now();
+        let filter = lifecycle_filter(&rule(LifecycleAction::Move), now).unwrap();
+        let resource = |key: &str, kind: Value, days: i64| Resource {
+            created_at: now - Duration::days(days),
+            ..Resource::default()
+                .with_key(key)
+                .with_tags(json!({ "kind": kind }).as_object().unwrap().clone())
+        };
+        assert!(matches_filter(
+            &filter,
+            &resource("exports/a.csv", json!("report"), 8)
+        ));
+        assert!(!matches_filter(
+            &filter,
+            &resource("exports/a.csv", json!("report"), 6)
+        ));
+        assert!(!matches_filter(
+            &filter,
+            &resource("exports/a.csv", json!("log"), 8)
+        ));
+        assert!(!matches_filter(
+            &filter,
+            &resource("imports/a.csv", json!("report"), 8)
+        ));
+        assert!(!matches_filter(
+            &filter,
+            &resource("cold/a.csv", json!("report"), 8)
+        ));
+
+        let numbered = LifecycleFilter {
+            tag: Some("kind".to_owned()),
+            tag_value: Some("7".to_owned()),
+            ..LifecycleFilter::default()
+        };
+        assert!(matches_filter(&numbered, &resource("a.csv", json!(7), 0)));
+        assert!(!matches_filter(
+            &numbered,
+            &Resource::default().with_key("a.csv")
+        ));
+    }
+
+    #[test]
+    fn test_moved_resources_match_no_more() {
+        let now = Utc::now();
+        let tagged = LifecycleRule {
+            prefix: None,
+            tag: Some("archive".to_owned()),
+            older_than: None,
+            ..rule(LifecycleAction::Move)
+        };
+        let filter = lifecycle_filter(&tagged, now).unwrap();
+        let resource = Resource::default()
+            .with_key("a.csv")
+            .with_tags(json!({ "archive": true }).as_object().unwrap().clone());
+        assert!(matches_filter(&filter, &resource));
+        let moved = Resource {
+            key: moved_key(&tagged, &resource.key),
+            ..resource
+        };
+        assert!(!matches_filter(&filter, &moved));
+
+        let into_prefix = LifecycleRule {
+            destination: Some("exports/cold/".to_owned()),
+            ..rule(LifecycleAction::Move)
+        };
+        assert!(lifecycle_filter(&into_prefix, now).is_err());
+    }
+
+    #[test]
+    fn test_moved_key() {
+        let rule = rule(LifecycleAction::Move);
+        assert_eq!(moved_key(&rule, "exports/2022/a.csv"), "cold/2022/a.csv");
+        let unprefixed = LifecycleRule {
+            prefix: None,
+            ..rule
+        };
+        assert_eq!(moved_key(&unprefixed, "a.csv"), "cold/a.csv");
+    }
+}
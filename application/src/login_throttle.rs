@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, LockoutConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use domain::*;
+use repository::LoginThrottleRepository;
+use sea_orm::DbConn;
+use uuid::Uuid;
+
+use crate::{AuditService, AuthError, DefaultAuditService};
+
+/// Throttling of failed logins per user name and per client address with exponential backoff
+/// and temporary lockout.
+#[async_trait]
+pub trait LoginThrottleService {
+    /// Fails with `AuthError::TooManyAttempts` if the user or the client has to wait.
+    async fn check(&self, name: &str, client_ip: &str) -> Result<()>;
+    async fn record_failure(
+        &self,
+        name: &str,
+        user_id: Option<Uuid>,
+        client_ip: &str,
+    ) -> Result<()>;
+    /// Forgets failures of the user, failures of the client are kept.
+    async fn record_success(&self, name: &str) -> Result<()>;
+    /// Counts a CAPTCHA issued to the client, the client is locked out like after failed logins.
+    /// Fails with `AuthError::TooManyAttempts` while the client is locked.
+    async fn record_captcha(&self, client_ip: &str) -> Result<()>;
+}
+
+pub struct DefaultLoginThrottleService {
+    throttles: Box<dyn LoginThrottles + Send + Sync>,
+    audit: Box<dyn AuditService + Send + Sync>,
+    config: LockoutConfig,
+}
+
+impl DefaultLoginThrottleService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            throttles: Box::new(LoginThrottleRepository::new(db.clone())),
+            audit: Box::new(DefaultAuditService::new(db)),
+            config: config.lockout.clone(),
+        }
+    }
+
+    fn next_attempt_at(&self, throttle: &LoginThrottle) -> Option<DateTime<Utc>> {
+        throttle.next_attempt_at(self.config.backoff_base, self.config.backoff_max)
+    }
+
+    /// Counts the failure, returns `true` if the key got locked.
+    async fn fail(&self, key: &str, max_failures: i32) -> Result<bool> {
+        let window_start = Utc::now() - Duration::seconds(self.config.failure_window);
+        let throttle = self.throttles.record_failure(key, window_start).await?;
+        if throttle.failures < max_failures {
+            return Ok(false);
+        }
+        let until = Utc::now() + Duration::seconds(self.config.lockout_duration);
+        self.throttles.lock(key, until).await?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl LoginThrottleService for DefaultLoginThrottleService {
+    async fn check(&self, name: &str, client_ip: &str) -> Result<()> {
+        let now = Utc::now();
+        for key in [
+            LoginThrottle::user_key(name),
+            LoginThrottle::client_key(client_ip),
+        ] {
+            if let Ok(throttle) = self.throttles.get_by_key(key).await {
+                if self.next_attempt_at(&throttle).map_or(false, |at| at > now) {
+                    return Err(AuthError::TooManyAttempts.into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    async fn record_failure(
+        &self,
+        name: &str,
+        user_id: Option<Uuid>,
+        client_ip: &str,
+    ) -> Result<()> {
+        if self
+            .fail(&LoginThrottle::user_key(name), self.config.max_failures)
+            .await?
+        {
+            self.audit
+                .record(
+                    AuditEvent::new(
+                        AuditEventKind::AccountLocked,
+                        &format!(
+                            "Logins of user {} are locked for {} seconds after {} failed attempts",
+                            name, self.config.lockout_duration, self.config.max_failures
+                        ),
+                    )
+                    .with_user_id(user_id)
+                    .with_client_ip(client_ip),
+                )
+                .await?;
+        }
+        if self
+            .fail(
+                &LoginThrottle::client_key(client_ip),
+                self.config.client_max_failures,
+            )
+            .await?
+        {
+            self.audit
+                .record(
+                    AuditEvent::new(
+                        AuditEventKind::ClientLocked,
+                        &format!(
+                            "Logins from {} are locked for {} seconds after {} failed attempts",
+                            client_ip,
+                            self.config.lockout_duration,
+                            self.config.client_max_failures
+                        ),
+                    )
+                    .with_client_ip(client_ip),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn record_success(&self, name: &str) -> Result<()> {
+        self.throttles.clear(&LoginThrottle::user_key(name)).await
+    }
+
+    /// Only the lockout applies, the backoff would slow down clients that reload a form.
+    async fn record_captcha(&self, client_ip: &str) -> Result<()> {
+        let key = LoginThrottle::captcha_key(client_ip);
+        if let Ok(throttle) = self.throttles.get_by_key(key.clone()).await {
+            if throttle
+                .locked_until
+                .map_or(false, |until| until > Utc::now())
+            {
+                return Err(AuthError::TooManyAttempts.into());
+            }
+        }
+        if self.fail(&key, self.config.client_max_failures).await? {
+            self.audit
+                .record(
+                    AuditEvent::new(
+                        AuditEventKind::ClientLocked,
+                        &format!(
+                            "CAPTCHAs for {} are locked for {} seconds after {} requests",
+                            client_ip,
+                            self.config.lockout_duration,
+                            self.config.client_max_failures
+                        ),
+                    )
+                    .with_client_ip(client_ip),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn throttle(failures: i32, last_failure_at: DateTime<Utc>) -> LoginThrottle {
+        LoginThrottle {
+            id: None,
+            key: LoginThrottle::user_key("Admin"),
+            failures,
+            last_failure_at: Some(last_failure_at),
+            locked_until: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_exponential_backoff() {
+        let now = Utc::now();
+
+        assert_eq!(
+            throttle(1, now).next_attempt_at(1, 30),
+            Some(now + Duration::seconds(1))
+        );
+        assert_eq!(
+            throttle(4, now).next_attempt_at(1, 30),
+            Some(now + Duration::seconds(8))
+        );
+        assert_eq!(
+            throttle(100, now).next_attempt_at(1, 30),
+            Some(now + Duration::seconds(30))
+        );
+    }
+
+    #[test]
+    fn test_lockout_outlasts_backoff() {
+        let now = Utc::now();
+        let locked = LoginThrottle {
+            locked_until: Some(now + Duration::seconds(900)),
+            ..throttle(0, now)
+        };
+
+        assert_eq!(
+            locked.next_attempt_at(1, 30),
+            Some(now + Duration::seconds(900))
+        );
+        assert_eq!(locked.key, "user:admin");
+    }
+}
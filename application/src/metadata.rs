@@ -0,0 +1,457 @@
+use std::io::Cursor;
+
+use exif::{Exif, In, Tag, Value as ExifValue};
+use lofty::{Accessor, AudioFile, ItemKey, Probe};
+use lopdf::{Dictionary, Document, Object};
+use serde_json::{json, Map, Value};
+
+/// Key in `Resource.metadata` owned by the application, values sent by clients are replaced.
+pub const RESERVED_METADATA_KEY: &str = "_system";
+
+/// Metadata embedded in images (EXIF), audio (ID3, Vorbis comments and others) and PDF
+/// documents. Unknown or broken files have no embedded metadata.
+pub fn extract_metadata(data: &[u8]) -> Map<String, Value> {
+    let mut extracted = Map::new();
+    if data.starts_with(b"%PDF-") {
+        if let Some(pdf) = pdf_metadata(data) {
+            extracted.insert("pdf".to_owned(), Value::Object(pdf));
+        }
+    } else if let Some(exif) = exif_metadata(data) {
+        extracted.insert("exif".to_owned(), Value::Object(exif));
+    } else if let Some(audio) = audio_metadata(data) {
+        extracted.insert("audio".to_owned(), Value::Object(audio));
+    }
+    extracted
+}
+
+/// Puts the extracted values under the reserved key, other client fields are kept as they are.
+/// Metadata that isn't a JSON object is left untouched.
+pub fn merge_metadata(metadata: Option<Value>, extracted: Map<String, Value>) -> Option<Value> {
+    let mut metadata = match metadata {
+        Some(Value::Object(metadata)) => metadata,
+        None => Map::new(),
+        other => return other,
+    };
+    metadata.remove(RESERVED_METADATA_KEY);
+    if !extracted.is_empty() {
+        metadata.insert(RESERVED_METADATA_KEY.to_owned(), Value::Object(extracted));
+    }
+    if metadata.is_empty() {
+        None
+    } else {
+        Some(Value::Object(metadata))
+    }
+}
+
+/// Sets a value under the reserved key, e.g. the state of a publication.
+/// Metadata that isn't a JSON object is left untouched.
+pub fn set_system_metadata(metadata: Option<Value>, key: &str, value: Value) -> Option<Value> {
+    let mut metadata = match metadata {
+        Some(Value::Object(metadata)) => metadata,
+        None => Map::new(),
+        other => return other,
+    };
+    let system = metadata
+        .entry(RESERVED_METADATA_KEY)
+        .or_insert_with(|| Value::Object(Map::new()));
+    match system {
+        Value::Object(system) => {
+            system.insert(key.to_owned(), value);
+        }
+        other => *other = json!({ key: value }),
+    }
+    Some(Value::Object(metadata))
+}
+
+fn exif_metadata(data: &[u8]) -> Option<Map<String, Value>> {
+    let exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(data))
+        .ok()?;
+    let mut metadata = Map::new();
+    for (key, tag) in [
+        ("make", Tag::Make),
+        ("model", Tag::Model),
+        ("lens", Tag::LensModel),
+        ("software", Tag::Software),
+    ] {
+        insert(&mut metadata, key, exif_text(&exif, tag).map(Value::from));
+    }
+    let captured_at = exif_text(&exif, Tag::DateTimeOriginal)
+        .or_else(|| exif_text(&exif, Tag::DateTime))
+        .and_then(|value| exif_date_time(&value));
+    insert(&mut metadata, "captured_at", captured_at.map(Value::from));
+    for (key, tag) in [
+        ("orientation", Tag::Orientation),
+        ("width", Tag::PixelXDimension),
+        ("height", Tag::PixelYDimension),
+        ("iso", Tag::PhotographicSensitivity),
+    ] {
+        insert(&mut metadata, key, exif_uint(&exif, tag).map(Value::from));
+    }
+    for (key, tag) in [
+        ("exposure_time", Tag::ExposureTime),
+        ("f_number", Tag::FNumber),
+        ("focal_length", Tag::FocalLength),
+    ] {
+        insert(
+            &mut metadata,
+            key,
+            exif.get_field(tag, In::PRIMARY)
+                .map(|field| Value::from(field.display_value().with_unit(&exif).to_string())),
+        );
+    }
+    insert(&mut metadata, "gps", gps(&exif));
+    Some(metadata)
+}
+
+fn gps(exif: &Exif) -> Option<Value> {
+    let latitude = coordinate(exif, Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")?;
+    let longitude = coordinate(exif, Tag::GPSLongitude, Tag::GPSLongitudeRef, "W")?;
+    let mut gps = json!({ "latitude": latitude, "longitude": longitude });
+    if let Some(ExifValue::Rational(altitude)) = exif
+        .get_field(Tag::GPSAltitude, In::PRIMARY)
+        .map(|field| &field.value)
+    {
+        if let Some(altitude) = altitude.first() {
+            // Reference 1 means below sea level.
+            let sign = match exif_uint(exif, Tag::GPSAltitudeRef) {
+                Some(1) => -1.0,
+                _ => 1.0,
+            };
+            gps["altitude"] = json!(sign * altitude.to_f64());
+        }
+    }
+    Some(gps)
+}
+
+/// Degrees, minutes and seconds as signed decimal degrees.
+fn coordinate(exif: &Exif, tag: Tag, reference: Tag, negative: &str) -> Option<f64> {
+    let parts = match &exif.get_field(tag, In::PRIMARY)?.value {
+        ExifValue::Rational(parts) if parts.len() == 3 => parts,
+        _ => return None,
+    };
+    let degrees = parts[0].to_f64() + parts[1].to_f64() / 60.0 + parts[2].to_f64() / 3600.0;
+    if !degrees.is_finite() {
+        return None;
+    }
+    match exif_text(exif, reference) {
+        Some(reference) if reference.eq_ignore_ascii_case(negative) => Some(-degrees),
+        _ => Some(degrees),
+    }
+}
+
+fn exif_text(exif: &Exif, tag: Tag) -> Option<String> {
+    match &exif.get_field(tag, In::PRIMARY)?.value {
+        ExifValue::Ascii(values) => values
+            .first()
+            .map(|value| {
+                String::from_utf8_lossy(value)
+                    .trim_matches(char::from(0))
+                    .trim()
+                    .to_owned()
+            })
+            .filter(|value| !value.is_empty()),
+        _ => None,
+    }
+}
+
+fn exif_uint(exif: &Exif, tag: Tag) -> Option<u32> {
+    exif.get_field(tag, In::PRIMARY)?.value.get_uint(0)
+}
+
+/// `2022:09:01 12:30:00` in ISO 8601, EXIF has no time zone.
+fn exif_date_time(value: &str) -> Option<String> {
+    let date_time = exif::DateTime::from_ascii(value.as_bytes()).ok()?;
+    Some(format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date_time.year,
+        date_time.month,
+        date_time.day,
+        date_time.hour,
+        date_time.minute,
+        date_time.second
+    ))
+}
+
+fn audio_metadata(data: &[u8]) -> Option<Map<String, Value>> {
+    let file = Probe::new(Cursor::new(data))
+        .guess_file_type()
+        .ok()?
+        .read()
+        .ok()?;
+    let mut metadata = Map::new();
+    if let Some(tag) = file.primary_tag().or_else(|| file.first_tag()) {
+        insert(&mut metadata, "title", tag.title().map(Value::from));
+        insert(&mut metadata, "artist", tag.artist().map(Value::from));
+        insert(&mut metadata, "album", tag.album().map(Value::from));
+        insert(
+            &mut metadata,
+            "album_artist",
+            tag.get_string(&ItemKey::AlbumArtist).map(Value::from),
+        );
+        insert(&mut metadata, "genre", tag.genre().map(Value::from));
+        insert(&mut metadata, "year", tag.year().map(Value::from));
+        insert(&mut metadata, "track", tag.track().map(Value::from));
+    }
+    let properties = file.properties();
+    metadata.insert(
+        "duration_ms".to_owned(),
+        Value::from(properties.duration().as_millis() as u64),
+    );
+    insert(
+        &mut metadata,
+        "bitrate",
+        properties.audio_bitrate().map(Value::from),
+    );
+    insert(
+        &mut metadata,
+        "sample_rate",
+        properties.sample_rate().map(Value::from),
+    );
+    insert(
+        &mut metadata,
+        "channels",
+        properties.channels().map(Value::from),
+    );
+    Some(metadata)
+}
+
+fn pdf_metadata(data: &[u8]) -> Option<Map<String, Value>> {
+    let document = Document::load_mem(data).ok()?;
+    let mut metadata = Map::new();
+    metadata.insert(
+        "pages".to_owned(),
+        Value::from(document.get_pages().len() as u64),
+    );
+    if let Some(info) = pdf_info(&document) {
+        for (key, name) in [
+            ("title", "Title"),
+            ("author", "Author"),
+            ("subject", "Subject"),
+            ("keywords", "Keywords"),
+            ("creator", "Creator"),
+            ("producer", "Producer"),
+        ] {
+            insert(&mut metadata, key, pdf_text(info, name).map(Value::from));
+        }
+        for (key, name) in [("created_at", "CreationDate"), ("modified_at", "ModDate")] {
+            insert(
+                &mut metadata,
+                key,
+                pdf_text(info, name)
+                    .map(|value| pdf_date(&value).unwrap_or(value))
+                    .map(Value::from),
+            );
+        }
+    }
+    Some(metadata)
+}
+
+fn pdf_info(document: &Document) -> Option<&Dictionary> {
+    match document.trailer.get(b"Info").ok()? {
+        Object::Reference(id) => document.get_dictionary(*id).ok(),
+        Object::Dictionary(info) => Some(info),
+        _ => None,
+    }
+}
+
+/// Text strings are UTF-16BE with a byte order mark or PDFDocEncoding, which matches
+/// Latin-1 for the printable characters.
+fn pdf_text(info: &Dictionary, name: &str) -> Option<String> {
+    let bytes = match info.get(name.as_bytes()).ok()? {
+        Object::String(bytes, _) => bytes,
+        _ => return None,
+    };
+    let text = match bytes.strip_prefix(&[0xfe, 0xff]) {
+        Some(utf16) => String::from_utf16_lossy(
+            &utf16
+                .chunks_exact(2)
+                .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                .collect::<Vec<u16>>(),
+        ),
+        None => bytes.iter().map(|byte| *byte as char).collect(),
+    };
+    Some(text.trim().to_owned()).filter(|text| !text.is_empty())
+}
+
+/// `D:20220901123000+02'00'` in ISO 8601.
+fn pdf_date(value: &str) -> Option<String> {
+    let value = value.strip_prefix("D:").unwrap_or(value);
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+    if digits.len() < 8 {
+        return None;
+    }
+    let part = |from: usize, default: &'static str| {
+        digits.get(from..from + 2).unwrap_or(default).to_owned()
+    };
+    let mut date = format!(
+        "{}-{}-{}T{}:{}:{}",
+        &digits[0..4],
+        &digits[4..6],
+        &digits[6..8],
+        part(8, "00"),
+        part(10, "00"),
+        part(12, "00")
+    );
+    let zone: Vec<char> = value[digits.len()..]
+        .chars()
+        .filter(|c| *c != '\'')
+        .collect();
+    match zone.first() {
+        Some('Z') => date.push('Z'),
+        Some(sign @ ('+' | '-')) if zone.len() >= 3 => {
+            let hours: String = zone[1..3].iter().collect();
+            let minutes: String = zone
+                .get(3..5)
+                .map_or("00".to_owned(), |m| m.iter().collect());
+            date.push_str(&format!("{}{}:{}", sign, hours, minutes));
+        }
+        _ => {}
+    }
+    Some(date)
+}
+
+fn insert(metadata: &mut Map<String, Value>, key: &str, value: Option<Value>) {
+    if let Some(value) = value {
+        metadata.insert(key.to_owned(), value);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Rational};
+
+    fn ascii(tag: Tag, value: &str) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: ExifValue::Ascii(vec![value.as_bytes().to_vec()]),
+        }
+    }
+
+    fn rationals(tag: Tag, values: &[(u32, u32)]) -> Field {
+        Field {
+            tag,
+            ifd_num: In::PRIMARY,
+            value: ExifValue::Rational(
+                values
+                    .iter()
+                    .map(|(num, denom)| Rational {
+                        num: *num,
+                        denom: *denom,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    #[test]
+    fn test_extract_exif() {
+        let fields = [
+            ascii(Tag::Make, "Canon"),
+            ascii(Tag::Model, "EOS 5D"),
+            ascii(Tag::DateTimeOriginal, "2022:09:01 12:30:05"),
+            Field {
+                tag: Tag::Orientation,
+                ifd_num: In::PRIMARY,
+                value: ExifValue::Short(vec![6]),
+            },
+            ascii(Tag::GPSLatitudeRef, "N"),
+            rationals(Tag::GPSLatitude, &[(52, 1), (30, 1), (0, 1)]),
+            ascii(Tag::GPSLongitudeRef, "W"),
+            rationals(Tag::GPSLongitude, &[(13, 1), (15, 1), (36, 1)]),
+        ];
+        let mut writer = Writer::new();
+        fields.iter().for_each(|field| writer.push_field(field));
+        let mut data = Cursor::new(Vec::new());
+        writer.write(&mut data, false).unwrap();
+
+        let extracted = extract_metadata(data.get_ref());
+
+        let exif = &extracted["exif"];
+        assert_eq!(exif["make"], "Canon");
+        assert_eq!(exif["model"], "EOS 5D");
+        assert_eq!(exif["captured_at"], "2022-09-01T12:30:05");
+        assert_eq!(exif["orientation"], 6);
+        assert_eq!(exif["gps"]["latitude"], 52.5);
+        assert_eq!(exif["gps"]["longitude"], -13.26);
+    }
+
+    #[test]
+    fn test_extract_pdf() {
+        let mut document = Document::with_version("1.5");
+        let mut info = Dictionary::new();
+        info.set("Title", Object::string_literal("Annual report"));
+        info.set(
+            "Author",
+            Object::String(
+                vec![0xfe, 0xff, 0x00, 0x4a, 0x00, 0xf6],
+                lopdf::StringFormat::Hexadecimal,
+            ),
+        );
+        info.set(
+            "CreationDate",
+            Object::string_literal("D:20220901123000+02'00'"),
+        );
+        let info = document.add_object(info);
+        document.trailer.set("Info", info);
+        let mut data = Vec::new();
+        document.save_to(&mut data).unwrap();
+
+        let extracted = extract_metadata(&data);
+
+        let pdf = &extracted["pdf"];
+        assert_eq!(pdf["title"], "Annual report");
+        assert_eq!(pdf["author"], "Jö");
+        assert_eq!(pdf["created_at"], "2022-09-01T12:30:00+02:00");
+    }
+
+    #[test]
+    fn test_merge_metadata() {
+        let extracted = extract_metadata(b"plain text");
+        assert!(extracted.is_empty());
+
+        let client = json!({ "title": "Cat", "_system": { "forged": true } });
+        assert_eq!(
+            merge_metadata(Some(client.clone()), extracted),
+            Some(json!({ "title": "Cat" }))
+        );
+
+        let mut extracted = Map::new();
+        extracted.insert("exif".to_owned(), json!({ "make": "Canon" }));
+        assert_eq!(
+            merge_metadata(Some(client), extracted.clone()),
+            Some(json!({ "title": "Cat", "_system": { "exif": { "make": "Canon" } } }))
+        );
+        assert_eq!(
+            merge_metadata(None, extracted.clone()),
+            Some(json!({ "_system": { "exif": { "make": "Canon" } } }))
+        );
+        assert_eq!(
+            merge_metadata(Some(json!(["tag"])), extracted),
+            Some(json!(["tag"]))
+        );
+    }
+
+    #[test]
+    fn test_set_system_metadata() {
+        let metadata = json!({ "title": "Cat", "_system": { "exif": { "make": "Canon" } } });
+        assert_eq!(
+            set_system_metadata(Some(metadata), "vk", json!({ "post_id": 1 })),
+            Some(json!({
+                "title": "Cat",
+                "_system": { "exif": { "make": "Canon" }, "vk": { "post_id": 1 } }
+            }))
+        );
+        assert_eq!(
+            set_system_metadata(None, "vk", json!({ "post_id": 1 })),
+            Some(json!({ "_system": { "vk": { "post_id": 1 } } }))
+        );
+        assert_eq!(
+            set_system_metadata(Some(json!(["tag"])), "vk", json!({})),
+            Some(json!(["tag"]))
+        );
+    }
+}
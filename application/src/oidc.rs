@@ -0,0 +1,297 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, OidcConfig};
+use async_trait::async_trait;
+use chrono::Utc;
+use domain::*;
+use log::{info, warn};
+use remote::{IdTokenClaims, OidcClient};
+use repository::{UserIdentityRepository, UserRepository};
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use util::{PasswordEncoder, SecretCipher};
+use uuid::Uuid;
+
+use crate::{
+    generate_token, AuthError, AuthService, DefaultAuthService, LoginResponse, ServiceError,
+};
+
+/// Seconds the user has to complete the login at the identity provider.
+pub const OIDC_LOGIN_STATE_TTL: i64 = 600;
+
+/// Redirect to the identity provider together with the sealed state of the login, the state
+/// has to be handed back with the callback.
+#[derive(Debug)]
+pub struct OidcLogin {
+    pub url: String,
+    pub login_state: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LoginState {
+    state: String,
+    nonce: String,
+    code_verifier: String,
+    expires_at: i64,
+}
+
+/// Single sign-on with the OpenID Connect authorization code flow with PKCE.
+#[async_trait]
+pub trait OidcService {
+    async fn begin_login(&self) -> Result<OidcLogin>;
+    /// Provisions or links the user on the first login and starts a session, users with
+    /// 2FA enabled get a challenge like after a password login.
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        login_state: &str,
+    ) -> Result<LoginResponse>;
+}
+
+pub struct DefaultOidcService {
+    users: Box<dyn UserDirectory + Send + Sync>,
+    identities: Box<dyn UserIdentities + Send + Sync>,
+    auth: Box<dyn AuthService + Send + Sync>,
+    cipher: SecretCipher,
+    config: OidcConfig,
+}
+
+impl DefaultOidcService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            users: Box::new(UserRepository::new(db.clone())),
+            identities: Box::new(UserIdentityRepository::new(db.clone())),
+            auth: Box::new(DefaultAuthService::new(config, db)),
+            cipher: SecretCipher::new(&config.oidc.login_state_secret),
+            config: config.oidc.clone(),
+        }
+    }
+
+    async fn client(&self) -> Result<OidcClient> {
+        if !self.config.enabled {
+            return Err(ServiceError::NotFound("OIDC login is disabled".to_owned()).into());
+        }
+        OidcClient::discover(self.config.clone()).await
+    }
+
+    fn open_login_state(&self, login_state: &str, state: &str) -> Result<LoginState> {
+        let login_state: LoginState = self
+            .cipher
+            .decrypt(login_state)
+            .ok()
+            .and_then(|json| serde_json::from_slice(&json).ok())
+            .ok_or(AuthError::InvalidToken)?;
+        if login_state.state != state || login_state.expires_at < Utc::now().timestamp() {
+            return Err(AuthError::InvalidToken.into());
+        }
+        Ok(login_state)
+    }
+
+    /// Finds the user linked to the subject, links a user with the same verified email
+    /// or creates a new one. Also returns whether the user was created for the subject.
+    async fn provision(&self, claims: &IdTokenClaims) -> Result<(User, bool)> {
+        if let Some(identity) = self
+            .identities
+            .get_by_subject(&claims.iss, &claims.sub)
+            .await?
+        {
+            let user = self.users.get_by_id(identity.user_id).await?;
+            return Ok((user, identity.provisioned));
+        }
+        let email = claims
+            .email
+            .clone()
+            .ok_or_else(|| ServiceError::BadRequest("ID token has no email claim".to_owned()))?;
+        let (user, provisioned) = match self.users.get_by_email(email.clone()).await {
+            Ok(user) if claims.email_verified == Some(true) => {
+                info!("Link user {} to subject {}", user.name, claims.sub);
+                (user, false)
+            }
+            Ok(_) => {
+                return Err(ServiceError::Conflict(format!(
+                    "User with email {} exists, the identity provider didn't verify the email",
+                    email
+                ))
+                .into())
+            }
+            Err(_) => (self.create_user(claims, &email).await?, true),
+        };
+        let user_id = user.id.ok_or(AuthError::InvalidCredentials)?;
+        let identity = UserIdentity::new(user_id, &claims.iss, &claims.sub);
+        self.identities
+            .create(match provisioned {
+                true => identity.provisioned(),
+                false => identity,
+            })
+            .await?;
+        Ok((user, provisioned))
+    }
+
+    async fn create_user(&self, claims: &IdTokenClaims, email: &str) -> Result<User> {
+        let role = match map_role(&self.config, claims) {
+            Some(role) => role,
+            None => self.config.default_role.parse().map_err(|_| {
+                anyhow::Error::msg(format!("Unknown role {}", self.config.default_role))
+            })?,
+        };
+        let name = self.unique_name(claims, email).await;
+        info!("Provision user {} for subject {}", name, claims.sub);
+        // The password is never handed out, the user logs in through the identity provider.
+        let password = PasswordEncoder::encode(&generate_token().0);
+        self.users
+            .create(
+                User::default()
+                    .with_name(&name)
+                    .with_email(email)
+                    .with_password(&password)
+                    .with_role(role)
+                    .enable(true)
+                    .verified(),
+            )
+            .await
+    }
+
+    async fn unique_name(&self, claims: &IdTokenClaims, email: &str) -> String {
+        let name = claims
+            .preferred_username
+            .clone()
+            .or_else(|| email.split('@').next().map(str::to_owned))
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| claims.sub.clone());
+        match self.users.get_by_key(name.clone()).await {
+            Ok(_) => format!("{}-{}", name, &Uuid::new_v4().simple().to_string()[..6]),
+            Err(_) => name,
+        }
+    }
+
+    /// The identity provider owns the roles of the users it provisioned, a mapped role
+    /// replaces the current one. Linked local accounts keep their role.
+    async fn sync_role(&self, user: User, claims: &IdTokenClaims) -> Result<User> {
+        match (map_role(&self.config, claims), user.id) {
+            (Some(role), Some(user_id)) if role != user.role => {
+                info!("Set role {} of user {} from the ID token", role, user.name);
+                self.users.update(user_id, user.with_role(role)).await
+            }
+            _ => Ok(user),
+        }
+    }
+}
+
+#[async_trait]
+impl OidcService for DefaultOidcService {
+    async fn begin_login(&self) -> Result<OidcLogin> {
+        let request = self.client().await?.authorization_request()?;
+        let login_state = serde_json::to_vec(&LoginState {
+            state: request.state,
+            nonce: request.nonce,
+            code_verifier: request.code_verifier,
+            expires_at: Utc::now().timestamp() + OIDC_LOGIN_STATE_TTL,
+        })?;
+        Ok(OidcLogin {
+            url: request.url,
+            login_state: self.cipher.encrypt(&login_state)?,
+        })
+    }
+
+    async fn complete_login(
+        &self,
+        code: &str,
+        state: &str,
+        login_state: &str,
+    ) -> Result<LoginResponse> {
+        let client = self.client().await?;
+        let login_state = self.open_login_state(login_state, state)?;
+        let claims = client
+            .exchange_code(code, &login_state.code_verifier, &login_state.nonce)
+            .await
+            .map_err(|err| {
+                warn!("OIDC code exchange failed: {}", err);
+                AuthError::InvalidCredentials
+            })?;
+        let user = match self.provision(&claims).await? {
+            (user, true) => self.sync_role(user, &claims).await?,
+            (user, false) => user,
+        };
+        self.auth.create_session(&user).await
+    }
+}
+
+/// Role with the most privileges among the mapped values of the role claim.
+/// Claim values are compared case-insensitively.
+pub fn map_role(config: &OidcConfig, claims: &IdTokenClaims) -> Option<Role> {
+    let values: Vec<String> = match claims.extra.get(&config.role_claim) {
+        Some(serde_json::Value::String(value)) => vec![value.clone()],
+        Some(serde_json::Value::Array(values)) => values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_owned))
+            .collect(),
+        _ => vec![],
+    };
+    values
+        .iter()
+        .filter_map(|value| config.role_mapping.get(&value.to_lowercase()))
+        .filter_map(|role| role.parse::<Role>().ok())
+        .max_by_key(privilege)
+}
+
+fn privilege(role: &Role) -> u8 {
+    match role {
+        Role::GUEST => 0,
+        Role::USER => 1,
+        Role::ADMIN => 2,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config() -> OidcConfig {
+        OidcConfig {
+            enabled: true,
+            issuer: "http://localhost".to_owned(),
+            client_id: "assets".to_owned(),
+            client_secret: "secret".to_owned(),
+            redirect_url: "http://localhost/auth/oidc/callback".to_owned(),
+            scopes: "openid email".to_owned(),
+            role_claim: "groups".to_owned(),
+            role_mapping: HashMap::from([
+                ("asset-admins".to_owned(), "ADMIN".to_owned()),
+                ("asset-guests".to_owned(), "GUEST".to_owned()),
+            ]),
+            default_role: "USER".to_owned(),
+            login_state_secret: "state-secret".to_owned(),
+        }
+    }
+
+    fn claims(groups: serde_json::Value) -> IdTokenClaims {
+        serde_json::from_value(serde_json::json!({
+            "iss": "http://localhost",
+            "sub": "subject",
+            "groups": groups,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_map_role() {
+        assert_eq!(
+            map_role(
+                &config(),
+                &claims(serde_json::json!(["asset-guests", "Asset-Admins"]))
+            ),
+            Some(Role::ADMIN)
+        );
+        assert_eq!(
+            map_role(&config(), &claims(serde_json::json!("asset-guests"))),
+            Some(Role::GUEST)
+        );
+        assert_eq!(
+            map_role(&config(), &claims(serde_json::json!(["developers"]))),
+            None
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::io::Cursor;
+
+use anyhow::Result;
+use exif::{In, Tag};
+use image::{DynamicImage, ImageFormat};
+use serde_json::{json, Map, Value};
+
+use crate::{encode, is_image, OutputFormat};
+
+/// Image without embedded metadata.
+#[derive(Debug)]
+pub struct ScrubbedImage {
+    pub data: Vec<u8>,
+    /// EXIF orientation of the original, the pixels are rotated accordingly.
+    pub orientation: u32,
+}
+
+/// Re-encodes JPEG and PNG images, which drops EXIF, XMP and GPS data, and applies the EXIF
+/// orientation to the pixels. Other formats, like WebP, TIFF and HEIC, can't be re-encoded as
+/// they are and are left untouched.
+pub fn scrub_image(data: &[u8], jpeg_quality: u8) -> Result<Option<ScrubbedImage>> {
+    let format = match image::guess_format(data) {
+        Ok(ImageFormat::Jpeg) => OutputFormat::Jpeg(jpeg_quality.clamp(1, 100)),
+        Ok(ImageFormat::Png) => OutputFormat::Png,
+        _ => return Ok(None),
+    };
+    let orientation = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(data))
+        .ok()
+        .and_then(|exif| {
+            exif.get_field(Tag::Orientation, In::PRIMARY)
+                .and_then(|field| field.value.get_uint(0))
+        })
+        .unwrap_or(1);
+    let image = orient(image::load_from_memory(data)?, orientation);
+    Ok(Some(ScrubbedImage {
+        data: encode(&image, format)?,
+        orientation,
+    }))
+}
+
+/// Rotates and flips the image so that it is displayed upright without the EXIF orientation.
+pub fn orient(image: DynamicImage, orientation: u32) -> DynamicImage {
+    match orientation {
+        2 => image.fliph(),
+        3 => image.rotate180(),
+        4 => image.flipv(),
+        5 => image.rotate90().fliph(),
+        6 => image.rotate90(),
+        7 => image.rotate270().fliph(),
+        8 => image.rotate270(),
+        _ => image,
+    }
+}
+
+/// Record of the scrubbing for the reserved metadata. The GPS position is removed from the
+/// extracted EXIF values as well.
+pub fn record_scrubbing(extracted: &mut Map<String, Value>, scrubbed: &ScrubbedImage) {
+    if let Some(Value::Object(exif)) = extracted.get_mut("exif") {
+        exif.remove("gps");
+        exif.remove("orientation");
+    }
+    extracted.insert(
+        "privacy".to_owned(),
+        json!({
+            "scrubbed": true,
+            "orientation": scrubbed.orientation,
+        }),
+    );
+}
+
+/// Record for images and other EXIF containers that were left untouched, the extracted GPS
+/// position is kept as the stored file still has it. Returns `false` for other data.
+pub fn record_unscrubbed(extracted: &mut Map<String, Value>, data: &[u8]) -> bool {
+    let has_exif = exif::Reader::new()
+        .read_from_container(&mut Cursor::new(data))
+        .is_ok();
+    if !has_exif && !is_image(data) {
+        return false;
+    }
+    extracted.insert("privacy".to_owned(), json!({ "scrubbed": false }));
+    true
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use exif::experimental::Writer;
+    use exif::{Field, Value as ExifValue};
+    use image::{GenericImageView, ImageBuffer, Rgb};
+
+    #[test]
+    fn test_orient() {
+        let mut image = ImageBuffer::from_pixel(4, 2, Rgb([0u8, 0, 0]));
+        image.put_pixel(0, 0, Rgb([255, 0, 0]));
+        let image = DynamicImage::ImageRgb8(image);
+        let red = |image: &DynamicImage| {
+            image
+                .pixels()
+                .find(|(_, _, pixel)| pixel[0] == 255)
+                .map(|(x, y, _)| (x, y))
+                .unwrap()
+        };
+
+        assert_eq!(red(&orient(image.clone(), 1)), (0, 0));
+        assert_eq!(red(&orient(image.clone(), 2)), (3, 0));
+        assert_eq!(red(&orient(image.clone(), 3)), (3, 1));
+        assert_eq!(red(&orient(image.clone(), 4)), (0, 1));
+        assert_eq!(red(&orient(image.clone(), 5)), (0, 0));
+        assert_eq!(orient(image.clone(), 5).dimensions(), (2, 4));
+        assert_eq!(red(&orient(image.clone(), 6)), (1, 0));
+        assert_eq!(red(&orient(image.clone(), 7)), (1, 3));
+        assert_eq!(red(&orient(image, 8)), (0, 3));
+    }
+
+    /// TIFF holding the EXIF orientation and a GPS position.
+    fn tiff_with_exif(orientation: u16) -> Vec<u8> {
+        let fields = [
+            Field {
+                tag: Tag::Orientation,
+                ifd_num: In::PRIMARY,
+                value: ExifValue::Short(vec![orientation]),
+            },
+            Field {
+                tag: Tag::GPSLatitudeRef,
+                ifd_num: In::PRIMARY,
+                value: ExifValue::Ascii(vec![b"N".to_vec()]),
+            },
+        ];
+        let mut writer = Writer::new();
+        fields.iter().for_each(|field| writer.push_field(field));
+        let mut tiff = Cursor::new(Vec::new());
+        writer.write(&mut tiff, false).unwrap();
+        tiff.into_inner()
+    }
+
+    /// JPEG with an APP1 segment holding the EXIF orientation and a GPS position.
+    fn jpeg_with_exif(orientation: u16) -> Vec<u8> {
+        let app1 = [b"Exif\0\0".as_slice(), &tiff_with_exif(orientation)].concat();
+
+        let mut jpeg = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(ImageBuffer::from_pixel(4, 2, Rgb([10u8, 20, 30])))
+            .write_to(&mut jpeg, image::ImageOutputFormat::Jpeg(90))
+            .unwrap();
+        let jpeg = jpeg.into_inner();
+        let length = (app1.len() + 2) as u16;
+        [
+            &jpeg[..2],
+            &[0xff, 0xe1],
+            &length.to_be_bytes(),
+            &app1,
+            &jpeg[2..],
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn test_scrub_image() {
+        let data = jpeg_with_exif(6);
+        assert!(exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&data))
+            .is_ok());
+
+        let scrubbed = scrub_image(&data, 90).unwrap().unwrap();
+
+        assert_eq!(scrubbed.orientation, 6);
+        assert_eq!(
+            image::load_from_memory(&scrubbed.data)
+                .unwrap()
+                .dimensions(),
+            (2, 4)
+        );
+        assert!(exif::Reader::new()
+            .read_from_container(&mut Cursor::new(&scrubbed.data))
+            .is_err());
+        assert!(scrub_image(b"%PDF-1.5", 90).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_record_scrubbing() {
+        let scrubbed = ScrubbedImage {
+            data: vec![],
+            orientation: 6,
+        };
+        let mut extracted = Map::new();
+        extracted.insert(
+            "exif".to_owned(),
+            json!({ "make": "Canon", "orientation": 6, "gps": { "latitude": 52.5 } }),
+        );
+
+        record_scrubbing(&mut extracted, &scrubbed);
+
+        assert_eq!(
+            Value::Object(extracted),
+            json!({
+                "exif": { "make": "Canon" },
+                "privacy": { "scrubbed": true, "orientation": 6 },
+            })
+        );
+    }
+
+    #[test]
+    fn test_record_unscrubbed() {
+        let data = tiff_with_exif(6);
+        assert!(scrub_image(&data, 90).unwrap().is_none());
+        let mut extracted = Map::new();
+        extracted.insert(
+            "exif".to_owned(),
+            json!({ "orientation": 6, "gps": { "latitude": 52.5 } }),
+        );
+
+        assert!(record_unscrubbed(&mut extracted, &data));
+
+        assert_eq!(
+            Value::Object(extracted),
+            json!({
+                "exif": { "orientation": 6, "gps": { "latitude": 52.5 } },
+                "privacy": { "scrubbed": false },
+            })
+        );
+        let mut extracted = Map::new();
+        assert!(!record_unscrubbed(&mut extracted, b"%PDF-1.5"));
+        assert!(extracted.is_empty());
+    }
+}
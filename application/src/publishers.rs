@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, PublishingConfig, TelegramConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use domain::*;
+use log::{info, warn};
+use remote::{DefaultStorage, TelegramClient, WebhookClient};
+use repository::{PublicationRepository, ResourceRepository};
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tasks::{JobHandler, JobQueue, JobType};
+use uuid::Uuid;
+
+use crate::users::MAX_PAGE_SIZE;
+use crate::{set_system_metadata, ServiceError, VkPublisher};
+
+/// Destination of publications, e.g. a social network or another bucket.
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    /// Name of the target the publications are queued for.
+    fn target(&self) -> &'static str;
+    /// Publishes the resource with its data, returns the id of the remote post or object.
+    async fn publish(&self, resource: &Resource, data: Bytes) -> Result<String>;
+}
+
+/// Job publishing a publication at the time it was scheduled for.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PublishResource {
+    pub publication_id: Uuid,
+    /// A publication rescheduled since has another job, this one leaves it alone.
+    pub scheduled_at: DateTime<Utc>,
+}
+
+impl JobType for PublishResource {
+    const KIND: &'static str = "publish_resource";
+}
+
+#[async_trait]
+pub trait PublicationService {
+    /// Queues the resource with the key for the target, by default to be published right away.
+    async fn enqueue(
+        &self,
+        key: &str,
+        target: &str,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Publication>;
+    async fn reschedule(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<Publication>;
+    async fn cancel(&self, id: Uuid) -> Result<Publication>;
+    async fn get(&self, id: Uuid) -> Result<Publication>;
+    async fn list(&self, filter: PublicationFilter) -> Result<Page<Publication>>;
+    /// Names of the configured targets.
+    fn targets(&self) -> Vec<&'static str>;
+}
+
+pub struct DefaultPublicationService {
+    publications: Box<dyn Publications + Send + Sync>,
+    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    queue: JobQueue,
+    publishers: HashMap<&'static str, Arc<dyn Publisher>>,
+    bucket: String,
+    config: PublishingConfig,
+}
+
+impl DefaultPublicationService {
+    /// Registers the publishers enabled in the configuration.
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        let mut publishers: Vec<Arc<dyn Publisher>> = vec![];
+        if config.vk.enabled {
+            publishers.push(Arc::new(VkPublisher::new(config)));
+        }
+        let publishing = &config.publishing;
+        if publishing.webhook.enabled {
+            publishers.push(Arc::new(WebhookPublisher {
+                client: WebhookClient::new(&publishing.webhook.url, &publishing.webhook.token),
+            }));
+        }
+        if publishing.telegram.enabled {
+            publishers.push(Arc::new(TelegramPublisher::new(&publishing.telegram)));
+        }
+        if publishing.s3.enabled {
+            publishers.push(Arc::new(S3Publisher {
+                storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+                bucket: publishing.s3.bucket.clone(),
+            }));
+        }
+        Self {
+            publications: Box::new(PublicationRepository::new(db.clone())),
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            queue: JobQueue::new(config, db),
+            publishers: publishers
+                .into_iter()
+                .map(|publisher| (publisher.target(), publisher))
+                .collect(),
+            bucket: config.aws.bucket.clone(),
+            config: publishing.clone(),
+        }
+    }
+
+    async fn find(&self, id: Uuid) -> Result<Publication> {
+        Ok(self
+            .publications
+            .get_by_id(id)
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Publication {} doesn't exist", id)))?)
+    }
+
+    /// Enqueues the job publishing the publication at its scheduled time.
+    async fn schedule(&self, publication: &Publication) -> Result<()> {
+        let publication_id = publication
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Publication has no id"))?;
+        self.queue
+            .enqueue_with_attempts(
+                &PublishResource {
+                    publication_id,
+                    scheduled_at: publication.scheduled_at,
+                },
+                publication.scheduled_at,
+                self.config.max_attempts,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// A failed attempt keeps its error on the publication and fails the job, which is
+    /// retried by the job queue.
+    async fn publish(&self, publication: Publication) -> Result<()> {
+        let id = publication
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Started publication has no id"))?;
+        let attempt = publication.attempts;
+        let result = match self.publishers.get(publication.target.as_str()) {
+            Some(publisher) => self.run(publisher.as_ref(), &publication).await,
+            None => Err(anyhow::Error::msg(format!(
+                "Target {} isn't configured",
+                publication.target
+            ))),
+        };
+        match result {
+            Ok(remote_id) => {
+                if self.publications.complete(id, attempt, &remote_id).await? {
+                    self.record(
+                        &publication,
+                        json!({
+                            "status": "published",
+                            "remote_id": remote_id,
+                            "published_at": Utc::now(),
+                        }),
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            Err(err) => {
+                info!(
+                    "Publication {} to {} failed on attempt {}: {}",
+                    id, publication.target, attempt, err
+                );
+                self.publications
+                    .retry(id, attempt, &err.to_string())
+                    .await?;
+                Err(err)
+            }
+        }
+    }
+
+    async fn run(&self, publisher: &dyn Publisher, publication: &Publication) -> Result<String> {
+        let resource = self.resources.get_by_id(publication.resource_id).await?;
+        let data = self
+            .storage
+            .download_object(self.bucket.as_str(), resource.key.as_str())
+            .await?;
+        publisher.publish(&resource, data).await
+    }
+
+    /// Keeps the outcome in the system metadata of the resource as well.
+    async fn record(&self, publication: &Publication, state: Value) -> Result<()> {
+        let resource = self.resources.get_by_id(publication.resource_id).await?;
+        let metadata = set_system_metadata(resource.metadata.clone(), &publication.target, state);
+        self.resources
+            .update(
+                publication.resource_id,
+                Resource {
+                    metadata,
+                    ..resource
+                },
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PublicationService for DefaultPublicationService {
+    async fn enqueue(
+        &self,
+        key: &str,
+        target: &str,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<Publication> {
+        if !self.publishers.contains_key(target) {
+            return Err(ServiceError::BadRequest(format!("Unknown target {}", target)).into());
+        }
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        let resource_id = resource
+            .id
+            .ok_or_else(|| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        info!("Queue {} for {}", key, target);
+        let publication = self
+            .publications
+            .create(Publication::new(
+                resource_id,
+                target,
+                scheduled_at.unwrap_or_else(Utc::now),
+                self.config.max_attempts,
+            ))
+            .await?;
+        if let Err(err) = self.schedule(&publication).await {
+            if let Some(id) = publication.id {
+                self.publications.delete_by_id(id).await?;
+            }
+            return Err(err);
+        }
+        Ok(publication)
+    }
+
+    async fn reschedule(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<Publication> {
+        let publication = self.find(id).await?;
+        if !self.publications.reschedule(id, scheduled_at).await? {
+            return Err(ServiceError::Conflict(format!(
+                "Publication {} is {} and can't be rescheduled",
+                id, publication.status
+            ))
+            .into());
+        }
+        let rescheduled = self.find(id).await?;
+        // The job of the unchanged schedule is still pending.
+        let unchanged = publication.status == PublicationStatus::Scheduled
+            && publication.scheduled_at == rescheduled.scheduled_at;
+        if !unchanged {
+            self.schedule(&rescheduled).await?;
+        }
+        Ok(rescheduled)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<Publication> {
+        let publication = self.find(id).await?;
+        if !self.publications.cancel(id).await? {
+            return Err(ServiceError::Conflict(format!(
+                "Publication {} is {} and can't be cancelled",
+                id, publication.status
+            ))
+            .into());
+        }
+        self.find(id).await
+    }
+
+    async fn get(&self, id: Uuid) -> Result<Publication> {
+        self.find(id).await
+    }
+
+    async fn list(&self, filter: PublicationFilter) -> Result<Page<Publication>> {
+        self.publications
+            .find(PublicationFilter {
+                page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+                ..filter
+            })
+            .await
+    }
+
+    fn targets(&self) -> Vec<&'static str> {
+        let mut targets: Vec<&'static str> = self.publishers.keys().copied().collect();
+        targets.sort_unstable();
+        targets
+    }
+}
+
+/// Publishes the publication of the job unless it was rescheduled or cancelled since.
+#[async_trait]
+impl JobHandler for DefaultPublicationService {
+    type Job = PublishResource;
+
+    async fn handle(&self, job: PublishResource) -> Result<()> {
+        match self
+            .publications
+            .start(job.publication_id, job.scheduled_at)
+            .await?
+        {
+            Some(publication) => self.publish(publication).await,
+            None => {
+                info!(
+                    "Publication {} isn't scheduled at {} anymore",
+                    job.publication_id, job.scheduled_at
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn dead_lettered(&self, job: PublishResource, error: &str) -> Result<()> {
+        let id = job.publication_id;
+        if !self.publications.fail(id, job.scheduled_at, error).await? {
+            return Ok(());
+        }
+        warn!("Publication {} failed on the last attempt: {}", id, error);
+        let publication = self.find(id).await?;
+        self.record(
+            &publication,
+            json!({
+                "status": "failed",
+                "error": error,
+                "failed_at": Utc::now(),
+            }),
+        )
+        .await
+    }
+}
+
+/// The `caption` tag of the resource, if any.
+pub fn caption(resource: &Resource) -> String {
+    resource
+        .tags
+        .as_ref()
+        .and_then(|tags| tags.get("caption"))
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_owned()
+}
+
+fn file_name(resource: &Resource) -> &str {
+    resource.key.rsplit('/').next().unwrap_or_default()
+}
+
+pub struct WebhookPublisher {
+    client: WebhookClient,
+}
+
+#[async_trait]
+impl Publisher for WebhookPublisher {
+    fn target(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn publish(&self, resource: &Resource, data: Bytes) -> Result<String> {
+        let description = json!({
+            "key": resource.key,
+            "tags": resource.tags,
+            "metadata": resource.metadata,
+            "created_at": resource.created_at,
+        });
+        self.client
+            .post_file(file_name(resource), data.to_vec(), &description)
+            .await
+    }
+}
+
+pub struct TelegramPublisher {
+    client: TelegramClient,
+    chat_id: String,
+}
+
+impl TelegramPublisher {
+    pub fn new(config: &TelegramConfig) -> Self {
+        Self {
+            client: TelegramClient::new(&config.api_url, &config.bot_token),
+            chat_id: config.chat_id.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for TelegramPublisher {
+    fn target(&self) -> &'static str {
+        "telegram"
+    }
+
+    async fn publish(&self, resource: &Resource, data: Bytes) -> Result<String> {
+        let message_id = self
+            .client
+            .send_photo(
+                &self.chat_id,
+                file_name(resource),
+                data.to_vec(),
+                &caption(resource),
+            )
+            .await?;
+        Ok(message_id.to_string())
+    }
+}
+
+/// Copies the object to another bucket under the same key.
+pub struct S3Publisher {
+    storage: Box<dyn Storage + Send + Sync>,
+    bucket: String,
+}
+
+#[async_trait]
+impl Publisher for S3Publisher {
+    fn target(&self) -> &'static str {
+        "s3"
+    }
+
+    async fn publish(&self, resource: &Resource, data: Bytes) -> Result<String> {
+        self.storage
+            .upload_object(self.bucket.as_str(), &data, resource.key.as_str())
+            .await?;
+        Ok(format!("{}/{}", self.bucket, resource.key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_caption() {
+        let resource = Resource {
+            key: "albums/2022/cat.png".to_owned(),
+            tags: Some(json!({ "publish": "vk", "caption": "Cat" })),
+            ..Resource::default()
+        };
+        assert_eq!(caption(&resource), "Cat");
+        assert_eq!(file_name(&resource), "cat.png");
+        assert_eq!(caption(&Resource::default()), "");
+    }
+}
@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::ApplicationConfig;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use domain::*;
+use log::info;
+use remote::DefaultStorage;
+use repository::{ResourceRepository, ShareRepository};
+use sea_orm::DbConn;
+use util::PasswordEncoder;
+use uuid::Uuid;
+
+use crate::{
+    generate_token, hash_token, AuthError, Claims, DefaultLoginThrottleService,
+    LoginThrottleService, ServiceError,
+};
+
+/// Restrictions of a new share link, a link without options is valid until it is revoked.
+#[derive(Debug, Default)]
+pub struct ShareOptions {
+    /// Seconds until the link expires.
+    pub expires_in: Option<i64>,
+    pub password: Option<String>,
+    pub max_downloads: Option<i32>,
+}
+
+/// New share link, the token is only known to the creator of the link.
+#[derive(Debug)]
+pub struct CreatedShare {
+    pub token: String,
+    pub share: Share,
+}
+
+/// Object behind a share link.
+pub struct SharedObject {
+    pub key: String,
+    pub object: ObjectStream,
+}
+
+/// Public links to resources that can be downloaded without an account.
+#[async_trait]
+pub trait ShareService {
+    async fn create(
+        &self,
+        claims: &Claims,
+        key: &str,
+        options: ShareOptions,
+    ) -> Result<CreatedShare>;
+    async fn list(&self, claims: &Claims, key: &str) -> Result<Vec<Share>>;
+    async fn revoke(&self, claims: &Claims, id: Uuid) -> Result<()>;
+    /// Counts the download and streams the object, unknown, expired, revoked and used up
+    /// links are reported as not found. Wrong passwords are throttled per link and client
+    /// like failed logins.
+    async fn open(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        client_ip: &str,
+    ) -> Result<SharedObject>;
+}
+
+pub struct DefaultShareService {
+    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    shares: Box<dyn Shares + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    throttle: Box<dyn LoginThrottleService + Send + Sync>,
+    bucket: String,
+}
+
+impl DefaultShareService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            shares: Box::new(ShareRepository::new(db.clone())),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            throttle: Box::new(DefaultLoginThrottleService::new(config, db)),
+            bucket: config.aws.bucket.clone(),
+        }
+    }
+
+    /// Admins can share any resource, users only their own ones.
+    async fn owned_resource(&self, claims: &Claims, key: &str) -> Result<Resource> {
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        check_owner(claims, &resource)?;
+        Ok(resource)
+    }
+}
+
+#[async_trait]
+impl ShareService for DefaultShareService {
+    async fn create(
+        &self,
+        claims: &Claims,
+        key: &str,
+        options: ShareOptions,
+    ) -> Result<CreatedShare> {
+        validate(&options)?;
+        let resource = self.owned_resource(claims, key).await?;
+        let resource_id = resource.id.ok_or_else(|| {
+            ServiceError::NotFound(format!("Resource {} doesn't exist", resource.key))
+        })?;
+        info!("User {} shares resource {}", claims.name, resource.key);
+        let (token, token_hash) = generate_token();
+        let share = self
+            .shares
+            .create(
+                Share::new(resource_id, &token_hash, claims.sub)
+                    .with_expires_at(
+                        options
+                            .expires_in
+                            .map(|seconds| Utc::now() + Duration::seconds(seconds)),
+                    )
+                    .with_password_hash(options.password.as_deref().map(PasswordEncoder::encode))
+                    .with_max_downloads(options.max_downloads),
+            )
+            .await?;
+        Ok(CreatedShare { token, share })
+    }
+
+    async fn list(&self, claims: &Claims, key: &str) -> Result<Vec<Share>> {
+        let resource = self.owned_resource(claims, key).await?;
+        match resource.id {
+            Some(resource_id) => self.shares.find_by_resource(resource_id).await,
+            None => Ok(vec![]),
+        }
+    }
+
+    async fn revoke(&self, claims: &Claims, id: Uuid) -> Result<()> {
+        let share = self
+            .shares
+            .get_by_id(id)
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Share {} doesn't exist", id)))?;
+        let resource = self.resources.get_by_id(share.resource_id).await?;
+        check_owner(claims, &resource)?;
+        info!("User {} revokes share {}", claims.name, id);
+        self.shares.revoke(id).await?;
+        Ok(())
+    }
+
+    async fn open(
+        &self,
+        token: &str,
+        password: Option<&str>,
+        client_ip: &str,
+    ) -> Result<SharedObject> {
+        let not_found = || ServiceError::NotFound("Share link doesn't exist".to_owned());
+        let share = self
+            .shares
+            .get_by_key(hash_token(token))
+            .await
+            .map_err(|_| not_found())?;
+        let id = share.id.ok_or_else(not_found)?;
+        if !share.is_active() {
+            return Err(not_found().into());
+        }
+        if let Some(password_hash) = &share.password_hash {
+            let name = format!("share:{}", id);
+            self.throttle.check(&name, client_ip).await?;
+            match password {
+                Some(password) if PasswordEncoder::verify(password_hash, password) => {
+                    self.throttle.record_success(&name).await?
+                }
+                _ => {
+                    self.throttle.record_failure(&name, None, client_ip).await?;
+                    return Err(AuthError::InvalidCredentials.into());
+                }
+            }
+        }
+        // The resource may be in the trash.
+        let resource = self
+            .resources
+            .get_by_id(share.resource_id)
+            .await
+            .map_err(|_| not_found())?;
+        let object = self
+            .storage
+            .stream_object(self.bucket.as_str(), resource.key.as_str())
+            .await?;
+        // Another download may have used up the link in the meantime.
+        if !self.shares.record_download(id).await? {
+            return Err(not_found().into());
+        }
+        info!("Download resource {} through share {}", resource.key, id);
+        Ok(SharedObject {
+            key: resource.key,
+            object,
+        })
+    }
+}
+
+pub(crate) fn check_owner(claims: &Claims, resource: &Resource) -> Result<(), AuthError> {
+    match claims.role {
+        Role::ADMIN => Ok(()),
+        _ if resource.user_id == Some(claims.sub) => Ok(()),
+        _ => Err(AuthError::Forbidden),
+    }
+}
+
+fn validate(options: &ShareOptions) -> Result<(), ServiceError> {
+    if options.expires_in.map_or(false, |seconds| seconds <= 0) {
+        return Err(ServiceError::BadRequest(
+            "Expiry of a share link must be positive".to_owned(),
+        ));
+    }
+    if options.max_downloads.map_or(false, |max| max <= 0) {
+        return Err(ServiceError::BadRequest(
+            "Max downloads of a share link must be positive".to_owned(),
+        ));
+    }
+    if options.password.as_ref().map_or(false, String::is_empty) {
+        return Err(ServiceError::BadRequest(
+            "Password of a share link must not be empty".to_owned(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&ShareOptions::default()).is_ok());
+        assert!(validate(&ShareOptions {
+            expires_in: Some(60),
+            password: Some("secret".to_owned()),
+            max_downloads: Some(1),
+        })
+        .is_ok());
+        assert!(validate(&ShareOptions {
+            expires_in: Some(0),
+            ..ShareOptions::default()
+        })
+        .is_err());
+        assert!(validate(&ShareOptions {
+            max_downloads: Some(-1),
+            ..ShareOptions::default()
+        })
+        .is_err());
+        assert!(validate(&ShareOptions {
+            password: Some(String::new()),
+            ..ShareOptions::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_share_is_active() {
+        let share = Share::new(Uuid::new_v4(), "hash", Uuid::new_v4());
+        assert!(share.is_active());
+        assert!(!share
+            .clone()
+            .with_expires_at(Some(Utc::now() - Duration::seconds(1)))
+            .is_active());
+        assert!(!share.clone().with_max_downloads(Some(0)).is_active());
+
+        let mut revoked = share;
+        revoked.revoked_at = Some(Utc::now());
+        assert!(!revoked.is_active());
+    }
+}
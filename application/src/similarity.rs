@@ -0,0 +1,293 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::ApplicationConfig;
+use async_trait::async_trait;
+use domain::*;
+use image::{imageops::FilterType, DynamicImage};
+use log::{info, warn};
+use remote::DefaultStorage;
+use repository::ResourceRepository;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use tasks::{JobHandler, JobQueue, JobType};
+use uuid::Uuid;
+
+use crate::{check_owner, Claims, ServiceError};
+
+/// Hamming distance up to which two images count as visually similar.
+pub const DEFAULT_SIMILARITY_DISTANCE: u32 = 10;
+const MAX_SIMILARITY_DISTANCE: u32 = 32;
+const MAX_SIMILAR_RESOURCES: u64 = 100;
+/// Images read per query of the backfill.
+const BACKFILL_BATCH_SIZE: u64 = 500;
+
+/// Job computing the perceptual hash of an image uploaded before hashes were computed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HashResource {
+    pub resource_id: Uuid,
+}
+
+impl JobType for HashResource {
+    const KIND: &'static str = "hash_resource";
+}
+
+/// Resources whose images are near duplicates of each other, oldest first.
+#[derive(Clone, Debug, Serialize)]
+pub struct DuplicateCluster {
+    pub resources: Vec<Resource>,
+}
+
+/// Finds visually similar images by the Hamming distance of their perceptual hashes.
+#[async_trait]
+pub trait SimilarityService {
+    /// Resources similar to the one with the key, without the resource itself. Users only
+    /// compare their own files, admins the files of all users.
+    async fn similar(
+        &self,
+        claims: &Claims,
+        key: String,
+        max_distance: u32,
+        limit: u64,
+    ) -> Result<Vec<SimilarResource>>;
+    async fn duplicates(&self, max_distance: u32) -> Result<Vec<DuplicateCluster>>;
+    /// Enqueues a job hashing every image without a perceptual hash, returns their number.
+    async fn backfill(&self) -> Result<usize>;
+}
+
+pub struct DefaultSimilarityService {
+    resources: Box<dyn Resources + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    queue: JobQueue,
+    bucket: String,
+}
+
+impl DefaultSimilarityService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            queue: JobQueue::new(config, db),
+            bucket: config.aws.bucket.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl SimilarityService for DefaultSimilarityService {
+    async fn similar(
+        &self,
+        claims: &Claims,
+        key: String,
+        max_distance: u32,
+        limit: u64,
+    ) -> Result<Vec<SimilarResource>> {
+        validate_distance(max_distance)?;
+        let resource = self
+            .resources
+            .get_by_key(key.clone())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("File {} doesn't exist", key)))?;
+        check_owner(claims, &resource)?;
+        let user_id = match claims.role {
+            Role::ADMIN => None,
+            _ => Some(claims.sub),
+        };
+        let perceptual_hash = resource.perceptual_hash.ok_or_else(|| {
+            ServiceError::BadRequest(format!("Resource {} is not an image", resource.key))
+        })?;
+        let limit = limit.clamp(1, MAX_SIMILAR_RESOURCES);
+        Ok(self
+            .resources
+            .find_similar(perceptual_hash, max_distance, user_id, limit + 1)
+            .await?
+            .into_iter()
+            .filter(|similar| similar.resource.id != resource.id)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn duplicates(&self, max_distance: u32) -> Result<Vec<DuplicateCluster>> {
+        validate_distance(max_distance)?;
+        let resources = self.resources.find_hashed().await?;
+        info!(
+            "Cluster {} hashed resources within distance {}",
+            resources.len(),
+            max_distance
+        );
+        // Comparing all pairs is quadratic, keep it off the async workers.
+        let clusters =
+            tokio::task::spawn_blocking(move || cluster(resources, max_distance)).await?;
+        Ok(clusters)
+    }
+
+    async fn backfill(&self) -> Result<usize> {
+        let mut enqueued = 0;
+        let mut after = None;
+        loop {
+            let unhashed = self
+                .resources
+                .find_unhashed(after, BACKFILL_BATCH_SIZE)
+                .await?;
+            for resource in &unhashed {
+                if let Some(resource_id) = resource.id {
+                    self.queue.enqueue(&HashResource { resource_id }).await?;
+                    enqueued += 1;
+                }
+            }
+            match unhashed.last() {
+                Some(last) if unhashed.len() as u64 == BACKFILL_BATCH_SIZE => after = last.id,
+                _ => break,
+            }
+        }
+        info!("Enqueued {} images to hash", enqueued);
+        Ok(enqueued)
+    }
+}
+
+/// Hashes the image of the job unless it was hashed, trashed or deleted meanwhile. An object
+/// that isn't a decodable image is left without a hash.
+#[async_trait]
+impl JobHandler for DefaultSimilarityService {
+    type Job = HashResource;
+
+    async fn handle(&self, job: HashResource) -> Result<()> {
+        let resource = match self.resources.get_by_id(job.resource_id).await {
+            Ok(resource) if resource.perceptual_hash.is_none() => resource,
+            _ => return Ok(()),
+        };
+        let data = self
+            .storage
+            .download_object(self.bucket.as_str(), resource.key.as_str())
+            .await?;
+        // Decoding is CPU bound, keep it off the async workers.
+        let hashed =
+            tokio::task::spawn_blocking(move || image::load_from_memory(&data).map(|i| dhash(&i)))
+                .await?;
+        match hashed {
+            Ok(perceptual_hash) => {
+                self.resources
+                    .set_perceptual_hash(job.resource_id, perceptual_hash)
+                    .await?;
+            }
+            Err(err) => warn!("Failed to decode the image {}: {}", resource.key, err),
+        }
+        Ok(())
+    }
+}
+
+fn validate_distance(max_distance: u32) -> Result<(), ServiceError> {
+    if max_distance > MAX_SIMILARITY_DISTANCE {
+        return Err(ServiceError::BadRequest(format!(
+            "Distance must not be greater than {}",
+            MAX_SIMILARITY_DISTANCE
+        )));
+    }
+    Ok(())
+}
+
+/// Difference hash: the image is reduced to 9x8 grayscale pixels and each bit tells whether a
+/// pixel is brighter than its right neighbour. Resizing and re-encoding barely change the hash.
+pub fn dhash(image: &DynamicImage) -> i64 {
+    let pixels = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash as i64
+}
+
+/// Groups resources transitively connected by a distance of at most `max_distance`,
+/// resources without a near duplicate are left out.
+fn cluster(resources: Vec<Resource>, max_distance: u32) -> Vec<DuplicateCluster> {
+    let hashes: Vec<i64> = resources
+        .iter()
+        .map(|resource| resource.perceptual_hash.unwrap_or_default())
+        .collect();
+    let mut parents: Vec<usize> = (0..resources.len()).collect();
+    for i in 0..hashes.len() {
+        for j in i + 1..hashes.len() {
+            if (hashes[i] ^ hashes[j]).count_ones() <= max_distance {
+                let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+    let mut clusters: Vec<Vec<Resource>> = vec![vec![]; resources.len()];
+    for (i, resource) in resources.into_iter().enumerate() {
+        let root = root(&mut parents, i);
+        clusters[root].push(resource);
+    }
+    clusters
+        .into_iter()
+        .filter(|resources| resources.len() > 1)
+        .map(|resources| DuplicateCluster { resources })
+        .collect()
+}
+
+fn root(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn gradient(width: u32, height: u32, inverted: bool) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+            let value = ((x * 255 / width + y * 64 / height) % 256) as u8;
+            let value = if inverted { 255 - value } else { value };
+            Rgb([value, value / 2, 255 - value])
+        }))
+    }
+
+    fn resource(key: &str, perceptual_hash: i64) -> Resource {
+        Resource {
+            key: key.to_owned(),
+            ..Resource::default()
+        }
+        .with_perceptual_hash(Some(perceptual_hash))
+    }
+
+    #[test]
+    fn test_dhash() {
+        let original = dhash(&gradient(640, 480, false));
+        let resized = dhash(&gradient(320, 240, false));
+        let inverted = dhash(&gradient(640, 480, true));
+
+        assert!((original ^ resized).count_ones() <= 4);
+        assert!((original ^ inverted).count_ones() > DEFAULT_SIMILARITY_DISTANCE);
+    }
+
+    #[test]
+    fn test_cluster() {
+        let clusters = cluster(
+            vec![
+                resource("a", 0b0000),
+                resource("b", 0b0111_0000),
+                resource("c", 0b0011),
+                resource("d", 0b1111),
+                resource("e", -1),
+            ],
+            2,
+        );
+
+        assert_eq!(clusters.len(), 1);
+        let keys: Vec<&str> = clusters[0]
+            .resources
+            .iter()
+            .map(|resource| resource.key.as_str())
+            .collect();
+        assert_eq!(keys, vec!["a", "c", "d"]);
+    }
+}
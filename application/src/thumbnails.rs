@@ -0,0 +1,248 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, ThumbnailConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::*;
+use image::{imageops::FilterType, DynamicImage, GenericImageView};
+use log::info;
+use remote::DefaultStorage;
+use repository::{DerivativeRepository, ResourceRepository};
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use tasks::{JobHandler, JobType};
+use uuid::Uuid;
+
+use crate::{encode, OutputFormat, ServiceError};
+
+/// Encoded thumbnail of one size.
+#[derive(Debug)]
+pub struct Rendition {
+    pub size: u32,
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+/// Job generating the thumbnails of an uploaded image.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerateThumbnails {
+    pub key: String,
+}
+
+impl JobType for GenerateThumbnails {
+    const KIND: &'static str = "generate_thumbnails";
+}
+
+/// Thumbnails of image resources, stored in the bucket next to the original object.
+#[async_trait]
+pub trait ThumbnailService {
+    /// Generates the configured thumbnails of an uploaded image, replacing existing ones.
+    async fn generate(&self, resource: &Resource, data: Bytes) -> Result<Vec<Derivative>>;
+    /// Thumbnail of the resource with the given key that fits into `size` pixels.
+    async fn thumbnail(&self, key: &str, size: u32) -> Result<ObjectStream>;
+}
+
+pub struct DefaultThumbnailService {
+    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    derivatives: Box<dyn Derivatives + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    bucket: String,
+    config: ThumbnailConfig,
+}
+
+impl DefaultThumbnailService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            derivatives: Box::new(DerivativeRepository::new(db)),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            bucket: config.aws.bucket.clone(),
+            config: config.thumbnails.clone(),
+        }
+    }
+
+    async fn store(
+        &self,
+        resource_id: Uuid,
+        key: &str,
+        format: OutputFormat,
+        rendition: Rendition,
+    ) -> Result<Derivative> {
+        let derivative_key = thumbnail_key(key, rendition.size, format);
+        self.storage
+            .upload_object(
+                self.bucket.as_str(),
+                &rendition.data,
+                derivative_key.as_str(),
+            )
+            .await?;
+        let derivative = Derivative::new(
+            resource_id,
+            rendition.size as i32,
+            &derivative_key,
+            format.content_type(),
+        )
+        .with_dimensions(rendition.width as i32, rendition.height as i32)
+        .with_byte_size(rendition.data.len() as i64);
+        match self
+            .derivatives
+            .get_by_size(resource_id, rendition.size as i32)
+            .await?
+        {
+            Some(Derivative { id: Some(id), .. }) => self.derivatives.update(id, derivative).await,
+            _ => self.derivatives.create(derivative).await,
+        }
+    }
+}
+
+#[async_trait]
+impl ThumbnailService for DefaultThumbnailService {
+    async fn generate(&self, resource: &Resource, data: Bytes) -> Result<Vec<Derivative>> {
+        let resource_id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        let format = OutputFormat::parse(&self.config.format, self.config.quality)?;
+        let sizes = self.config.sizes.clone();
+        info!(
+            "Generate thumbnails {:?} of resource {}",
+            sizes, resource.key
+        );
+        // Decoding and resizing is CPU bound, keep it off the async workers.
+        let renditions =
+            tokio::task::spawn_blocking(move || render_thumbnails(&data, &sizes, format)).await??;
+        let mut derivatives = Vec::with_capacity(renditions.len());
+        for rendition in renditions {
+            derivatives.push(
+                self.store(resource_id, &resource.key, format, rendition)
+                    .await?,
+            );
+        }
+        Ok(derivatives)
+    }
+
+    async fn thumbnail(&self, key: &str, size: u32) -> Result<ObjectStream> {
+        if !self.config.sizes.contains(&size) {
+            return Err(
+                ServiceError::BadRequest(format!("Unknown thumbnail size {}", size)).into(),
+            );
+        }
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        let derivative = match resource.id {
+            Some(resource_id) => {
+                self.derivatives
+                    .get_by_size(resource_id, size as i32)
+                    .await?
+            }
+            None => None,
+        }
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("Thumbnail {} of {} doesn't exist", size, key))
+        })?;
+        let object = self
+            .storage
+            .stream_object(self.bucket.as_str(), derivative.key.as_str())
+            .await?;
+        Ok(ObjectStream {
+            content_type: Some(derivative.content_type),
+            ..object
+        })
+    }
+}
+
+/// Thumbnails fitting into each of the sizes, images are never scaled up.
+pub fn render_thumbnails(
+    data: &[u8],
+    sizes: &[u32],
+    format: OutputFormat,
+) -> Result<Vec<Rendition>> {
+    let image = image::load_from_memory(data)?;
+    sizes
+        .iter()
+        .map(|size| render(&image, *size, format))
+        .collect()
+}
+
+fn render(image: &DynamicImage, size: u32, format: OutputFormat) -> Result<Rendition> {
+    let (width, height) = image.dimensions();
+    let thumbnail = if width > size || height > size {
+        image.resize(size, size, FilterType::Lanczos3)
+    } else {
+        image.clone()
+    };
+    Ok(Rendition {
+        size,
+        width: thumbnail.width(),
+        height: thumbnail.height(),
+        data: encode(&thumbnail, format)?,
+    })
+}
+
+fn thumbnail_key(key: &str, size: u32, format: OutputFormat) -> String {
+    derivative_key(key, &format!("{}.{}", size, format.extension()))
+}
+
+/// Generates the thumbnails after the upload has been answered, a failed job is retried with
+/// backoff and dead-lettered after its last attempt.
+#[async_trait]
+impl JobHandler for DefaultThumbnailService {
+    type Job = GenerateThumbnails;
+
+    async fn handle(&self, job: GenerateThumbnails) -> Result<()> {
+        let resource = self.resources.get_by_key(job.key.clone()).await?;
+        let data = self
+            .storage
+            .download_object(self.bucket.as_str(), job.key.as_str())
+            .await?;
+        self.generate(&resource, data).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::is_image;
+    use image::{ImageBuffer, ImageOutputFormat, Rgba};
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let image = ImageBuffer::from_pixel(width, height, Rgba([200u8, 100, 50, 128]));
+        let mut data = Cursor::new(Vec::new());
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut data, ImageOutputFormat::Png)
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn test_render_thumbnails() {
+        let data = png(300, 200);
+        assert!(is_image(&data));
+        assert!(!is_image(b"plain text"));
+
+        let renditions = render_thumbnails(&data, &[128, 1024], OutputFormat::Jpeg(80)).unwrap();
+
+        assert_eq!((renditions[0].width, renditions[0].height), (128, 85));
+        assert_eq!((renditions[1].width, renditions[1].height), (300, 200));
+        let thumbnail = image::load_from_memory(&renditions[0].data).unwrap();
+        assert_eq!(thumbnail.dimensions(), (128, 85));
+        assert_eq!(
+            image::guess_format(&renditions[0].data).unwrap(),
+            image::ImageFormat::Jpeg
+        );
+    }
+
+    #[test]
+    fn test_thumbnail_key() {
+        assert_eq!(
+            thumbnail_key("cats/cat.png", 128, OutputFormat::Png),
+            "cats/cat.png@128.png"
+        );
+    }
+}
@@ -0,0 +1,409 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, TransformConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::*;
+use image::imageops::FilterType;
+use log::info;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use remote::DefaultStorage;
+use repository::ResourceRepository;
+use sea_orm::DbConn;
+use util::Signer;
+
+use crate::{encode, AuthError, OutputFormat, ServiceError};
+
+/// Characters of keys that are kept as is in the path segment, slashes are encoded too.
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+/// Requested transformation as it appears in the signed URL.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransformParams {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fit: Option<String>,
+    pub format: Option<String>,
+    pub quality: Option<u8>,
+    /// Etag of the original when signed, a replaced original invalidates the URL.
+    pub version: Option<String>,
+}
+
+impl TransformParams {
+    /// Query string in a fixed order, this is what the signature covers.
+    pub fn to_query(&self) -> String {
+        let mut query = vec![];
+        if let Some(width) = self.width {
+            query.push(format!("w={}", width));
+        }
+        if let Some(height) = self.height {
+            query.push(format!("h={}", height));
+        }
+        if let Some(fit) = &self.fit {
+            query.push(format!("fit={}", fit));
+        }
+        if let Some(format) = &self.format {
+            query.push(format!("format={}", format));
+        }
+        if let Some(quality) = self.quality {
+            query.push(format!("quality={}", quality));
+        }
+        if let Some(version) = &self.version {
+            query.push(format!("v={}", version));
+        }
+        query.join("&")
+    }
+}
+
+/// How the image is fitted into the requested width and height.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fit {
+    /// Scales the image to fit into the box, keeping the aspect ratio.
+    Contain,
+    /// Scales and crops the image to fill the box, keeping the aspect ratio.
+    Cover,
+    /// Scales the image to the box, ignoring the aspect ratio.
+    Fill,
+}
+
+impl Fit {
+    fn name(&self) -> &'static str {
+        match self {
+            Fit::Contain => "contain",
+            Fit::Cover => "cover",
+            Fit::Fill => "fill",
+        }
+    }
+}
+
+/// Validated transformation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Transform {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub fit: Fit,
+    pub format: OutputFormat,
+}
+
+impl Transform {
+    pub fn from_params(
+        key: &str,
+        params: &TransformParams,
+        config: &TransformConfig,
+    ) -> Result<Self, ServiceError> {
+        for dimension in [params.width, params.height].into_iter().flatten() {
+            if dimension == 0 || dimension > config.max_dimension {
+                return Err(ServiceError::BadRequest(format!(
+                    "Width and height must be between 1 and {}",
+                    config.max_dimension
+                )));
+            }
+        }
+        let fit = match params.fit.as_deref() {
+            None | Some("contain") => Fit::Contain,
+            Some("cover") => Fit::Cover,
+            Some("fill") => Fit::Fill,
+            Some(fit) => return Err(ServiceError::BadRequest(format!("Unknown fit {}", fit))),
+        };
+        let quality = params.quality.unwrap_or(config.quality);
+        let format = match &params.format {
+            Some(format) => OutputFormat::parse(format, quality)?,
+            None => OutputFormat::of_key(key, quality),
+        };
+        Ok(Self {
+            width: params.width,
+            height: params.height,
+            fit,
+            format,
+        })
+    }
+
+    /// Deterministic key of the cached result, next to the original object. The version
+    /// keeps renditions of a replaced original from being served.
+    pub fn cache_key(&self, key: &str, version: &str) -> String {
+        let mut parts = vec![];
+        if let Some(width) = self.width {
+            parts.push(format!("w{}", width));
+        }
+        if let Some(height) = self.height {
+            parts.push(format!("h{}", height));
+        }
+        parts.push(self.fit.name().to_owned());
+        if let OutputFormat::Jpeg(quality) = self.format {
+            parts.push(format!("q{}", quality));
+        }
+        derivative_key(
+            key,
+            &format!(
+                "{}/{}.{}",
+                version,
+                parts.join("-"),
+                self.format.extension()
+            ),
+        )
+    }
+
+    pub fn apply(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let image = image::load_from_memory(data)?;
+        let image = match (self.width, self.height, self.fit) {
+            (None, None, _) => image,
+            (Some(width), Some(height), Fit::Cover) => {
+                image.resize_to_fill(width, height, FilterType::Lanczos3)
+            }
+            (Some(width), Some(height), Fit::Fill) => {
+                image.resize_exact(width, height, FilterType::Lanczos3)
+            }
+            (width, height, _) => image.resize(
+                width.unwrap_or(u32::MAX),
+                height.unwrap_or(u32::MAX),
+                FilterType::Lanczos3,
+            ),
+        };
+        encode(&image, self.format)
+    }
+}
+
+/// Resizes, crops and converts images on request, results are cached in the bucket.
+#[async_trait]
+pub trait TransformService {
+    /// Query string of the transformation of the current version including its signature.
+    async fn sign(&self, key: &str, params: &TransformParams) -> Result<String>;
+    async fn transform(
+        &self,
+        key: &str,
+        params: &TransformParams,
+        signature: &str,
+    ) -> Result<ObjectStream>;
+}
+
+pub struct DefaultTransformService {
+    resources: Box<dyn Repository<Type = Resource> + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    signer: Signer,
+    bucket: String,
+    config: TransformConfig,
+}
+
+impl DefaultTransformService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db)),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            signer: Signer::new(&config.transform.signing_key),
+            bucket: config.aws.bucket.clone(),
+            config: config.transform.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl TransformService for DefaultTransformService {
+    async fn sign(&self, key: &str, params: &TransformParams) -> Result<String> {
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        let query = TransformParams {
+            version: resource.etag,
+            ..params.clone()
+        }
+        .to_query();
+        let signature = self.signer.sign(&signed_message(key, &query));
+        if query.is_empty() {
+            Ok(format!("s={}", signature))
+        } else {
+            Ok(format!("{}&s={}", query, signature))
+        }
+    }
+
+    async fn transform(
+        &self,
+        key: &str,
+        params: &TransformParams,
+        signature: &str,
+    ) -> Result<ObjectStream> {
+        if !self
+            .signer
+            .verify(&signed_message(key, &params.to_query()), signature)
+        {
+            return Err(AuthError::Forbidden.into());
+        }
+        let transform = Transform::from_params(key, params, &self.config)?;
+        // Cached renditions outlive trashed resources, so they are only served for live ones.
+        let resource = self
+            .resources
+            .get_by_key(key.to_owned())
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Resource {} doesn't exist", key)))?;
+        // URLs signed for a replaced original must not serve the new one under the old URL.
+        if params.version != resource.etag {
+            return Err(ServiceError::NotFound(format!(
+                "Version of resource {} doesn't exist",
+                key
+            ))
+            .into());
+        }
+        let cache_key = transform.cache_key(key, params.version.as_deref().unwrap_or_default());
+        let content_type = Some(transform.format.content_type().to_owned());
+        if let Ok(object) = self
+            .storage
+            .stream_object(self.bucket.as_str(), cache_key.as_str())
+            .await
+        {
+            return Ok(ObjectStream {
+                content_type,
+                ..object
+            });
+        }
+
+        info!("Transform resource {} into {}", resource.key, cache_key);
+        let original = self
+            .storage
+            .download_object(self.bucket.as_str(), resource.key.as_str())
+            .await?;
+        // Decoding and resizing is CPU bound, keep it off the async workers.
+        let data = tokio::task::spawn_blocking(move || transform.apply(&original)).await??;
+        self.storage
+            .upload_object(self.bucket.as_str(), &data, cache_key.as_str())
+            .await?;
+        let data = Bytes::from(data);
+        Ok(ObjectStream {
+            content_type,
+            content_length: Some(data.len() as i64),
+            body: Box::pin(futures::stream::once(async move { Ok(data) })),
+        })
+    }
+}
+
+/// Path of the transformed image, the key is a single percent-encoded segment.
+pub fn transform_path(key: &str) -> String {
+    format!("/img/{}", utf8_percent_encode(key, PATH_SEGMENT))
+}
+
+fn signed_message(key: &str, query: &str) -> String {
+    format!("{}?{}", transform_path(key), query)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::{DynamicImage, GenericImageView, ImageBuffer, Rgb};
+
+    fn config() -> TransformConfig {
+        TransformConfig {
+            signing_key: "secret".to_owned(),
+            max_dimension: 1000,
+            quality: 80,
+        }
+    }
+
+    fn params(width: Option<u32>, height: Option<u32>, fit: Option<&str>) -> TransformParams {
+        TransformParams {
+            width,
+            height,
+            fit: fit.map(str::to_owned),
+            ..TransformParams::default()
+        }
+    }
+
+    #[test]
+    fn test_transform_from_params() {
+        let transform =
+            Transform::from_params("cat.png", &params(Some(300), None, None), &config()).unwrap();
+        assert_eq!(transform.fit, Fit::Contain);
+        assert_eq!(transform.format, OutputFormat::Png);
+        assert_eq!(
+            transform.cache_key("cat.png", "5289df73"),
+            "cat.png@5289df73/w300-contain.png"
+        );
+
+        let transform = Transform::from_params(
+            "cat.png",
+            &TransformParams {
+                format: Some("jpeg".to_owned()),
+                quality: Some(60),
+                ..params(Some(300), Some(200), Some("cover"))
+            },
+            &config(),
+        )
+        .unwrap();
+        assert_eq!(
+            transform.cache_key("cat.png", "5289df73"),
+            "cat.png@5289df73/w300-h200-cover-q60.jpg"
+        );
+        assert_ne!(
+            transform.cache_key("cat.png", "5289df73"),
+            transform.cache_key("cat.png", "d41d8cd9")
+        );
+
+        assert!(Transform::from_params("cat", &params(Some(1001), None, None), &config()).is_err());
+        assert!(Transform::from_params("cat", &params(Some(0), None, None), &config()).is_err());
+        assert!(
+            Transform::from_params("cat", &params(None, None, Some("crop")), &config()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_transform_apply() {
+        let mut data = std::io::Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(ImageBuffer::from_pixel(400, 200, Rgb([10u8, 20, 30])))
+            .write_to(&mut data, image::ImageOutputFormat::Png)
+            .unwrap();
+        let data = data.into_inner();
+        let dimensions = |fit: Fit, width: Option<u32>, height: Option<u32>| {
+            let transform = Transform {
+                width,
+                height,
+                fit,
+                format: OutputFormat::Png,
+            };
+            image::load_from_memory(&transform.apply(&data).unwrap())
+                .unwrap()
+                .dimensions()
+        };
+
+        assert_eq!(dimensions(Fit::Contain, Some(100), Some(100)), (100, 50));
+        assert_eq!(dimensions(Fit::Contain, None, Some(100)), (200, 100));
+        assert_eq!(dimensions(Fit::Cover, Some(100), Some(100)), (100, 100));
+        assert_eq!(dimensions(Fit::Fill, Some(100), Some(30)), (100, 30));
+        assert_eq!(dimensions(Fit::Cover, None, None), (400, 200));
+    }
+
+    #[test]
+    fn test_transform_path() {
+        assert_eq!(transform_path("cat.png"), "/img/cat.png");
+        assert_eq!(
+            transform_path("photos/my cat?#1%.png"),
+            "/img/photos%2Fmy%20cat%3F%231%25.png"
+        );
+    }
+
+    #[test]
+    fn test_signed_query() {
+        let signer = Signer::new("secret");
+        let params = TransformParams {
+            version: Some("5289df73".to_owned()),
+            ..params(Some(300), Some(200), Some("cover"))
+        };
+        let query = params.to_query();
+        assert_eq!(query, "w=300&h=200&fit=cover&v=5289df73");
+
+        let signature = signer.sign(&signed_message("cat.png", &query));
+        assert!(signer.verify(&signed_message("cat.png", &query), &signature));
+        assert!(!signer.verify(&signed_message("dog.png", &query), &signature));
+        assert!(!signer.verify(
+            &signed_message("cat.png", "w=4000&h=200&fit=cover&v=5289df73"),
+            &signature
+        ));
+        assert!(!signer.verify(
+            &signed_message("cat.png", "w=300&h=200&fit=cover&v=d41d8cd9"),
+            &signature
+        ));
+    }
+}
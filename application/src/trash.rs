@@ -0,0 +1,294 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, TrashConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use domain::*;
+use log::{info, warn};
+use remote::DefaultStorage;
+use repository::{is_unique_violation, DerivativeRepository, ResourceRepository};
+use sea_orm::DbConn;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tasks::ScheduledTask;
+use uuid::Uuid;
+
+use crate::users::MAX_PAGE_SIZE;
+use crate::{check_owner, set_system_metadata, Claims, ServiceError};
+
+/// File in the trash.
+#[derive(Debug, Serialize)]
+pub struct TrashedFile {
+    pub id: Uuid,
+    /// Key the file is restored to.
+    pub key: String,
+    pub url: Option<String>,
+    pub tags: Option<Value>,
+    pub user_id: Option<Uuid>,
+    pub metadata: Option<Value>,
+    pub size: Option<i64>,
+    pub deleted_at: DateTime<Utc>,
+    /// The file is purged after this time.
+    pub purge_at: DateTime<Utc>,
+}
+
+#[async_trait]
+pub trait TrashService {
+    /// Moves the resource to the trash, its object under the trash prefix.
+    async fn trash(&self, resource: Resource, deleted_by: Option<Uuid>) -> Result<()>;
+    /// Trashed files of the user, of all users for admins.
+    async fn list(&self, claims: &Claims, page: u64, page_size: u64) -> Result<Page<TrashedFile>>;
+    /// Only the owner or an admin can restore a file, unless its key is taken meanwhile.
+    async fn restore(&self, claims: &Claims, id: Uuid) -> Result<FileObject>;
+    /// Purges the resources trashed longer than the retention period, returns their number.
+    async fn purge_due(&self) -> Result<usize>;
+}
+
+pub struct DefaultTrashService {
+    resources: Box<dyn Resources + Send + Sync>,
+    derivatives: Box<dyn Derivatives + Send + Sync>,
+    storage: Box<dyn Storage + Send + Sync>,
+    bucket: String,
+    hostname: String,
+    config: TrashConfig,
+}
+
+impl DefaultTrashService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            derivatives: Box::new(DerivativeRepository::new(db)),
+            storage: Box::new(DefaultStorage::from_config(config.aws.clone())),
+            bucket: config.aws.bucket.clone(),
+            hostname: config.aws.endpoint.clone(),
+            config: config.trash.clone(),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}/{}/{}", self.hostname, self.bucket, key)
+    }
+
+    /// The resource is changed already, a leftover object isn't served anymore.
+    async fn delete_object(&self, key: &str) {
+        if let Err(err) = self.storage.delete_object(&self.bucket, key).await {
+            warn!("Failed to delete the object {}: {}", key, err);
+        }
+    }
+
+    /// Deletes the resource with its object and the derivatives of its original key, i.e. the
+    /// thumbnails, transforms and IIIF images. Derivatives under a key taken by another
+    /// resource meanwhile are left to that resource.
+    async fn purge(&self, resource: Resource) -> Result<()> {
+        let id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        // The deleted event was recorded when the resource was trashed.
+        self.resources.delete_by_id(id).await?;
+        self.derivatives.delete_by_resource(id).await?;
+        self.delete_object(&resource.key).await;
+        let key = match original_key(&resource) {
+            Some(key) => key,
+            None => return Ok(()),
+        };
+        if self.resources.get_by_key(key.to_owned()).await.is_ok() {
+            info!("Keep the derivatives of {}, the key is taken", key);
+            return Ok(());
+        }
+        let derivatives = self
+            .storage
+            .list_objects_with_prefix(&self.bucket, &derivative_key(key, ""))
+            .await?;
+        for derivative in derivatives {
+            self.delete_object(&derivative).await;
+        }
+        Ok(())
+    }
+
+    fn to_trashed_file(&self, resource: Resource) -> Result<TrashedFile> {
+        let id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        let deleted_at = resource
+            .deleted_at
+            .ok_or_else(|| anyhow::Error::msg(format!("Resource {} isn't trashed", id)))?;
+        Ok(TrashedFile {
+            id,
+            key: original_key(&resource)
+                .unwrap_or(resource.key.as_str())
+                .to_owned(),
+            url: resource.url,
+            tags: resource.tags,
+            user_id: resource.user_id,
+            metadata: resource.metadata,
+            size: resource.size,
+            deleted_at,
+            purge_at: deleted_at + Duration::days(self.config.retention),
+        })
+    }
+}
+
+#[async_trait]
+impl TrashService for DefaultTrashService {
+    async fn trash(&self, resource: Resource, deleted_by: Option<Uuid>) -> Result<()> {
+        let id = resource
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+        let trash_key = format!("{}{}/{}", self.config.prefix, id, resource.key);
+        info!("Move {} to the trash", resource.key);
+        self.storage
+            .copy_object(&self.bucket, &resource.key, &self.bucket, &trash_key)
+            .await?;
+        let metadata = set_system_metadata(
+            resource.metadata.clone(),
+            "trash",
+            json!({ "key": resource.key, "deleted_by": deleted_by }),
+        );
+        let trashed = Resource {
+            key: trash_key.clone(),
+            url: Some(self.url(&trash_key)),
+            metadata,
+            deleted_at: Some(Utc::now()),
+            ..resource.clone()
+        };
+        if let Err(err) = self.resources.trash_with_event(id, trashed).await {
+            self.delete_object(&trash_key).await;
+            return Err(err);
+        }
+        self.delete_object(&resource.key).await;
+        Ok(())
+    }
+
+    async fn list(&self, claims: &Claims, page: u64, page_size: u64) -> Result<Page<TrashedFile>> {
+        let user_id = match claims.role {
+            Role::ADMIN => None,
+            _ => Some(claims.sub),
+        };
+        let trashed = self
+            .resources
+            .find_trashed(TrashFilter {
+                user_id,
+                page,
+                page_size: page_size.clamp(1, MAX_PAGE_SIZE),
+            })
+            .await?;
+        let items = trashed
+            .items
+            .into_iter()
+            .map(|resource| self.to_trashed_file(resource))
+            .collect::<Result<_>>()?;
+        Ok(Page {
+            items,
+            page: trashed.page,
+            page_size: trashed.page_size,
+            total: trashed.total,
+        })
+    }
+
+    async fn restore(&self, claims: &Claims, id: Uuid) -> Result<FileObject> {
+        let resource =
+            self.resources.get_trashed(id).await.map_err(|_| {
+                ServiceError::NotFound(format!("Trashed file {} doesn't exist", id))
+            })?;
+        check_owner(claims, &resource)?;
+        let key = original_key(&resource)
+            .ok_or_else(|| anyhow::Error::msg(format!("Trashed file {} has no key", id)))?
+            .to_owned();
+        if self.resources.get_by_key(key.clone()).await.is_ok() {
+            return Err(ServiceError::Conflict(format!("File {} exists", key)).into());
+        }
+        info!("User {} restores file {}", claims.name, key);
+        let metadata = set_system_metadata(
+            resource.metadata.clone(),
+            "trash",
+            json!({ "restored_at": Utc::now(), "restored_by": claims.sub }),
+        );
+        let restored = Resource {
+            key: key.clone(),
+            url: Some(self.url(&key)),
+            metadata,
+            ..resource.clone()
+        };
+        // Restored before the copy, so the bucket notification of the copy finds the resource
+        // instead of registering a new one.
+        let restored = match self.resources.restore_with_event(id, restored).await {
+            Ok(restored) => restored,
+            Err(err) if is_unique_violation(&err) => {
+                return Err(ServiceError::Conflict(format!("File {} exists", key)).into());
+            }
+            Err(err) => return Err(err),
+        };
+        if let Err(err) = self
+            .storage
+            .copy_object(&self.bucket, &resource.key, &self.bucket, &key)
+            .await
+        {
+            // Back in the trash, where its object still is.
+            self.resources.trash_with_event(id, resource).await?;
+            return Err(err);
+        }
+        self.delete_object(&resource.key).await;
+        Ok(to_file_object(restored))
+    }
+
+    async fn purge_due(&self) -> Result<usize> {
+        let deleted_before = Utc::now() - Duration::days(self.config.retention);
+        let purgeable = self
+            .resources
+            .find_purgeable(deleted_before, self.config.batch_size)
+            .await?;
+        let mut purged = 0;
+        for resource in purgeable {
+            let key = resource.key.clone();
+            match self.purge(resource).await {
+                Ok(()) => purged += 1,
+                Err(err) => warn!("Failed to purge {} from the trash: {}", key, err),
+            }
+        }
+        Ok(purged)
+    }
+}
+
+/// Runs every hour.
+#[async_trait]
+impl ScheduledTask for DefaultTrashService {
+    async fn run(&self) -> Result<()> {
+        let purged = self.purge_due().await?;
+        if purged > 0 {
+            info!("Purged {} resources from the trash", purged);
+        }
+        Ok(())
+    }
+}
+
+/// Trashed objects are kept under `<prefix><id>/<key>`.
+fn original_key(resource: &Resource) -> Option<&str> {
+    let id = resource.id?;
+    resource
+        .key
+        .split_once(&format!("{}/", id))
+        .map(|(_, key)| key)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_original_key() {
+        let id = Uuid::new_v4();
+        let resource = Resource {
+            id: Some(id),
+            key: format!(".trash/{}/albums/cat.png", id),
+            ..Resource::default()
+        };
+        assert_eq!(original_key(&resource), Some("albums/cat.png"));
+        let untrashed = Resource {
+            id: Some(id),
+            key: "albums/cat.png".to_owned(),
+            ..Resource::default()
+        };
+        assert_eq!(original_key(&untrashed), None);
+    }
+}
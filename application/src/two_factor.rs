@@ -0,0 +1,244 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, AuthConfig};
+use async_trait::async_trait;
+use chrono::Utc;
+use domain::*;
+use log::info;
+use qrcode::{render::svg, QrCode};
+use rand::Rng;
+use repository::{RecoveryCodeRepository, TwoFactorRepository, UserRepository};
+use sea_orm::DbConn;
+use serde::Serialize;
+use util::{SecretCipher, Totp};
+use uuid::Uuid;
+
+use crate::{hash_token, AuthError, ServiceError};
+
+const RECOVERY_CODES_COUNT: usize = 10;
+const RECOVERY_CODE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+const RECOVERY_CODE_PART_LENGTH: usize = 5;
+
+/// Secret of a pending enrollment, shown to the user once to configure an authenticator app.
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetup {
+    pub secret: String,
+    pub provisioning_uri: String,
+    pub qr_code_svg: String,
+}
+
+/// RFC 6238 TOTP enrollment of user accounts with single-use recovery codes.
+#[async_trait]
+pub trait TwoFactorService {
+    /// Starts a new enrollment, replacing a pending one. Fails if 2FA is already enabled.
+    async fn setup(&self, user_id: Uuid) -> Result<TwoFactorSetup>;
+    /// Confirms a pending enrollment with a code of the app, returns the recovery codes.
+    async fn enable(&self, user_id: Uuid, code: &str) -> Result<Vec<String>>;
+    async fn disable(&self, user_id: Uuid, code: &str) -> Result<()>;
+    async fn regenerate_recovery_codes(&self, user_id: Uuid, code: &str) -> Result<Vec<String>>;
+    /// Removes the enrollment without a code, for users who lost their device.
+    async fn reset(&self, user_id: Uuid) -> Result<()>;
+    async fn is_enabled(&self, user_id: Uuid) -> Result<bool>;
+    /// Accepts either a TOTP code or an unused recovery code.
+    async fn verify(&self, user_id: Uuid, code: &str) -> Result<bool>;
+}
+
+pub struct DefaultTwoFactorService {
+    users: Box<dyn Repository<Type = User> + Send + Sync>,
+    two_factors: Box<dyn TwoFactors + Send + Sync>,
+    recovery_codes: Box<dyn RecoveryCodes + Send + Sync>,
+    cipher: SecretCipher,
+    config: AuthConfig,
+}
+
+impl DefaultTwoFactorService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            users: Box::new(UserRepository::new(db.clone())),
+            two_factors: Box::new(TwoFactorRepository::new(db.clone())),
+            recovery_codes: Box::new(RecoveryCodeRepository::new(db)),
+            cipher: SecretCipher::new(&config.auth.totp_encryption_key),
+            config: config.auth.clone(),
+        }
+    }
+
+    async fn enabled_two_factor(&self, user_id: Uuid) -> Result<TwoFactor> {
+        match self.two_factors.get_by_user(user_id).await? {
+            Some(two_factor) if two_factor.enabled => Ok(two_factor),
+            _ => Err(ServiceError::BadRequest(
+                "Two-factor authentication is not enabled".to_owned(),
+            )
+            .into()),
+        }
+    }
+
+    /// Checks a TOTP code, a code of an already used time step is rejected to prevent replays.
+    async fn verify_totp(&self, two_factor: TwoFactor, code: &str) -> Result<bool> {
+        let id = two_factor.id.ok_or(AuthError::InvalidCode)?;
+        let secret = self.cipher.decrypt(&two_factor.secret)?;
+        let step = match Totp::verify(&secret, code, Utc::now().timestamp() as u64) {
+            Some(step) => step as i64,
+            None => return Ok(false),
+        };
+        if two_factor.last_used_step.map_or(false, |last| step <= last) {
+            return Ok(false);
+        }
+        self.two_factors
+            .update(id, two_factor.with_last_used_step(step))
+            .await?;
+        Ok(true)
+    }
+
+    async fn verify_enrolled(&self, two_factor: TwoFactor, code: &str) -> Result<bool> {
+        let user_id = two_factor.user_id;
+        if self.verify_totp(two_factor, code).await? {
+            return Ok(true);
+        }
+        self.recovery_codes
+            .consume(user_id, &hash_token(&normalize_recovery_code(code)))
+            .await
+    }
+
+    async fn create_recovery_codes(&self, user_id: Uuid) -> Result<Vec<String>> {
+        self.recovery_codes.delete_by_user(user_id).await?;
+        let codes = generate_recovery_codes();
+        for code in &codes {
+            self.recovery_codes
+                .create(RecoveryCode::new(user_id, &hash_token(code)))
+                .await?;
+        }
+        Ok(codes)
+    }
+}
+
+#[async_trait]
+impl TwoFactorService for DefaultTwoFactorService {
+    async fn setup(&self, user_id: Uuid) -> Result<TwoFactorSetup> {
+        let user = self.users.get_by_id(user_id).await?;
+        if let Some(two_factor) = self.two_factors.get_by_user(user_id).await? {
+            if two_factor.enabled {
+                return Err(ServiceError::Conflict(
+                    "Two-factor authentication is already enabled".to_owned(),
+                )
+                .into());
+            }
+            self.two_factors.delete_by_user(user_id).await?;
+        }
+        info!("Setup two-factor authentication for user {}", user.name);
+        let secret = Totp::generate_secret();
+        self.two_factors
+            .create(TwoFactor::new(user_id, &self.cipher.encrypt(&secret)?))
+            .await?;
+
+        let provisioning_uri =
+            Totp::provisioning_uri(&secret, &self.config.totp_issuer, &user.name);
+        let qr_code_svg = QrCode::new(provisioning_uri.as_bytes())?
+            .render::<svg::Color>()
+            .min_dimensions(200, 200)
+            .build();
+        Ok(TwoFactorSetup {
+            secret: Totp::encode_secret(&secret),
+            provisioning_uri,
+            qr_code_svg,
+        })
+    }
+
+    async fn enable(&self, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+        let two_factor = match self.two_factors.get_by_user(user_id).await? {
+            Some(two_factor) if !two_factor.enabled => two_factor,
+            Some(_) => {
+                return Err(ServiceError::Conflict(
+                    "Two-factor authentication is already enabled".to_owned(),
+                )
+                .into())
+            }
+            None => {
+                return Err(ServiceError::BadRequest(
+                    "Two-factor authentication is not set up".to_owned(),
+                )
+                .into())
+            }
+        };
+        let id = two_factor.id.ok_or(AuthError::InvalidCode)?;
+        if !self.verify_totp(two_factor, code).await? {
+            return Err(AuthError::InvalidCode.into());
+        }
+        let two_factor = self.two_factors.get_by_id(id).await?;
+        self.two_factors.update(id, two_factor.enable(true)).await?;
+        info!("Enabled two-factor authentication for user {}", user_id);
+        self.create_recovery_codes(user_id).await
+    }
+
+    async fn disable(&self, user_id: Uuid, code: &str) -> Result<()> {
+        let two_factor = self.enabled_two_factor(user_id).await?;
+        if !self.verify_enrolled(two_factor, code).await? {
+            return Err(AuthError::InvalidCode.into());
+        }
+        self.reset(user_id).await
+    }
+
+    async fn regenerate_recovery_codes(&self, user_id: Uuid, code: &str) -> Result<Vec<String>> {
+        let two_factor = self.enabled_two_factor(user_id).await?;
+        if !self.verify_totp(two_factor, code).await? {
+            return Err(AuthError::InvalidCode.into());
+        }
+        info!("Regenerate recovery codes of user {}", user_id);
+        self.create_recovery_codes(user_id).await
+    }
+
+    async fn reset(&self, user_id: Uuid) -> Result<()> {
+        info!("Disable two-factor authentication for user {}", user_id);
+        self.recovery_codes.delete_by_user(user_id).await?;
+        self.two_factors.delete_by_user(user_id).await
+    }
+
+    async fn is_enabled(&self, user_id: Uuid) -> Result<bool> {
+        Ok(self
+            .two_factors
+            .get_by_user(user_id)
+            .await?
+            .map_or(false, |two_factor| two_factor.enabled))
+    }
+
+    async fn verify(&self, user_id: Uuid, code: &str) -> Result<bool> {
+        match self.two_factors.get_by_user(user_id).await? {
+            Some(two_factor) if two_factor.enabled => self.verify_enrolled(two_factor, code).await,
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Recovery codes look like `abcde-fghjk`, without characters that are easy to confuse.
+fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::thread_rng();
+    let mut part = || -> String {
+        (0..RECOVERY_CODE_PART_LENGTH)
+            .map(|_| RECOVERY_CODE_ALPHABET[rng.gen_range(0..RECOVERY_CODE_ALPHABET.len())] as char)
+            .collect()
+    };
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| format!("{}-{}", part(), part()))
+        .collect()
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.trim().to_lowercase()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_generate_recovery_codes() {
+        let codes = generate_recovery_codes();
+
+        assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+        for code in &codes {
+            assert_eq!(code.len(), RECOVERY_CODE_PART_LENGTH * 2 + 1);
+            assert_eq!(&normalize_recovery_code(&code.to_uppercase()), code);
+        }
+        assert_ne!(codes[0], codes[1]);
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use domain::*;
+use repository::{RefreshTokenRepository, UserRepository};
+use sea_orm::DbConn;
+use util::PasswordEncoder;
+use uuid::Uuid;
+
+use crate::{AuthError, Claims, ServiceError};
+
+pub(crate) const MAX_PAGE_SIZE: u64 = 100;
+
+#[async_trait]
+pub trait UserService {
+    async fn list(&self, admin: &Claims, filter: UserFilter) -> Result<Page<User>>;
+    async fn create(&self, admin: &Claims, user: User) -> Result<User>;
+    async fn update_role(&self, admin: &Claims, id: Uuid, role: Role) -> Result<User>;
+    async fn set_enabled(&self, admin: &Claims, id: Uuid, enabled: bool) -> Result<User>;
+    async fn delete(&self, admin: &Claims, id: Uuid) -> Result<()>;
+}
+
+pub struct DefaultUserService {
+    users: Box<dyn UserDirectory + Send + Sync>,
+    tokens: Box<dyn TokenRepository + Send + Sync>,
+}
+
+impl DefaultUserService {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self {
+            users: Box::new(UserRepository::new(db.clone())),
+            tokens: Box::new(RefreshTokenRepository::new(db)),
+        }
+    }
+
+    async fn get(&self, id: Uuid) -> Result<User> {
+        self.users.get_by_id(id).await.map_err(|_| {
+            ServiceError::NotFound(format!("User with id {} doesn't exist", id)).into()
+        })
+    }
+
+    /// Creates a user from the raw password, the password is stored encoded. Registration
+    /// creates users without an admin, all others go through [`UserService::create`].
+    pub(crate) async fn create_user(&self, user: User) -> Result<User> {
+        if user.name.is_empty() || user.email.is_empty() || user.password.is_empty() {
+            return Err(ServiceError::BadRequest(
+                "Name, email and password are required".to_owned(),
+            )
+            .into());
+        }
+        if self.users.get_by_key(user.name.clone()).await.is_ok() {
+            return Err(
+                ServiceError::Conflict(format!("User {} already exists", user.name)).into(),
+            );
+        }
+        if self.users.get_by_email(user.email.clone()).await.is_ok() {
+            return Err(ServiceError::Conflict(format!(
+                "User with email {} already exists",
+                user.email
+            ))
+            .into());
+        }
+        let password = PasswordEncoder::encode(&user.password);
+        self.users
+            .create(User {
+                id: None,
+                ..user.with_password(&password)
+            })
+            .await
+    }
+}
+
+#[async_trait]
+impl UserService for DefaultUserService {
+    async fn list(&self, admin: &Claims, filter: UserFilter) -> Result<Page<User>> {
+        check_admin(admin)?;
+        self.users
+            .find(UserFilter {
+                page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+                ..filter
+            })
+            .await
+    }
+
+    async fn create(&self, admin: &Claims, user: User) -> Result<User> {
+        check_admin(admin)?;
+        self.create_user(user).await
+    }
+
+    async fn update_role(&self, admin: &Claims, id: Uuid, role: Role) -> Result<User> {
+        check_admin(admin)?;
+        if admin.sub == id {
+            return Err(
+                ServiceError::BadRequest("Admins can't change their own role".to_owned()).into(),
+            );
+        }
+        let user = self.get(id).await?;
+        self.users.update(id, user.with_role(role)).await
+    }
+
+    /// Disabling a user also revokes all of their sessions.
+    async fn set_enabled(&self, admin: &Claims, id: Uuid, enabled: bool) -> Result<User> {
+        check_admin(admin)?;
+        if admin.sub == id && !enabled {
+            return Err(
+                ServiceError::BadRequest("Admins can't disable themselves".to_owned()).into(),
+            );
+        }
+        let user = self.get(id).await?;
+        let updated = self.users.update(id, user.enable(enabled)).await?;
+        if !enabled {
+            self.tokens.revoke_all_for_user(id).await?;
+        }
+        Ok(updated)
+    }
+
+    async fn delete(&self, admin: &Claims, id: Uuid) -> Result<()> {
+        check_admin(admin)?;
+        if admin.sub == id {
+            return Err(
+                ServiceError::BadRequest("Admins can't delete themselves".to_owned()).into(),
+            );
+        }
+        self.get(id).await?;
+        self.tokens.revoke_all_for_user(id).await?;
+        self.users.delete_by_id(id).await
+    }
+}
+
+fn check_admin(claims: &Claims) -> Result<(), AuthError> {
+    match claims.role {
+        Role::ADMIN => Ok(()),
+        _ => Err(AuthError::Forbidden),
+    }
+}
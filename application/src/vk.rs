@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, VkConfig};
+use async_trait::async_trait;
+use bytes::Bytes;
+use domain::*;
+use log::info;
+use remote::VkClient;
+use repository::ResourceRepository;
+use sea_orm::DbConn;
+use tasks::ScheduledTask;
+
+use crate::{caption, DefaultPublicationService, PublicationService, Publisher};
+
+/// Value of the `publish` tag of images queued for the VK community, also the name of the
+/// publication target.
+pub const VK_TARGET: &str = "vk";
+
+/// Posts images to the wall of the configured community.
+pub struct VkPublisher {
+    client: VkClient,
+}
+
+impl VkPublisher {
+    pub fn new(config: &ApplicationConfig) -> Self {
+        Self {
+            client: VkClient::new(config.vk.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for VkPublisher {
+    fn target(&self) -> &'static str {
+        VK_TARGET
+    }
+
+    async fn publish(&self, resource: &Resource, data: Bytes) -> Result<String> {
+        let file_name = resource.key.rsplit('/').next().unwrap_or_default();
+        let post_id = self
+            .client
+            .post_photo(file_name, data.to_vec(), &caption(resource))
+            .await?;
+        Ok(post_id.to_string())
+    }
+}
+
+#[async_trait]
+pub trait VkService {
+    /// Queues the next images tagged `publish: vk` for publishing right away. An image is
+    /// queued again only after its publication failed.
+    async fn queue_tagged(&self) -> Result<Vec<Publication>>;
+}
+
+pub struct DefaultVkService {
+    resources: Box<dyn Resources + Send + Sync>,
+    publications: DefaultPublicationService,
+    config: VkConfig,
+}
+
+impl DefaultVkService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            resources: Box::new(ResourceRepository::new(db.clone())),
+            publications: DefaultPublicationService::new(config, db),
+            config: config.vk.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl VkService for DefaultVkService {
+    async fn queue_tagged(&self) -> Result<Vec<Publication>> {
+        let tagged = self
+            .resources
+            .find_publishable(VK_TARGET, self.config.batch_size)
+            .await?;
+        info!("Queue {} images for VK", tagged.len());
+        let mut queued = vec![];
+        for resource in tagged {
+            queued.push(
+                self.publications
+                    .enqueue(&resource.key, VK_TARGET, None)
+                    .await?,
+            );
+        }
+        Ok(queued)
+    }
+}
+
+/// Runs once a day at the configured time, the jobs of the publications post the images.
+#[async_trait]
+impl ScheduledTask for DefaultVkService {
+    async fn run(&self) -> Result<()> {
+        self.queue_tagged().await?;
+        Ok(())
+    }
+}
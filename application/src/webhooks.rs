@@ -0,0 +1,300 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, WebhooksConfig};
+use async_trait::async_trait;
+use chrono::Utc;
+use domain::*;
+use log::{info, warn};
+use remote::WebhookNotifier;
+use repository::{WebhookDeliveryRepository, WebhookSubscriptionRepository};
+use reqwest::Url;
+use sea_orm::DbConn;
+use serde::{Deserialize, Serialize};
+use tasks::{JobHandler, JobType};
+use util::Signer;
+use uuid::Uuid;
+
+use crate::users::MAX_PAGE_SIZE;
+use crate::{generate_token, ServiceError};
+
+/// Id of the delivery, the same for all attempts.
+pub const DELIVERY_HEADER: &str = "X-Webhook-Id";
+/// Name of the event, e.g. `resource.created`.
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// Unix time of the attempt, part of the signed message.
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+/// `v1=` followed by the signature of the timestamp and the body, see `signature`.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Job sending a delivery, enqueued with the delivery by `WebhookDelivery::job`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeliverWebhook {
+    pub delivery_id: Uuid,
+}
+
+impl JobType for DeliverWebhook {
+    const KIND: &'static str = DELIVER_WEBHOOK_JOB;
+}
+
+/// Created subscription with its secret, the secret isn't returned again.
+#[derive(Debug)]
+pub struct CreatedSubscription {
+    pub secret: String,
+    pub subscription: WebhookSubscription,
+}
+
+#[async_trait]
+pub trait WebhookService {
+    /// Subscribes the URL to the event types, to all types if empty. A secret is generated
+    /// unless one is given.
+    async fn subscribe(
+        &self,
+        url: &str,
+        secret: Option<String>,
+        event_types: &[String],
+    ) -> Result<CreatedSubscription>;
+    /// Pending deliveries of the subscription aren't sent anymore.
+    async fn unsubscribe(&self, id: Uuid) -> Result<()>;
+    async fn subscriptions(&self) -> Result<Vec<WebhookSubscription>>;
+    async fn deliveries(&self, filter: WebhookDeliveryFilter) -> Result<Page<WebhookDelivery>>;
+}
+
+pub struct DefaultWebhookService {
+    subscriptions: Box<dyn Repository<Type = WebhookSubscription> + Send + Sync>,
+    deliveries: Box<dyn WebhookDeliveries + Send + Sync>,
+    notifier: WebhookNotifier,
+    config: WebhooksConfig,
+}
+
+impl DefaultWebhookService {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            subscriptions: Box::new(WebhookSubscriptionRepository::new(db.clone())),
+            deliveries: Box::new(WebhookDeliveryRepository::new(db)),
+            notifier: WebhookNotifier::new(config.webhooks.timeout),
+            config: config.webhooks.clone(),
+        }
+    }
+
+    /// A failed attempt keeps its outcome on the delivery and fails the job, which is retried
+    /// by the job queue.
+    async fn deliver(&self, delivery: WebhookDelivery) -> Result<()> {
+        let id = delivery
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Started delivery has no id"))?;
+        let attempt = delivery.attempts;
+        let subscription = match self.subscriptions.get_by_id(delivery.subscription_id).await {
+            Ok(subscription) => subscription,
+            Err(_) => {
+                self.deliveries.fail(id, "Subscription was removed").await?;
+                return Ok(());
+            }
+        };
+        let (response_status, error) = match self.send(id, &subscription, &delivery).await {
+            Ok(status) if (200..300).contains(&status) => {
+                self.deliveries.complete(id, attempt, status).await?;
+                return Ok(());
+            }
+            Ok(status) => (Some(status), format!("Responded with status {}", status)),
+            Err(err) => (None, err.to_string()),
+        };
+        info!(
+            "Delivery {} of event {} failed on attempt {}: {}",
+            id, delivery.event_id, attempt, error
+        );
+        self.deliveries
+            .retry(id, attempt, response_status, &error)
+            .await?;
+        Err(anyhow::Error::msg(error))
+    }
+
+    async fn send(
+        &self,
+        id: Uuid,
+        subscription: &WebhookSubscription,
+        delivery: &WebhookDelivery,
+    ) -> Result<i32> {
+        let body = delivery.payload.to_string();
+        let timestamp = Utc::now().timestamp();
+        let headers = [
+            (DELIVERY_HEADER, id.to_string()),
+            (EVENT_HEADER, delivery.event_type.name()),
+            (TIMESTAMP_HEADER, timestamp.to_string()),
+            (
+                SIGNATURE_HEADER,
+                signature(&subscription.secret, timestamp, &body),
+            ),
+        ];
+        let status = self
+            .notifier
+            .notify(&subscription.url, body, &headers)
+            .await?;
+        Ok(status as i32)
+    }
+}
+
+#[async_trait]
+impl WebhookService for DefaultWebhookService {
+    async fn subscribe(
+        &self,
+        url: &str,
+        secret: Option<String>,
+        event_types: &[String],
+    ) -> Result<CreatedSubscription> {
+        match Url::parse(url) {
+            Ok(parsed) if ["http", "https"].contains(&parsed.scheme()) => {}
+            _ => return Err(ServiceError::BadRequest(format!("Invalid URL {}", url)).into()),
+        }
+        let event_types = event_types
+            .iter()
+            .map(|name| {
+                name.parse()
+                    .map_err(|_| ServiceError::BadRequest(format!("Unknown event type {}", name)))
+            })
+            .collect::<Result<Vec<ResourceEventType>, ServiceError>>()?;
+        let secret = match secret {
+            Some(secret) if secret.is_empty() => {
+                return Err(ServiceError::BadRequest(
+                    "Secret of a webhook can't be empty".to_owned(),
+                )
+                .into())
+            }
+            Some(secret) => secret,
+            None => generate_token().0,
+        };
+        info!("Subscribe {} to {:?}", url, event_types);
+        let subscription = self
+            .subscriptions
+            .create(WebhookSubscription::new(
+                url,
+                &secret,
+                event_types,
+                self.config.max_attempts,
+            ))
+            .await?;
+        Ok(CreatedSubscription {
+            secret,
+            subscription,
+        })
+    }
+
+    async fn unsubscribe(&self, id: Uuid) -> Result<()> {
+        self.subscriptions
+            .get_by_id(id)
+            .await
+            .map_err(|_| ServiceError::NotFound(format!("Webhook {} doesn't exist", id)))?;
+        info!("Unsubscribe webhook {}", id);
+        self.subscriptions.delete_by_id(id).await
+    }
+
+    async fn subscriptions(&self) -> Result<Vec<WebhookSubscription>> {
+        self.subscriptions.get_all().await
+    }
+
+    async fn deliveries(&self, filter: WebhookDeliveryFilter) -> Result<Page<WebhookDelivery>> {
+        self.deliveries
+            .find(WebhookDeliveryFilter {
+                page_size: filter.page_size.clamp(1, MAX_PAGE_SIZE),
+                ..filter
+            })
+            .await
+    }
+}
+
+/// Sends the delivery of the job unless it was delivered or failed already.
+#[async_trait]
+impl JobHandler for DefaultWebhookService {
+    type Job = DeliverWebhook;
+
+    async fn handle(&self, job: DeliverWebhook) -> Result<()> {
+        match self.deliveries.start(job.delivery_id).await? {
+            Some(delivery) => self.deliver(delivery).await,
+            None => {
+                info!("Delivery {} isn't pending anymore", job.delivery_id);
+                Ok(())
+            }
+        }
+    }
+
+    async fn dead_lettered(&self, job: DeliverWebhook, error: &str) -> Result<()> {
+        if self.deliveries.fail(job.delivery_id, error).await? {
+            warn!(
+                "Delivery {} failed on the last attempt: {}",
+                job.delivery_id, error
+            );
+        }
+        Ok(())
+    }
+}
+
+/// HMAC-SHA256 of `<timestamp>.<body>` with the secret of the subscription, as URL safe
+/// base64 without padding. Receivers should reject old timestamps to prevent replays.
+pub fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    format!(
+        "v1={}",
+        Signer::new(secret).sign(&format!("{}.{}", timestamp, body))
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        let body = r#"{"type":"resource.created"}"#;
+        let signature = signature("secret", 1668902400, body);
+        let signed = signature.strip_prefix("v1=").unwrap();
+
+        assert!(Signer::new("secret").verify(&format!("1668902400.{}", body), signed));
+        assert!(!Signer::new("secret").verify(&format!("1668902401.{}", body), signed));
+        assert!(!Signer::new("other").verify(&format!("1668902400.{}", body), signed));
+    }
+
+    #[test]
+    fn test_subscription_matches() {
+        let all = WebhookSubscription::new("http://localhost/hook", "secret", vec![], 8);
+        let deleted = WebhookSubscription::new(
+            "http://localhost/hook",
+            "secret",
+            vec![ResourceEventType::Deleted],
+            8,
+        );
+
+        assert!(all.matches(ResourceEventType::Created));
+        assert!(deleted.matches(ResourceEventType::Deleted));
+        assert!(!deleted.matches(ResourceEventType::Updated));
+        assert!(!WebhookSubscription {
+            enabled: false,
+            ..all
+        }
+        .matches(ResourceEventType::Created));
+        assert_eq!(
+            "resource.deleted".parse::<ResourceEventType>(),
+            Ok(ResourceEventType::Deleted)
+        );
+    }
+
+    #[test]
+    fn test_delivery_job() {
+        let event = ResourceEvent::new(
+            ResourceEventType::Created,
+            Uuid::new_v4(),
+            &Resource::default(),
+        );
+        let delivery = WebhookDelivery {
+            id: Some(Uuid::new_v4()),
+            ..WebhookDelivery::new(Uuid::new_v4(), 1, &event)
+        };
+        let job = delivery.job(8).unwrap();
+        let payload: DeliverWebhook = serde_json::from_value(job.payload).unwrap();
+
+        assert_eq!(job.kind, DeliverWebhook::KIND);
+        assert_eq!(job.max_attempts, 8);
+        assert_eq!(Some(payload.delivery_id), delivery.id);
+        assert!(WebhookDelivery::new(Uuid::new_v4(), 1, &event)
+            .job(8)
+            .is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{Page, Repository};
+
+/// Security relevant event, kept for review by admins.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub id: Option<Uuid>,
+    pub kind: AuditEventKind,
+    pub user_id: Option<Uuid>,
+    pub client_ip: Option<String>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditEventKind {
+    AccountLocked,
+    ClientLocked,
+}
+
+impl Display for AuditEventKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for AuditEventKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<AuditEventKind, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "accountlocked" => Ok(AuditEventKind::AccountLocked),
+            "clientlocked" => Ok(AuditEventKind::ClientLocked),
+            _ => Err(()),
+        }
+    }
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditEventKind, message: &str) -> Self {
+        Self {
+            id: None,
+            kind,
+            user_id: None,
+            client_ip: None,
+            message: message.to_owned(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_user_id(mut self, user_id: Option<Uuid>) -> Self {
+        self.user_id = user_id;
+        self
+    }
+
+    pub fn with_client_ip(mut self, client_ip: &str) -> Self {
+        self.client_ip = Some(client_ip.to_owned());
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuditEventFilter {
+    pub kind: Option<AuditEventKind>,
+    pub user_id: Option<Uuid>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+#[async_trait]
+pub trait AuditLog: Repository<Type = AuditEvent> {
+    /// Events matching the filter, the most recent first.
+    async fn find(&self, filter: AuditEventFilter) -> Result<Page<AuditEvent>>;
+}
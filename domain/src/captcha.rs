@@ -0,0 +1,40 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Challenge of an image CAPTCHA, only the hash of the answer is stored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Captcha {
+    pub id: Option<Uuid>,
+    pub answer_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Captcha {
+    pub fn new(answer_hash: &str, expires_at: DateTime<Utc>) -> Self {
+        Self {
+            id: None,
+            answer_hash: answer_hash.to_owned(),
+            expires_at,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+#[async_trait]
+pub trait Captchas: Repository<Type = Captcha> {
+    /// Deletes the challenge and returns it, so that every challenge is answered only once.
+    async fn take(&self, id: Uuid) -> Result<Option<Captcha>>;
+    async fn delete_expired(&self) -> Result<u64>;
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Generated rendition of a resource, e.g. a thumbnail, stored next to the original object.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Derivative {
+    pub id: Option<Uuid>,
+    pub resource_id: Uuid,
+    /// Bounding box the rendition fits into, in pixels.
+    pub size: i32,
+    pub key: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+    pub byte_size: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Derivative {
+    pub fn new(resource_id: Uuid, size: i32, key: &str, content_type: &str) -> Self {
+        Self {
+            id: None,
+            resource_id,
+            size,
+            key: key.to_owned(),
+            content_type: content_type.to_owned(),
+            width: 0,
+            height: 0,
+            byte_size: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_dimensions(mut self, width: i32, height: i32) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn with_byte_size(mut self, byte_size: i64) -> Self {
+        self.byte_size = byte_size;
+        self
+    }
+}
+
+/// Separates the key of the original from the suffix of its derived objects, e.g.
+/// `cat.png@128.png`.
+pub const DERIVATIVE_SEPARATOR: char = '@';
+
+/// Key of an object derived from the original, stored next to it.
+pub fn derivative_key(key: &str, suffix: &str) -> String {
+    format!("{}{}{}", key, DERIVATIVE_SEPARATOR, suffix)
+}
+
+/// Whether the key is of a derived object rather than of an original.
+pub fn is_derivative_key(key: &str) -> bool {
+    key.contains(DERIVATIVE_SEPARATOR)
+}
+
+#[async_trait]
+pub trait Derivatives: Repository<Type = Derivative> {
+    async fn find_by_resource(&self, resource_id: Uuid) -> Result<Vec<Derivative>>;
+    async fn get_by_size(&self, resource_id: Uuid, size: i32) -> Result<Option<Derivative>>;
+    async fn delete_by_resource(&self, resource_id: Uuid) -> Result<()>;
+}
@@ -1,4 +1,5 @@
 use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use serde::Serialize;
 use serde_json::Value;
 use uuid::Uuid;
@@ -10,5 +11,10 @@ pub struct FileObject {
     pub tags: Option<Value>,
     pub user_id: Option<Uuid>,
     pub metadata: Option<Value>,
+    pub blurhash: Option<String>,
+    pub palette: Option<Value>,
+    pub size: Option<i64>,
+    pub etag: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
     pub data: Option<Bytes>,
 }
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{Page, Repository};
+
+/// Background work of the durable job queue.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: Option<Uuid>,
+    /// Selects the handler of the job.
+    pub kind: String,
+    pub payload: Value,
+    pub status: JobStatus,
+    /// Number of times the job was claimed by a worker.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// The job is not claimed before this time.
+    pub run_at: DateTime<Utc>,
+    /// A running job whose lock expired is claimed again.
+    pub locked_until: Option<DateTime<Utc>>,
+    /// Start of the latest attempt.
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    /// Failed on the last attempt, kept for inspection.
+    Dead,
+    Cancelled,
+}
+
+impl Display for JobStatus {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<JobStatus, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "pending" => Ok(JobStatus::Pending),
+            "running" => Ok(JobStatus::Running),
+            "done" => Ok(JobStatus::Done),
+            "dead" => Ok(JobStatus::Dead),
+            "cancelled" => Ok(JobStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Job {
+    pub fn new(kind: &str, payload: Value, max_attempts: i32) -> Self {
+        Self {
+            id: None,
+            kind: kind.to_owned(),
+            payload,
+            status: JobStatus::Pending,
+            attempts: 0,
+            max_attempts,
+            run_at: Utc::now(),
+            locked_until: None,
+            started_at: None,
+            last_error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_run_at(mut self, run_at: DateTime<Utc>) -> Self {
+        self.run_at = run_at;
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct JobFilter {
+    pub kind: Option<String>,
+    pub status: Option<JobStatus>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// State of the jobs of one kind, throughput and latencies are of the jobs done since a time.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct JobQueueStats {
+    pub kind: String,
+    /// Paused queues are skipped by the workers.
+    pub paused: bool,
+    pub pending: i64,
+    pub running: i64,
+    pub done: i64,
+    pub dead: i64,
+    pub cancelled: i64,
+    pub done_since: i64,
+    /// Average seconds from the due time to the start of the last attempt.
+    pub avg_latency: Option<f64>,
+    /// Average seconds of the last attempt.
+    pub avg_duration: Option<f64>,
+}
+
+/// Transitions of claimed jobs are guarded by the attempt, so a worker whose lock expired
+/// can't overwrite the outcome of the worker that took the job over.
+#[async_trait]
+pub trait Jobs: Repository<Type = Job> {
+    /// Locks up to `limit` due jobs of the kind until `locked_until`, skipping jobs locked
+    /// by other workers. Running jobs whose lock expired are claimed again.
+    async fn claim(&self, kind: &str, limit: u64, locked_until: DateTime<Utc>) -> Result<Vec<Job>>;
+    async fn complete(&self, id: Uuid, attempt: i32) -> Result<bool>;
+    /// Releases the job to be claimed again at `run_at`.
+    async fn retry(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        run_at: DateTime<Utc>,
+        error: &str,
+    ) -> Result<bool>;
+    async fn dead_letter(&self, id: Uuid, attempt: i32, error: &str) -> Result<bool>;
+    /// Newest first.
+    async fn find(&self, filter: JobFilter) -> Result<Page<Job>>;
+    /// Runs a dead or cancelled job again right away with all attempts left.
+    async fn requeue(&self, id: Uuid) -> Result<bool>;
+    /// Cancels a pending or dead job.
+    async fn cancel(&self, id: Uuid) -> Result<bool>;
+    /// Jobs of a paused kind aren't claimed, running ones finish.
+    async fn set_paused(&self, kind: &str, paused: bool) -> Result<()>;
+    async fn stats(&self, since: DateTime<Utc>) -> Result<Vec<JobQueueStats>>;
+}
@@ -1,12 +1,44 @@
+mod audit_event;
+mod captcha;
+mod derivative;
 mod file_object;
+mod job;
+mod login_throttle;
+mod mailer;
+mod page;
+mod publication;
+mod refresh_token;
 mod resource;
+mod resource_event;
+mod schedule_run;
+mod share;
 mod storage;
+mod two_factor;
 mod user;
+mod user_identity;
+mod user_token;
+mod webhook;
 
+pub use audit_event::*;
+pub use captcha::*;
+pub use derivative::*;
 pub use file_object::*;
+pub use job::*;
+pub use login_throttle::*;
+pub use mailer::*;
+pub use page::*;
+pub use publication::*;
+pub use refresh_token::*;
 pub use resource::*;
+pub use resource_event::*;
+pub use schedule_run::*;
+pub use share::*;
 pub use storage::*;
+pub use two_factor::*;
 pub use user::*;
+pub use user_identity::*;
+pub use user_token::*;
+pub use webhook::*;
 
 use anyhow::Result;
 use async_trait::async_trait;
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Failed login attempts of a user name or a client address.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LoginThrottle {
+    pub id: Option<Uuid>,
+    pub key: String,
+    pub failures: i32,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl LoginThrottle {
+    pub fn user_key(name: &str) -> String {
+        format!("user:{}", name.trim().to_lowercase())
+    }
+
+    pub fn client_key(client_ip: &str) -> String {
+        format!("ip:{}", client_ip)
+    }
+
+    pub fn captcha_key(client_ip: &str) -> String {
+        format!("captcha:{}", client_ip)
+    }
+
+    /// Time the next attempt is allowed at, considering both the lockout and the
+    /// exponential backoff after the last failure.
+    pub fn next_attempt_at(&self, backoff_base: i64, backoff_max: i64) -> Option<DateTime<Utc>> {
+        let backoff = self.last_failure_at.map(|last| {
+            let exponent = (self.failures - 1).clamp(0, 30) as u32;
+            let delay = backoff_base
+                .saturating_mul(2i64.saturating_pow(exponent))
+                .min(backoff_max);
+            last + Duration::seconds(delay)
+        });
+        match (self.locked_until, backoff) {
+            (Some(locked), Some(backoff)) => Some(locked.max(backoff)),
+            (locked, backoff) => locked.or(backoff),
+        }
+    }
+}
+
+#[async_trait]
+pub trait LoginThrottles: Repository<Type = LoginThrottle> {
+    /// Atomically counts a failed attempt, failures before the start of the window are forgotten.
+    async fn record_failure(&self, key: &str, window_start: DateTime<Utc>)
+        -> Result<LoginThrottle>;
+    /// Locks the key until the given time and starts counting failures from zero.
+    async fn lock(&self, key: &str, until: DateTime<Utc>) -> Result<()>;
+    async fn clear(&self, key: &str) -> Result<()>;
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{Page, Repository};
+
+/// Resource queued for publishing to an external target, e.g. a VK community. A job of the
+/// job queue publishes it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Publication {
+    pub id: Option<Uuid>,
+    pub resource_id: Uuid,
+    /// Name of the publisher.
+    pub target: String,
+    pub status: PublicationStatus,
+    /// The publication isn't published before this time.
+    pub scheduled_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Id of the post or object at the target.
+    pub remote_id: Option<String>,
+    pub last_error: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PublicationStatus {
+    Scheduled,
+    Publishing,
+    Published,
+    /// Failed on the last attempt, it can be rescheduled.
+    Failed,
+    Cancelled,
+}
+
+impl Display for PublicationStatus {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for PublicationStatus {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<PublicationStatus, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "scheduled" => Ok(PublicationStatus::Scheduled),
+            "publishing" => Ok(PublicationStatus::Publishing),
+            "published" => Ok(PublicationStatus::Published),
+            "failed" => Ok(PublicationStatus::Failed),
+            "cancelled" => Ok(PublicationStatus::Cancelled),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Publication {
+    pub fn new(
+        resource_id: Uuid,
+        target: &str,
+        scheduled_at: DateTime<Utc>,
+        max_attempts: i32,
+    ) -> Self {
+        Self {
+            id: None,
+            resource_id,
+            target: target.to_owned(),
+            status: PublicationStatus::Scheduled,
+            scheduled_at,
+            attempts: 0,
+            max_attempts,
+            remote_id: None,
+            last_error: None,
+            published_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct PublicationFilter {
+    pub target: Option<String>,
+    pub status: Option<PublicationStatus>,
+    pub resource_id: Option<Uuid>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// The job of a publication carries its `scheduled_at`, a rescheduled publication gets a new
+/// job and the jobs of earlier schedules leave it alone. Transitions of a started
+/// publication are guarded by the attempt.
+#[async_trait]
+pub trait Publications: Repository<Type = Publication> {
+    /// Starts an attempt of the publication if it is still scheduled at `scheduled_at`.
+    async fn start(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<Option<Publication>>;
+    async fn complete(&self, id: Uuid, attempt: i32, remote_id: &str) -> Result<bool>;
+    /// Keeps the error of the attempt, the job is retried.
+    async fn retry(&self, id: Uuid, attempt: i32, error: &str) -> Result<bool>;
+    /// Fails the publication after the last attempt of its job.
+    async fn fail(&self, id: Uuid, scheduled_at: DateTime<Utc>, error: &str) -> Result<bool>;
+    /// Moves a scheduled or failed publication to `scheduled_at` with all attempts left.
+    async fn reschedule(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<bool>;
+    /// Cancels a scheduled or failed publication.
+    async fn cancel(&self, id: Uuid) -> Result<bool>;
+    /// Latest scheduled first.
+    async fn find(&self, filter: PublicationFilter) -> Result<Page<Publication>>;
+}
@@ -0,0 +1,63 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RefreshToken {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub replaced_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RefreshToken {
+    /// Starts a new token family, used on login.
+    pub fn new(user_id: Uuid, token_hash: &str, ttl: Duration) -> Self {
+        Self {
+            id: None,
+            user_id,
+            family_id: Uuid::new_v4(),
+            token_hash: token_hash.to_owned(),
+            expires_at: Utc::now() + ttl,
+            revoked_at: None,
+            replaced_by: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Issues the next token of the same family, used on refresh. The id is assigned
+    /// upfront so the previous token can point to its replacement.
+    pub fn rotate(&self, token_hash: &str, ttl: Duration) -> Self {
+        Self {
+            id: Some(Uuid::new_v4()),
+            family_id: self.family_id,
+            ..Self::new(self.user_id, token_hash, ttl)
+        }
+    }
+
+    pub fn is_revoked(&self) -> bool {
+        self.revoked_at.is_some()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at <= Utc::now()
+    }
+}
+
+#[async_trait]
+pub trait TokenRepository: Repository<Type = RefreshToken> {
+    /// Revokes a single active token, returns `false` if it was already revoked.
+    async fn revoke(&self, id: Uuid, replaced_by: Option<Uuid>) -> Result<bool>;
+    async fn revoke_family(&self, family_id: Uuid) -> Result<()>;
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<()>;
+}
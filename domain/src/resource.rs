@@ -1,9 +1,11 @@
+use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use uuid::Uuid;
 
-use crate::FileObject;
+use crate::{FileObject, Page, Repository};
 
 #[derive(Clone, Debug, Eq, Serialize, Deserialize)]
 pub struct Resource {
@@ -13,6 +15,23 @@ pub struct Resource {
     pub tags: Option<Value>,
     pub user_id: Option<Uuid>,
     pub metadata: Option<Value>,
+    /// 64 bit difference hash of images, similar images have a small Hamming distance.
+    pub perceptual_hash: Option<i64>,
+    /// Compact placeholder of images, see <https://blurha.sh>.
+    pub blurhash: Option<String>,
+    /// Dominant colors of images as `#rrggbb`, most frequent first.
+    pub palette: Option<Value>,
+    /// Most frequent color of the palette as `0xRRGGBB`.
+    pub dominant_color: Option<i32>,
+    /// Size of the object in bytes.
+    pub size: Option<i64>,
+    /// Entity tag of the object, the hex MD5 digest of objects uploaded in one part.
+    pub etag: Option<String>,
+    /// The resource is deleted by the lifecycle task after this time.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Time the resource was moved to the trash, trashed resources are purged after the
+    /// retention period.
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -43,6 +62,34 @@ impl Resource {
         self.url = Some(url);
         self
     }
+
+    pub fn with_perceptual_hash(mut self, perceptual_hash: Option<i64>) -> Self {
+        self.perceptual_hash = perceptual_hash;
+        self
+    }
+
+    pub fn with_blurhash(mut self, blurhash: &str) -> Self {
+        self.blurhash = Some(blurhash.to_owned());
+        self
+    }
+
+    pub fn with_object(mut self, size: i64, etag: &str) -> Self {
+        self.size = Some(size);
+        self.etag = Some(etag.to_owned());
+        self
+    }
+
+    /// Sets the palette, the first color is the dominant one.
+    pub fn with_palette(mut self, palette: &[i32]) -> Self {
+        self.dominant_color = palette.first().copied();
+        self.palette = Some(Value::Array(
+            palette
+                .iter()
+                .map(|color| Value::String(format!("#{:06x}", color)))
+                .collect(),
+        ));
+        self
+    }
 }
 
 pub fn from_file_object(object: &FileObject) -> Resource {
@@ -53,11 +100,35 @@ pub fn from_file_object(object: &FileObject) -> Resource {
         tags: object.tags.to_owned(),
         user_id: object.user_id,
         metadata: object.metadata.to_owned(),
+        perceptual_hash: None,
+        blurhash: None,
+        palette: None,
+        dominant_color: None,
+        size: None,
+        etag: None,
+        expires_at: object.expires_at,
+        deleted_at: None,
         created_at: Utc::now(),
         updated_at: Utc::now(),
     }
 }
 
+pub fn to_file_object(resource: Resource) -> FileObject {
+    FileObject {
+        key: resource.key,
+        url: resource.url,
+        tags: resource.tags,
+        user_id: resource.user_id,
+        metadata: resource.metadata,
+        blurhash: resource.blurhash,
+        palette: resource.palette,
+        size: resource.size,
+        etag: resource.etag,
+        expires_at: resource.expires_at,
+        data: None,
+    }
+}
+
 impl Default for Resource {
     fn default() -> Self {
         Self {
@@ -67,8 +138,105 @@ impl Default for Resource {
             tags: None,
             user_id: None,
             metadata: None,
+            perceptual_hash: None,
+            blurhash: None,
+            palette: None,
+            dominant_color: None,
+            size: None,
+            etag: None,
+            expires_at: None,
+            deleted_at: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
     }
 }
+
+/// Resource with the Hamming distance between its perceptual hash and the searched one.
+#[derive(Clone, Debug, Serialize)]
+pub struct SimilarResource {
+    pub resource: Resource,
+    pub distance: u32,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ResourceFilter {
+    /// Only resources whose dominant color is near this `0xRRGGBB` color, nearest first.
+    pub color: Option<i32>,
+    /// Maximal Euclidean distance of the dominant color in RGB space.
+    pub max_color_distance: u32,
+    /// Only the resources of this user.
+    pub user_id: Option<Uuid>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// Resources a lifecycle rule applies to, all conditions have to match.
+#[derive(Clone, Debug, Default)]
+pub struct LifecycleFilter {
+    pub prefix: Option<String>,
+    /// Tag the resources have, with the value if any.
+    pub tag: Option<String>,
+    pub tag_value: Option<String>,
+    pub created_before: Option<DateTime<Utc>>,
+    /// Resources already under this prefix are skipped, e.g. the destination of moved ones.
+    pub exclude_prefix: Option<String>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TrashFilter {
+    /// Only the resources of this user.
+    pub user_id: Option<Uuid>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// Queries leave out the resources in the trash unless they are about the trash.
+#[async_trait]
+pub trait Resources: Repository<Type = Resource> {
+    /// Resources whose perceptual hash differs in at most `max_distance` bits, closest first,
+    /// only the ones of the user if given.
+    async fn find_similar(
+        &self,
+        perceptual_hash: i64,
+        max_distance: u32,
+        user_id: Option<Uuid>,
+        limit: u64,
+    ) -> Result<Vec<SimilarResource>>;
+    /// All resources with a perceptual hash.
+    async fn find_hashed(&self) -> Result<Vec<Resource>>;
+    /// Images without a perceptual hash by id, the ones after `after` if given.
+    async fn find_unhashed(&self, after: Option<Uuid>, limit: u64) -> Result<Vec<Resource>>;
+    /// Sets the perceptual hash of a resource that has none yet.
+    async fn set_perceptual_hash(&self, id: Uuid, perceptual_hash: i64) -> Result<bool>;
+    /// Newest resources first unless filtered by color.
+    async fn find(&self, filter: ResourceFilter) -> Result<Page<Resource>>;
+    /// Images tagged `publish: <target>` without a pending or completed publication to the
+    /// target, oldest first. Images whose publication failed are returned again.
+    async fn find_publishable(&self, target: &str, limit: u64) -> Result<Vec<Resource>>;
+    /// Resources matching a lifecycle rule, oldest first.
+    async fn find_lifecycle(&self, filter: LifecycleFilter, limit: u64) -> Result<Vec<Resource>>;
+    /// Resources that expired before `now`, longest expired first.
+    async fn find_expired(&self, now: DateTime<Utc>, limit: u64) -> Result<Vec<Resource>>;
+    /// Like `create`, recording the event and the webhook deliveries in the same transaction.
+    async fn create_with_event(&self, item: Resource) -> Result<Resource>;
+    /// Like `update`, recording the event and the webhook deliveries in the same transaction.
+    async fn update_with_event(&self, id: Uuid, item: Resource) -> Result<Resource>;
+    /// Like `delete_by_id`, recording the event and the webhook deliveries in the same
+    /// transaction.
+    async fn delete_with_event(&self, id: Uuid) -> Result<()>;
+    /// Moves the resource to the trash under the key of the item, recording a deleted event.
+    async fn trash_with_event(&self, id: Uuid, item: Resource) -> Result<Resource>;
+    /// Takes the resource out of the trash under the key of the item, recording a created
+    /// event.
+    async fn restore_with_event(&self, id: Uuid, item: Resource) -> Result<Resource>;
+    async fn get_trashed(&self, id: Uuid) -> Result<Resource>;
+    /// Most recently trashed first.
+    async fn find_trashed(&self, filter: TrashFilter) -> Result<Page<Resource>>;
+    /// Resources trashed before `deleted_before`, longest trashed first.
+    async fn find_purgeable(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: u64,
+    ) -> Result<Vec<Resource>>;
+}
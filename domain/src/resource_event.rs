@@ -0,0 +1,88 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::Resource;
+
+/// Change of a resource, recorded in the same transaction as the change itself.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResourceEvent {
+    /// Sequence number, increasing in the order the events were recorded.
+    pub id: Option<i64>,
+    pub event_type: ResourceEventType,
+    pub resource_id: Uuid,
+    pub key: String,
+    /// The resource after the change, before it for deletions.
+    pub resource: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ResourceEventType {
+    Created,
+    Updated,
+    Deleted,
+}
+
+impl Display for ResourceEventType {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Accepts the names of the webhook payloads as well, e.g. `resource.created`.
+impl FromStr for ResourceEventType {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<ResourceEventType, Self::Err> {
+        match input.to_lowercase().trim_start_matches("resource.") {
+            "created" => Ok(ResourceEventType::Created),
+            "updated" => Ok(ResourceEventType::Updated),
+            "deleted" => Ok(ResourceEventType::Deleted),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ResourceEventType {
+    /// Name of the event in webhook payloads.
+    pub fn name(&self) -> String {
+        format!("resource.{}", self.to_string().to_lowercase())
+    }
+}
+
+impl ResourceEvent {
+    pub fn new(event_type: ResourceEventType, resource_id: Uuid, resource: &Resource) -> Self {
+        Self {
+            id: None,
+            event_type,
+            resource_id,
+            key: resource.key.clone(),
+            resource: serde_json::to_value(resource).unwrap_or(Value::Null),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Body of the notifications of the event.
+    pub fn payload(&self) -> Value {
+        json!({
+            "id": self.id,
+            "type": self.event_type.name(),
+            "key": self.key,
+            "resource": self.resource,
+            "created_at": self.created_at,
+        })
+    }
+}
+
+/// Ordered log of the changes of resources, the change feed reads it from a sequence number.
+#[async_trait]
+pub trait ResourceEvents {
+    /// Up to `limit` events after the sequence number `since`, oldest first.
+    async fn find_since(&self, since: i64, limit: u64) -> Result<Vec<ResourceEvent>>;
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Run of a scheduled task, there is at most one run per schedule and fire time.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleRun {
+    pub id: Option<Uuid>,
+    /// Name the task was scheduled under.
+    pub name: String,
+    /// Fire time the run belongs to.
+    pub scheduled_at: DateTime<Utc>,
+    pub status: ScheduleRunStatus,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduleRunStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl Display for ScheduleRunStatus {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for ScheduleRunStatus {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<ScheduleRunStatus, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "running" => Ok(ScheduleRunStatus::Running),
+            "succeeded" => Ok(ScheduleRunStatus::Succeeded),
+            "failed" => Ok(ScheduleRunStatus::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl ScheduleRun {
+    pub fn new(name: &str, scheduled_at: DateTime<Utc>) -> Self {
+        Self {
+            id: None,
+            name: name.to_owned(),
+            scheduled_at,
+            status: ScheduleRunStatus::Running,
+            started_at: Utc::now(),
+            finished_at: None,
+            error: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait ScheduleRuns: Repository<Type = ScheduleRun> {
+    /// Records the start of the run of `name` at `scheduled_at`, unless another replica holds
+    /// the lock of the schedule or the run was already recorded.
+    async fn start(&self, name: &str, scheduled_at: DateTime<Utc>) -> Result<Option<ScheduleRun>>;
+    async fn finish(&self, id: Uuid, status: ScheduleRunStatus, error: Option<&str>) -> Result<()>;
+    /// Latest runs of the schedule, newest first.
+    async fn find_by_name(&self, name: &str, limit: u64) -> Result<Vec<ScheduleRun>>;
+}
@@ -0,0 +1,74 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Public link to a resource, only the hash of the link token is stored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Share {
+    pub id: Option<Uuid>,
+    pub resource_id: Uuid,
+    pub token_hash: String,
+    pub created_by: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub password_hash: Option<String>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Share {
+    pub fn new(resource_id: Uuid, token_hash: &str, created_by: Uuid) -> Self {
+        Self {
+            id: None,
+            resource_id,
+            token_hash: token_hash.to_owned(),
+            created_by,
+            expires_at: None,
+            password_hash: None,
+            max_downloads: None,
+            download_count: 0,
+            revoked_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn with_expires_at(mut self, expires_at: Option<DateTime<Utc>>) -> Self {
+        self.expires_at = expires_at;
+        self
+    }
+
+    pub fn with_password_hash(mut self, password_hash: Option<String>) -> Self {
+        self.password_hash = password_hash;
+        self
+    }
+
+    pub fn with_max_downloads(mut self, max_downloads: Option<i32>) -> Self {
+        self.max_downloads = max_downloads;
+        self
+    }
+
+    /// Not revoked, not expired and downloads are left.
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+            && self.expires_at.map_or(true, |at| at > Utc::now())
+            && self
+                .max_downloads
+                .map_or(true, |max| self.download_count < max)
+    }
+}
+
+#[async_trait]
+pub trait Shares: Repository<Type = Share> {
+    async fn find_by_resource(&self, resource_id: Uuid) -> Result<Vec<Share>>;
+    /// Atomically counts a download of an active share, returns `false` if the share
+    /// is not active anymore.
+    async fn record_download(&self, id: Uuid) -> Result<bool>;
+    async fn revoke(&self, id: Uuid) -> Result<bool>;
+}
@@ -1,6 +1,17 @@
 use anyhow::Result;
 use async_trait::async_trait;
 use bytes::Bytes;
+use futures::Stream;
+use std::pin::Pin;
+
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes>> + Send>>;
+
+/// Object body that is read from the storage while it is sent to the client.
+pub struct ObjectStream {
+    pub content_type: Option<String>,
+    pub content_length: Option<i64>,
+    pub body: ByteStream,
+}
 
 #[async_trait]
 pub trait Storage {
@@ -10,15 +21,29 @@ pub trait Storage {
 
     async fn list_objects(&self, bucket: &str) -> Result<Vec<String>>;
 
+    /// Keys of all objects starting with the prefix.
+    async fn list_objects_with_prefix(&self, bucket: &str, prefix: &str) -> Result<Vec<String>>;
+
     async fn list_buckets(&self) -> Result<Vec<String>>;
 
     async fn upload_file(&self, bucket: &str, filename: &str, key: &str) -> Result<()>;
 
     async fn download_object(&self, bucket: &str, key: &str) -> Result<Bytes>;
 
+    async fn stream_object(&self, bucket: &str, key: &str) -> Result<ObjectStream>;
+
     async fn upload_object(&self, bucket: &str, file: &[u8], key: &str) -> Result<()>;
 
     async fn delete_object(&self, bucket: &str, key: &str) -> Result<()>;
 
+    /// Copies the object within the storage, the buckets may differ.
+    async fn copy_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        destination_bucket: &str,
+        destination_key: &str,
+    ) -> Result<()>;
+
     async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<()>;
 }
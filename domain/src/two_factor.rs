@@ -0,0 +1,79 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// TOTP enrollment of a user, the secret is stored encrypted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TwoFactor {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TwoFactor {
+    pub fn new(user_id: Uuid, secret: &str) -> Self {
+        Self {
+            id: None,
+            user_id,
+            secret: secret.to_owned(),
+            enabled: false,
+            last_used_step: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn enable(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn with_last_used_step(mut self, step: i64) -> Self {
+        self.last_used_step = Some(step);
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryCode {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl RecoveryCode {
+    pub fn new(user_id: Uuid, code_hash: &str) -> Self {
+        Self {
+            id: None,
+            user_id,
+            code_hash: code_hash.to_owned(),
+            used_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+}
+
+#[async_trait]
+pub trait TwoFactors: Repository<Type = TwoFactor> {
+    async fn get_by_user(&self, user_id: Uuid) -> Result<Option<TwoFactor>>;
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<()>;
+}
+
+#[async_trait]
+pub trait RecoveryCodes: Repository<Type = RecoveryCode> {
+    /// Marks an unused code of the user as used, returns `false` if there is no such code.
+    async fn consume(&self, user_id: Uuid, code_hash: &str) -> Result<bool>;
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<()>;
+}
@@ -1,9 +1,13 @@
+use anyhow::Result;
+use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 use uuid::Uuid;
 
+use crate::{Page, Repository};
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct User {
     pub id: Option<Uuid>,
@@ -11,6 +15,9 @@ pub struct User {
     pub email: String,
     pub password: String,
     pub enabled: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    /// Self-registered user that is enabled by verifying the email.
+    pub verification_pending: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub role: Role,
@@ -50,6 +57,8 @@ impl Default for User {
             email: "".to_owned(),
             password: "".to_owned(),
             enabled: false,
+            email_verified_at: None,
+            verification_pending: false,
             created_at: Utc::now(),
             updated_at: Utc::now(),
             role: Role::GUEST,
@@ -74,8 +83,17 @@ impl User {
         self
     }
 
+    /// Enabling or disabling a user ends a pending verification.
     pub fn enable(mut self, enabled: bool) -> Self {
         self.enabled = enabled;
+        self.verification_pending = false;
+        self
+    }
+
+    /// Disabled until the email is verified.
+    pub fn pending_verification(mut self) -> Self {
+        self.enabled = false;
+        self.verification_pending = true;
         self
     }
 
@@ -83,4 +101,27 @@ impl User {
         self.role = role;
         self
     }
+
+    /// Marks the email as verified, only a pending verification enables the user.
+    pub fn verified(mut self) -> Self {
+        self.email_verified_at = Some(Utc::now());
+        if self.verification_pending {
+            self = self.enable(true);
+        }
+        self
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserFilter {
+    pub role: Option<Role>,
+    pub enabled: Option<bool>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+#[async_trait]
+pub trait UserDirectory: Repository<Type = User> {
+    async fn get_by_email(&self, email: String) -> Result<User>;
+    async fn find(&self, filter: UserFilter) -> Result<Page<User>>;
 }
@@ -0,0 +1,44 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// Link of a user to the subject of an external identity provider.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserIdentity {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    /// The user was created for the identity, so the identity provider owns its role.
+    pub provisioned: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl UserIdentity {
+    pub fn new(user_id: Uuid, issuer: &str, subject: &str) -> Self {
+        Self {
+            id: None,
+            user_id,
+            issuer: issuer.to_owned(),
+            subject: subject.to_owned(),
+            provisioned: false,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn provisioned(mut self) -> Self {
+        self.provisioned = true;
+        self
+    }
+}
+
+#[async_trait]
+pub trait UserIdentities: Repository<Type = UserIdentity> {
+    async fn get_by_subject(&self, issuer: &str, subject: &str) -> Result<Option<UserIdentity>>;
+}
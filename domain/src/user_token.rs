@@ -0,0 +1,73 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::Repository;
+
+/// One-time token sent to the user by email.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserToken {
+    pub id: Option<Uuid>,
+    pub user_id: Uuid,
+    pub kind: UserTokenKind,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UserTokenKind {
+    EmailVerification,
+    PasswordReset,
+}
+
+impl Display for UserTokenKind {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for UserTokenKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<UserTokenKind, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "emailverification" => Ok(UserTokenKind::EmailVerification),
+            "passwordreset" => Ok(UserTokenKind::PasswordReset),
+            _ => Err(()),
+        }
+    }
+}
+
+impl UserToken {
+    pub fn new(user_id: Uuid, kind: UserTokenKind, token_hash: &str, ttl: Duration) -> Self {
+        Self {
+            id: None,
+            user_id,
+            kind,
+            token_hash: token_hash.to_owned(),
+            expires_at: Utc::now() + ttl,
+            used_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn is_valid(&self, kind: &UserTokenKind) -> bool {
+        &self.kind == kind && self.used_at.is_none() && self.expires_at > Utc::now()
+    }
+}
+
+#[async_trait]
+pub trait UserTokens: Repository<Type = UserToken> {
+    /// Marks an unused token as used, returns `false` if it was already used.
+    async fn consume(&self, id: Uuid) -> Result<bool>;
+    /// Marks all unused tokens of the kind issued to the user as used.
+    async fn invalidate_all(&self, user_id: Uuid, kind: UserTokenKind) -> Result<()>;
+}
@@ -0,0 +1,161 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use uuid::Uuid;
+
+use crate::{Job, Page, Repository, ResourceEvent, ResourceEventType};
+
+/// URL notified of resource events, the notifications are signed with the secret.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookSubscription {
+    pub id: Option<Uuid>,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Types of the events the URL is notified of, all types if empty.
+    pub event_types: Vec<ResourceEventType>,
+    pub enabled: bool,
+    /// Attempts of the jobs delivering the events to the URL.
+    pub max_attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    pub fn new(
+        url: &str,
+        secret: &str,
+        event_types: Vec<ResourceEventType>,
+        max_attempts: i32,
+    ) -> Self {
+        Self {
+            id: None,
+            url: url.to_owned(),
+            secret: secret.to_owned(),
+            event_types,
+            enabled: true,
+            max_attempts,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    pub fn matches(&self, event_type: ResourceEventType) -> bool {
+        self.enabled && (self.event_types.is_empty() || self.event_types.contains(&event_type))
+    }
+}
+
+/// Kind of the jobs delivering webhooks, the job is enqueued with the delivery.
+pub const DELIVER_WEBHOOK_JOB: &str = "deliver_webhook";
+
+/// Notification of a subscription about an event, kept as the delivery log. A job of the job
+/// queue delivers it, see `WebhookDelivery::job`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: Option<Uuid>,
+    pub subscription_id: Uuid,
+    pub event_id: i64,
+    pub event_type: ResourceEventType,
+    pub payload: Value,
+    pub status: WebhookDeliveryStatus,
+    pub attempts: i32,
+    /// HTTP status of the last response.
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WebhookDeliveryStatus {
+    Pending,
+    Delivering,
+    Delivered,
+    /// Failed on the last attempt.
+    Failed,
+}
+
+impl Display for WebhookDeliveryStatus {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for WebhookDeliveryStatus {
+    type Err = ();
+
+    fn from_str(input: &str) -> std::result::Result<WebhookDeliveryStatus, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "pending" => Ok(WebhookDeliveryStatus::Pending),
+            "delivering" => Ok(WebhookDeliveryStatus::Delivering),
+            "delivered" => Ok(WebhookDeliveryStatus::Delivered),
+            "failed" => Ok(WebhookDeliveryStatus::Failed),
+            _ => Err(()),
+        }
+    }
+}
+
+impl WebhookDelivery {
+    pub fn new(subscription_id: Uuid, event_id: i64, event: &ResourceEvent) -> Self {
+        Self {
+            id: None,
+            subscription_id,
+            event_id,
+            event_type: event.event_type,
+            payload: event.payload(),
+            status: WebhookDeliveryStatus::Pending,
+            attempts: 0,
+            response_status: None,
+            last_error: None,
+            delivered_at: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    /// Job delivering the delivery, the payload is read by the handler of
+    /// `DELIVER_WEBHOOK_JOB` jobs.
+    pub fn job(&self, max_attempts: i32) -> Result<Job> {
+        let id = self
+            .id
+            .ok_or_else(|| anyhow::Error::msg("Delivery has no id"))?;
+        Ok(Job::new(
+            DELIVER_WEBHOOK_JOB,
+            json!({ "delivery_id": id }),
+            max_attempts,
+        ))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct WebhookDeliveryFilter {
+    pub subscription_id: Option<Uuid>,
+    pub status: Option<WebhookDeliveryStatus>,
+    pub page: u64,
+    pub page_size: u64,
+}
+
+/// Like publications, transitions of a started delivery are guarded by the attempt.
+#[async_trait]
+pub trait WebhookDeliveries: Repository<Type = WebhookDelivery> {
+    /// Starts an attempt of the delivery unless it was delivered or failed already.
+    async fn start(&self, id: Uuid) -> Result<Option<WebhookDelivery>>;
+    async fn complete(&self, id: Uuid, attempt: i32, response_status: i32) -> Result<bool>;
+    /// Keeps the outcome of the attempt, the job is retried.
+    async fn retry(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        response_status: Option<i32>,
+        error: &str,
+    ) -> Result<bool>;
+    /// Fails the delivery after the last attempt of its job, or when its subscription is gone.
+    async fn fail(&self, id: Uuid, error: &str) -> Result<bool>;
+    /// Latest first.
+    async fn find(&self, filter: WebhookDeliveryFilter) -> Result<Page<WebhookDelivery>>;
+}
@@ -2,6 +2,31 @@ pub use sea_orm_migration::prelude::*;
 
 mod m20220302_000001_create_user_table;
 mod m20220430_000001_create_resource_table;
+mod m20220815_000001_create_refresh_token_table;
+mod m20220901_000001_add_user_unique_indexes;
+mod m20220910_000001_create_user_token_table;
+mod m20220910_000002_add_user_email_verified_at;
+mod m20220920_000001_create_two_factor_table;
+mod m20220920_000002_create_recovery_code_table;
+mod m20220925_000001_create_login_throttle_table;
+mod m20220925_000002_create_audit_event_table;
+mod m20221001_000001_create_user_identity_table;
+mod m20221005_000001_create_share_table;
+mod m20221010_000001_create_derivative_table;
+mod m20221015_000001_add_resource_perceptual_hash;
+mod m20221020_000001_add_resource_colors;
+mod m20221025_000001_create_captcha_table;
+mod m20221101_000001_create_job_table;
+mod m20221105_000001_create_schedule_run_table;
+mod m20221110_000001_create_publication_table;
+mod m20221115_000001_create_job_queue_table;
+mod m20221115_000002_add_job_started_at;
+mod m20221120_000001_create_resource_event_table;
+mod m20221120_000002_create_webhook_subscription_table;
+mod m20221120_000003_create_webhook_delivery_table;
+mod m20221125_000001_add_resource_size_etag;
+mod m20221130_000001_add_resource_expires_at;
+mod m20221205_000001_add_resource_deleted_at;
 
 pub struct Migrator;
 
@@ -11,6 +36,31 @@ impl MigratorTrait for Migrator {
         vec![
             Box::new(m20220302_000001_create_user_table::Migration),
             Box::new(m20220430_000001_create_resource_table::Migration),
+            Box::new(m20220815_000001_create_refresh_token_table::Migration),
+            Box::new(m20220901_000001_add_user_unique_indexes::Migration),
+            Box::new(m20220910_000001_create_user_token_table::Migration),
+            Box::new(m20220910_000002_add_user_email_verified_at::Migration),
+            Box::new(m20220920_000001_create_two_factor_table::Migration),
+            Box::new(m20220920_000002_create_recovery_code_table::Migration),
+            Box::new(m20220925_000001_create_login_throttle_table::Migration),
+            Box::new(m20220925_000002_create_audit_event_table::Migration),
+            Box::new(m20221001_000001_create_user_identity_table::Migration),
+            Box::new(m20221005_000001_create_share_table::Migration),
+            Box::new(m20221010_000001_create_derivative_table::Migration),
+            Box::new(m20221015_000001_add_resource_perceptual_hash::Migration),
+            Box::new(m20221020_000001_add_resource_colors::Migration),
+            Box::new(m20221025_000001_create_captcha_table::Migration),
+            Box::new(m20221101_000001_create_job_table::Migration),
+            Box::new(m20221105_000001_create_schedule_run_table::Migration),
+            Box::new(m20221110_000001_create_publication_table::Migration),
+            Box::new(m20221115_000001_create_job_queue_table::Migration),
+            Box::new(m20221115_000002_add_job_started_at::Migration),
+            Box::new(m20221120_000001_create_resource_event_table::Migration),
+            Box::new(m20221120_000002_create_webhook_subscription_table::Migration),
+            Box::new(m20221120_000003_create_webhook_delivery_table::Migration),
+            Box::new(m20221125_000001_add_resource_size_etag::Migration),
+            Box::new(m20221130_000001_add_resource_expires_at::Migration),
+            Box::new(m20221205_000001_add_resource_deleted_at::Migration),
         ]
     }
 }
@@ -0,0 +1,102 @@
+use entity::refresh_token;
+use entity::refresh_token::Entity as RefreshToken;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220815_000001_create_refresh_token_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(RefreshToken)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(refresh_token::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::UserId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::FamilyId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::RevokedAt).timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(refresh_token::Column::ReplacedBy).uuid())
+                    .col(
+                        ColumnDef::new(refresh_token::Column::CreatedAt).timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(refresh_token::Column::UpdatedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__refresh_tokens__family_id")
+                    .table(RefreshToken)
+                    .col(refresh_token::Column::FamilyId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__refresh_tokens__user_id")
+                    .table(RefreshToken)
+                    .col(refresh_token::Column::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__refresh_tokens__user_id")
+                    .table(RefreshToken)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__refresh_tokens__family_id")
+                    .table(RefreshToken)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(RefreshToken).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,56 @@
+use entity::user;
+use entity::user::Entity as User;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220901_000001_add_user_unique_indexes"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__users__email")
+                    .table(User)
+                    .col(user::Column::Email)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__users__name")
+                    .table(User)
+                    .col(user::Column::Name)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__users__name")
+                    .table(User)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__users__email")
+                    .table(User)
+                    .to_owned(),
+            )
+            .await
+    }
+}
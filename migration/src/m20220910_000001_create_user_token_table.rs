@@ -0,0 +1,70 @@
+use entity::user_token;
+use entity::user_token::Entity as UserToken;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220910_000001_create_user_token_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(UserToken)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(user_token::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(user_token::Column::UserId).uuid().not_null())
+                    .col(ColumnDef::new(user_token::Column::Kind).string().not_null())
+                    .col(
+                        ColumnDef::new(user_token::Column::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(user_token::Column::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(user_token::Column::UsedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(user_token::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(user_token::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__user_tokens__user_id")
+                    .table(UserToken)
+                    .col(user_token::Column::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__user_tokens__user_id")
+                    .table(UserToken)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(UserToken).to_owned())
+            .await
+    }
+}
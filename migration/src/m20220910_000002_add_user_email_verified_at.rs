@@ -0,0 +1,45 @@
+use entity::user;
+use entity::user::Entity as User;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220910_000002_add_user_email_verified_at"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(User)
+                    .add_column(
+                        ColumnDef::new(user::Column::EmailVerifiedAt).timestamp_with_time_zone(),
+                    )
+                    .add_column(
+                        ColumnDef::new(user::Column::VerificationPending)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(User)
+                    .drop_column(user::Column::EmailVerifiedAt)
+                    .drop_column(user::Column::VerificationPending)
+                    .to_owned(),
+            )
+            .await
+    }
+}
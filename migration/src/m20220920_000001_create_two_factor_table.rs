@@ -0,0 +1,57 @@
+use entity::two_factor;
+use entity::two_factor::Entity as TwoFactor;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220920_000001_create_two_factor_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(TwoFactor)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(two_factor::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(two_factor::Column::UserId)
+                            .uuid()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(two_factor::Column::Secret)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(two_factor::Column::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(two_factor::Column::LastUsedStep).big_integer())
+                    .col(ColumnDef::new(two_factor::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(two_factor::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(sea_query::Table::drop().table(TwoFactor).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,71 @@
+use entity::recovery_code;
+use entity::recovery_code::Entity as RecoveryCode;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220920_000002_create_recovery_code_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(RecoveryCode)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(recovery_code::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(recovery_code::Column::UserId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(recovery_code::Column::CodeHash)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(recovery_code::Column::UsedAt).timestamp_with_time_zone())
+                    .col(
+                        ColumnDef::new(recovery_code::Column::CreatedAt).timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(recovery_code::Column::UpdatedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__recovery_codes__user_id")
+                    .table(RecoveryCode)
+                    .col(recovery_code::Column::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__recovery_codes__user_id")
+                    .table(RecoveryCode)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(RecoveryCode).to_owned())
+            .await
+    }
+}
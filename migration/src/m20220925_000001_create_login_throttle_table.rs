@@ -0,0 +1,65 @@
+use entity::login_throttle;
+use entity::login_throttle::Entity as LoginThrottle;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220925_000001_create_login_throttle_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(LoginThrottle)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(login_throttle::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::Key)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::Failures)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::LastFailureAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::LockedUntil)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::CreatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(login_throttle::Column::UpdatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(sea_query::Table::drop().table(LoginThrottle).to_owned())
+            .await
+    }
+}
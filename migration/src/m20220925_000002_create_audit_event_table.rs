@@ -0,0 +1,68 @@
+use entity::audit_event;
+use entity::audit_event::Entity as AuditEvent;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20220925_000002_create_audit_event_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(AuditEvent)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(audit_event::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(audit_event::Column::Kind)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(audit_event::Column::UserId).uuid())
+                    .col(ColumnDef::new(audit_event::Column::ClientIp).string())
+                    .col(
+                        ColumnDef::new(audit_event::Column::Message)
+                            .text()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(audit_event::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(audit_event::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__audit_events__created_at")
+                    .table(AuditEvent)
+                    .col(audit_event::Column::CreatedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__audit_events__created_at")
+                    .table(AuditEvent)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(AuditEvent).to_owned())
+            .await
+    }
+}
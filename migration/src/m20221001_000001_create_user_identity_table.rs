@@ -0,0 +1,100 @@
+use entity::user_identity;
+use entity::user_identity::Entity as UserIdentity;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221001_000001_create_user_identity_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(UserIdentity)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(user_identity::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::UserId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::Issuer)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::Subject)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::Provisioned)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::CreatedAt).timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(user_identity::Column::UpdatedAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__user_identities__issuer__subject")
+                    .table(UserIdentity)
+                    .col(user_identity::Column::Issuer)
+                    .col(user_identity::Column::Subject)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__user_identities__user_id")
+                    .table(UserIdentity)
+                    .col(user_identity::Column::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__user_identities__user_id")
+                    .table(UserIdentity)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__user_identities__issuer__subject")
+                    .table(UserIdentity)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(UserIdentity).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,74 @@
+use entity::share;
+use entity::share::Entity as Share;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221005_000001_create_share_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(Share)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(share::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(share::Column::ResourceId).uuid().not_null())
+                    .col(
+                        ColumnDef::new(share::Column::TokenHash)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(share::Column::CreatedBy).uuid().not_null())
+                    .col(ColumnDef::new(share::Column::ExpiresAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(share::Column::PasswordHash).string())
+                    .col(ColumnDef::new(share::Column::MaxDownloads).integer())
+                    .col(
+                        ColumnDef::new(share::Column::DownloadCount)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(share::Column::RevokedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(share::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(share::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__shares__resource_id")
+                    .table(Share)
+                    .col(share::Column::ResourceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__shares__resource_id")
+                    .table(Share)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(Share).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,89 @@
+use entity::derivative;
+use entity::derivative::Entity as Derivative;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221010_000001_create_derivative_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(Derivative)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(derivative::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(derivative::Column::ResourceId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(derivative::Column::Size)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(derivative::Column::Key).string().not_null())
+                    .col(
+                        ColumnDef::new(derivative::Column::ContentType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(derivative::Column::Width)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(derivative::Column::Height)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(derivative::Column::ByteSize)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(derivative::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(derivative::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__derivatives__resource_id__size")
+                    .table(Derivative)
+                    .col(derivative::Column::ResourceId)
+                    .col(derivative::Column::Size)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__derivatives__resource_id__size")
+                    .table(Derivative)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(Derivative).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use entity::resource;
+use entity::resource::Entity as Resource;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221015_000001_add_resource_perceptual_hash"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .add_column(ColumnDef::new(resource::Column::PerceptualHash).big_integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .drop_column(resource::Column::PerceptualHash)
+                    .to_owned(),
+            )
+            .await
+    }
+}
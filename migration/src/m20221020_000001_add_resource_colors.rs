@@ -0,0 +1,40 @@
+use entity::resource;
+use entity::resource::Entity as Resource;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221020_000001_add_resource_colors"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .add_column(ColumnDef::new(resource::Column::Blurhash).string())
+                    .add_column(ColumnDef::new(resource::Column::Palette).json())
+                    .add_column(ColumnDef::new(resource::Column::DominantColor).integer())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .drop_column(resource::Column::Blurhash)
+                    .drop_column(resource::Column::Palette)
+                    .drop_column(resource::Column::DominantColor)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,79 @@
+use entity::job;
+use entity::job::Entity as Job;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221101_000001_create_job_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(Job)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(job::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(job::Column::Kind).string().not_null())
+                    .col(ColumnDef::new(job::Column::Payload).json().not_null())
+                    .col(ColumnDef::new(job::Column::Status).string().not_null())
+                    .col(
+                        ColumnDef::new(job::Column::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(job::Column::MaxAttempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(job::Column::RunAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(job::Column::LockedUntil).timestamp_with_time_zone())
+                    .col(ColumnDef::new(job::Column::LastError).text())
+                    .col(ColumnDef::new(job::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(job::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__jobs__kind__status__run_at")
+                    .table(Job)
+                    .col(job::Column::Kind)
+                    .col(job::Column::Status)
+                    .col(job::Column::RunAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__jobs__kind__status__run_at")
+                    .table(Job)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(Job).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,82 @@
+use entity::schedule_run;
+use entity::schedule_run::Entity as ScheduleRun;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221105_000001_create_schedule_run_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(ScheduleRun)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(schedule_run::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(schedule_run::Column::Name)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(schedule_run::Column::ScheduledAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(schedule_run::Column::Status)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(schedule_run::Column::StartedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(schedule_run::Column::FinishedAt).timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(schedule_run::Column::Error).text())
+                    .col(ColumnDef::new(schedule_run::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(schedule_run::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__schedule_runs__name__scheduled_at")
+                    .table(ScheduleRun)
+                    .col(schedule_run::Column::Name)
+                    .col(schedule_run::Column::ScheduledAt)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__schedule_runs__name__scheduled_at")
+                    .table(ScheduleRun)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(ScheduleRun).to_owned())
+            .await
+    }
+}
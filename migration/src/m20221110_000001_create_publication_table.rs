@@ -0,0 +1,111 @@
+use entity::publication;
+use entity::publication::Entity as Publication;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221110_000001_create_publication_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(Publication)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(publication::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::ResourceId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::Target)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::Status)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::ScheduledAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(
+                        ColumnDef::new(publication::Column::MaxAttempts)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(publication::Column::RemoteId).string())
+                    .col(ColumnDef::new(publication::Column::LastError).text())
+                    .col(
+                        ColumnDef::new(publication::Column::PublishedAt).timestamp_with_time_zone(),
+                    )
+                    .col(ColumnDef::new(publication::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(publication::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__publications__status__scheduled_at")
+                    .table(Publication)
+                    .col(publication::Column::Status)
+                    .col(publication::Column::ScheduledAt)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__publications__resource_id__target")
+                    .table(Publication)
+                    .col(publication::Column::ResourceId)
+                    .col(publication::Column::Target)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__publications__resource_id__target")
+                    .table(Publication)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__publications__status__scheduled_at")
+                    .table(Publication)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(Publication).to_owned())
+            .await
+    }
+}
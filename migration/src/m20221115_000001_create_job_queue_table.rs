@@ -0,0 +1,45 @@
+use entity::job_queue;
+use entity::job_queue::Entity as JobQueue;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221115_000001_create_job_queue_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(JobQueue)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(job_queue::Column::Kind)
+                            .string()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(job_queue::Column::Paused)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(ColumnDef::new(job_queue::Column::CreatedAt).timestamp_with_time_zone())
+                    .col(ColumnDef::new(job_queue::Column::UpdatedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(sea_query::Table::drop().table(JobQueue).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,36 @@
+use entity::job;
+use entity::job::Entity as Job;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221115_000002_add_job_started_at"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Job)
+                    .add_column(ColumnDef::new(job::Column::StartedAt).timestamp_with_time_zone())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Job)
+                    .drop_column(job::Column::StartedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
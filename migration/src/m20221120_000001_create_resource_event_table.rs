@@ -0,0 +1,79 @@
+use entity::resource_event;
+use entity::resource_event::Entity as ResourceEvent;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221120_000001_create_resource_event_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(ResourceEvent)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(resource_event::Column::Id)
+                            .big_integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(resource_event::Column::EventType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resource_event::Column::ResourceId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resource_event::Column::Key)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resource_event::Column::Resource)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(resource_event::Column::CreatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__resource_events__resource_id")
+                    .table(ResourceEvent)
+                    .col(resource_event::Column::ResourceId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__resource_events__resource_id")
+                    .table(ResourceEvent)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(ResourceEvent).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,76 @@
+use entity::webhook_subscription;
+use entity::webhook_subscription::Entity as WebhookSubscription;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221120_000002_create_webhook_subscription_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(WebhookSubscription)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::Url)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::Secret)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::EventTypes)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::Enabled)
+                            .boolean()
+                            .not_null()
+                            .default(true),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::MaxAttempts)
+                            .integer()
+                            .not_null()
+                            .default(8),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::CreatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_subscription::Column::UpdatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(
+                sea_query::Table::drop()
+                    .table(WebhookSubscription)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,99 @@
+use entity::webhook_delivery;
+use entity::webhook_delivery::Entity as WebhookDelivery;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221120_000003_create_webhook_delivery_table"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                sea_query::Table::create()
+                    .table(WebhookDelivery)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Id)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::SubscriptionId)
+                            .uuid()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::EventId)
+                            .big_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::EventType)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Payload)
+                            .json()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Status)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::Attempts)
+                            .integer()
+                            .not_null()
+                            .default(0),
+                    )
+                    .col(ColumnDef::new(webhook_delivery::Column::ResponseStatus).integer())
+                    .col(ColumnDef::new(webhook_delivery::Column::LastError).text())
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::DeliveredAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::CreatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .col(
+                        ColumnDef::new(webhook_delivery::Column::UpdatedAt)
+                            .timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__webhook_deliveries__subscription_id")
+                    .table(WebhookDelivery)
+                    .col(webhook_delivery::Column::SubscriptionId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__webhook_deliveries__subscription_id")
+                    .table(WebhookDelivery)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(sea_query::Table::drop().table(WebhookDelivery).to_owned())
+            .await
+    }
+}
@@ -0,0 +1,38 @@
+use entity::resource;
+use entity::resource::Entity as Resource;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221125_000001_add_resource_size_etag"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .add_column(ColumnDef::new(resource::Column::Size).big_integer())
+                    .add_column(ColumnDef::new(resource::Column::Etag).string())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .drop_column(resource::Column::Size)
+                    .drop_column(resource::Column::Etag)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -0,0 +1,55 @@
+use entity::resource;
+use entity::resource::Entity as Resource;
+use sea_orm_migration::prelude::*;
+
+pub struct Migration;
+
+impl MigrationName for Migration {
+    fn name(&self) -> &str {
+        "m20221130_000001_add_resource_expires_at"
+    }
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .add_column(
+                        ColumnDef::new(resource::Column::ExpiresAt).timestamp_with_time_zone(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                sea_query::Index::create()
+                    .name("idx__resources__expires_at")
+                    .table(Resource)
+                    .col(resource::Column::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                sea_query::Index::drop()
+                    .name("idx__resources__expires_at")
+                    .table(Resource)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                sea_query::Table::alter()
+                    .table(Resource)
+                    .drop_column(resource::Column::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
@@ -1,2 +1,12 @@
+mod mail;
+mod oidc;
 mod s3;
+mod telegram;
+mod vk;
+mod webhook;
+pub use mail::*;
+pub use oidc::*;
 pub use s3::*;
+pub use telegram::*;
+pub use vk::*;
+pub use webhook::*;
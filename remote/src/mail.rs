@@ -0,0 +1,86 @@
+use anyhow::Result;
+use app_config::MailConfig;
+use async_trait::async_trait;
+use domain::{Email, Mailer};
+use lettre::{
+    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use log::info;
+
+/// Builds the mailer selected by `mail.transport`: `smtp` or `log`.
+pub fn mailer_from_config(config: MailConfig) -> Result<Box<dyn Mailer + Send + Sync>> {
+    match config.transport.as_str() {
+        "smtp" => Ok(Box::new(SmtpMailer::from_config(config)?)),
+        "log" => Ok(Box::new(LogMailer::new(config.from))),
+        transport => Err(anyhow::Error::msg(format!(
+            "Unknown mail transport {}",
+            transport
+        ))),
+    }
+}
+
+/// Mailer for development, it only writes messages to the log.
+#[derive(Debug, Clone)]
+pub struct LogMailer {
+    from: String,
+}
+
+impl LogMailer {
+    pub fn new(from: String) -> Self {
+        LogMailer { from }
+    }
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<()> {
+        info!(
+            "Mail from: {} to: {} subject: {}\n{}",
+            self.from, email.to, email.subject, email.body
+        );
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailer {
+    pub fn from_config(config: MailConfig) -> Result<Self> {
+        let mut builder = if config.smtp_tls {
+            AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host)
+        }
+        .port(config.smtp_port);
+        if !config.smtp_username.is_empty() {
+            builder =
+                builder.credentials(Credentials::new(config.smtp_username, config.smtp_password));
+        }
+
+        Ok(SmtpMailer {
+            transport: builder.build(),
+            from: config.from,
+        })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<()> {
+        info!("Send mail to: {} subject: {}", email.to, email.subject);
+        let message = Message::builder()
+            .from(self.from.parse()?)
+            .to(email.to.parse()?)
+            .subject(email.subject)
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body)?;
+        self.transport.send(message).await?;
+
+        Ok(())
+    }
+}
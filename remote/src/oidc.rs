@@ -0,0 +1,251 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use app_config::OidcConfig;
+use jsonwebtoken::jwk::{AlgorithmParameters, Jwk, JwkSet};
+use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+use log::info;
+use rand::RngCore;
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// Algorithms of ID tokens signed with RSA keys, the only keys supported.
+const RSA_ALGORITHMS: [Algorithm; 6] = [
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+];
+
+/// Endpoints of the identity provider from its discovery document.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+}
+
+/// Authorization code request, the state, nonce and verifier must be kept until the callback.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuthorizationRequest {
+    pub url: String,
+    pub state: String,
+    pub nonce: String,
+    pub code_verifier: String,
+}
+
+/// Verified claims of an ID token.
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdTokenClaims {
+    pub iss: String,
+    pub sub: String,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+    pub preferred_username: Option<String>,
+    pub name: Option<String>,
+    /// All other claims, role claims are looked up here.
+    #[serde(flatten)]
+    pub extra: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// OpenID Connect relying party using the authorization code flow with PKCE.
+pub struct OidcClient {
+    http: reqwest::Client,
+    config: OidcConfig,
+    metadata: ProviderMetadata,
+}
+
+impl OidcClient {
+    /// Fetches the discovery document of the configured issuer.
+    pub async fn discover(config: OidcConfig) -> Result<Self> {
+        let http = reqwest::Client::new();
+        let url = format!("{}{}", config.issuer.trim_end_matches('/'), DISCOVERY_PATH);
+        info!("Discover OpenID provider {}", url);
+        let metadata: ProviderMetadata = http
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if metadata.issuer.trim_end_matches('/') != config.issuer.trim_end_matches('/') {
+            return Err(anyhow::Error::msg(format!(
+                "Issuer {} of the discovery document doesn't match {}",
+                metadata.issuer, config.issuer
+            )));
+        }
+        Ok(Self {
+            http,
+            config,
+            metadata,
+        })
+    }
+
+    pub fn metadata(&self) -> &ProviderMetadata {
+        &self.metadata
+    }
+
+    pub fn authorization_request(&self) -> Result<AuthorizationRequest> {
+        let state = random_string();
+        let nonce = random_string();
+        let code_verifier = random_string();
+        let mut scopes = vec!["openid"];
+        scopes.extend(
+            self.config
+                .scopes
+                .split_whitespace()
+                .filter(|scope| *scope != "openid"),
+        );
+        let url = Url::parse_with_params(
+            &self.metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.config.client_id.as_str()),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("scope", scopes.join(" ").as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge(&code_verifier).as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )?;
+        Ok(AuthorizationRequest {
+            url: url.to_string(),
+            state,
+            nonce,
+            code_verifier,
+        })
+    }
+
+    /// Redeems the authorization code and verifies the returned ID token.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        code_verifier: &str,
+        nonce: &str,
+    ) -> Result<IdTokenClaims> {
+        let response: TokenResponse = self
+            .http
+            .post(&self.metadata.token_endpoint)
+            .basic_auth(&self.config.client_id, Some(&self.config.client_secret))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.config.redirect_url.as_str()),
+                ("code_verifier", code_verifier),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let claims = self.verify_id_token(&response.id_token).await?;
+        if claims.nonce.as_deref() != Some(nonce) {
+            return Err(anyhow::Error::msg("Nonce of the ID token doesn't match"));
+        }
+        Ok(claims)
+    }
+
+    async fn verify_id_token(&self, id_token: &str) -> Result<IdTokenClaims> {
+        let header = decode_header(id_token)?;
+        // The header is chosen by whoever made the token, so HMAC must never be accepted.
+        if !RSA_ALGORITHMS.contains(&header.alg) {
+            return Err(anyhow::Error::msg(format!(
+                "Signing algorithm {:?} of the ID token isn't supported",
+                header.alg
+            )));
+        }
+        let jwks: JwkSet = self
+            .http
+            .get(&self.metadata.jwks_uri)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            None => jwks.keys.first(),
+        }
+        .ok_or_else(|| anyhow::Error::msg("Signing key of the ID token is unknown"))?;
+        let key = match &jwk.algorithm {
+            AlgorithmParameters::RSA(rsa) => DecodingKey::from_rsa_components(&rsa.n, &rsa.e)?,
+            _ => {
+                return Err(anyhow::Error::msg(
+                    "Only RSA signing keys are supported for ID tokens",
+                ))
+            }
+        };
+        let mut validation = Validation::new(signing_algorithm(jwk, header.alg)?);
+        validation.set_audience(&[&self.config.client_id]);
+        validation.set_issuer(&[&self.metadata.issuer]);
+        Ok(decode::<IdTokenClaims>(id_token, &key, &validation)?.claims)
+    }
+}
+
+/// Algorithm pinned by the key, RS256 of OpenID Connect if the key doesn't name one.
+fn signing_algorithm(jwk: &Jwk, requested: Algorithm) -> Result<Algorithm> {
+    let algorithm = jwk.common.algorithm.unwrap_or(Algorithm::RS256);
+    if algorithm != requested {
+        return Err(anyhow::Error::msg(format!(
+            "Signing algorithm {:?} of the ID token doesn't match the key",
+            requested
+        )));
+    }
+    Ok(algorithm)
+}
+
+/// S256 code challenge of RFC 7636.
+pub fn code_challenge(code_verifier: &str) -> String {
+    base64::encode_config(
+        Sha256::digest(code_verifier.as_bytes()),
+        base64::URL_SAFE_NO_PAD,
+    )
+}
+
+fn random_string() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn jwk(alg: Option<&str>) -> Jwk {
+        let mut jwk = json!({ "kty": "RSA", "kid": "1", "n": "AQAB", "e": "AQAB" });
+        if let Some(alg) = alg {
+            jwk["alg"] = json!(alg);
+        }
+        serde_json::from_value(jwk).unwrap()
+    }
+
+    #[test]
+    fn test_signing_algorithm() {
+        assert_eq!(
+            signing_algorithm(&jwk(None), Algorithm::RS256).unwrap(),
+            Algorithm::RS256
+        );
+        assert!(signing_algorithm(&jwk(None), Algorithm::RS512).is_err());
+        assert_eq!(
+            signing_algorithm(&jwk(Some("PS256")), Algorithm::PS256).unwrap(),
+            Algorithm::PS256
+        );
+        assert!(signing_algorithm(&jwk(Some("RS256")), Algorithm::HS256).is_err());
+        assert!(!RSA_ALGORITHMS.contains(&Algorithm::HS256));
+    }
+}
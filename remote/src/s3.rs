@@ -10,11 +10,21 @@ use aws_sdk_s3::{
 use aws_smithy_http::endpoint::Endpoint;
 use aws_types::{credentials::SharedCredentialsProvider, region::Region};
 use bytes::Bytes;
-use domain::Storage;
+use domain::{ObjectStream, Storage};
+use futures::TryStreamExt;
 use http::Uri;
 use log::info;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::{path::Path, str::FromStr};
 
+/// Characters of keys that are kept as is in the copy source.
+const COPY_SOURCE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
 #[derive(Debug, Clone)]
 pub struct DefaultStorage {
     client: Client,
@@ -85,6 +95,35 @@ impl Storage for DefaultStorage {
             .collect::<Vec<String>>())
     }
 
+    async fn list_objects_with_prefix(&self, bucket: &str, prefix: &str) -> Result<Vec<String>> {
+        info!(
+            "List of objects in bucket: {} with prefix: {}",
+            bucket, prefix
+        );
+        let mut keys = vec![];
+        let mut continuation_token = None;
+        loop {
+            let resp = self
+                .client
+                .list_objects_v2()
+                .bucket(bucket)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token)
+                .send()
+                .await?;
+            keys.extend(
+                resp.contents
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|obj| obj.key),
+            );
+            match resp.next_continuation_token {
+                Some(token) if resp.is_truncated => continuation_token = Some(token),
+                _ => return Ok(keys),
+            }
+        }
+    }
+
     async fn upload_file(&self, bucket: &str, filename: &str, key: &str) -> Result<()> {
         info!(
             "Upload file: {} into bucket: {} with key: {}",
@@ -133,6 +172,26 @@ impl Storage for DefaultStorage {
         Ok(object.body.collect().await.unwrap().into_bytes())
     }
 
+    async fn stream_object(&self, bucket: &str, key: &str) -> Result<ObjectStream> {
+        info!(
+            "Stream object from the bucket: {} with key: {}",
+            bucket, key
+        );
+        let object = self
+            .client
+            .get_object()
+            .bucket(bucket)
+            .key(key)
+            .send()
+            .await?;
+
+        Ok(ObjectStream {
+            content_type: object.content_type,
+            content_length: Some(object.content_length).filter(|length| *length > 0),
+            body: Box::pin(object.body.map_err(anyhow::Error::from)),
+        })
+    }
+
     async fn delete_object(&self, bucket: &str, key: &str) -> Result<()> {
         info!("Delete object from bucket: {} with key: {}", bucket, key);
         self.client
@@ -145,6 +204,32 @@ impl Storage for DefaultStorage {
         Ok(())
     }
 
+    async fn copy_object(
+        &self,
+        bucket: &str,
+        key: &str,
+        destination_bucket: &str,
+        destination_key: &str,
+    ) -> Result<()> {
+        info!(
+            "Copy object {} from bucket: {} to {} in bucket: {}",
+            key, bucket, destination_key, destination_bucket
+        );
+        self.client
+            .copy_object()
+            .copy_source(format!(
+                "{}/{}",
+                bucket,
+                utf8_percent_encode(key, COPY_SOURCE)
+            ))
+            .bucket(destination_bucket)
+            .key(destination_key)
+            .send()
+            .await?;
+
+        Ok(())
+    }
+
     async fn delete_objects(&self, bucket: &str, keys: Vec<String>) -> Result<()> {
         for key in keys.iter() {
             self.delete_object(bucket, key).await?;
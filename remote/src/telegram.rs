@@ -0,0 +1,110 @@
+use anyhow::Result;
+use log::info;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+struct TelegramResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    message_id: i64,
+}
+
+/// Sends photos to a chat through the Telegram Bot API, or an API compatible with it.
+pub struct TelegramClient {
+    http: reqwest::Client,
+    api_url: String,
+    bot_token: String,
+}
+
+impl TelegramClient {
+    pub fn new(api_url: &str, bot_token: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            api_url: api_url.trim_end_matches('/').to_owned(),
+            bot_token: bot_token.to_owned(),
+        }
+    }
+
+    /// Returns the id of the sent message.
+    pub async fn send_photo(
+        &self,
+        chat_id: &str,
+        file_name: &str,
+        data: Vec<u8>,
+        caption: &str,
+    ) -> Result<i64> {
+        let url = format!("{}/bot{}/sendPhoto", self.api_url, self.bot_token);
+        let form = Form::new()
+            .text("chat_id", chat_id.to_owned())
+            .text("caption", caption.to_owned())
+            .part("photo", Part::bytes(data).file_name(file_name.to_owned()));
+        // Failed calls answer with an error status and a description in the body.
+        let response: TelegramResponse<Message> = self
+            .http
+            .post(url)
+            .multipart(form)
+            .send()
+            .await?
+            .json()
+            .await?;
+        match response.result {
+            Some(message) if response.ok => {
+                info!("Sent {} to Telegram chat {}", file_name, chat_id);
+                Ok(message.message_id)
+            }
+            _ => Err(anyhow::Error::msg(format!(
+                "Telegram sendPhoto failed: {}",
+                response.description.unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_send_photo() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/bottoken/sendPhoto"))
+            .and(body_string_contains("name=\"chat_id\""))
+            .and(body_string_contains("@assets"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "ok": true, "result": {"message_id": 17}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/botother/sendPhoto"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+                "ok": false, "description": "Unauthorized"
+            })))
+            .mount(&server)
+            .await;
+
+        let client = TelegramClient::new(&server.uri(), "token");
+        let message_id = client
+            .send_photo("@assets", "cat.png", vec![1, 2, 3], "Cat")
+            .await
+            .unwrap();
+        assert_eq!(message_id, 17);
+
+        let client = TelegramClient::new(&server.uri(), "other");
+        let error = client
+            .send_photo("@assets", "cat.png", vec![1, 2, 3], "Cat")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("Unauthorized"));
+    }
+}
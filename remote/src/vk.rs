@@ -0,0 +1,232 @@
+use anyhow::Result;
+use app_config::VkConfig;
+use log::info;
+use reqwest::multipart::{Form, Part};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+/// Error object of a failed VK API method call.
+#[derive(Debug, Deserialize)]
+struct VkError {
+    error_code: i64,
+    error_msg: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct VkResponse<T> {
+    response: Option<T>,
+    error: Option<VkError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadServer {
+    upload_url: String,
+}
+
+/// Answer of the upload server, passed on as is to save the photo.
+#[derive(Debug, Deserialize)]
+struct UploadedPhoto {
+    server: i64,
+    photo: String,
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SavedPhoto {
+    id: i64,
+    owner_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WallPost {
+    post_id: i64,
+}
+
+/// Posts photos to the wall of the configured VK community on behalf of the community.
+pub struct VkClient {
+    http: reqwest::Client,
+    config: VkConfig,
+}
+
+impl VkClient {
+    pub fn new(config: VkConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    /// Uploads the photo to the wall of the community and posts it with the message,
+    /// returns the id of the post.
+    pub async fn post_photo(&self, file_name: &str, data: Vec<u8>, message: &str) -> Result<i64> {
+        let group_id = self.config.group_id.as_str();
+        let server: UploadServer = self
+            .call("photos.getWallUploadServer", &[("group_id", group_id)])
+            .await?;
+
+        let part = Part::bytes(data).file_name(file_name.to_owned());
+        let uploaded: UploadedPhoto = self
+            .http
+            .post(&server.upload_url)
+            .multipart(Form::new().part("photo", part))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let server_id = uploaded.server.to_string();
+        let saved: Vec<SavedPhoto> = self
+            .call(
+                "photos.saveWallPhoto",
+                &[
+                    ("group_id", group_id),
+                    ("server", server_id.as_str()),
+                    ("photo", uploaded.photo.as_str()),
+                    ("hash", uploaded.hash.as_str()),
+                ],
+            )
+            .await?;
+        let photo = saved
+            .first()
+            .ok_or_else(|| anyhow::Error::msg("VK saved no photo"))?;
+
+        let owner_id = format!("-{}", group_id);
+        let attachment = format!("photo{}_{}", photo.owner_id, photo.id);
+        let post: WallPost = self
+            .call(
+                "wall.post",
+                &[
+                    ("owner_id", owner_id.as_str()),
+                    ("from_group", "1"),
+                    ("attachments", attachment.as_str()),
+                    ("message", message),
+                ],
+            )
+            .await?;
+        info!("Posted {} to VK community {}", attachment, group_id);
+        Ok(post.post_id)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: &[(&str, &str)]) -> Result<T> {
+        let url = format!("{}/{}", self.config.api_url.trim_end_matches('/'), method);
+        let mut form = vec![
+            ("access_token", self.config.access_token.as_str()),
+            ("v", self.config.api_version.as_str()),
+        ];
+        form.extend_from_slice(params);
+        let response: VkResponse<T> = self
+            .http
+            .post(url)
+            .form(&form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        match (response.response, response.error) {
+            (_, Some(error)) => Err(anyhow::Error::msg(format!(
+                "VK method {} failed with error {}: {}",
+                method, error.error_code, error.error_msg
+            ))),
+            (Some(response), None) => Ok(response),
+            (None, None) => Err(anyhow::Error::msg(format!(
+                "VK method {} returned no response",
+                method
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config(api_url: String) -> VkConfig {
+        VkConfig {
+            enabled: true,
+            client_id: "".to_owned(),
+            secret_key: "".to_owned(),
+            access_token: "token".to_owned(),
+            group_id: "42".to_owned(),
+            hour: 7,
+            minute: 5,
+            api_url,
+            api_version: "5.131".to_owned(),
+            batch_size: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_post_photo() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/method/photos.getWallUploadServer"))
+            .and(body_string_contains("group_id=42"))
+            .and(body_string_contains("access_token=token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "response": {"upload_url": format!("{}/upload", server.uri())}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/upload"))
+            .and(body_string_contains("name=\"photo\""))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "server": 7, "photo": "[{\"photo\":\"abc\"}]", "hash": "h"
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/method/photos.saveWallPhoto"))
+            .and(body_string_contains("server=7"))
+            .and(body_string_contains("hash=h"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "response": [{"id": 100, "owner_id": -42}]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/method/wall.post"))
+            .and(body_string_contains("owner_id=-42"))
+            .and(body_string_contains("attachments=photo-42_100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "response": {"post_id": 555}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = VkClient::new(config(format!("{}/method", server.uri())));
+        let post_id = client
+            .post_photo("cat.png", vec![1, 2, 3], "Cat")
+            .await
+            .unwrap();
+        assert_eq!(post_id, 555);
+    }
+
+    #[tokio::test]
+    async fn test_post_photo_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/method/photos.getWallUploadServer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "error": {"error_code": 5, "error_msg": "User authorization failed"}
+            })))
+            .mount(&server)
+            .await;
+
+        let client = VkClient::new(config(format!("{}/method", server.uri())));
+        let error = client
+            .post_photo("cat.png", vec![1, 2, 3], "")
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("User authorization failed"));
+    }
+}
@@ -0,0 +1,140 @@
+use anyhow::Result;
+use log::info;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::multipart::{Form, Part};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+#[derive(Debug, Deserialize)]
+struct Created {
+    id: Value,
+}
+
+/// Posts files to an HTTP endpoint as `multipart/form-data` with a `file` and a JSON
+/// `resource` part. The endpoint answers with the id of the created object, `{"id": ...}`.
+pub struct WebhookClient {
+    http: reqwest::Client,
+    url: String,
+    token: String,
+}
+
+impl WebhookClient {
+    /// An empty token sends no `Authorization` header.
+    pub fn new(url: &str, token: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: url.to_owned(),
+            token: token.to_owned(),
+        }
+    }
+
+    pub async fn post_file(
+        &self,
+        file_name: &str,
+        data: Vec<u8>,
+        resource: &Value,
+    ) -> Result<String> {
+        let form = Form::new()
+            .part("file", Part::bytes(data).file_name(file_name.to_owned()))
+            .part(
+                "resource",
+                Part::text(resource.to_string()).mime_str("application/json")?,
+            );
+        let mut request = self.http.post(&self.url).multipart(form);
+        if !self.token.is_empty() {
+            request = request.bearer_auth(&self.token);
+        }
+        let created: Created = request.send().await?.error_for_status()?.json().await?;
+        let id = match created.id {
+            Value::String(id) => id,
+            id => id.to_string(),
+        };
+        info!("Posted {} to {} as {}", file_name, self.url, id);
+        Ok(id)
+    }
+}
+
+/// Posts JSON notifications of events to subscribed URLs.
+pub struct WebhookNotifier {
+    http: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    /// A request that takes longer than the timeout in seconds fails.
+    pub fn new(timeout: u64) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(timeout))
+                .build()
+                .expect("Failed to build the HTTP client"),
+        }
+    }
+
+    /// Posts the body with the headers, returns the HTTP status of any response.
+    pub async fn notify(&self, url: &str, body: String, headers: &[(&str, String)]) -> Result<u16> {
+        let mut request = self
+            .http
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .body(body);
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let status = request.send().await?.status().as_u16();
+        info!("Notified {}, response status {}", url, status);
+        Ok(status)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_post_file() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(header("authorization", "Bearer secret"))
+            .and(body_string_contains("\"key\":\"cat.png\""))
+            .respond_with(ResponseTemplate::new(201).set_body_json(json!({ "id": 42 })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let client = WebhookClient::new(&format!("{}/hook", server.uri()), "secret");
+        let id = client
+            .post_file("cat.png", vec![1, 2, 3], &json!({ "key": "cat.png" }))
+            .await
+            .unwrap();
+        assert_eq!(id, "42");
+    }
+
+    #[tokio::test]
+    async fn test_notify() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/events"))
+            .and(header("content-type", "application/json"))
+            .and(header("x-webhook-signature", "v1=abc"))
+            .and(body_string_contains("resource.created"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let status = WebhookNotifier::new(5)
+            .notify(
+                &format!("{}/events", server.uri()),
+                json!({ "type": "resource.created" }).to_string(),
+                &[("X-Webhook-Signature", "v1=abc".to_owned())],
+            )
+            .await
+            .unwrap();
+        assert_eq!(status, 503);
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use domain::AuditEvent;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "audit_events")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub kind: String,
+    pub user_id: Option<Uuid>,
+    pub client_ip: Option<String>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<AuditEvent> for ActiveModel {
+    fn from(event: AuditEvent) -> Self {
+        Self {
+            id: ActiveValue::Set(event.id.unwrap_or_else(Uuid::new_v4)),
+            kind: ActiveValue::Set(event.kind.to_string()),
+            user_id: ActiveValue::Set(event.user_id),
+            client_ip: ActiveValue::Set(event.client_ip.clone()),
+            message: ActiveValue::Set(event.message.clone()),
+            created_at: ActiveValue::Set(event.created_at),
+            updated_at: ActiveValue::Set(event.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for AuditEvent {
+    fn from(model: ActiveModel) -> Self {
+        AuditEvent {
+            id: Some(model.id.unwrap()),
+            kind: model.kind.unwrap().parse().unwrap(),
+            user_id: model.user_id.unwrap(),
+            client_ip: model.client_ip.unwrap(),
+            message: model.message.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, event: AuditEvent) -> Self {
+        Self {
+            id: self.id,
+            kind: ActiveValue::Set(event.kind.to_string()),
+            user_id: ActiveValue::Set(event.user_id),
+            client_ip: ActiveValue::Set(event.client_ip.clone()),
+            message: ActiveValue::Set(event.message.clone()),
+            created_at: ActiveValue::Set(event.created_at),
+            updated_at: ActiveValue::Set(event.updated_at),
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use domain::Captcha;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "captchas")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub answer_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<Captcha> for ActiveModel {
+    fn from(captcha: Captcha) -> Self {
+        Self {
+            id: ActiveValue::Set(captcha.id.unwrap_or_else(Uuid::new_v4)),
+            answer_hash: ActiveValue::Set(captcha.answer_hash.clone()),
+            expires_at: ActiveValue::Set(captcha.expires_at),
+            created_at: ActiveValue::Set(captcha.created_at),
+            updated_at: ActiveValue::Set(captcha.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for Captcha {
+    fn from(model: ActiveModel) -> Self {
+        Captcha {
+            id: Some(model.id.unwrap()),
+            answer_hash: model.answer_hash.unwrap(),
+            expires_at: model.expires_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, captcha: Captcha) -> Self {
+        Self {
+            id: self.id,
+            answer_hash: ActiveValue::Set(captcha.answer_hash.clone()),
+            expires_at: ActiveValue::Set(captcha.expires_at),
+            created_at: ActiveValue::Set(captcha.created_at),
+            updated_at: ActiveValue::Set(captcha.updated_at),
+        }
+    }
+}
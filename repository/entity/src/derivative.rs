@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use domain::Derivative;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "derivatives")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub size: i32,
+    pub key: String,
+    pub content_type: String,
+    pub width: i32,
+    pub height: i32,
+    pub byte_size: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<Derivative> for ActiveModel {
+    fn from(derivative: Derivative) -> Self {
+        Self {
+            id: ActiveValue::Set(derivative.id.unwrap_or_else(Uuid::new_v4)),
+            resource_id: ActiveValue::Set(derivative.resource_id),
+            size: ActiveValue::Set(derivative.size),
+            key: ActiveValue::Set(derivative.key.clone()),
+            content_type: ActiveValue::Set(derivative.content_type.clone()),
+            width: ActiveValue::Set(derivative.width),
+            height: ActiveValue::Set(derivative.height),
+            byte_size: ActiveValue::Set(derivative.byte_size),
+            created_at: ActiveValue::Set(derivative.created_at),
+            updated_at: ActiveValue::Set(derivative.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for Derivative {
+    fn from(model: ActiveModel) -> Self {
+        Derivative {
+            id: Some(model.id.unwrap()),
+            resource_id: model.resource_id.unwrap(),
+            size: model.size.unwrap(),
+            key: model.key.unwrap(),
+            content_type: model.content_type.unwrap(),
+            width: model.width.unwrap(),
+            height: model.height.unwrap(),
+            byte_size: model.byte_size.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, derivative: Derivative) -> Self {
+        Self {
+            id: self.id,
+            resource_id: ActiveValue::Set(derivative.resource_id),
+            size: ActiveValue::Set(derivative.size),
+            key: ActiveValue::Set(derivative.key.clone()),
+            content_type: ActiveValue::Set(derivative.content_type.clone()),
+            width: ActiveValue::Set(derivative.width),
+            height: ActiveValue::Set(derivative.height),
+            byte_size: ActiveValue::Set(derivative.byte_size),
+            created_at: ActiveValue::Set(derivative.created_at),
+            updated_at: ActiveValue::Set(derivative.updated_at),
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use domain::Job;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: DateTime<Utc>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<Job> for ActiveModel {
+    fn from(job: Job) -> Self {
+        Self {
+            id: ActiveValue::Set(job.id.unwrap_or_else(Uuid::new_v4)),
+            kind: ActiveValue::Set(job.kind.clone()),
+            payload: ActiveValue::Set(job.payload),
+            status: ActiveValue::Set(job.status.to_string()),
+            attempts: ActiveValue::Set(job.attempts),
+            max_attempts: ActiveValue::Set(job.max_attempts),
+            run_at: ActiveValue::Set(job.run_at),
+            locked_until: ActiveValue::Set(job.locked_until),
+            started_at: ActiveValue::Set(job.started_at),
+            last_error: ActiveValue::Set(job.last_error),
+            created_at: ActiveValue::Set(job.created_at),
+            updated_at: ActiveValue::Set(job.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for Job {
+    fn from(model: ActiveModel) -> Self {
+        Job {
+            id: Some(model.id.unwrap()),
+            kind: model.kind.unwrap(),
+            payload: model.payload.unwrap(),
+            status: model.status.unwrap().parse().unwrap(),
+            attempts: model.attempts.unwrap(),
+            max_attempts: model.max_attempts.unwrap(),
+            run_at: model.run_at.unwrap(),
+            locked_until: model.locked_until.unwrap(),
+            started_at: model.started_at.unwrap(),
+            last_error: model.last_error.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, job: Job) -> Self {
+        Self {
+            id: self.id,
+            kind: ActiveValue::Set(job.kind.clone()),
+            payload: ActiveValue::Set(job.payload),
+            status: ActiveValue::Set(job.status.to_string()),
+            attempts: ActiveValue::Set(job.attempts),
+            max_attempts: ActiveValue::Set(job.max_attempts),
+            run_at: ActiveValue::Set(job.run_at),
+            locked_until: ActiveValue::Set(job.locked_until),
+            started_at: ActiveValue::Set(job.started_at),
+            last_error: ActiveValue::Set(job.last_error),
+            created_at: ActiveValue::Set(job.created_at),
+            updated_at: ActiveValue::Set(job.updated_at),
+        }
+    }
+}
@@ -1,3 +1,20 @@
 pub use sea_orm;
+pub mod audit_event;
+pub mod captcha;
+pub mod derivative;
+pub mod job;
+pub mod job_queue;
+pub mod login_throttle;
+pub mod publication;
+pub mod recovery_code;
+pub mod refresh_token;
 pub mod resource;
+pub mod resource_event;
+pub mod schedule_run;
+pub mod share;
+pub mod two_factor;
 pub mod user;
+pub mod user_identity;
+pub mod user_token;
+pub mod webhook_delivery;
+pub mod webhook_subscription;
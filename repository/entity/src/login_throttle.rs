@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use domain::LoginThrottle;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "login_throttles")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(unique)]
+    pub key: String,
+    pub failures: i32,
+    pub last_failure_at: Option<DateTime<Utc>>,
+    pub locked_until: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<LoginThrottle> for ActiveModel {
+    fn from(throttle: LoginThrottle) -> Self {
+        Self {
+            id: ActiveValue::Set(throttle.id.unwrap_or_else(Uuid::new_v4)),
+            key: ActiveValue::Set(throttle.key.clone()),
+            failures: ActiveValue::Set(throttle.failures),
+            last_failure_at: ActiveValue::Set(throttle.last_failure_at),
+            locked_until: ActiveValue::Set(throttle.locked_until),
+            created_at: ActiveValue::Set(throttle.created_at),
+            updated_at: ActiveValue::Set(throttle.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for LoginThrottle {
+    fn from(model: ActiveModel) -> Self {
+        LoginThrottle {
+            id: Some(model.id.unwrap()),
+            key: model.key.unwrap(),
+            failures: model.failures.unwrap(),
+            last_failure_at: model.last_failure_at.unwrap(),
+            locked_until: model.locked_until.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, throttle: LoginThrottle) -> Self {
+        Self {
+            id: self.id,
+            key: ActiveValue::Set(throttle.key.clone()),
+            failures: ActiveValue::Set(throttle.failures),
+            last_failure_at: ActiveValue::Set(throttle.last_failure_at),
+            locked_until: ActiveValue::Set(throttle.locked_until),
+            created_at: ActiveValue::Set(throttle.created_at),
+            updated_at: ActiveValue::Set(throttle.updated_at),
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use domain::Publication;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "publications")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    pub target: String,
+    pub status: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub remote_id: Option<String>,
+    pub last_error: Option<String>,
+    pub published_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<Publication> for ActiveModel {
+    fn from(publication: Publication) -> Self {
+        Self {
+            id: ActiveValue::Set(publication.id.unwrap_or_else(Uuid::new_v4)),
+            resource_id: ActiveValue::Set(publication.resource_id),
+            target: ActiveValue::Set(publication.target.clone()),
+            status: ActiveValue::Set(publication.status.to_string()),
+            scheduled_at: ActiveValue::Set(publication.scheduled_at),
+            attempts: ActiveValue::Set(publication.attempts),
+            max_attempts: ActiveValue::Set(publication.max_attempts),
+            remote_id: ActiveValue::Set(publication.remote_id),
+            last_error: ActiveValue::Set(publication.last_error),
+            published_at: ActiveValue::Set(publication.published_at),
+            created_at: ActiveValue::Set(publication.created_at),
+            updated_at: ActiveValue::Set(publication.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for Publication {
+    fn from(model: ActiveModel) -> Self {
+        Publication {
+            id: Some(model.id.unwrap()),
+            resource_id: model.resource_id.unwrap(),
+            target: model.target.unwrap(),
+            status: model.status.unwrap().parse().unwrap(),
+            scheduled_at: model.scheduled_at.unwrap(),
+            attempts: model.attempts.unwrap(),
+            max_attempts: model.max_attempts.unwrap(),
+            remote_id: model.remote_id.unwrap(),
+            last_error: model.last_error.unwrap(),
+            published_at: model.published_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, publication: Publication) -> Self {
+        Self {
+            id: self.id,
+            resource_id: ActiveValue::Set(publication.resource_id),
+            target: ActiveValue::Set(publication.target.clone()),
+            status: ActiveValue::Set(publication.status.to_string()),
+            scheduled_at: ActiveValue::Set(publication.scheduled_at),
+            attempts: ActiveValue::Set(publication.attempts),
+            max_attempts: ActiveValue::Set(publication.max_attempts),
+            remote_id: ActiveValue::Set(publication.remote_id),
+            last_error: ActiveValue::Set(publication.last_error),
+            published_at: ActiveValue::Set(publication.published_at),
+            created_at: ActiveValue::Set(publication.created_at),
+            updated_at: ActiveValue::Set(publication.updated_at),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use domain::RecoveryCode;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "recovery_codes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub code_hash: String,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<RecoveryCode> for ActiveModel {
+    fn from(code: RecoveryCode) -> Self {
+        Self {
+            id: ActiveValue::Set(code.id.unwrap_or_else(Uuid::new_v4)),
+            user_id: ActiveValue::Set(code.user_id),
+            code_hash: ActiveValue::Set(code.code_hash.clone()),
+            used_at: ActiveValue::Set(code.used_at),
+            created_at: ActiveValue::Set(code.created_at),
+            updated_at: ActiveValue::Set(code.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for RecoveryCode {
+    fn from(model: ActiveModel) -> Self {
+        RecoveryCode {
+            id: Some(model.id.unwrap()),
+            user_id: model.user_id.unwrap(),
+            code_hash: model.code_hash.unwrap(),
+            used_at: model.used_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, code: RecoveryCode) -> Self {
+        Self {
+            id: self.id,
+            user_id: ActiveValue::Set(code.user_id),
+            code_hash: ActiveValue::Set(code.code_hash.clone()),
+            used_at: ActiveValue::Set(code.used_at),
+            created_at: ActiveValue::Set(code.created_at),
+            updated_at: ActiveValue::Set(code.updated_at),
+        }
+    }
+}
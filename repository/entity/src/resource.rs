@@ -16,6 +16,14 @@ pub struct Model {
     pub tags: Option<Value>,
     pub user_id: Option<Uuid>,
     pub metadata: Option<Value>,
+    pub perceptual_hash: Option<i64>,
+    pub blurhash: Option<String>,
+    pub palette: Option<Value>,
+    pub dominant_color: Option<i32>,
+    pub size: Option<i64>,
+    pub etag: Option<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -48,6 +56,14 @@ impl From<Resource> for ActiveModel {
             tags: ActiveValue::Set(res.tags),
             user_id: ActiveValue::Set(res.user_id),
             metadata: ActiveValue::Set(res.metadata),
+            perceptual_hash: ActiveValue::Set(res.perceptual_hash),
+            blurhash: ActiveValue::Set(res.blurhash),
+            palette: ActiveValue::Set(res.palette),
+            dominant_color: ActiveValue::Set(res.dominant_color),
+            size: ActiveValue::Set(res.size),
+            etag: ActiveValue::Set(res.etag),
+            expires_at: ActiveValue::Set(res.expires_at),
+            deleted_at: ActiveValue::Set(res.deleted_at),
             created_at: ActiveValue::Set(res.created_at),
             updated_at: ActiveValue::Set(res.updated_at),
         }
@@ -63,6 +79,14 @@ impl From<ActiveModel> for Resource {
             tags: model.tags.unwrap(),
             user_id: model.user_id.unwrap(),
             metadata: model.metadata.unwrap(),
+            perceptual_hash: model.perceptual_hash.unwrap(),
+            blurhash: model.blurhash.unwrap(),
+            palette: model.palette.unwrap(),
+            dominant_color: model.dominant_color.unwrap(),
+            size: model.size.unwrap(),
+            etag: model.etag.unwrap(),
+            expires_at: model.expires_at.unwrap(),
+            deleted_at: model.deleted_at.unwrap(),
             created_at: model.created_at.unwrap(),
             updated_at: model.updated_at.unwrap(),
         }
@@ -77,6 +101,24 @@ impl ActiveModel {
             tags: ActiveValue::Set(res.tags.or_else(|| ActiveValue::unwrap(self.tags))),
             user_id: ActiveValue::Set(res.user_id.or_else(|| ActiveValue::unwrap(self.user_id))),
             metadata: ActiveValue::Set(res.metadata.or_else(|| ActiveValue::unwrap(self.metadata))),
+            perceptual_hash: ActiveValue::Set(
+                res.perceptual_hash
+                    .or_else(|| ActiveValue::unwrap(self.perceptual_hash)),
+            ),
+            blurhash: ActiveValue::Set(res.blurhash.or_else(|| ActiveValue::unwrap(self.blurhash))),
+            palette: ActiveValue::Set(res.palette.or_else(|| ActiveValue::unwrap(self.palette))),
+            dominant_color: ActiveValue::Set(
+                res.dominant_color
+                    .or_else(|| ActiveValue::unwrap(self.dominant_color)),
+            ),
+            size: ActiveValue::Set(res.size.or_else(|| ActiveValue::unwrap(self.size))),
+            etag: ActiveValue::Set(res.etag.or_else(|| ActiveValue::unwrap(self.etag))),
+            expires_at: ActiveValue::Set(
+                res.expires_at
+                    .or_else(|| ActiveValue::unwrap(self.expires_at)),
+            ),
+            // Only moving to and out of the trash changes it.
+            deleted_at: self.deleted_at,
             created_at: ActiveValue::Set(res.created_at),
             updated_at: ActiveValue::Set(res.updated_at),
             id: ActiveValue::Set(self.id.unwrap()),
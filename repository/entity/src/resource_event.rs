@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use domain::ResourceEvent;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "resource_events")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+    pub event_type: String,
+    pub resource_id: Uuid,
+    pub key: String,
+    pub resource: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl From<ResourceEvent> for ActiveModel {
+    fn from(event: ResourceEvent) -> Self {
+        Self {
+            id: match event.id {
+                Some(id) => ActiveValue::Set(id),
+                None => ActiveValue::NotSet,
+            },
+            event_type: ActiveValue::Set(event.event_type.to_string()),
+            resource_id: ActiveValue::Set(event.resource_id),
+            key: ActiveValue::Set(event.key),
+            resource: ActiveValue::Set(event.resource),
+            created_at: ActiveValue::Set(event.created_at),
+        }
+    }
+}
+
+impl From<Model> for ResourceEvent {
+    fn from(model: Model) -> Self {
+        ResourceEvent {
+            id: Some(model.id),
+            event_type: model.event_type.parse().unwrap(),
+            resource_id: model.resource_id,
+            key: model.key,
+            resource: model.resource,
+            created_at: model.created_at,
+        }
+    }
+}
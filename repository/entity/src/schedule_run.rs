@@ -0,0 +1,88 @@
+use chrono::{DateTime, Utc};
+use domain::ScheduleRun;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "schedule_runs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub name: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub status: String,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<ScheduleRun> for ActiveModel {
+    fn from(run: ScheduleRun) -> Self {
+        Self {
+            id: ActiveValue::Set(run.id.unwrap_or_else(Uuid::new_v4)),
+            name: ActiveValue::Set(run.name.clone()),
+            scheduled_at: ActiveValue::Set(run.scheduled_at),
+            status: ActiveValue::Set(run.status.to_string()),
+            started_at: ActiveValue::Set(run.started_at),
+            finished_at: ActiveValue::Set(run.finished_at),
+            error: ActiveValue::Set(run.error),
+            created_at: ActiveValue::Set(run.created_at),
+            updated_at: ActiveValue::Set(run.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for ScheduleRun {
+    fn from(model: ActiveModel) -> Self {
+        ScheduleRun {
+            id: Some(model.id.unwrap()),
+            name: model.name.unwrap(),
+            scheduled_at: model.scheduled_at.unwrap(),
+            status: model.status.unwrap().parse().unwrap(),
+            started_at: model.started_at.unwrap(),
+            finished_at: model.finished_at.unwrap(),
+            error: model.error.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, run: ScheduleRun) -> Self {
+        Self {
+            id: self.id,
+            name: ActiveValue::Set(run.name.clone()),
+            scheduled_at: ActiveValue::Set(run.scheduled_at),
+            status: ActiveValue::Set(run.status.to_string()),
+            started_at: ActiveValue::Set(run.started_at),
+            finished_at: ActiveValue::Set(run.finished_at),
+            error: ActiveValue::Set(run.error),
+            created_at: ActiveValue::Set(run.created_at),
+            updated_at: ActiveValue::Set(run.updated_at),
+        }
+    }
+}
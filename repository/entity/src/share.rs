@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use domain::Share;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "shares")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub resource_id: Uuid,
+    #[sea_orm(unique)]
+    pub token_hash: String,
+    pub created_by: Uuid,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub password_hash: Option<String>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<Share> for ActiveModel {
+    fn from(share: Share) -> Self {
+        Self {
+            id: ActiveValue::Set(share.id.unwrap_or_else(Uuid::new_v4)),
+            resource_id: ActiveValue::Set(share.resource_id),
+            token_hash: ActiveValue::Set(share.token_hash.clone()),
+            created_by: ActiveValue::Set(share.created_by),
+            expires_at: ActiveValue::Set(share.expires_at),
+            password_hash: ActiveValue::Set(share.password_hash.clone()),
+            max_downloads: ActiveValue::Set(share.max_downloads),
+            download_count: ActiveValue::Set(share.download_count),
+            revoked_at: ActiveValue::Set(share.revoked_at),
+            created_at: ActiveValue::Set(share.created_at),
+            updated_at: ActiveValue::Set(share.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for Share {
+    fn from(model: ActiveModel) -> Self {
+        Share {
+            id: Some(model.id.unwrap()),
+            resource_id: model.resource_id.unwrap(),
+            token_hash: model.token_hash.unwrap(),
+            created_by: model.created_by.unwrap(),
+            expires_at: model.expires_at.unwrap(),
+            password_hash: model.password_hash.unwrap(),
+            max_downloads: model.max_downloads.unwrap(),
+            download_count: model.download_count.unwrap(),
+            revoked_at: model.revoked_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, share: Share) -> Self {
+        Self {
+            id: self.id,
+            resource_id: ActiveValue::Set(share.resource_id),
+            token_hash: ActiveValue::Set(share.token_hash.clone()),
+            created_by: ActiveValue::Set(share.created_by),
+            expires_at: ActiveValue::Set(share.expires_at),
+            password_hash: ActiveValue::Set(share.password_hash.clone()),
+            max_downloads: ActiveValue::Set(share.max_downloads),
+            download_count: ActiveValue::Set(share.download_count),
+            revoked_at: ActiveValue::Set(share.revoked_at),
+            created_at: ActiveValue::Set(share.created_at),
+            updated_at: ActiveValue::Set(share.updated_at),
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use chrono::{DateTime, Utc};
+use domain::TwoFactor;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "two_factors")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    #[sea_orm(unique)]
+    pub user_id: Uuid,
+    pub secret: String,
+    pub enabled: bool,
+    pub last_used_step: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<TwoFactor> for ActiveModel {
+    fn from(two_factor: TwoFactor) -> Self {
+        Self {
+            id: ActiveValue::Set(two_factor.id.unwrap_or_else(Uuid::new_v4)),
+            user_id: ActiveValue::Set(two_factor.user_id),
+            secret: ActiveValue::Set(two_factor.secret.clone()),
+            enabled: ActiveValue::Set(two_factor.enabled),
+            last_used_step: ActiveValue::Set(two_factor.last_used_step),
+            created_at: ActiveValue::Set(two_factor.created_at),
+            updated_at: ActiveValue::Set(two_factor.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for TwoFactor {
+    fn from(model: ActiveModel) -> Self {
+        TwoFactor {
+            id: Some(model.id.unwrap()),
+            user_id: model.user_id.unwrap(),
+            secret: model.secret.unwrap(),
+            enabled: model.enabled.unwrap(),
+            last_used_step: model.last_used_step.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, two_factor: TwoFactor) -> Self {
+        Self {
+            id: self.id,
+            user_id: ActiveValue::Set(two_factor.user_id),
+            secret: ActiveValue::Set(two_factor.secret.clone()),
+            enabled: ActiveValue::Set(two_factor.enabled),
+            last_used_step: ActiveValue::Set(two_factor.last_used_step),
+            created_at: ActiveValue::Set(two_factor.created_at),
+            updated_at: ActiveValue::Set(two_factor.updated_at),
+        }
+    }
+}
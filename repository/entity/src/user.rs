@@ -13,6 +13,8 @@ pub struct Model {
     pub email: String,
     pub password: String,
     pub enabled: bool,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub verification_pending: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub role: String,
@@ -45,6 +47,8 @@ impl From<User> for ActiveModel {
             email: ActiveValue::Set(user.email.clone()),
             password: ActiveValue::Set(user.password.clone()),
             enabled: ActiveValue::Set(user.enabled),
+            email_verified_at: ActiveValue::Set(user.email_verified_at),
+            verification_pending: ActiveValue::Set(user.verification_pending),
             role: ActiveValue::Set(user.role.to_string()),
             created_at: ActiveValue::Set(user.created_at),
             updated_at: ActiveValue::Set(user.updated_at),
@@ -60,6 +64,8 @@ impl From<ActiveModel> for User {
             email: model.email.unwrap(),
             password: model.password.unwrap(),
             enabled: model.enabled.unwrap(),
+            email_verified_at: model.email_verified_at.unwrap(),
+            verification_pending: model.verification_pending.unwrap(),
             role: model.role.unwrap().parse().unwrap(),
             created_at: model.created_at.unwrap(),
             updated_at: model.updated_at.unwrap(),
@@ -75,6 +81,8 @@ impl ActiveModel {
             email: ActiveValue::Set(user.email.clone()),
             password: ActiveValue::Set(user.password.clone()),
             enabled: ActiveValue::Set(user.enabled),
+            email_verified_at: ActiveValue::Set(user.email_verified_at),
+            verification_pending: ActiveValue::Set(user.verification_pending),
             role: ActiveValue::Set(user.role.to_string()),
             created_at: ActiveValue::Set(user.created_at),
             updated_at: ActiveValue::Set(user.updated_at),
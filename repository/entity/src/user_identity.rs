@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use domain::UserIdentity;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "user_identities")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub issuer: String,
+    pub subject: String,
+    pub provisioned: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<UserIdentity> for ActiveModel {
+    fn from(identity: UserIdentity) -> Self {
+        Self {
+            id: ActiveValue::Set(identity.id.unwrap_or_else(Uuid::new_v4)),
+            user_id: ActiveValue::Set(identity.user_id),
+            issuer: ActiveValue::Set(identity.issuer.clone()),
+            subject: ActiveValue::Set(identity.subject.clone()),
+            provisioned: ActiveValue::Set(identity.provisioned),
+            created_at: ActiveValue::Set(identity.created_at),
+            updated_at: ActiveValue::Set(identity.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for UserIdentity {
+    fn from(model: ActiveModel) -> Self {
+        UserIdentity {
+            id: Some(model.id.unwrap()),
+            user_id: model.user_id.unwrap(),
+            issuer: model.issuer.unwrap(),
+            subject: model.subject.unwrap(),
+            provisioned: model.provisioned.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, identity: UserIdentity) -> Self {
+        Self {
+            id: self.id,
+            user_id: ActiveValue::Set(identity.user_id),
+            issuer: ActiveValue::Set(identity.issuer.clone()),
+            subject: ActiveValue::Set(identity.subject.clone()),
+            provisioned: ActiveValue::Set(identity.provisioned),
+            created_at: ActiveValue::Set(identity.created_at),
+            updated_at: ActiveValue::Set(identity.updated_at),
+        }
+    }
+}
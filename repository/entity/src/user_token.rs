@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use domain::UserToken;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "user_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub kind: String,
+    pub token_hash: String,
+    pub expires_at: DateTime<Utc>,
+    pub used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<UserToken> for ActiveModel {
+    fn from(token: UserToken) -> Self {
+        Self {
+            id: ActiveValue::Set(token.id.unwrap_or_else(Uuid::new_v4)),
+            user_id: ActiveValue::Set(token.user_id),
+            kind: ActiveValue::Set(token.kind.to_string()),
+            token_hash: ActiveValue::Set(token.token_hash.clone()),
+            expires_at: ActiveValue::Set(token.expires_at),
+            used_at: ActiveValue::Set(token.used_at),
+            created_at: ActiveValue::Set(token.created_at),
+            updated_at: ActiveValue::Set(token.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for UserToken {
+    fn from(model: ActiveModel) -> Self {
+        UserToken {
+            id: Some(model.id.unwrap()),
+            user_id: model.user_id.unwrap(),
+            kind: model.kind.unwrap().parse().unwrap(),
+            token_hash: model.token_hash.unwrap(),
+            expires_at: model.expires_at.unwrap(),
+            used_at: model.used_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, token: UserToken) -> Self {
+        Self {
+            id: self.id,
+            user_id: ActiveValue::Set(token.user_id),
+            kind: ActiveValue::Set(token.kind.to_string()),
+            token_hash: ActiveValue::Set(token.token_hash.clone()),
+            expires_at: ActiveValue::Set(token.expires_at),
+            used_at: ActiveValue::Set(token.used_at),
+            created_at: ActiveValue::Set(token.created_at),
+            updated_at: ActiveValue::Set(token.updated_at),
+        }
+    }
+}
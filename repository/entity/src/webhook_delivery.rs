@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use domain::WebhookDelivery;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "webhook_deliveries")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<WebhookDelivery> for ActiveModel {
+    fn from(delivery: WebhookDelivery) -> Self {
+        Self {
+            id: ActiveValue::Set(delivery.id.unwrap_or_else(Uuid::new_v4)),
+            subscription_id: ActiveValue::Set(delivery.subscription_id),
+            event_id: ActiveValue::Set(delivery.event_id),
+            event_type: ActiveValue::Set(delivery.event_type.to_string()),
+            payload: ActiveValue::Set(delivery.payload),
+            status: ActiveValue::Set(delivery.status.to_string()),
+            attempts: ActiveValue::Set(delivery.attempts),
+            response_status: ActiveValue::Set(delivery.response_status),
+            last_error: ActiveValue::Set(delivery.last_error),
+            delivered_at: ActiveValue::Set(delivery.delivered_at),
+            created_at: ActiveValue::Set(delivery.created_at),
+            updated_at: ActiveValue::Set(delivery.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for WebhookDelivery {
+    fn from(model: ActiveModel) -> Self {
+        WebhookDelivery {
+            id: Some(model.id.unwrap()),
+            subscription_id: model.subscription_id.unwrap(),
+            event_id: model.event_id.unwrap(),
+            event_type: model.event_type.unwrap().parse().unwrap(),
+            payload: model.payload.unwrap(),
+            status: model.status.unwrap().parse().unwrap(),
+            attempts: model.attempts.unwrap(),
+            response_status: model.response_status.unwrap(),
+            last_error: model.last_error.unwrap(),
+            delivered_at: model.delivered_at.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, delivery: WebhookDelivery) -> Self {
+        Self {
+            id: self.id,
+            subscription_id: ActiveValue::Set(delivery.subscription_id),
+            event_id: ActiveValue::Set(delivery.event_id),
+            event_type: ActiveValue::Set(delivery.event_type.to_string()),
+            payload: ActiveValue::Set(delivery.payload),
+            status: ActiveValue::Set(delivery.status.to_string()),
+            attempts: ActiveValue::Set(delivery.attempts),
+            response_status: ActiveValue::Set(delivery.response_status),
+            last_error: ActiveValue::Set(delivery.last_error),
+            delivered_at: ActiveValue::Set(delivery.delivered_at),
+            created_at: ActiveValue::Set(delivery.created_at),
+            updated_at: ActiveValue::Set(delivery.updated_at),
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use domain::WebhookSubscription;
+use sea_orm::entity::prelude::*;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, DeriveEntityModel)]
+#[sea_orm(table_name = "webhook_subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    /// JSON array of the names of the event types.
+    pub event_types: Value,
+    pub enabled: bool,
+    pub max_attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {
+    fn new() -> Self {
+        Self {
+            id: ActiveValue::Set(Uuid::new_v4()),
+            created_at: ActiveValue::Set(Utc::now()),
+            ..ActiveModelTrait::default()
+        }
+    }
+
+    /// Will be triggered before insert / update
+    fn before_save(mut self, _: bool) -> Result<Self, DbErr> {
+        self.updated_at = ActiveValue::Set(Utc::now());
+        Ok(self)
+    }
+}
+
+impl From<WebhookSubscription> for ActiveModel {
+    fn from(subscription: WebhookSubscription) -> Self {
+        Self {
+            id: ActiveValue::Set(subscription.id.unwrap_or_else(Uuid::new_v4)),
+            url: ActiveValue::Set(subscription.url),
+            secret: ActiveValue::Set(subscription.secret),
+            event_types: ActiveValue::Set(serde_json::to_value(subscription.event_types).unwrap()),
+            enabled: ActiveValue::Set(subscription.enabled),
+            max_attempts: ActiveValue::Set(subscription.max_attempts),
+            created_at: ActiveValue::Set(subscription.created_at),
+            updated_at: ActiveValue::Set(subscription.updated_at),
+        }
+    }
+}
+
+impl From<ActiveModel> for WebhookSubscription {
+    fn from(model: ActiveModel) -> Self {
+        WebhookSubscription {
+            id: Some(model.id.unwrap()),
+            url: model.url.unwrap(),
+            secret: model.secret.unwrap(),
+            event_types: serde_json::from_value(model.event_types.unwrap()).unwrap_or_default(),
+            enabled: model.enabled.unwrap(),
+            max_attempts: model.max_attempts.unwrap(),
+            created_at: model.created_at.unwrap(),
+            updated_at: model.updated_at.unwrap(),
+        }
+    }
+}
+
+impl ActiveModel {
+    pub fn update_model(self, subscription: WebhookSubscription) -> Self {
+        Self {
+            id: self.id,
+            url: ActiveValue::Set(subscription.url),
+            secret: ActiveValue::Set(subscription.secret),
+            event_types: ActiveValue::Set(serde_json::to_value(subscription.event_types).unwrap()),
+            enabled: ActiveValue::Set(subscription.enabled),
+            max_attempts: ActiveValue::Set(subscription.max_attempts),
+            created_at: ActiveValue::Set(subscription.created_at),
+            updated_at: ActiveValue::Set(subscription.updated_at),
+        }
+    }
+}
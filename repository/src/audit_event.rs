@@ -0,0 +1,120 @@
+use anyhow::Result;
+use entity::audit_event;
+use entity::audit_event::{ActiveModel as AuditEventModel, Entity as AuditEventEntity};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
+
+use async_trait::async_trait;
+use domain::{AuditEvent, AuditEventFilter, AuditLog, Page, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct AuditEventRepository {
+    db: Arc<DbConn>,
+}
+
+impl AuditEventRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for AuditEventRepository {
+    type Type = AuditEvent;
+
+    async fn create(&self, item: AuditEvent) -> Result<AuditEvent> {
+        info!("creating audit event: {}", item.kind);
+        let result = AuditEventModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: AuditEvent) -> Result<AuditEvent> {
+        info!("updating audit event {}", id);
+        let result = AuditEventEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<AuditEvent> {
+        info!("getting audit event by id: {}", id);
+        let result = AuditEventEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Audit events have no natural key, the key is the id of the event.
+    async fn get_by_key(&self, key: String) -> Result<AuditEvent> {
+        self.get_by_id(Uuid::parse_str(&key)?).await
+    }
+
+    async fn get_all(&self) -> Result<Vec<AuditEvent>> {
+        info!("getting all audit events");
+        let events: Vec<audit_event::Model> =
+            AuditEventEntity::find().all(self.db.as_ref()).await?;
+        Ok(events
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        AuditEventEntity::delete_many()
+            .filter(audit_event::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        AuditEventEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AuditLog for AuditEventRepository {
+    async fn find(&self, filter: AuditEventFilter) -> Result<Page<AuditEvent>> {
+        info!("finding audit events by filter: {:?}", filter);
+        let mut query = AuditEventEntity::find().order_by_desc(audit_event::Column::CreatedAt);
+        if let Some(kind) = filter.kind {
+            query = query.filter(audit_event::Column::Kind.eq(kind.to_string()));
+        }
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(audit_event::Column::UserId.eq(user_id));
+        }
+        let paginator = query.paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let events = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: events
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+}
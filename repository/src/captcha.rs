@@ -0,0 +1,120 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::captcha;
+use entity::captcha::{ActiveModel as CaptchaModel, Entity as CaptchaEntity};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbBackend, DbConn, EntityTrait, IntoActiveModel, QueryFilter,
+    Statement,
+};
+
+use async_trait::async_trait;
+use domain::{Captcha, Captchas, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct CaptchaRepository {
+    db: Arc<DbConn>,
+}
+
+impl CaptchaRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for CaptchaRepository {
+    type Type = Captcha;
+
+    async fn create(&self, item: Captcha) -> Result<Captcha> {
+        info!("creating captcha expiring at {}", item.expires_at);
+        let result = CaptchaModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: Captcha) -> Result<Captcha> {
+        info!("updating captcha {}", id);
+        let result = CaptchaEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Captcha> {
+        info!("getting captcha by id: {}", id);
+        let result = CaptchaEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Captchas are looked up by the hash of the answer, never by the raw value.
+    async fn get_by_key(&self, key: String) -> Result<Captcha> {
+        info!("getting captcha by hash");
+        let result = CaptchaEntity::find()
+            .filter(captcha::Column::AnswerHash.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Captcha doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Captcha>> {
+        info!("getting all captchas");
+        let captchas: Vec<captcha::Model> = CaptchaEntity::find().all(self.db.as_ref()).await?;
+        Ok(captchas
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        CaptchaEntity::delete_many()
+            .filter(captcha::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        CaptchaEntity::delete_many().exec(self.db.as_ref()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Captchas for CaptchaRepository {
+    async fn take(&self, id: Uuid) -> Result<Option<Captcha>> {
+        info!("taking captcha {}", id);
+        let result = CaptchaEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"DELETE FROM captchas WHERE id = $1 RETURNING *"#,
+                vec![id.into()],
+            ))
+            .one(self.db.as_ref())
+            .await?;
+        Ok(result.map(|model| model.into_active_model().into()))
+    }
+
+    async fn delete_expired(&self) -> Result<u64> {
+        let result = CaptchaEntity::delete_many()
+            .filter(captcha::Column::ExpiresAt.lte(Utc::now()))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use entity::derivative;
+use entity::derivative::{ActiveModel as DerivativeModel, Entity as DerivativeEntity};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+};
+
+use async_trait::async_trait;
+use domain::{Derivative, Derivatives, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct DerivativeRepository {
+    db: Arc<DbConn>,
+}
+
+impl DerivativeRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for DerivativeRepository {
+    type Type = Derivative;
+
+    async fn create(&self, item: Derivative) -> Result<Derivative> {
+        info!(
+            "creating derivative {} of resource: {}",
+            item.size, item.resource_id
+        );
+        let result = DerivativeModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: Derivative) -> Result<Derivative> {
+        info!("updating derivative {}", id);
+        let result = DerivativeEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Derivative> {
+        info!("getting derivative by id: {}", id);
+        let result = DerivativeEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    async fn get_by_key(&self, key: String) -> Result<Derivative> {
+        info!("getting derivative by key: {}", key);
+        let result = DerivativeEntity::find()
+            .filter(derivative::Column::Key.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Derivative doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Derivative>> {
+        info!("getting all derivatives");
+        let derivatives: Vec<derivative::Model> =
+            DerivativeEntity::find().all(self.db.as_ref()).await?;
+        Ok(derivatives
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        DerivativeEntity::delete_many()
+            .filter(derivative::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        DerivativeEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Derivatives for DerivativeRepository {
+    async fn find_by_resource(&self, resource_id: Uuid) -> Result<Vec<Derivative>> {
+        info!("getting derivatives of resource {}", resource_id);
+        let derivatives: Vec<derivative::Model> = DerivativeEntity::find()
+            .filter(derivative::Column::ResourceId.eq(resource_id))
+            .order_by_asc(derivative::Column::Size)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(derivatives
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn get_by_size(&self, resource_id: Uuid, size: i32) -> Result<Option<Derivative>> {
+        info!("getting derivative {} of resource {}", size, resource_id);
+        let result = DerivativeEntity::find()
+            .filter(derivative::Column::ResourceId.eq(resource_id))
+            .filter(derivative::Column::Size.eq(size))
+            .one(self.db.as_ref())
+            .await?;
+        Ok(result.map(|e| e.into_active_model().into()))
+    }
+
+    async fn delete_by_resource(&self, resource_id: Uuid) -> Result<()> {
+        DerivativeEntity::delete_many()
+            .filter(derivative::Column::ResourceId.eq(resource_id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,343 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use entity::job;
+use entity::job::{ActiveModel as JobModel, Entity as JobEntity};
+use entity::job_queue;
+use entity::job_queue::Entity as JobQueueEntity;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbConn, EntityTrait,
+    FromQueryResult, IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Statement,
+};
+
+use async_trait::async_trait;
+use domain::{Job, JobFilter, JobQueueStats, JobStatus, Jobs, Page, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Row of the statistics query, the pause state is merged in afterwards.
+#[derive(Debug, FromQueryResult)]
+struct JobKindStats {
+    kind: String,
+    pending: i64,
+    running: i64,
+    done: i64,
+    dead: i64,
+    cancelled: i64,
+    done_since: i64,
+    avg_latency: Option<f64>,
+    avg_duration: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct JobRepository {
+    db: Arc<DbConn>,
+}
+
+impl JobRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+
+    /// Moves a claimed job out of the running state, if the attempt still owns it.
+    async fn release(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        status: JobStatus,
+        run_at: Option<DateTime<Utc>>,
+        error: Option<&str>,
+    ) -> Result<bool> {
+        let mut update = JobEntity::update_many()
+            .col_expr(job::Column::Status, Expr::value(status.to_string()))
+            .col_expr(
+                job::Column::LockedUntil,
+                Expr::value(Option::<DateTime<Utc>>::None),
+            )
+            .col_expr(job::Column::UpdatedAt, Expr::value(Utc::now()));
+        if let Some(run_at) = run_at {
+            update = update.col_expr(job::Column::RunAt, Expr::value(run_at));
+        }
+        if let Some(error) = error {
+            update = update.col_expr(job::Column::LastError, Expr::value(error.to_owned()));
+        }
+        let result = update
+            .filter(job::Column::Id.eq(id))
+            .filter(job::Column::Status.eq(JobStatus::Running.to_string()))
+            .filter(job::Column::Attempts.eq(attempt))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
+
+#[async_trait]
+impl Repository for JobRepository {
+    type Type = Job;
+
+    async fn create(&self, item: Job) -> Result<Job> {
+        info!("creating job: {}", item.kind);
+        let result = JobModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: Job) -> Result<Job> {
+        info!("updating job {}", id);
+        let result = JobEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Job> {
+        info!("getting job by id: {}", id);
+        let result = JobEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Jobs have no unique key, the first pending job of the kind is returned.
+    async fn get_by_key(&self, key: String) -> Result<Job> {
+        info!("getting pending job of kind: {}", key);
+        let result = JobEntity::find()
+            .filter(job::Column::Kind.eq(key.clone()))
+            .filter(job::Column::Status.eq(JobStatus::Pending.to_string()))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Pending job of kind {} doesn't exist",
+                key
+            ))),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Job>> {
+        info!("getting all jobs");
+        let jobs: Vec<job::Model> = JobEntity::find().all(self.db.as_ref()).await?;
+        Ok(jobs
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        JobEntity::delete_many()
+            .filter(job::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        JobEntity::delete_many().exec(self.db.as_ref()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Jobs for JobRepository {
+    async fn claim(&self, kind: &str, limit: u64, locked_until: DateTime<Utc>) -> Result<Vec<Job>> {
+        let now = Utc::now();
+        let jobs = JobEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"UPDATE jobs
+                   SET status = 'Running', attempts = attempts + 1, locked_until = $4,
+                       started_at = $3, updated_at = $3
+                   WHERE id IN (
+                       SELECT id FROM jobs
+                       WHERE kind = $1
+                           AND ((status = 'Pending' AND run_at <= $3)
+                               OR (status = 'Running' AND locked_until < $3))
+                           AND NOT EXISTS (
+                               SELECT 1 FROM job_queues WHERE job_queues.kind = $1 AND paused
+                           )
+                       ORDER BY run_at
+                       LIMIT $2
+                       FOR UPDATE SKIP LOCKED
+                   )
+                   RETURNING *"#,
+                vec![
+                    kind.into(),
+                    (limit as i64).into(),
+                    now.into(),
+                    locked_until.into(),
+                ],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+        if !jobs.is_empty() {
+            info!("claimed {} jobs of kind {}", jobs.len(), kind);
+        }
+        Ok(jobs
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn complete(&self, id: Uuid, attempt: i32) -> Result<bool> {
+        self.release(id, attempt, JobStatus::Done, None, None).await
+    }
+
+    async fn retry(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        run_at: DateTime<Utc>,
+        error: &str,
+    ) -> Result<bool> {
+        self.release(id, attempt, JobStatus::Pending, Some(run_at), Some(error))
+            .await
+    }
+
+    async fn dead_letter(&self, id: Uuid, attempt: i32, error: &str) -> Result<bool> {
+        self.release(id, attempt, JobStatus::Dead, None, Some(error))
+            .await
+    }
+
+    async fn find(&self, filter: JobFilter) -> Result<Page<Job>> {
+        info!("finding jobs by filter: {:?}", filter);
+        let mut query = JobEntity::find().order_by_desc(job::Column::CreatedAt);
+        if let Some(kind) = filter.kind {
+            query = query.filter(job::Column::Kind.eq(kind));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(job::Column::Status.eq(status.to_string()));
+        }
+        let paginator = query.paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let jobs = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: jobs
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+
+    async fn requeue(&self, id: Uuid) -> Result<bool> {
+        info!("requeueing job {}", id);
+        let now = Utc::now();
+        let result = JobEntity::update_many()
+            .col_expr(
+                job::Column::Status,
+                Expr::value(JobStatus::Pending.to_string()),
+            )
+            .col_expr(job::Column::Attempts, Expr::value(0))
+            .col_expr(job::Column::RunAt, Expr::value(now))
+            .col_expr(job::Column::UpdatedAt, Expr::value(now))
+            .filter(job::Column::Id.eq(id))
+            .filter(job::Column::Status.is_in([
+                JobStatus::Dead.to_string(),
+                JobStatus::Cancelled.to_string(),
+            ]))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<bool> {
+        info!("cancelling job {}", id);
+        let result = JobEntity::update_many()
+            .col_expr(
+                job::Column::Status,
+                Expr::value(JobStatus::Cancelled.to_string()),
+            )
+            .col_expr(job::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(job::Column::Id.eq(id))
+            .filter(
+                job::Column::Status
+                    .is_in([JobStatus::Pending.to_string(), JobStatus::Dead.to_string()]),
+            )
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn set_paused(&self, kind: &str, paused: bool) -> Result<()> {
+        info!("setting paused of {} jobs to {}", kind, paused);
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"INSERT INTO job_queues (kind, paused, created_at, updated_at)
+                   VALUES ($1, $2, $3, $3)
+                   ON CONFLICT (kind) DO UPDATE SET paused = $2, updated_at = $3"#,
+                vec![kind.into(), paused.into(), Utc::now().into()],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn stats(&self, since: DateTime<Utc>) -> Result<Vec<JobQueueStats>> {
+        let stats = JobKindStats::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            r#"SELECT kind,
+                   count(*) FILTER (WHERE status = 'Pending') AS pending,
+                   count(*) FILTER (WHERE status = 'Running') AS running,
+                   count(*) FILTER (WHERE status = 'Done') AS done,
+                   count(*) FILTER (WHERE status = 'Dead') AS dead,
+                   count(*) FILTER (WHERE status = 'Cancelled') AS cancelled,
+                   count(*) FILTER (WHERE status = 'Done' AND updated_at >= $1) AS done_since,
+                   avg(extract(epoch FROM started_at - run_at)::float8)
+                       FILTER (WHERE status = 'Done' AND updated_at >= $1) AS avg_latency,
+                   avg(extract(epoch FROM updated_at - started_at)::float8)
+                       FILTER (WHERE status = 'Done' AND updated_at >= $1) AS avg_duration
+               FROM jobs
+               GROUP BY kind
+               ORDER BY kind"#,
+            vec![since.into()],
+        ))
+        .all(self.db.as_ref())
+        .await?;
+        let paused: Vec<String> = JobQueueEntity::find()
+            .filter(job_queue::Column::Paused.eq(true))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|queue| queue.kind)
+            .collect();
+
+        let mut queues: Vec<JobQueueStats> = stats
+            .into_iter()
+            .map(|stats| JobQueueStats {
+                paused: paused.contains(&stats.kind),
+                kind: stats.kind,
+                pending: stats.pending,
+                running: stats.running,
+                done: stats.done,
+                dead: stats.dead,
+                cancelled: stats.cancelled,
+                done_since: stats.done_since,
+                avg_latency: stats.avg_latency,
+                avg_duration: stats.avg_duration,
+            })
+            .collect();
+        // Paused queues without any jobs are listed as well.
+        for kind in paused {
+            if !queues.iter().any(|queue| queue.kind == kind) {
+                queues.push(JobQueueStats {
+                    kind,
+                    paused: true,
+                    ..JobQueueStats::default()
+                });
+            }
+        }
+        Ok(queues)
+    }
+}
@@ -1,6 +1,47 @@
+mod audit_event;
+pub use audit_event::*;
+mod captcha;
+pub use captcha::*;
+mod derivative;
+pub use derivative::*;
+mod job;
+pub use job::*;
+mod login_throttle;
+pub use login_throttle::*;
+mod publication;
+pub use publication::*;
+mod recovery_code;
+pub use recovery_code::*;
+mod refresh_token;
+pub use refresh_token::*;
 mod resource;
 pub use resource::*;
+mod resource_event;
+pub use resource_event::*;
+mod schedule_run;
+pub use schedule_run::*;
+mod share;
+pub use share::*;
+mod two_factor;
+pub use two_factor::*;
 mod user;
 pub use user::*;
+mod user_identity;
+pub use user_identity::*;
+mod user_token;
+pub use user_token::*;
+mod webhook_delivery;
+pub use webhook_delivery::*;
+mod webhook_subscription;
+pub use webhook_subscription::*;
 
 pub use entity::*;
+
+/// Whether the error is a violation of a unique constraint, e.g. of a key that is taken.
+pub fn is_unique_violation(err: &anyhow::Error) -> bool {
+    matches!(
+        err.downcast_ref::<sea_orm::DbErr>(),
+        Some(sea_orm::DbErr::Exec(message) | sea_orm::DbErr::Query(message))
+            if message.contains("duplicate key value violates unique constraint")
+    )
+}
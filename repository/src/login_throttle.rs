@@ -0,0 +1,164 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use entity::login_throttle;
+use entity::login_throttle::{ActiveModel as LoginThrottleModel, Entity as LoginThrottleEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbBackend, DbConn, EntityTrait, IntoActiveModel, QueryFilter,
+    Statement,
+};
+
+use async_trait::async_trait;
+use domain::{LoginThrottle, LoginThrottles, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct LoginThrottleRepository {
+    db: Arc<DbConn>,
+}
+
+impl LoginThrottleRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for LoginThrottleRepository {
+    type Type = LoginThrottle;
+
+    async fn create(&self, item: LoginThrottle) -> Result<LoginThrottle> {
+        info!("creating login throttle for key: {}", item.key);
+        let result = LoginThrottleModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: LoginThrottle) -> Result<LoginThrottle> {
+        info!("updating login throttle {}", id);
+        let result = LoginThrottleEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<LoginThrottle> {
+        info!("getting login throttle by id: {}", id);
+        let result = LoginThrottleEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Login throttles are looked up by the throttled key.
+    async fn get_by_key(&self, key: String) -> Result<LoginThrottle> {
+        info!("getting login throttle by key: {}", key);
+        let result = LoginThrottleEntity::find()
+            .filter(login_throttle::Column::Key.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Login throttle doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<LoginThrottle>> {
+        info!("getting all login throttles");
+        let throttles: Vec<login_throttle::Model> =
+            LoginThrottleEntity::find().all(self.db.as_ref()).await?;
+        Ok(throttles
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        LoginThrottleEntity::delete_many()
+            .filter(login_throttle::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        LoginThrottleEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl LoginThrottles for LoginThrottleRepository {
+    async fn record_failure(
+        &self,
+        key: &str,
+        window_start: DateTime<Utc>,
+    ) -> Result<LoginThrottle> {
+        info!("recording failed login for key: {}", key);
+        let now = Utc::now();
+        let result = LoginThrottleEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"INSERT INTO login_throttles (id, "key", failures, last_failure_at, created_at, updated_at)
+                   VALUES ($1, $2, 1, $3, $3, $3)
+                   ON CONFLICT ("key") DO UPDATE SET
+                       failures = CASE
+                           WHEN login_throttles.last_failure_at IS NULL
+                               OR login_throttles.last_failure_at < $4 THEN 1
+                           ELSE login_throttles.failures + 1
+                       END,
+                       last_failure_at = $3,
+                       updated_at = $3
+                   RETURNING *"#,
+                vec![
+                    Uuid::new_v4().into(),
+                    key.into(),
+                    now.into(),
+                    window_start.into(),
+                ],
+            ))
+            .one(self.db.as_ref())
+            .await?;
+        result.map(|e| e.into_active_model().into()).ok_or_else(|| {
+            anyhow::Error::msg(format!("Failed to record login failure for {}", key))
+        })
+    }
+
+    async fn lock(&self, key: &str, until: DateTime<Utc>) -> Result<()> {
+        info!("locking key {} until {}", key, until);
+        LoginThrottleEntity::update_many()
+            .col_expr(login_throttle::Column::LockedUntil, Expr::value(until))
+            .col_expr(login_throttle::Column::Failures, Expr::value(0))
+            .col_expr(login_throttle::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(login_throttle::Column::Key.eq(key))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn clear(&self, key: &str) -> Result<()> {
+        LoginThrottleEntity::delete_many()
+            .filter(login_throttle::Column::Key.eq(key))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
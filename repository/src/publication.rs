@@ -0,0 +1,276 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use entity::publication;
+use entity::publication::{ActiveModel as PublicationModel, Entity as PublicationEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbBackend, DbConn, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, Statement,
+};
+
+use async_trait::async_trait;
+use domain::{Page, Publication, PublicationFilter, PublicationStatus, Publications, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct PublicationRepository {
+    db: Arc<DbConn>,
+}
+
+impl PublicationRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+
+    /// Moves a started publication out of the publishing state, if the attempt still owns it.
+    async fn release(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        status: PublicationStatus,
+        remote_id: Option<&str>,
+        error: Option<&str>,
+    ) -> Result<bool> {
+        let mut update = PublicationEntity::update_many()
+            .col_expr(publication::Column::Status, Expr::value(status.to_string()))
+            .col_expr(publication::Column::UpdatedAt, Expr::value(Utc::now()));
+        if let Some(remote_id) = remote_id {
+            update = update
+                .col_expr(
+                    publication::Column::RemoteId,
+                    Expr::value(remote_id.to_owned()),
+                )
+                .col_expr(publication::Column::PublishedAt, Expr::value(Utc::now()));
+        }
+        if let Some(error) = error {
+            update = update.col_expr(
+                publication::Column::LastError,
+                Expr::value(error.to_owned()),
+            );
+        }
+        let result = update
+            .filter(publication::Column::Id.eq(id))
+            .filter(publication::Column::Status.eq(PublicationStatus::Publishing.to_string()))
+            .filter(publication::Column::Attempts.eq(attempt))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    /// Changes a publication that isn't being published or done yet.
+    async fn update_pending(
+        &self,
+        id: Uuid,
+        status: PublicationStatus,
+        scheduled_at: Option<DateTime<Utc>>,
+    ) -> Result<bool> {
+        let mut update = PublicationEntity::update_many()
+            .col_expr(publication::Column::Status, Expr::value(status.to_string()))
+            .col_expr(publication::Column::UpdatedAt, Expr::value(Utc::now()));
+        if let Some(scheduled_at) = scheduled_at {
+            update = update
+                .col_expr(publication::Column::ScheduledAt, Expr::value(scheduled_at))
+                .col_expr(publication::Column::Attempts, Expr::value(0))
+                .col_expr(
+                    publication::Column::LastError,
+                    Expr::value(Option::<String>::None),
+                );
+        }
+        let result = update
+            .filter(publication::Column::Id.eq(id))
+            .filter(publication::Column::Status.is_in([
+                PublicationStatus::Scheduled.to_string(),
+                PublicationStatus::Failed.to_string(),
+            ]))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
+
+#[async_trait]
+impl Repository for PublicationRepository {
+    type Type = Publication;
+
+    async fn create(&self, item: Publication) -> Result<Publication> {
+        info!("creating publication to {}", item.target);
+        let result = PublicationModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: Publication) -> Result<Publication> {
+        info!("updating publication {}", id);
+        let result = PublicationEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Publication> {
+        info!("getting publication by id: {}", id);
+        let result = PublicationEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Publications have no unique key, the next scheduled publication to the target is
+    /// returned.
+    async fn get_by_key(&self, key: String) -> Result<Publication> {
+        info!("getting next publication to: {}", key);
+        let result = PublicationEntity::find()
+            .filter(publication::Column::Target.eq(key.clone()))
+            .filter(publication::Column::Status.eq(PublicationStatus::Scheduled.to_string()))
+            .order_by_asc(publication::Column::ScheduledAt)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Scheduled publication to {} doesn't exist",
+                key
+            ))),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Publication>> {
+        info!("getting all publications");
+        let publications: Vec<publication::Model> =
+            PublicationEntity::find().all(self.db.as_ref()).await?;
+        Ok(publications
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        PublicationEntity::delete_many()
+            .filter(publication::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        PublicationEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Publications for PublicationRepository {
+    async fn start(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<Option<Publication>> {
+        let publication = PublicationEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"UPDATE publications
+                   SET status = 'Publishing', attempts = attempts + 1, updated_at = $3
+                   WHERE id = $1
+                       AND scheduled_at = $2
+                       AND status IN ('Scheduled', 'Publishing')
+                   RETURNING *"#,
+                vec![id.into(), scheduled_at.into(), Utc::now().into()],
+            ))
+            .one(self.db.as_ref())
+            .await?;
+        if publication.is_some() {
+            info!("started publication {}", id);
+        }
+        Ok(publication.map(|e| e.into_active_model().into()))
+    }
+
+    async fn complete(&self, id: Uuid, attempt: i32, remote_id: &str) -> Result<bool> {
+        self.release(
+            id,
+            attempt,
+            PublicationStatus::Published,
+            Some(remote_id),
+            None,
+        )
+        .await
+    }
+
+    async fn retry(&self, id: Uuid, attempt: i32, error: &str) -> Result<bool> {
+        self.release(id, attempt, PublicationStatus::Scheduled, None, Some(error))
+            .await
+    }
+
+    async fn fail(&self, id: Uuid, scheduled_at: DateTime<Utc>, error: &str) -> Result<bool> {
+        let result = PublicationEntity::update_many()
+            .col_expr(
+                publication::Column::Status,
+                Expr::value(PublicationStatus::Failed.to_string()),
+            )
+            .col_expr(
+                publication::Column::LastError,
+                Expr::value(error.to_owned()),
+            )
+            .col_expr(publication::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(publication::Column::Id.eq(id))
+            .filter(publication::Column::ScheduledAt.eq(scheduled_at))
+            .filter(publication::Column::Status.is_in([
+                PublicationStatus::Scheduled.to_string(),
+                PublicationStatus::Publishing.to_string(),
+            ]))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn reschedule(&self, id: Uuid, scheduled_at: DateTime<Utc>) -> Result<bool> {
+        info!("rescheduling publication {} to {}", id, scheduled_at);
+        self.update_pending(id, PublicationStatus::Scheduled, Some(scheduled_at))
+            .await
+    }
+
+    async fn cancel(&self, id: Uuid) -> Result<bool> {
+        info!("cancelling publication {}", id);
+        self.update_pending(id, PublicationStatus::Cancelled, None)
+            .await
+    }
+
+    async fn find(&self, filter: PublicationFilter) -> Result<Page<Publication>> {
+        info!("finding publications by filter: {:?}", filter);
+        let mut query = PublicationEntity::find().order_by_desc(publication::Column::ScheduledAt);
+        if let Some(target) = filter.target {
+            query = query.filter(publication::Column::Target.eq(target));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(publication::Column::Status.eq(status.to_string()));
+        }
+        if let Some(resource_id) = filter.resource_id {
+            query = query.filter(publication::Column::ResourceId.eq(resource_id));
+        }
+        let paginator = query.paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let publications = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: publications
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::recovery_code;
+use entity::recovery_code::{ActiveModel as RecoveryCodeModel, Entity as RecoveryCodeEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+
+use async_trait::async_trait;
+use domain::{RecoveryCode, RecoveryCodes, Repository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct RecoveryCodeRepository {
+    db: Arc<DbConn>,
+}
+
+impl RecoveryCodeRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for RecoveryCodeRepository {
+    type Type = RecoveryCode;
+
+    async fn create(&self, item: RecoveryCode) -> Result<RecoveryCode> {
+        info!("creating recovery code for user: {}", item.user_id);
+        let result = RecoveryCodeModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: RecoveryCode) -> Result<RecoveryCode> {
+        info!("updating recovery code {}", id);
+        let result = RecoveryCodeEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<RecoveryCode> {
+        info!("getting recovery code by id: {}", id);
+        let result = RecoveryCodeEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Recovery codes are looked up by the hash of the code, never by the raw value.
+    async fn get_by_key(&self, key: String) -> Result<RecoveryCode> {
+        info!("getting recovery code by hash");
+        let result = RecoveryCodeEntity::find()
+            .filter(recovery_code::Column::CodeHash.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Recovery code doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<RecoveryCode>> {
+        info!("getting all recovery codes");
+        let codes: Vec<recovery_code::Model> =
+            RecoveryCodeEntity::find().all(self.db.as_ref()).await?;
+        Ok(codes
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        RecoveryCodeEntity::delete_many()
+            .filter(recovery_code::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        RecoveryCodeEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RecoveryCodes for RecoveryCodeRepository {
+    async fn consume(&self, user_id: Uuid, code_hash: &str) -> Result<bool> {
+        info!("consuming recovery code of user {}", user_id);
+        let result = RecoveryCodeEntity::update_many()
+            .col_expr(recovery_code::Column::UsedAt, Expr::value(Utc::now()))
+            .col_expr(recovery_code::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(recovery_code::Column::UserId.eq(user_id))
+            .filter(recovery_code::Column::CodeHash.eq(code_hash))
+            .filter(recovery_code::Column::UsedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<()> {
+        RecoveryCodeEntity::delete_many()
+            .filter(recovery_code::Column::UserId.eq(user_id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
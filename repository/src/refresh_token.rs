@@ -0,0 +1,143 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::refresh_token;
+use entity::refresh_token::{ActiveModel as RefreshTokenModel, Entity as RefreshTokenEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+
+use async_trait::async_trait;
+use domain::{RefreshToken, Repository, TokenRepository};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct RefreshTokenRepository {
+    db: Arc<DbConn>,
+}
+
+impl RefreshTokenRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for RefreshTokenRepository {
+    type Type = RefreshToken;
+
+    async fn create(&self, item: RefreshToken) -> Result<RefreshToken> {
+        info!("creating refresh token for user: {}", item.user_id);
+        let result = RefreshTokenModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: RefreshToken) -> Result<RefreshToken> {
+        info!("updating refresh token {}", id);
+        let result = RefreshTokenEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<RefreshToken> {
+        info!("getting refresh token by id: {}", id);
+        let result = RefreshTokenEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Refresh tokens are looked up by the hash of the token value, never by the raw value.
+    async fn get_by_key(&self, key: String) -> Result<RefreshToken> {
+        info!("getting refresh token by hash");
+        let result = RefreshTokenEntity::find()
+            .filter(refresh_token::Column::TokenHash.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Refresh token doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<RefreshToken>> {
+        info!("getting all refresh tokens");
+        let tokens: Vec<refresh_token::Model> =
+            RefreshTokenEntity::find().all(self.db.as_ref()).await?;
+        Ok(tokens
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        RefreshTokenEntity::delete_many()
+            .filter(refresh_token::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        RefreshTokenEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TokenRepository for RefreshTokenRepository {
+    async fn revoke(&self, id: Uuid, replaced_by: Option<Uuid>) -> Result<bool> {
+        info!("revoking refresh token {}", id);
+        let result = RefreshTokenEntity::update_many()
+            .col_expr(refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(refresh_token::Column::ReplacedBy, Expr::value(replaced_by))
+            .col_expr(refresh_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(refresh_token::Column::Id.eq(id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn revoke_family(&self, family_id: Uuid) -> Result<()> {
+        info!("revoking refresh token family {}", family_id);
+        RefreshTokenEntity::update_many()
+            .col_expr(refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(refresh_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(refresh_token::Column::FamilyId.eq(family_id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn revoke_all_for_user(&self, user_id: Uuid) -> Result<()> {
+        info!("revoking all refresh tokens of user {}", user_id);
+        RefreshTokenEntity::update_many()
+            .col_expr(refresh_token::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(refresh_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(refresh_token::Column::UserId.eq(user_id))
+            .filter(refresh_token::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
@@ -1,14 +1,34 @@
 use anyhow::Result;
+use entity::job::ActiveModel as JobModel;
 use entity::resource;
 use entity::resource::{ActiveModel as ResourceModel, Entity as ResourceEntity};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+use entity::resource_event::ActiveModel as ResourceEventModel;
+use entity::webhook_delivery::ActiveModel as WebhookDeliveryModel;
+use entity::webhook_subscription;
+use entity::webhook_subscription::Entity as WebhookSubscriptionEntity;
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectionTrait, DatabaseTransaction, DbBackend,
+    DbConn, EntityTrait, IntoActiveModel, Order, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Select, Statement, TransactionTrait,
+};
 
 use async_trait::async_trait;
-use domain::{Repository, Resource};
+use chrono::{DateTime, Utc};
+use domain::{
+    LifecycleFilter, Page, PublicationStatus, Repository, Resource, ResourceEvent,
+    ResourceEventType, ResourceFilter, Resources, SimilarResource, TrashFilter, WebhookDelivery,
+    WebhookSubscription,
+};
 use log::info;
 use std::sync::Arc;
+
+use crate::RESOURCE_EVENTS_CHANNEL;
 use uuid::Uuid;
 
+/// Keys of images by the file extension, the content type isn't kept on resources.
+const IMAGE_KEY_PATTERN: &str = r"\.(jpe?g|png|gif|webp|bmp|tiff?|heic|avif)$";
+
 #[derive(Debug)]
 pub struct ResourceRepository {
     db: Arc<DbConn>,
@@ -18,6 +38,98 @@ impl ResourceRepository {
     pub fn new(db: Arc<DbConn>) -> Self {
         Self { db }
     }
+
+    /// Moves the resource in or out of the trash, `deleted_at` tells which.
+    async fn set_trashed(
+        &self,
+        id: Uuid,
+        item: Resource,
+        event_type: ResourceEventType,
+    ) -> Result<Resource> {
+        let txn = self.db.begin().await?;
+        let trashed = resource::Column::DeletedAt;
+        let model = ResourceEntity::find_by_id(id)
+            .filter(match item.deleted_at {
+                Some(_) => trashed.is_null(),
+                None => trashed.is_not_null(),
+            })
+            .one(&txn)
+            .await?
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let mut model = model.into_active_model();
+        model.key = ActiveValue::Set(item.key);
+        model.url = ActiveValue::Set(item.url);
+        model.metadata = ActiveValue::Set(item.metadata);
+        model.deleted_at = ActiveValue::Set(item.deleted_at);
+        let updated: Resource = model.update(&txn).await?.into_active_model().into();
+        record_event(&txn, event_type, &updated).await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+}
+
+/// Resources that aren't in the trash.
+fn active() -> Select<ResourceEntity> {
+    ResourceEntity::find().filter(resource::Column::DeletedAt.is_null())
+}
+
+/// Records the event of a change and a pending delivery for every subscription of its type,
+/// the deliveries are sent and the listeners notified once the transaction commits.
+async fn record_event(
+    txn: &DatabaseTransaction,
+    event_type: ResourceEventType,
+    resource: &Resource,
+) -> Result<()> {
+    let resource_id = resource
+        .id
+        .ok_or_else(|| anyhow::Error::msg("Resource has no id"))?;
+    // Serializes the writers of events, so the sequence numbers commit in order and a reader
+    // of the change feed never skips an event committed after a later one.
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_advisory_xact_lock(hashtext($1))",
+        vec![RESOURCE_EVENTS_CHANNEL.into()],
+    ))
+    .await?;
+    let event: ResourceEvent =
+        ResourceEventModel::from(ResourceEvent::new(event_type, resource_id, resource))
+            .insert(txn)
+            .await?
+            .into();
+    let event_id = event
+        .id
+        .ok_or_else(|| anyhow::Error::msg("Recorded event has no id"))?;
+    // Delivered to the listeners when the transaction commits.
+    txn.execute(Statement::from_sql_and_values(
+        DbBackend::Postgres,
+        "SELECT pg_notify($1, $2)",
+        vec![RESOURCE_EVENTS_CHANNEL.into(), event_id.to_string().into()],
+    ))
+    .await?;
+    info!(
+        "recorded {} event {} of {}",
+        event_type, event_id, resource.key
+    );
+    let subscriptions = WebhookSubscriptionEntity::find()
+        .filter(webhook_subscription::Column::Enabled.eq(true))
+        .all(txn)
+        .await?;
+    for subscription in subscriptions {
+        let subscription: WebhookSubscription = subscription.into_active_model().into();
+        if let (true, Some(subscription_id)) = (subscription.matches(event_type), subscription.id) {
+            let delivery: WebhookDelivery =
+                WebhookDeliveryModel::from(WebhookDelivery::new(subscription_id, event_id, &event))
+                    .insert(txn)
+                    .await?
+                    .into_active_model()
+                    .into();
+            // Enqueued with the event, so no committed delivery is left without its job.
+            JobModel::from(delivery.job(subscription.max_attempts)?)
+                .insert(txn)
+                .await?;
+        }
+    }
+    Ok(())
 }
 
 #[async_trait]
@@ -32,7 +144,10 @@ impl Repository for ResourceRepository {
 
     async fn update(&self, id: Uuid, item: Resource) -> Result<Resource> {
         info!("updating resource {}", id);
-        let result = ResourceEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        let result = active()
+            .filter(resource::Column::Id.eq(id))
+            .one(self.db.as_ref())
+            .await?;
         let model = result
             .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
         let updated_model = model
@@ -45,7 +160,10 @@ impl Repository for ResourceRepository {
 
     async fn get_by_id(&self, id: Uuid) -> Result<Resource> {
         info!("getting resource by id: {}", id);
-        let result = ResourceEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        let result = active()
+            .filter(resource::Column::Id.eq(id))
+            .one(self.db.as_ref())
+            .await?;
         match result {
             Some(result) => Ok(result.into_active_model().into()),
             None => Err(anyhow::Error::msg(format!(
@@ -57,7 +175,7 @@ impl Repository for ResourceRepository {
 
     async fn get_by_key(&self, key: String) -> Result<Resource> {
         info!("getting resource by key: {}", key);
-        let result = ResourceEntity::find()
+        let result = active()
             .filter(resource::Column::Key.eq(key.clone()))
             .one(self.db.as_ref())
             .await?;
@@ -72,14 +190,14 @@ impl Repository for ResourceRepository {
 
     async fn get_all(&self) -> Result<Vec<Resource>> {
         info!("getting all resources");
-        let cakes: Vec<entity::resource::Model> =
-            ResourceEntity::find().all(self.db.as_ref()).await?;
+        let cakes: Vec<entity::resource::Model> = active().all(self.db.as_ref()).await?;
         Ok(cakes
             .into_iter()
             .map(|e| e.into_active_model().into())
             .collect())
     }
 
+    /// Deletes trashed resources as well.
     async fn delete_by_id(&self, id: Uuid) -> Result<()> {
         ResourceEntity::delete_many()
             .filter(resource::Column::Id.eq(id))
@@ -93,3 +211,371 @@ impl Repository for ResourceRepository {
         Ok(())
     }
 }
+
+#[async_trait]
+impl Resources for ResourceRepository {
+    async fn find_similar(
+        &self,
+        perceptual_hash: i64,
+        max_distance: u32,
+        user_id: Option<Uuid>,
+        limit: u64,
+    ) -> Result<Vec<SimilarResource>> {
+        info!("getting resources similar to hash {:016x}", perceptual_hash);
+        // Hamming distance as the number of set bits of the XOR.
+        let resources = ResourceEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT * FROM resources
+                   WHERE perceptual_hash IS NOT NULL
+                       AND deleted_at IS NULL
+                       AND length(replace((perceptual_hash # $1)::bit(64)::text, '0', '')) <= $2
+                       AND ($4::uuid IS NULL OR user_id = $4)
+                   ORDER BY length(replace((perceptual_hash # $1)::bit(64)::text, '0', '')), created_at
+                   LIMIT $3"#,
+                vec![
+                    perceptual_hash.into(),
+                    (max_distance as i32).into(),
+                    (limit as i64).into(),
+                    user_id.into(),
+                ],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|model| {
+                let distance = model
+                    .perceptual_hash
+                    .map_or(64, |hash| (hash ^ perceptual_hash).count_ones());
+                SimilarResource {
+                    resource: model.into_active_model().into(),
+                    distance,
+                }
+            })
+            .collect())
+    }
+
+    async fn find_hashed(&self) -> Result<Vec<Resource>> {
+        info!("getting resources with a perceptual hash");
+        let resources: Vec<resource::Model> = active()
+            .filter(resource::Column::PerceptualHash.is_not_null())
+            .order_by_asc(resource::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn find_unhashed(&self, after: Option<Uuid>, limit: u64) -> Result<Vec<Resource>> {
+        info!("getting images without a perceptual hash after {:?}", after);
+        let resources = ResourceEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT * FROM resources
+                   WHERE key ~* $1
+                       AND deleted_at IS NULL
+                       AND perceptual_hash IS NULL
+                       AND ($2::uuid IS NULL OR id > $2)
+                   ORDER BY id
+                   LIMIT $3"#,
+                vec![
+                    IMAGE_KEY_PATTERN.into(),
+                    after.into(),
+                    (limit as i64).into(),
+                ],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn set_perceptual_hash(&self, id: Uuid, perceptual_hash: i64) -> Result<bool> {
+        let result = ResourceEntity::update_many()
+            .col_expr(
+                resource::Column::PerceptualHash,
+                Expr::value(perceptual_hash),
+            )
+            .col_expr(resource::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(resource::Column::Id.eq(id))
+            .filter(resource::Column::PerceptualHash.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn find_publishable(&self, target: &str, limit: u64) -> Result<Vec<Resource>> {
+        info!("getting resources to publish to {}", target);
+        let resources = ResourceEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT * FROM resources
+                   WHERE key ~* $3
+                       AND deleted_at IS NULL
+                       AND tags ->> 'publish' = $1
+                       AND NOT EXISTS (
+                           SELECT 1 FROM publications
+                           WHERE publications.resource_id = resources.id
+                               AND publications.target = $1
+                               AND publications.status <> $4
+                       )
+                   ORDER BY created_at
+                   LIMIT $2"#,
+                vec![
+                    target.into(),
+                    (limit as i64).into(),
+                    IMAGE_KEY_PATTERN.into(),
+                    PublicationStatus::Failed.to_string().into(),
+                ],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn find_lifecycle(&self, filter: LifecycleFilter, limit: u64) -> Result<Vec<Resource>> {
+        info!("getting resources by lifecycle filter: {:?}", filter);
+        let resources = ResourceEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT * FROM resources
+                   WHERE deleted_at IS NULL
+                       AND ($1::text IS NULL OR key LIKE $1)
+                       AND ($2::text IS NULL OR tags -> $2 IS NOT NULL)
+                       AND ($3::text IS NULL OR tags ->> $2 = $3)
+                       AND ($4::timestamptz IS NULL OR created_at < $4)
+                       AND ($5::text IS NULL OR key NOT LIKE $5)
+                   ORDER BY created_at
+                   LIMIT $6"#,
+                vec![
+                    filter.prefix.as_deref().map(like_prefix).into(),
+                    filter.tag.into(),
+                    filter.tag_value.into(),
+                    filter.created_before.into(),
+                    filter.exclude_prefix.as_deref().map(like_prefix).into(),
+                    (limit as i64).into(),
+                ],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn find_expired(&self, now: DateTime<Utc>, limit: u64) -> Result<Vec<Resource>> {
+        info!("getting resources expired before {}", now);
+        let resources = active()
+            .filter(resource::Column::ExpiresAt.lte(now))
+            .order_by_asc(resource::Column::ExpiresAt)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn create_with_event(&self, item: Resource) -> Result<Resource> {
+        info!("creating resource with event: {}", item.key);
+        let txn = self.db.begin().await?;
+        let created: Resource = ResourceModel::from(item)
+            .insert(&txn)
+            .await?
+            .into_active_model()
+            .into();
+        record_event(&txn, ResourceEventType::Created, &created).await?;
+        txn.commit().await?;
+        Ok(created)
+    }
+
+    async fn update_with_event(&self, id: Uuid, item: Resource) -> Result<Resource> {
+        info!("updating resource {} with event", id);
+        let txn = self.db.begin().await?;
+        let model = active()
+            .filter(resource::Column::Id.eq(id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated: Resource = model
+            .into_active_model()
+            .update_model(item)
+            .save(&txn)
+            .await?
+            .into();
+        record_event(&txn, ResourceEventType::Updated, &updated).await?;
+        txn.commit().await?;
+        Ok(updated)
+    }
+
+    async fn delete_with_event(&self, id: Uuid) -> Result<()> {
+        info!("deleting resource {} with event", id);
+        let txn = self.db.begin().await?;
+        let deleted: Resource = active()
+            .filter(resource::Column::Id.eq(id))
+            .one(&txn)
+            .await?
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?
+            .into_active_model()
+            .into();
+        ResourceEntity::delete_many()
+            .filter(resource::Column::Id.eq(id))
+            .exec(&txn)
+            .await?;
+        record_event(&txn, ResourceEventType::Deleted, &deleted).await?;
+        txn.commit().await?;
+        Ok(())
+    }
+
+    async fn trash_with_event(&self, id: Uuid, item: Resource) -> Result<Resource> {
+        info!("trashing resource {} with event", id);
+        self.set_trashed(
+            id,
+            Resource {
+                deleted_at: item.deleted_at.or_else(|| Some(Utc::now())),
+                ..item
+            },
+            ResourceEventType::Deleted,
+        )
+        .await
+    }
+
+    async fn restore_with_event(&self, id: Uuid, item: Resource) -> Result<Resource> {
+        info!("restoring resource {} with event", id);
+        self.set_trashed(
+            id,
+            Resource {
+                deleted_at: None,
+                ..item
+            },
+            ResourceEventType::Created,
+        )
+        .await
+    }
+
+    async fn get_trashed(&self, id: Uuid) -> Result<Resource> {
+        info!("getting trashed resource by id: {}", id);
+        let result = ResourceEntity::find_by_id(id)
+            .filter(resource::Column::DeletedAt.is_not_null())
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Trashed entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    async fn find_trashed(&self, filter: TrashFilter) -> Result<Page<Resource>> {
+        info!("finding trashed resources by filter: {:?}", filter);
+        let mut query = ResourceEntity::find().filter(resource::Column::DeletedAt.is_not_null());
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(resource::Column::UserId.eq(user_id));
+        }
+        let paginator = query
+            .order_by_desc(resource::Column::DeletedAt)
+            .paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let resources = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: resources
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+
+    async fn find_purgeable(
+        &self,
+        deleted_before: DateTime<Utc>,
+        limit: u64,
+    ) -> Result<Vec<Resource>> {
+        info!("getting resources trashed before {}", deleted_before);
+        let resources = ResourceEntity::find()
+            .filter(resource::Column::DeletedAt.lt(deleted_before))
+            .order_by_asc(resource::Column::DeletedAt)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(resources
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn find(&self, filter: ResourceFilter) -> Result<Page<Resource>> {
+        info!("finding resources by filter: {:?}", filter);
+        let mut query = active();
+        if let Some(user_id) = filter.user_id {
+            query = query.filter(resource::Column::UserId.eq(user_id));
+        }
+        if let Some(color) = filter.color {
+            let distance = squared_color_distance(color);
+            let max_distance = filter.max_color_distance as i64;
+            query = query
+                .filter(resource::Column::DominantColor.is_not_null())
+                .filter(Expr::cust(&format!(
+                    "{} <= {}",
+                    distance,
+                    max_distance * max_distance
+                )))
+                .order_by(Expr::cust(&distance), Order::Asc);
+        }
+        let paginator = query
+            .order_by_desc(resource::Column::CreatedAt)
+            .paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let resources = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: resources
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+}
+
+/// Squared Euclidean distance in RGB space between the dominant color and the `0xRRGGBB` color.
+fn squared_color_distance(color: i32) -> String {
+    [16, 8, 0]
+        .iter()
+        .map(|shift| {
+            let channel = format!(
+                "(((dominant_color >> {}) & 255) - {})",
+                shift,
+                (color >> shift) & 255
+            );
+            format!("{} * {}", channel, channel)
+        })
+        .collect::<Vec<_>>()
+        .join(" + ")
+}
+
+/// Pattern of keys starting with the prefix, the wildcards of the prefix match literally.
+fn like_prefix(prefix: &str) -> String {
+    format!(
+        "{}%",
+        prefix
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_")
+    )
+}
@@ -0,0 +1,58 @@
+use anyhow::Result;
+use entity::resource_event;
+use entity::resource_event::Entity as ResourceEventEntity;
+use sea_orm::{ColumnTrait, DbConn, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use sqlx::postgres::PgListener;
+
+use async_trait::async_trait;
+use domain::{ResourceEvent, ResourceEvents};
+use log::info;
+use std::sync::Arc;
+
+/// Postgres channel notified with the sequence number of every recorded resource event.
+pub const RESOURCE_EVENTS_CHANNEL: &str = "resource_events";
+
+#[derive(Debug)]
+pub struct ResourceEventRepository {
+    db: Arc<DbConn>,
+}
+
+impl ResourceEventRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl ResourceEvents for ResourceEventRepository {
+    async fn find_since(&self, since: i64, limit: u64) -> Result<Vec<ResourceEvent>> {
+        info!("getting up to {} resource events after {}", limit, since);
+        let events = ResourceEventEntity::find()
+            .filter(resource_event::Column::Id.gt(since))
+            .order_by_asc(resource_event::Column::Id)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(events.into_iter().map(ResourceEvent::from).collect())
+    }
+}
+
+/// Dedicated connection listening to the notifications of recorded resource events.
+pub struct ResourceEventListener {
+    listener: PgListener,
+}
+
+impl ResourceEventListener {
+    pub async fn connect(url: &str) -> Result<Self> {
+        let mut listener = PgListener::connect(url).await?;
+        listener.listen(RESOURCE_EVENTS_CHANNEL).await?;
+        info!("listening to {}", RESOURCE_EVENTS_CHANNEL);
+        Ok(Self { listener })
+    }
+
+    /// Waits for the next notification, returns the sequence number of the event.
+    pub async fn recv(&mut self) -> Result<i64> {
+        let notification = self.listener.recv().await?;
+        Ok(notification.payload().parse()?)
+    }
+}
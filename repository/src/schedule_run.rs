@@ -0,0 +1,171 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use entity::schedule_run;
+use entity::schedule_run::{ActiveModel as ScheduleRunModel, Entity as ScheduleRunEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DbBackend, DbConn, EntityTrait,
+    IntoActiveModel, QueryFilter, QueryOrder, QuerySelect, Statement, TransactionTrait,
+};
+
+use async_trait::async_trait;
+use domain::{Repository, ScheduleRun, ScheduleRunStatus, ScheduleRuns};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ScheduleRunRepository {
+    db: Arc<DbConn>,
+}
+
+impl ScheduleRunRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for ScheduleRunRepository {
+    type Type = ScheduleRun;
+
+    async fn create(&self, item: ScheduleRun) -> Result<ScheduleRun> {
+        info!("creating schedule run: {}", item.name);
+        let result = ScheduleRunModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: ScheduleRun) -> Result<ScheduleRun> {
+        info!("updating schedule run {}", id);
+        let result = ScheduleRunEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<ScheduleRun> {
+        info!("getting schedule run by id: {}", id);
+        let result = ScheduleRunEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Runs have no unique key, the latest run of the schedule is returned.
+    async fn get_by_key(&self, key: String) -> Result<ScheduleRun> {
+        info!("getting latest run of schedule: {}", key);
+        self.find_by_name(&key, 1)
+            .await?
+            .pop()
+            .ok_or_else(|| anyhow::Error::msg(format!("Schedule {} has no runs", key)))
+    }
+
+    async fn get_all(&self) -> Result<Vec<ScheduleRun>> {
+        info!("getting all schedule runs");
+        let runs: Vec<schedule_run::Model> =
+            ScheduleRunEntity::find().all(self.db.as_ref()).await?;
+        Ok(runs
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        ScheduleRunEntity::delete_many()
+            .filter(schedule_run::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        ScheduleRunEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ScheduleRuns for ScheduleRunRepository {
+    async fn start(&self, name: &str, scheduled_at: DateTime<Utc>) -> Result<Option<ScheduleRun>> {
+        // The transaction scoped lock serializes the replicas checking and recording the run,
+        // it is released on commit or when the transaction is dropped.
+        let txn = self.db.begin().await?;
+        let locked = txn
+            .query_one(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"SELECT pg_try_advisory_xact_lock(hashtext($1)) AS locked"#,
+                vec![name.into()],
+            ))
+            .await?
+            .map(|row| row.try_get::<bool>("", "locked"))
+            .transpose()?
+            .unwrap_or(false);
+        if !locked {
+            info!("schedule {} is locked by another replica", name);
+            return Ok(None);
+        }
+        let recorded = ScheduleRunEntity::find()
+            .filter(schedule_run::Column::Name.eq(name))
+            .filter(schedule_run::Column::ScheduledAt.eq(scheduled_at))
+            .one(&txn)
+            .await?;
+        if recorded.is_some() {
+            info!("schedule {} already ran at {}", name, scheduled_at);
+            return Ok(None);
+        }
+        let result = ScheduleRunModel::from(ScheduleRun::new(name, scheduled_at))
+            .insert(&txn)
+            .await?;
+        txn.commit().await?;
+        Ok(Some(result.into_active_model().into()))
+    }
+
+    async fn finish(&self, id: Uuid, status: ScheduleRunStatus, error: Option<&str>) -> Result<()> {
+        let now = Utc::now();
+        ScheduleRunEntity::update_many()
+            .col_expr(
+                schedule_run::Column::Status,
+                Expr::value(status.to_string()),
+            )
+            .col_expr(schedule_run::Column::FinishedAt, Expr::value(now))
+            .col_expr(
+                schedule_run::Column::Error,
+                Expr::value(error.map(str::to_owned)),
+            )
+            .col_expr(schedule_run::Column::UpdatedAt, Expr::value(now))
+            .filter(schedule_run::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn find_by_name(&self, name: &str, limit: u64) -> Result<Vec<ScheduleRun>> {
+        let runs = ScheduleRunEntity::find()
+            .filter(schedule_run::Column::Name.eq(name))
+            .order_by_desc(schedule_run::Column::ScheduledAt)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(runs
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+}
@@ -0,0 +1,150 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::share;
+use entity::share::{ActiveModel as ShareModel, Entity as ShareEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DbConn, EntityTrait, IntoActiveModel, QueryFilter,
+    QueryOrder,
+};
+
+use async_trait::async_trait;
+use domain::{Repository, Share, Shares};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct ShareRepository {
+    db: Arc<DbConn>,
+}
+
+impl ShareRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for ShareRepository {
+    type Type = Share;
+
+    async fn create(&self, item: Share) -> Result<Share> {
+        info!("creating share of resource: {}", item.resource_id);
+        let result = ShareModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: Share) -> Result<Share> {
+        info!("updating share {}", id);
+        let result = ShareEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<Share> {
+        info!("getting share by id: {}", id);
+        let result = ShareEntity::find_by_id(id).one(self.db.as_ref()).await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Shares are looked up by the hash of the link token, never by the raw value.
+    async fn get_by_key(&self, key: String) -> Result<Share> {
+        info!("getting share by hash");
+        let result = ShareEntity::find()
+            .filter(share::Column::TokenHash.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("Share doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<Share>> {
+        info!("getting all shares");
+        let shares: Vec<share::Model> = ShareEntity::find().all(self.db.as_ref()).await?;
+        Ok(shares
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        ShareEntity::delete_many()
+            .filter(share::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        ShareEntity::delete_many().exec(self.db.as_ref()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Shares for ShareRepository {
+    async fn find_by_resource(&self, resource_id: Uuid) -> Result<Vec<Share>> {
+        info!("getting shares of resource {}", resource_id);
+        let shares: Vec<share::Model> = ShareEntity::find()
+            .filter(share::Column::ResourceId.eq(resource_id))
+            .order_by_desc(share::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(shares
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn record_download(&self, id: Uuid) -> Result<bool> {
+        info!("recording download of share {}", id);
+        let result = ShareEntity::update_many()
+            .col_expr(
+                share::Column::DownloadCount,
+                Expr::col(share::Column::DownloadCount).add(1),
+            )
+            .col_expr(share::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(share::Column::Id.eq(id))
+            .filter(share::Column::RevokedAt.is_null())
+            .filter(
+                Condition::any()
+                    .add(share::Column::ExpiresAt.is_null())
+                    .add(share::Column::ExpiresAt.gt(Utc::now())),
+            )
+            .filter(
+                Condition::any()
+                    .add(share::Column::MaxDownloads.is_null())
+                    .add(Expr::cust("download_count < max_downloads")),
+            )
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn revoke(&self, id: Uuid) -> Result<bool> {
+        info!("revoking share {}", id);
+        let result = ShareEntity::update_many()
+            .col_expr(share::Column::RevokedAt, Expr::value(Utc::now()))
+            .col_expr(share::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(share::Column::Id.eq(id))
+            .filter(share::Column::RevokedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
@@ -0,0 +1,113 @@
+use anyhow::Result;
+use entity::two_factor;
+use entity::two_factor::{ActiveModel as TwoFactorModel, Entity as TwoFactorEntity};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+
+use async_trait::async_trait;
+use domain::{Repository, TwoFactor, TwoFactors};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct TwoFactorRepository {
+    db: Arc<DbConn>,
+}
+
+impl TwoFactorRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for TwoFactorRepository {
+    type Type = TwoFactor;
+
+    async fn create(&self, item: TwoFactor) -> Result<TwoFactor> {
+        info!("creating two factor for user: {}", item.user_id);
+        let result = TwoFactorModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: TwoFactor) -> Result<TwoFactor> {
+        info!("updating two factor {}", id);
+        let result = TwoFactorEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<TwoFactor> {
+        info!("getting two factor by id: {}", id);
+        let result = TwoFactorEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Two factor enrollments are looked up by the id of the user.
+    async fn get_by_key(&self, key: String) -> Result<TwoFactor> {
+        let user_id = Uuid::parse_str(&key)?;
+        self.get_by_user(user_id).await?.ok_or_else(|| {
+            anyhow::Error::msg(format!("Two factor of user {} doesn't exist", user_id))
+        })
+    }
+
+    async fn get_all(&self) -> Result<Vec<TwoFactor>> {
+        info!("getting all two factors");
+        let items: Vec<two_factor::Model> = TwoFactorEntity::find().all(self.db.as_ref()).await?;
+        Ok(items
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        TwoFactorEntity::delete_many()
+            .filter(two_factor::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        TwoFactorEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TwoFactors for TwoFactorRepository {
+    async fn get_by_user(&self, user_id: Uuid) -> Result<Option<TwoFactor>> {
+        info!("getting two factor of user: {}", user_id);
+        let result = TwoFactorEntity::find()
+            .filter(two_factor::Column::UserId.eq(user_id))
+            .one(self.db.as_ref())
+            .await?;
+        Ok(result.map(|e| e.into_active_model().into()))
+    }
+
+    async fn delete_by_user(&self, user_id: Uuid) -> Result<()> {
+        TwoFactorEntity::delete_many()
+            .filter(two_factor::Column::UserId.eq(user_id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
@@ -1,21 +1,25 @@
 use anyhow::Result;
 use entity::user;
 use entity::user::{ActiveModel as UserModel, Entity as UserEntity};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder,
+};
 
 use async_trait::async_trait;
-use domain::{Repository, User};
+use domain::{Page, Repository, User, UserDirectory, UserFilter};
 use log::info;
 use mockall::automock;
+use std::sync::Arc;
 use uuid::Uuid;
 
 #[derive(Debug)]
 pub struct UserRepository {
-    db: DbConn,
+    db: Arc<DbConn>,
 }
 
 impl UserRepository {
-    pub fn new(db: DbConn) -> Self {
+    pub fn new(db: Arc<DbConn>) -> Self {
         Self { db }
     }
 }
@@ -27,26 +31,26 @@ impl Repository for UserRepository {
 
     async fn create(&self, user: User) -> Result<User> {
         info!("creating User: {:?}", user);
-        let result = UserModel::from(user).insert(&self.db).await?;
+        let result = UserModel::from(user).insert(self.db.as_ref()).await?;
         Ok(result.into_active_model().into())
     }
 
     async fn update(&self, id: Uuid, user: User) -> Result<User> {
         info!("updating User {}", id);
-        let result = UserEntity::find_by_id(id).one(&self.db).await?;
+        let result = UserEntity::find_by_id(id).one(self.db.as_ref()).await?;
         let model = result
             .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
         let updated_model = model
             .into_active_model()
             .update_model(user)
-            .save(&self.db)
+            .save(self.db.as_ref())
             .await?;
         Ok(updated_model.into())
     }
 
     async fn get_by_id(&self, id: Uuid) -> Result<User> {
         info!("getting User by id: {}", id);
-        let result = UserEntity::find_by_id(id).one(&self.db).await?;
+        let result = UserEntity::find_by_id(id).one(self.db.as_ref()).await?;
         match result {
             Some(result) => Ok(result.into_active_model().into()),
             None => Err(anyhow::Error::msg(format!(
@@ -60,7 +64,7 @@ impl Repository for UserRepository {
         info!("getting User by name: {}", key);
         let result = UserEntity::find()
             .filter(user::Column::Name.eq(key.clone()))
-            .one(&self.db)
+            .one(self.db.as_ref())
             .await?;
         match result {
             Some(result) => Ok(result.into_active_model().into()),
@@ -73,7 +77,7 @@ impl Repository for UserRepository {
 
     async fn get_all(&self) -> Result<Vec<User>> {
         info!("getting all Users");
-        let cakes: Vec<entity::user::Model> = UserEntity::find().all(&self.db).await?;
+        let cakes: Vec<entity::user::Model> = UserEntity::find().all(self.db.as_ref()).await?;
         Ok(cakes
             .into_iter()
             .map(|e| e.into_active_model().into())
@@ -83,13 +87,54 @@ impl Repository for UserRepository {
     async fn delete_by_id(&self, id: Uuid) -> Result<()> {
         UserEntity::delete_many()
             .filter(user::Column::Id.eq(id))
-            .exec(&self.db)
+            .exec(self.db.as_ref())
             .await?;
         Ok(())
     }
 
     async fn delete_all(&self) -> Result<()> {
-        UserEntity::delete_many().exec(&self.db).await?;
+        UserEntity::delete_many().exec(self.db.as_ref()).await?;
         Ok(())
     }
 }
+
+#[async_trait]
+impl UserDirectory for UserRepository {
+    async fn get_by_email(&self, email: String) -> Result<User> {
+        info!("getting User by email: {}", email);
+        let result = UserEntity::find()
+            .filter(user::Column::Email.eq(email.clone()))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with email {} doesn't exist",
+                email
+            ))),
+        }
+    }
+
+    async fn find(&self, filter: UserFilter) -> Result<Page<User>> {
+        info!("finding Users by filter: {:?}", filter);
+        let mut query = UserEntity::find().order_by_asc(user::Column::CreatedAt);
+        if let Some(role) = filter.role {
+            query = query.filter(user::Column::Role.eq(role.to_string()));
+        }
+        if let Some(enabled) = filter.enabled {
+            query = query.filter(user::Column::Enabled.eq(enabled));
+        }
+        let paginator = query.paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let users = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: users
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use entity::user_identity;
+use entity::user_identity::{ActiveModel as UserIdentityModel, Entity as UserIdentityEntity};
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+
+use async_trait::async_trait;
+use domain::{Repository, UserIdentities, UserIdentity};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct UserIdentityRepository {
+    db: Arc<DbConn>,
+}
+
+impl UserIdentityRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for UserIdentityRepository {
+    type Type = UserIdentity;
+
+    async fn create(&self, item: UserIdentity) -> Result<UserIdentity> {
+        info!("creating user identity for user: {}", item.user_id);
+        let result = UserIdentityModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: UserIdentity) -> Result<UserIdentity> {
+        info!("updating user identity {}", id);
+        let result = UserIdentityEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<UserIdentity> {
+        info!("getting user identity by id: {}", id);
+        let result = UserIdentityEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Identities are looked up by `issuer subject`, separated by a space.
+    async fn get_by_key(&self, key: String) -> Result<UserIdentity> {
+        let (issuer, subject) = key
+            .split_once(' ')
+            .ok_or_else(|| anyhow::Error::msg(format!("Invalid identity key {}", key)))?;
+        self.get_by_subject(issuer, subject)
+            .await?
+            .ok_or_else(|| anyhow::Error::msg(format!("Identity {} doesn't exist", key)))
+    }
+
+    async fn get_all(&self) -> Result<Vec<UserIdentity>> {
+        info!("getting all user identities");
+        let items: Vec<user_identity::Model> =
+            UserIdentityEntity::find().all(self.db.as_ref()).await?;
+        Ok(items
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        UserIdentityEntity::delete_many()
+            .filter(user_identity::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        UserIdentityEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserIdentities for UserIdentityRepository {
+    async fn get_by_subject(&self, issuer: &str, subject: &str) -> Result<Option<UserIdentity>> {
+        info!("getting user identity {} of issuer {}", subject, issuer);
+        let result = UserIdentityEntity::find()
+            .filter(user_identity::Column::Issuer.eq(issuer))
+            .filter(user_identity::Column::Subject.eq(subject))
+            .one(self.db.as_ref())
+            .await?;
+        Ok(result.map(|e| e.into_active_model().into()))
+    }
+}
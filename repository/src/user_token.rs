@@ -0,0 +1,128 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::user_token;
+use entity::user_token::{ActiveModel as UserTokenModel, Entity as UserTokenEntity};
+use sea_orm::sea_query::Expr;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter};
+
+use async_trait::async_trait;
+use domain::{Repository, UserToken, UserTokenKind, UserTokens};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct UserTokenRepository {
+    db: Arc<DbConn>,
+}
+
+impl UserTokenRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for UserTokenRepository {
+    type Type = UserToken;
+
+    async fn create(&self, item: UserToken) -> Result<UserToken> {
+        info!("creating user token for user: {}", item.user_id);
+        let result = UserTokenModel::from(item).insert(self.db.as_ref()).await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: UserToken) -> Result<UserToken> {
+        info!("updating user token {}", id);
+        let result = UserTokenEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<UserToken> {
+        info!("getting user token by id: {}", id);
+        let result = UserTokenEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// User tokens are looked up by the hash of the token value, never by the raw value.
+    async fn get_by_key(&self, key: String) -> Result<UserToken> {
+        info!("getting user token by hash");
+        let result = UserTokenEntity::find()
+            .filter(user_token::Column::TokenHash.eq(key))
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg("User token doesn't exist")),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<UserToken>> {
+        info!("getting all user tokens");
+        let tokens: Vec<user_token::Model> = UserTokenEntity::find().all(self.db.as_ref()).await?;
+        Ok(tokens
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        UserTokenEntity::delete_many()
+            .filter(user_token::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        UserTokenEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserTokens for UserTokenRepository {
+    async fn consume(&self, id: Uuid) -> Result<bool> {
+        info!("consuming user token {}", id);
+        let result = UserTokenEntity::update_many()
+            .col_expr(user_token::Column::UsedAt, Expr::value(Utc::now()))
+            .col_expr(user_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(user_token::Column::Id.eq(id))
+            .filter(user_token::Column::UsedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn invalidate_all(&self, user_id: Uuid, kind: UserTokenKind) -> Result<()> {
+        info!("invalidating {} tokens of user {}", kind, user_id);
+        UserTokenEntity::update_many()
+            .col_expr(user_token::Column::UsedAt, Expr::value(Utc::now()))
+            .col_expr(user_token::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(user_token::Column::UserId.eq(user_id))
+            .filter(user_token::Column::Kind.eq(kind.to_string()))
+            .filter(user_token::Column::UsedAt.is_null())
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,251 @@
+use anyhow::Result;
+use chrono::Utc;
+use entity::webhook_delivery;
+use entity::webhook_delivery::{
+    ActiveModel as WebhookDeliveryModel, Entity as WebhookDeliveryEntity,
+};
+use sea_orm::sea_query::Expr;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbBackend, DbConn, EntityTrait, IntoActiveModel, PaginatorTrait,
+    QueryFilter, QueryOrder, Statement,
+};
+
+use async_trait::async_trait;
+use domain::{
+    Page, Repository, WebhookDeliveries, WebhookDelivery, WebhookDeliveryFilter,
+    WebhookDeliveryStatus,
+};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct WebhookDeliveryRepository {
+    db: Arc<DbConn>,
+}
+
+impl WebhookDeliveryRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+
+    /// Moves a started delivery out of the delivering state, if the attempt still owns it.
+    async fn release(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        status: WebhookDeliveryStatus,
+        response_status: Option<i32>,
+        error: Option<&str>,
+    ) -> Result<bool> {
+        let mut update = WebhookDeliveryEntity::update_many()
+            .col_expr(
+                webhook_delivery::Column::Status,
+                Expr::value(status.to_string()),
+            )
+            .col_expr(
+                webhook_delivery::Column::ResponseStatus,
+                Expr::value(response_status),
+            )
+            .col_expr(
+                webhook_delivery::Column::LastError,
+                Expr::value(error.map(str::to_owned)),
+            )
+            .col_expr(webhook_delivery::Column::UpdatedAt, Expr::value(Utc::now()));
+        if status == WebhookDeliveryStatus::Delivered {
+            update = update.col_expr(
+                webhook_delivery::Column::DeliveredAt,
+                Expr::value(Utc::now()),
+            );
+        }
+        let result = update
+            .filter(webhook_delivery::Column::Id.eq(id))
+            .filter(
+                webhook_delivery::Column::Status.eq(WebhookDeliveryStatus::Delivering.to_string()),
+            )
+            .filter(webhook_delivery::Column::Attempts.eq(attempt))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+}
+
+#[async_trait]
+impl Repository for WebhookDeliveryRepository {
+    type Type = WebhookDelivery;
+
+    async fn create(&self, item: WebhookDelivery) -> Result<WebhookDelivery> {
+        info!("creating webhook delivery of event {}", item.event_id);
+        let result = WebhookDeliveryModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: WebhookDelivery) -> Result<WebhookDelivery> {
+        info!("updating webhook delivery {}", id);
+        let result = WebhookDeliveryEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<WebhookDelivery> {
+        info!("getting webhook delivery by id: {}", id);
+        let result = WebhookDeliveryEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// Deliveries have no unique key, the next pending delivery of the event type is
+    /// returned.
+    async fn get_by_key(&self, key: String) -> Result<WebhookDelivery> {
+        info!("getting next webhook delivery of: {}", key);
+        let result = WebhookDeliveryEntity::find()
+            .filter(webhook_delivery::Column::EventType.eq(key.clone()))
+            .filter(webhook_delivery::Column::Status.eq(WebhookDeliveryStatus::Pending.to_string()))
+            .order_by_asc(webhook_delivery::Column::EventId)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Pending delivery of {} doesn't exist",
+                key
+            ))),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<WebhookDelivery>> {
+        info!("getting all webhook deliveries");
+        let deliveries: Vec<webhook_delivery::Model> =
+            WebhookDeliveryEntity::find().all(self.db.as_ref()).await?;
+        Ok(deliveries
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        WebhookDeliveryEntity::delete_many()
+            .filter(webhook_delivery::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        WebhookDeliveryEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveries for WebhookDeliveryRepository {
+    async fn start(&self, id: Uuid) -> Result<Option<WebhookDelivery>> {
+        let delivery = WebhookDeliveryEntity::find()
+            .from_raw_sql(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                r#"UPDATE webhook_deliveries
+                   SET status = 'Delivering', attempts = attempts + 1, updated_at = $2
+                   WHERE id = $1 AND status IN ('Pending', 'Delivering')
+                   RETURNING *"#,
+                vec![id.into(), Utc::now().into()],
+            ))
+            .one(self.db.as_ref())
+            .await?;
+        if delivery.is_some() {
+            info!("started webhook delivery {}", id);
+        }
+        Ok(delivery.map(|e| e.into_active_model().into()))
+    }
+
+    async fn complete(&self, id: Uuid, attempt: i32, response_status: i32) -> Result<bool> {
+        self.release(
+            id,
+            attempt,
+            WebhookDeliveryStatus::Delivered,
+            Some(response_status),
+            None,
+        )
+        .await
+    }
+
+    async fn retry(
+        &self,
+        id: Uuid,
+        attempt: i32,
+        response_status: Option<i32>,
+        error: &str,
+    ) -> Result<bool> {
+        self.release(
+            id,
+            attempt,
+            WebhookDeliveryStatus::Pending,
+            response_status,
+            Some(error),
+        )
+        .await
+    }
+
+    async fn fail(&self, id: Uuid, error: &str) -> Result<bool> {
+        let result = WebhookDeliveryEntity::update_many()
+            .col_expr(
+                webhook_delivery::Column::Status,
+                Expr::value(WebhookDeliveryStatus::Failed.to_string()),
+            )
+            .col_expr(
+                webhook_delivery::Column::LastError,
+                Expr::value(error.to_owned()),
+            )
+            .col_expr(webhook_delivery::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(webhook_delivery::Column::Id.eq(id))
+            .filter(webhook_delivery::Column::Status.is_in([
+                WebhookDeliveryStatus::Pending.to_string(),
+                WebhookDeliveryStatus::Delivering.to_string(),
+            ]))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected == 1)
+    }
+
+    async fn find(&self, filter: WebhookDeliveryFilter) -> Result<Page<WebhookDelivery>> {
+        info!("finding webhook deliveries by filter: {:?}", filter);
+        let mut query =
+            WebhookDeliveryEntity::find().order_by_desc(webhook_delivery::Column::EventId);
+        if let Some(subscription_id) = filter.subscription_id {
+            query = query.filter(webhook_delivery::Column::SubscriptionId.eq(subscription_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(webhook_delivery::Column::Status.eq(status.to_string()));
+        }
+        let paginator = query.paginate(self.db.as_ref(), filter.page_size.max(1) as usize);
+        let total = paginator.num_items().await?;
+        let deliveries = paginator.fetch_page(filter.page as usize).await?;
+        Ok(Page {
+            items: deliveries
+                .into_iter()
+                .map(|e| e.into_active_model().into())
+                .collect(),
+            page: filter.page,
+            page_size: filter.page_size,
+            total: total as u64,
+        })
+    }
+}
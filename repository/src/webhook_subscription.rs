@@ -0,0 +1,111 @@
+use anyhow::Result;
+use entity::webhook_subscription;
+use entity::webhook_subscription::{
+    ActiveModel as WebhookSubscriptionModel, Entity as WebhookSubscriptionEntity,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbConn, EntityTrait, IntoActiveModel, QueryFilter, QueryOrder,
+};
+
+use async_trait::async_trait;
+use domain::{Repository, WebhookSubscription};
+use log::info;
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[derive(Debug)]
+pub struct WebhookSubscriptionRepository {
+    db: Arc<DbConn>,
+}
+
+impl WebhookSubscriptionRepository {
+    pub fn new(db: Arc<DbConn>) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl Repository for WebhookSubscriptionRepository {
+    type Type = WebhookSubscription;
+
+    async fn create(&self, item: WebhookSubscription) -> Result<WebhookSubscription> {
+        info!("creating webhook subscription of {}", item.url);
+        let result = WebhookSubscriptionModel::from(item)
+            .insert(self.db.as_ref())
+            .await?;
+        Ok(result.into_active_model().into())
+    }
+
+    async fn update(&self, id: Uuid, item: WebhookSubscription) -> Result<WebhookSubscription> {
+        info!("updating webhook subscription {}", id);
+        let result = WebhookSubscriptionEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        let model = result
+            .ok_or_else(|| anyhow::Error::msg(format!("Entity with id {} doesn't exist", id)))?;
+        let updated_model = model
+            .into_active_model()
+            .update_model(item)
+            .save(self.db.as_ref())
+            .await?;
+        Ok(updated_model.into())
+    }
+
+    async fn get_by_id(&self, id: Uuid) -> Result<WebhookSubscription> {
+        info!("getting webhook subscription by id: {}", id);
+        let result = WebhookSubscriptionEntity::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Entity with id {} doesn't exist",
+                id
+            ))),
+        }
+    }
+
+    /// The oldest subscription of the URL.
+    async fn get_by_key(&self, key: String) -> Result<WebhookSubscription> {
+        info!("getting webhook subscription by url: {}", key);
+        let result = WebhookSubscriptionEntity::find()
+            .filter(webhook_subscription::Column::Url.eq(key.clone()))
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .one(self.db.as_ref())
+            .await?;
+        match result {
+            Some(result) => Ok(result.into_active_model().into()),
+            None => Err(anyhow::Error::msg(format!(
+                "Subscription of {} doesn't exist",
+                key
+            ))),
+        }
+    }
+
+    async fn get_all(&self) -> Result<Vec<WebhookSubscription>> {
+        info!("getting all webhook subscriptions");
+        let subscriptions: Vec<webhook_subscription::Model> = WebhookSubscriptionEntity::find()
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(subscriptions
+            .into_iter()
+            .map(|e| e.into_active_model().into())
+            .collect())
+    }
+
+    async fn delete_by_id(&self, id: Uuid) -> Result<()> {
+        WebhookSubscriptionEntity::delete_many()
+            .filter(webhook_subscription::Column::Id.eq(id))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_all(&self) -> Result<()> {
+        WebhookSubscriptionEntity::delete_many()
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::Result;
+use app_config::ApplicationConfig;
+use application::{DefaultJobService, DefaultSimilarityService, JobService, SimilarityService};
+use clap::{Parser, Subcommand};
+use domain::JobFilter;
+use sea_orm::DbConn;
+use serde::Serialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Serves the API without a command.
+#[derive(Debug, Parser)]
+#[clap(version, about)]
+pub struct Cli {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Administers the background job queue.
+    #[clap(subcommand)]
+    Jobs(JobsCommand),
+    /// Maintains the perceptual hashes of the similarity search.
+    #[clap(subcommand)]
+    Similarity(SimilarityCommand),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum JobsCommand {
+    /// Lists jobs, latest first.
+    List {
+        #[clap(long)]
+        kind: Option<String>,
+        /// pending, running, done, dead or cancelled.
+        #[clap(long)]
+        status: Option<String>,
+        #[clap(long, default_value_t = 0)]
+        page: u64,
+        #[clap(long, default_value_t = 20)]
+        page_size: u64,
+    },
+    /// Shows a job with its payload and last error.
+    Show { id: Uuid },
+    /// Runs a dead or cancelled job again.
+    Retry { id: Uuid },
+    /// Cancels a pending or dead job.
+    Cancel { id: Uuid },
+    /// Stops claiming jobs of the kind.
+    Pause { kind: String },
+    /// Claims jobs of the kind again.
+    Resume { kind: String },
+    /// Shows counts, throughput and latency of the last hour per kind.
+    Stats,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum SimilarityCommand {
+    /// Enqueues jobs hashing the images uploaded before perceptual hashes were computed.
+    Backfill,
+}
+
+pub async fn run(command: Command, config: &ApplicationConfig, db: Arc<DbConn>) -> Result<()> {
+    match command {
+        Command::Jobs(command) => jobs(command, db).await,
+        Command::Similarity(command) => similarity(command, config, db).await,
+    }
+}
+
+async fn jobs(command: JobsCommand, db: Arc<DbConn>) -> Result<()> {
+    let service = DefaultJobService::new(db);
+    match command {
+        JobsCommand::List {
+            kind,
+            status,
+            page,
+            page_size,
+        } => {
+            let status =
+                match status {
+                    Some(status) => Some(status.parse().map_err(|_| {
+                        anyhow::Error::msg(format!("Unknown job status {}", status))
+                    })?),
+                    None => None,
+                };
+            print(
+                &service
+                    .list(JobFilter {
+                        kind,
+                        status,
+                        page,
+                        page_size,
+                    })
+                    .await?,
+            )
+        }
+        JobsCommand::Show { id } => print(&service.get(id).await?),
+        JobsCommand::Retry { id } => print(&service.retry(id).await?),
+        JobsCommand::Cancel { id } => print(&service.cancel(id).await?),
+        JobsCommand::Pause { kind } => service.pause(&kind).await,
+        JobsCommand::Resume { kind } => service.resume(&kind).await,
+        JobsCommand::Stats => print(&service.stats().await?),
+    }
+}
+
+async fn similarity(
+    command: SimilarityCommand,
+    config: &ApplicationConfig,
+    db: Arc<DbConn>,
+) -> Result<()> {
+    let service = DefaultSimilarityService::new(config, db);
+    match command {
+        SimilarityCommand::Backfill => print(&serde_json::json!({
+            "enqueued": service.backfill().await?,
+        })),
+    }
+}
+
+fn print<T: Serialize>(value: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
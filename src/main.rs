@@ -1,19 +1,48 @@
 use anyhow::Result;
+use api::audit::audit_routers;
+use api::auth::auth_routers;
+use api::bucket_events::bucket_events_routers;
+use api::captcha::captcha_routers;
+use api::changes::changes_routers;
 use api::files::files_routers;
+use api::iiif::iiif_routers;
+use api::jobs::jobs_routers;
+use api::lifecycle::lifecycle_routers;
+use api::oidc::oidc_routers;
+use api::publications::publications_routers;
+use api::shares::shares_routers;
+use api::similarity::similarity_routers;
+use api::transforms::transform_routers;
+use api::trash::trash_routers;
+use api::two_factor::two_factor_routers;
+use api::users::users_routers;
+use api::webhooks::webhooks_routers;
 use app_config::ApplicationConfig;
+use application::{job_worker, scheduler, ChangeFeed};
 use axum::{Extension, Router, Server};
+use clap::Parser;
 use log::info;
 use sea_orm::Database;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod cli;
+
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = cli::Cli::parse();
     let config = ApplicationConfig::default();
-    let db = Database::connect(config.db.url.clone())
-        .await
-        .expect("Failed to connect to database");
+    let db = Arc::new(
+        Database::connect(config.db.url.clone())
+            .await
+            .expect("Failed to connect to database"),
+    );
+    // Commands print JSON only, the logs are set up for the server.
+    if let Some(command) = cli.command {
+        return cli::run(command, &config, db).await;
+    }
+    config.validate().expect("Invalid configuration");
 
     tracing_subscriber::registry()
         .with(tracing_subscriber::EnvFilter::new(
@@ -22,16 +51,50 @@ async fn main() -> Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
 
+    if config.jobs.enabled {
+        info!("Starting job workers...");
+        job_worker(&config, db.clone()).start();
+    }
+    if config.scheduler.enabled {
+        info!("Starting scheduler...");
+        scheduler(&config, db.clone())
+            .expect("Failed to configure the scheduler")
+            .start();
+    }
+
+    let changes = ChangeFeed::new(db.clone());
+    changes.listen(&config.db.url);
+
     let app = Router::new()
         .merge(files_routers())
+        .merge(auth_routers())
+        .merge(users_routers())
+        .merge(two_factor_routers())
+        .merge(audit_routers())
+        .merge(oidc_routers())
+        .merge(shares_routers())
+        .merge(transform_routers())
+        .merge(iiif_routers())
+        .merge(similarity_routers())
+        .merge(captcha_routers())
+        .merge(publications_routers())
+        .merge(jobs_routers())
+        .merge(webhooks_routers())
+        .merge(changes_routers())
+        .merge(bucket_events_routers())
+        .merge(lifecycle_routers())
+        .merge(trash_routers())
         .layer(Extension(Arc::new(config)))
-        .layer(Extension(Arc::new(db)))
+        .layer(Extension(db))
+        .layer(Extension(changes))
         .layer(tower_http::trace::TraceLayer::new_for_http());
 
     info!("Starting server...");
     let addr = SocketAddr::from(([127, 0, 0, 1], 3000));
     tracing::debug!("listening on {}", addr);
-    Server::bind(&addr).serve(app.into_make_service()).await?;
+    Server::bind(&addr)
+        .serve(app.into_make_service_with_connect_info::<SocketAddr>())
+        .await?;
 
     Ok(())
 }
@@ -1,8 +1,6 @@
-#[cfg(test)]
-mod tests {
-    #[test]
-    fn it_works() {
-        let result = 2 + 2;
-        assert_eq!(result, 4);
-    }
-}
+mod queue;
+mod scheduler;
+mod worker;
+pub use queue::*;
+pub use scheduler::*;
+pub use worker::*;
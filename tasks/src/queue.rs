@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, JobsConfig};
+use chrono::{DateTime, Utc};
+use domain::*;
+use log::info;
+use repository::JobRepository;
+use sea_orm::DbConn;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Payload of a job, serialized into the queue and handed to the handler of its kind.
+pub trait JobType: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Name of the job type, stored with every job.
+    const KIND: &'static str;
+}
+
+/// Enqueues jobs for the workers, the jobs survive restarts of the application.
+pub struct JobQueue {
+    jobs: Box<dyn Jobs + Send + Sync>,
+    config: JobsConfig,
+}
+
+impl JobQueue {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            jobs: Box::new(JobRepository::new(db)),
+            config: config.jobs.clone(),
+        }
+    }
+
+    pub async fn enqueue<T: JobType>(&self, job: &T) -> Result<Job> {
+        self.enqueue_at(job, Utc::now()).await
+    }
+
+    pub async fn enqueue_at<T: JobType>(&self, job: &T, run_at: DateTime<Utc>) -> Result<Job> {
+        self.enqueue_with_attempts(job, run_at, self.config.max_attempts)
+            .await
+    }
+
+    /// Like `enqueue_at` with the attempts of the job instead of the configured default.
+    pub async fn enqueue_with_attempts<T: JobType>(
+        &self,
+        job: &T,
+        run_at: DateTime<Utc>,
+        max_attempts: i32,
+    ) -> Result<Job> {
+        info!("Enqueue {} job to run at {}", T::KIND, run_at);
+        self.jobs
+            .create(Job::new(T::KIND, serde_json::to_value(job)?, max_attempts).with_run_at(run_at))
+            .await
+    }
+}
@@ -0,0 +1,232 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use anyhow::Result;
+use app_config::ApplicationConfig;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use domain::*;
+use log::{info, warn};
+use repository::ScheduleRunRepository;
+use sea_orm::DbConn;
+use tokio::task::JoinHandle;
+
+/// Work run by the scheduler, see `Scheduler::schedule`.
+#[async_trait]
+pub trait ScheduledTask: Send + Sync + 'static {
+    async fn run(&self) -> Result<()>;
+}
+
+/// When a scheduled task fires, evaluated in the timezone of the scheduler.
+#[derive(Clone, Debug)]
+pub enum Schedule {
+    Cron(Box<cron::Schedule>),
+    /// Every day at the wall clock time. A time skipped by a daylight saving transition
+    /// fires on the next day only, a repeated time fires on its first occurrence.
+    Daily(NaiveTime),
+}
+
+impl Schedule {
+    /// Standard five field expression like `30 4 * * 1-5`, or six to seven fields
+    /// starting with seconds.
+    pub fn cron(expression: &str) -> Result<Self> {
+        let expression = expression.trim();
+        let expression = if expression.split_whitespace().count() == 5 {
+            format!("0 {}", expression)
+        } else {
+            expression.to_owned()
+        };
+        let schedule = cron::Schedule::from_str(&expression).map_err(|err| {
+            anyhow::Error::msg(format!("Invalid cron expression {}: {}", expression, err))
+        })?;
+        Ok(Schedule::Cron(Box::new(schedule)))
+    }
+
+    pub fn daily(hour: u32, minute: u32) -> Result<Self> {
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(|| {
+            anyhow::Error::msg(format!("Invalid time of day {}:{}", hour, minute))
+        })?;
+        Ok(Schedule::Daily(time))
+    }
+
+    /// First fire time strictly after `after`, if the schedule fires again at all.
+    pub fn next_after(&self, after: DateTime<Utc>, timezone: &Tz) -> Option<DateTime<Utc>> {
+        let after = after.with_timezone(timezone);
+        match self {
+            Schedule::Cron(schedule) => schedule
+                .after(&after)
+                .next()
+                .map(|next| next.with_timezone(&Utc)),
+            Schedule::Daily(time) => {
+                let mut date = after.naive_local().date();
+                // Two days more than needed cover a skipped time on the day after.
+                for _ in 0..3 {
+                    if let Some(next) = timezone
+                        .from_local_datetime(&date.and_time(*time))
+                        .earliest()
+                    {
+                        if next > after {
+                            return Some(next.with_timezone(&Utc));
+                        }
+                    }
+                    date = date.succ();
+                }
+                None
+            }
+        }
+    }
+}
+
+struct Entry {
+    name: String,
+    schedule: Schedule,
+    task: Arc<dyn ScheduledTask>,
+}
+
+/// Runs registered tasks on their schedules. Every replica runs the scheduler, a fire time
+/// is claimed by recording its run under a Postgres advisory lock, so it runs only once.
+pub struct Scheduler {
+    runs: Arc<dyn ScheduleRuns + Send + Sync>,
+    timezone: Tz,
+    entries: Vec<Entry>,
+}
+
+impl Scheduler {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Result<Self> {
+        let timezone = config.scheduler.timezone.parse::<Tz>().map_err(|err| {
+            anyhow::Error::msg(format!(
+                "Invalid scheduler timezone {}: {}",
+                config.scheduler.timezone, err
+            ))
+        })?;
+        Ok(Self {
+            runs: Arc::new(ScheduleRunRepository::new(db)),
+            timezone,
+            entries: vec![],
+        })
+    }
+
+    /// The name identifies the task across replicas and in the run history.
+    pub fn schedule<T: ScheduledTask>(mut self, name: &str, schedule: Schedule, task: T) -> Self {
+        self.entries.push(Entry {
+            name: name.to_owned(),
+            schedule,
+            task: Arc::new(task),
+        });
+        self
+    }
+
+    /// Spawns a loop per scheduled task.
+    pub fn start(self) -> Vec<JoinHandle<()>> {
+        self.entries
+            .into_iter()
+            .map(|entry| {
+                info!(
+                    "Schedule {} on {:?} in {}",
+                    entry.name, entry.schedule, self.timezone
+                );
+                tokio::spawn(tick(entry, self.runs.clone(), self.timezone))
+            })
+            .collect()
+    }
+}
+
+async fn tick(entry: Entry, runs: Arc<dyn ScheduleRuns + Send + Sync>, timezone: Tz) {
+    let mut after = Utc::now();
+    loop {
+        let scheduled_at = match entry.schedule.next_after(after, &timezone) {
+            Some(scheduled_at) => scheduled_at,
+            None => {
+                info!("Schedule {} doesn't fire anymore", entry.name);
+                return;
+            }
+        };
+        let wait = (scheduled_at - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+
+        if let Err(err) = run(&entry, runs.as_ref(), scheduled_at).await {
+            warn!("Failed to run schedule {}: {}", entry.name, err);
+        }
+        // Fire times missed while the task was running are skipped.
+        after = scheduled_at.max(Utc::now());
+    }
+}
+
+async fn run(
+    entry: &Entry,
+    runs: &(dyn ScheduleRuns + Send + Sync),
+    scheduled_at: DateTime<Utc>,
+) -> Result<()> {
+    let run = match runs.start(&entry.name, scheduled_at).await? {
+        Some(run) => run,
+        None => return Ok(()),
+    };
+    let id = run
+        .id
+        .ok_or_else(|| anyhow::Error::msg("Recorded run has no id"))?;
+    info!("Run schedule {} of {}", entry.name, scheduled_at);
+    match entry.task.run().await {
+        Ok(()) => {
+            runs.finish(id, ScheduleRunStatus::Succeeded, None).await?;
+        }
+        Err(err) => {
+            warn!(
+                "Schedule {} of {} failed: {}",
+                entry.name, scheduled_at, err
+            );
+            runs.finish(id, ScheduleRunStatus::Failed, Some(&err.to_string()))
+                .await?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_schedule() {
+        let schedule = Schedule::cron("30 4 * * Mon-Fri").unwrap();
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+
+        // Friday evening, the next weekday is Monday.
+        let next = schedule.next_after(utc("2022-11-04T20:00:00Z"), &berlin);
+        assert_eq!(next, Some(utc("2022-11-07T03:30:00Z")));
+        assert!(Schedule::cron("61 * * * *").is_err());
+        assert!(Schedule::cron("0 0 12 * * * 2023").is_ok());
+    }
+
+    #[test]
+    fn test_daily_schedule() {
+        let schedule = Schedule::daily(7, 5).unwrap();
+        let moscow: Tz = "Europe/Moscow".parse().unwrap();
+
+        let next = schedule.next_after(utc("2022-11-04T03:00:00Z"), &moscow);
+        assert_eq!(next, Some(utc("2022-11-04T04:05:00Z")));
+        let next = schedule.next_after(utc("2022-11-04T04:05:00Z"), &moscow);
+        assert_eq!(next, Some(utc("2022-11-05T04:05:00Z")));
+        assert!(Schedule::daily(24, 0).is_err());
+    }
+
+    #[test]
+    fn test_daily_schedule_daylight_saving() {
+        let schedule = Schedule::daily(2, 30).unwrap();
+        let new_york: Tz = "America/New_York".parse().unwrap();
+
+        // 02:30 doesn't exist on 2022-03-13.
+        let next = schedule.next_after(utc("2022-03-12T08:00:00Z"), &new_york);
+        assert_eq!(next, Some(utc("2022-03-14T06:30:00Z")));
+        // 01:30 happens twice on 2022-11-06, the first one is taken.
+        let schedule = Schedule::daily(1, 30).unwrap();
+        let next = schedule.next_after(utc("2022-11-06T04:00:00Z"), &new_york);
+        assert_eq!(next, Some(utc("2022-11-06T05:30:00Z")));
+    }
+}
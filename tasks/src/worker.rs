@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+
+use anyhow::Result;
+use app_config::{ApplicationConfig, JobsConfig};
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use domain::*;
+use log::{info, warn};
+use repository::JobRepository;
+use sea_orm::DbConn;
+use serde_json::Value;
+use tokio::sync::Semaphore;
+use tokio::task::JoinHandle;
+
+use crate::JobType;
+
+/// Runs the jobs of one type. A failed job is retried with exponential backoff until it runs
+/// out of attempts, then it is dead-lettered.
+#[async_trait]
+pub trait JobHandler: Send + Sync + 'static {
+    type Job: JobType;
+
+    async fn handle(&self, job: Self::Job) -> Result<()>;
+
+    /// Called once the job failed on its last attempt.
+    async fn dead_lettered(&self, _job: Self::Job, _error: &str) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Handler with the payload type erased, so handlers of all types can be kept together.
+#[async_trait]
+trait PayloadHandler: Send + Sync {
+    async fn handle(&self, payload: Value) -> Result<()>;
+    async fn dead_lettered(&self, payload: Value, error: &str) -> Result<()>;
+}
+
+struct TypedHandler<H>(H);
+
+#[async_trait]
+impl<H: JobHandler> PayloadHandler for TypedHandler<H> {
+    async fn handle(&self, payload: Value) -> Result<()> {
+        let job: H::Job = serde_json::from_value(payload)?;
+        self.0.handle(job).await
+    }
+
+    async fn dead_lettered(&self, payload: Value, error: &str) -> Result<()> {
+        let job: H::Job = serde_json::from_value(payload)?;
+        self.0.dead_lettered(job, error).await
+    }
+}
+
+/// Polls the queue for every registered job type, running up to the configured number of
+/// jobs of a type at the same time.
+pub struct Worker {
+    jobs: Arc<dyn Jobs + Send + Sync>,
+    handlers: HashMap<&'static str, Arc<dyn PayloadHandler>>,
+    config: JobsConfig,
+}
+
+impl Worker {
+    pub fn new(config: &ApplicationConfig, db: Arc<DbConn>) -> Self {
+        Self {
+            jobs: Arc::new(JobRepository::new(db)),
+            handlers: HashMap::new(),
+            config: config.jobs.clone(),
+        }
+    }
+
+    pub fn register<H: JobHandler>(mut self, handler: H) -> Self {
+        self.handlers
+            .insert(H::Job::KIND, Arc::new(TypedHandler(handler)));
+        self
+    }
+
+    /// Spawns a polling loop per job type.
+    pub fn start(self) -> Vec<JoinHandle<()>> {
+        let config = Arc::new(self.config);
+        self.handlers
+            .into_iter()
+            .map(|(kind, handler)| {
+                let concurrency = config
+                    .kind_concurrency
+                    .get(kind)
+                    .copied()
+                    .unwrap_or(config.concurrency)
+                    .max(1);
+                info!("Start worker of {} jobs, concurrency {}", kind, concurrency);
+                tokio::spawn(poll(
+                    kind,
+                    self.jobs.clone(),
+                    handler,
+                    Arc::new(Semaphore::new(concurrency)),
+                    config.clone(),
+                ))
+            })
+            .collect()
+    }
+}
+
+async fn poll(
+    kind: &'static str,
+    jobs: Arc<dyn Jobs + Send + Sync>,
+    handler: Arc<dyn PayloadHandler>,
+    permits: Arc<Semaphore>,
+    config: Arc<JobsConfig>,
+) {
+    let interval = StdDuration::from_millis(config.poll_interval);
+    loop {
+        // Only claim as many jobs as can run right away, the rest stays for other workers.
+        let available = permits.available_permits();
+        if available == 0 {
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+        let locked_until = Utc::now() + Duration::seconds(config.lock_timeout);
+        let claimed = match jobs.claim(kind, available as u64, locked_until).await {
+            Ok(claimed) => claimed,
+            Err(err) => {
+                warn!("Failed to claim {} jobs: {}", kind, err);
+                tokio::time::sleep(interval).await;
+                continue;
+            }
+        };
+        if claimed.is_empty() {
+            tokio::time::sleep(interval).await;
+            continue;
+        }
+        for job in claimed {
+            let permit = match permits.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => return,
+            };
+            let (jobs, handler, config) = (jobs.clone(), handler.clone(), config.clone());
+            tokio::spawn(async move {
+                if let Err(err) = run(job, jobs.as_ref(), handler.as_ref(), &config).await {
+                    warn!("Failed to record the outcome of a {} job: {}", kind, err);
+                }
+                drop(permit);
+            });
+        }
+    }
+}
+
+async fn run(
+    job: Job,
+    jobs: &(dyn Jobs + Send + Sync),
+    handler: &dyn PayloadHandler,
+    config: &JobsConfig,
+) -> Result<()> {
+    let id = job
+        .id
+        .ok_or_else(|| anyhow::Error::msg("Claimed job has no id"))?;
+    let attempt = job.attempts;
+    // The lock of the last attempt expired, the worker running it is gone.
+    if attempt > job.max_attempts {
+        let error = "Lock of the last attempt expired";
+        if jobs.dead_letter(id, attempt, error).await? {
+            handler.dead_lettered(job.payload, error).await?;
+        }
+        return Ok(());
+    }
+    let timeout = StdDuration::from_secs(config.lock_timeout.max(0) as u64);
+    let payload = job.payload.clone();
+    let error = match tokio::time::timeout(timeout, handler.handle(payload)).await {
+        Ok(Ok(())) => {
+            jobs.complete(id, attempt).await?;
+            return Ok(());
+        }
+        Ok(Err(err)) => err.to_string(),
+        Err(_) => format!("Timed out after {} seconds", config.lock_timeout),
+    };
+    if attempt >= job.max_attempts {
+        warn!(
+            "{} job {} failed on the last attempt {}: {}",
+            job.kind, id, attempt, error
+        );
+        if jobs.dead_letter(id, attempt, &error).await? {
+            handler.dead_lettered(job.payload, &error).await?;
+        }
+    } else {
+        let delay = backoff(attempt, config.backoff_base, config.backoff_max);
+        info!(
+            "{} job {} failed on attempt {}, retry in {}s: {}",
+            job.kind, id, attempt, delay, error
+        );
+        jobs.retry(id, attempt, Utc::now() + Duration::seconds(delay), &error)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Seconds before the next attempt, doubled with every attempt up to `max`.
+pub fn backoff(attempt: i32, base: i64, max: i64) -> i64 {
+    let exponent = (attempt.max(1) - 1).min(32) as u32;
+    base.saturating_mul(2i64.saturating_pow(exponent)).min(max)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1, 10, 3600), 10);
+        assert_eq!(backoff(2, 10, 3600), 20);
+        assert_eq!(backoff(4, 10, 3600), 80);
+        assert_eq!(backoff(10, 10, 3600), 3600);
+        assert_eq!(backoff(1000, 10, 3600), 3600);
+    }
+}
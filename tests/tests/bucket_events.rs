@@ -0,0 +1,56 @@
+use app_config::ApplicationConfig;
+use application::{BucketEventService, BucketNotification, DefaultBucketEventService};
+use domain::*;
+use repository::{JobRepository, ResourceRepository};
+use serde_json::json;
+use test_log::test;
+
+mod common;
+
+fn created(key: &str) -> serde_json::Value {
+    json!({
+        "eventName": "s3:ObjectCreated:Put",
+        "s3": {
+            "bucket": { "name": "assets" },
+            "object": {
+                "key": key,
+                "size": 3,
+                "eTag": "5289df737df57326fcdd22597afb1fac",
+                "contentType": "image/png"
+            }
+        }
+    })
+}
+
+#[test(tokio::test)]
+async fn ignore_derived_objects() {
+    let (_container, _url, db) = common::postgres().await;
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    let config = ApplicationConfig::default();
+    let service = DefaultBucketEventService::new(&config, db.clone());
+    let notification: BucketNotification = serde_json::from_value(json!({
+        "Records": [created("cat.png"), created("cat.png%40128.png")]
+    }))
+    .unwrap();
+
+    let report = service.ingest(notification).await.unwrap();
+
+    assert_eq!(report.created, 1);
+    assert_eq!(report.ignored, 1);
+    let resources = ResourceRepository::new(db.clone());
+    assert!(resources.get_by_key("cat.png".to_owned()).await.is_ok());
+    assert!(resources
+        .get_by_key("cat.png@128.png".to_owned())
+        .await
+        .is_err());
+    let jobs = JobRepository::new(db)
+        .find(JobFilter {
+            kind: Some("generate_thumbnails".to_owned()),
+            page_size: 10,
+            ..JobFilter::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(jobs.total, 1);
+    assert_eq!(jobs.items[0].payload["key"], "cat.png");
+}
@@ -0,0 +1,61 @@
+use application::ChangeFeed;
+use domain::*;
+use repository::{ResourceEventListener, ResourceRepository};
+use std::time::{Duration, Instant};
+use test_log::test;
+
+mod common;
+
+#[test(tokio::test)]
+async fn notify_recorded_events() {
+    let (_container, url, db) = common::postgres().await;
+    let mut listener = ResourceEventListener::connect(&url).await.unwrap();
+    let resources = ResourceRepository::new(db);
+
+    let created = resources
+        .create_with_event(Resource::default().with_key("notified.png"))
+        .await
+        .unwrap();
+    let first = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    resources
+        .delete_with_event(created.id.unwrap())
+        .await
+        .unwrap();
+    let second = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(second > first);
+}
+
+#[test(tokio::test)]
+async fn wake_long_polls_on_new_events() {
+    let (_container, url, db) = common::postgres().await;
+    let feed = ChangeFeed::new(db.clone());
+    feed.listen(&url);
+    // Consumes the wake-up of the connected listener.
+    feed.changes(0, 10, Duration::from_secs(2)).await.unwrap();
+
+    let waiting = tokio::spawn({
+        let feed = feed.clone();
+        async move {
+            let started = Instant::now();
+            let events = feed.changes(0, 10, Duration::from_secs(30)).await;
+            (events, started.elapsed())
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    ResourceRepository::new(db)
+        .create_with_event(Resource::default().with_key("pushed.png"))
+        .await
+        .unwrap();
+
+    let (events, elapsed) = waiting.await.unwrap();
+    let events = events.unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].key, "pushed.png");
+    assert!(elapsed < Duration::from_secs(5));
+}
@@ -0,0 +1,62 @@
+#![allow(dead_code)]
+
+use app_config::AwsConfig;
+use domain::Storage;
+use log::info;
+use migration::sea_orm::{Database, DbConn};
+use migration::{Migrator, MigratorTrait};
+use once_cell::sync::OnceCell;
+use remote::DefaultStorage;
+use std::sync::Arc;
+use testcontainers::{
+    clients::Cli, core::WaitFor, images::generic::GenericImage, images::minio::MinIO,
+    images::postgres::Postgres, *,
+};
+
+static DOCKER: OnceCell<Cli> = OnceCell::new();
+
+/// Migrated database of its own, removed with the returned container.
+pub async fn postgres() -> (Container<'static, Postgres>, String, Arc<DbConn>) {
+    let _ = env_logger::builder().is_test(true).try_init();
+    let container = DOCKER
+        .get_or_init(Cli::default)
+        .run(RunnableImage::from(Postgres::default()).with_tag("14-alpine"));
+    let url = format!(
+        "postgres://postgres@localhost:{}/postgres",
+        container.get_host_port_ipv4(5432)
+    );
+    let db = Database::connect(url.clone()).await.unwrap();
+    Migrator::up(&db, None).await.unwrap();
+    info!("Database {} migrated", url);
+    (container, url, Arc::new(db))
+}
+
+/// MinIO with an empty bucket `test`, removed with the returned container.
+pub async fn minio() -> (Container<'static, MinIO>, AwsConfig) {
+    let container = DOCKER.get_or_init(Cli::default).run(MinIO::default());
+    let config = AwsConfig {
+        access_key_id: String::from("minioadmin"),
+        secret_access_key: String::from("minioadmin"),
+        region: String::from("eu-west-1"),
+        endpoint: format!("http://127.0.0.1:{}", container.get_host_port_ipv4(9000)),
+        bucket: String::from("test"),
+    };
+    DefaultStorage::from_config(config.clone())
+        .create_bucket(&config.bucket, &config.region)
+        .await
+        .unwrap();
+    (container, config)
+}
+
+/// MailHog with its SMTP and HTTP API ports, removed with the returned container.
+pub fn mailhog() -> (Container<'static, GenericImage>, u16, u16) {
+    let image = GenericImage::new("mailhog/mailhog", "v1.0.1")
+        .with_exposed_port(1025)
+        .with_exposed_port(8025)
+        .with_wait_for(WaitFor::message_on_stdout("Serving under"));
+    let container = DOCKER.get_or_init(Cli::default).run(image);
+    info!("MailHog started...");
+    let smtp_port = container.get_host_port_ipv4(1025);
+    let api_port = container.get_host_port_ipv4(8025);
+    (container, smtp_port, api_port)
+}
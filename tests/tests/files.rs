@@ -0,0 +1,238 @@
+use api::files::files_routers;
+use app_config::ApplicationConfig;
+use application::{
+    encode_access_token, etag, AuthError, Claims, DefaultFileService, FileService, ServiceError,
+    UploadOptions,
+};
+use axum::{body::Bytes, Extension, Server};
+use domain::*;
+use remote::DefaultStorage;
+use repository::ResourceRepository;
+use reqwest::{Client, StatusCode};
+use serde_json::{json, Value};
+use std::net::TcpListener;
+use std::sync::Arc;
+use test_log::test;
+use uuid::Uuid;
+
+mod common;
+
+/// Configuration of the workspace, with the storage of the test.
+fn config(aws: app_config::AwsConfig) -> ApplicationConfig {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    ApplicationConfig {
+        aws,
+        ..ApplicationConfig::default()
+    }
+}
+
+/// Serves the file routes on a random port, returns their base URL.
+fn serve(config: &ApplicationConfig, db: Arc<sea_orm::DbConn>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = files_routers()
+        .layer(Extension(Arc::new(config.clone())))
+        .layer(Extension(db));
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+    url
+}
+
+fn token(config: &ApplicationConfig, id: Uuid, role: Role) -> String {
+    encode_access_token(
+        &config.auth,
+        &User {
+            id: Some(id),
+            role,
+            ..User::default()
+        },
+    )
+    .unwrap()
+}
+
+#[test(tokio::test)]
+async fn update_and_delete_files() {
+    let (_postgres, _url, db) = common::postgres().await;
+    let (_minio, aws) = common::minio().await;
+    let config = config(aws);
+    let storage = DefaultStorage::from_config(config.aws.clone());
+    storage
+        .upload_object(&config.aws.bucket, b"photo", "photo.png")
+        .await
+        .unwrap();
+    let resources = ResourceRepository::new(db.clone());
+    let owner = Uuid::new_v4();
+    let created = resources
+        .create(
+            Resource::default()
+                .with_key("photo.png")
+                .with_user_id(owner),
+        )
+        .await
+        .unwrap();
+    let url = serve(&config, db);
+    let client = Client::new();
+    let owner_token = token(&config, owner, Role::USER);
+    let other_token = token(&config, Uuid::new_v4(), Role::USER);
+    let tags = json!({ "kind": "photo" });
+
+    let response = client
+        .put(format!("{}/files/photo.png", url))
+        .json(&json!({ "tags": tags }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .put(format!("{}/files/photo.png", url))
+        .bearer_auth(&other_token)
+        .json(&json!({ "tags": tags }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = client
+        .put(format!("{}/files/missing.png", url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "tags": tags }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = client
+        .put(format!("{}/files/photo.png", url))
+        .bearer_auth(&owner_token)
+        .json(&json!({ "tags": tags }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: Value = response.json().await.unwrap();
+    assert_eq!(updated["tags"], tags);
+    assert_eq!(updated["user_id"], json!(owner));
+
+    let response = client
+        .delete(format!("{}/files/photo.png", url))
+        .bearer_auth(&other_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert!(resources.get_by_key("photo.png".to_owned()).await.is_ok());
+
+    let response = client
+        .delete(format!("{}/files/photo.png", url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(resources.get_by_key("photo.png".to_owned()).await.is_err());
+    let trashed = resources.get_trashed(created.id.unwrap()).await.unwrap();
+    let objects = storage.list_objects(&config.aws.bucket).await.unwrap();
+    assert!(objects.contains(&trashed.key));
+
+    let response = client
+        .delete(format!("{}/files/photo.png", url))
+        .bearer_auth(&owner_token)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+fn file(key: &str, data: &'static [u8], user_id: Option<Uuid>) -> Box<FileObject> {
+    Box::new(FileObject {
+        key: key.to_owned(),
+        url: None,
+        tags: None,
+        user_id,
+        metadata: None,
+        blurhash: None,
+        palette: None,
+        size: None,
+        etag: None,
+        expires_at: None,
+        data: Some(Bytes::from_static(data)),
+    })
+}
+
+fn claims(id: Uuid, role: Role) -> Claims {
+    Claims {
+        sub: id,
+        name: id.to_string(),
+        role,
+        iat: 0,
+        exp: 0,
+    }
+}
+
+#[test(tokio::test)]
+async fn replace_files_only_as_owner() {
+    let (_postgres, _url, db) = common::postgres().await;
+    let (_minio, aws) = common::minio().await;
+    let config = config(aws);
+    let storage = DefaultStorage::from_config(config.aws.clone());
+    let resources = ResourceRepository::new(db.clone());
+    let owner = claims(Uuid::new_v4(), Role::USER);
+    let other = claims(Uuid::new_v4(), Role::USER);
+    let options = || UploadOptions {
+        scrub_metadata: Some(false),
+    };
+    DefaultFileService::new(&config, db.clone())
+        .upload(
+            Some(&owner),
+            file("notes.txt", b"first", Some(owner.sub)),
+            options(),
+        )
+        .await
+        .unwrap();
+
+    let err = DefaultFileService::new(&config, db.clone())
+        .upload(
+            Some(&other),
+            file("notes.txt", b"other", Some(other.sub)),
+            options(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err.downcast_ref(), Some(AuthError::Forbidden)));
+    let err = DefaultFileService::new(&config, db.clone())
+        .upload(None, file("notes.txt", b"anonymous", None), options())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(ServiceError::Conflict(_))
+    ));
+    let data = storage
+        .download_object(&config.aws.bucket, "notes.txt")
+        .await
+        .unwrap();
+    assert_eq!(data.as_ref(), b"first");
+
+    DefaultFileService::new(&config, db)
+        .upload(
+            Some(&owner),
+            file("notes.txt", b"second", Some(owner.sub)),
+            options(),
+        )
+        .await
+        .unwrap();
+    let data = storage
+        .download_object(&config.aws.bucket, "notes.txt")
+        .await
+        .unwrap();
+    assert_eq!(data.as_ref(), b"second");
+    let replaced = resources.get_by_key("notes.txt".to_owned()).await.unwrap();
+    assert_eq!(replaced.user_id, Some(owner.sub));
+    assert_eq!(replaced.etag.as_deref(), Some(etag(b"second").as_str()));
+}
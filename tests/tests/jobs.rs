@@ -0,0 +1,88 @@
+use chrono::{Duration, Utc};
+use domain::*;
+use repository::JobRepository;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use test_log::test;
+
+mod common;
+
+const KIND: &str = "generate_thumbnails";
+
+#[test(tokio::test)]
+async fn claim_due_jobs() {
+    let (_container, _url, db) = common::postgres().await;
+    let jobs = JobRepository::new(db);
+    let due = jobs.create(Job::new(KIND, json!({}), 3)).await.unwrap();
+    jobs.create(Job::new(KIND, json!({}), 3).with_run_at(Utc::now() + Duration::hours(1)))
+        .await
+        .unwrap();
+    jobs.create(Job::new("deliver_webhook", json!({}), 3))
+        .await
+        .unwrap();
+
+    // The lock expires right away, as if the worker died.
+    let claimed = jobs
+        .claim(KIND, 10, Utc::now() - Duration::seconds(1))
+        .await
+        .unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].id, due.id);
+    assert_eq!(claimed[0].status, JobStatus::Running);
+    assert_eq!(claimed[0].attempts, 1);
+
+    let reclaimed = jobs
+        .claim(KIND, 10, Utc::now() + Duration::minutes(5))
+        .await
+        .unwrap();
+    assert_eq!(reclaimed.len(), 1);
+    assert_eq!(reclaimed[0].attempts, 2);
+    assert!(jobs
+        .claim(KIND, 10, Utc::now() + Duration::minutes(5))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let id = due.id.unwrap();
+    assert!(!jobs.complete(id, 1).await.unwrap());
+    assert!(jobs.complete(id, 2).await.unwrap());
+    assert_eq!(jobs.get_by_id(id).await.unwrap().status, JobStatus::Done);
+}
+
+#[test(tokio::test)]
+async fn skip_paused_queues() {
+    let (_container, _url, db) = common::postgres().await;
+    let jobs = JobRepository::new(db);
+    jobs.create(Job::new(KIND, json!({}), 3)).await.unwrap();
+    let locked_until = Utc::now() + Duration::minutes(5);
+
+    jobs.set_paused(KIND, true).await.unwrap();
+    assert!(jobs.claim(KIND, 10, locked_until).await.unwrap().is_empty());
+    jobs.set_paused(KIND, false).await.unwrap();
+    assert_eq!(jobs.claim(KIND, 10, locked_until).await.unwrap().len(), 1);
+}
+
+#[test(tokio::test)]
+async fn claim_each_job_once() {
+    let (_container, _url, db) = common::postgres().await;
+    let jobs = Arc::new(JobRepository::new(db));
+    for _ in 0..20 {
+        jobs.create(Job::new(KIND, json!({}), 3)).await.unwrap();
+    }
+
+    let locked_until = Utc::now() + Duration::minutes(5);
+    let workers: Vec<_> = (0..4)
+        .map(|_| {
+            let jobs = jobs.clone();
+            tokio::spawn(async move { jobs.claim(KIND, 5, locked_until).await.unwrap() })
+        })
+        .collect();
+    let mut claimed = HashSet::new();
+    for worker in workers {
+        for job in worker.await.unwrap() {
+            assert!(claimed.insert(job.id), "Job {:?} claimed twice", job.id);
+        }
+    }
+    assert_eq!(claimed.len(), 20);
+}
@@ -0,0 +1,34 @@
+use app_config::{ApplicationConfig, LockoutConfig};
+use application::{AuthError, DefaultLoginThrottleService, LoginThrottleService};
+use test_log::test;
+
+mod common;
+
+fn config() -> ApplicationConfig {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    let config = ApplicationConfig::default();
+    ApplicationConfig {
+        lockout: LockoutConfig {
+            client_max_failures: 2,
+            ..config.lockout.clone()
+        },
+        ..config
+    }
+}
+
+#[test(tokio::test)]
+async fn lock_out_clients_requesting_captchas() {
+    let (_container, _url, db) = common::postgres().await;
+    let throttle = DefaultLoginThrottleService::new(&config(), db);
+
+    throttle.record_captcha("10.0.0.1").await.unwrap();
+    throttle.record_captcha("10.0.0.1").await.unwrap();
+    let err = throttle.record_captcha("10.0.0.1").await.unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AuthError::TooManyAttempts)
+    ));
+
+    throttle.record_captcha("10.0.0.2").await.unwrap();
+    throttle.check("admin", "10.0.0.1").await.unwrap();
+}
@@ -0,0 +1,157 @@
+use app_config::{ApplicationConfig, MailConfig};
+use application::{
+    AccountService, AuthError, AuthService, Claims, DefaultAccountService, DefaultAuthService,
+    DefaultUserService, UserService,
+};
+use domain::*;
+use remote::*;
+use repository::UserRepository;
+use serde_json::Value;
+use test_log::test;
+
+mod common;
+
+#[test(tokio::test)]
+async fn send_mail_over_smtp() {
+    let (_container, smtp_port, _api_port) = common::mailhog();
+
+    let mailer = SmtpMailer::from_config(config(smtp_port)).unwrap();
+    mailer
+        .send(Email {
+            to: String::from("user@localhost"),
+            subject: String::from("Subject"),
+            body: String::from("Body"),
+        })
+        .await
+        .unwrap();
+}
+
+#[test(tokio::test)]
+async fn verify_email_with_mailed_token() {
+    let (_mailhog, smtp_port, api_port) = common::mailhog();
+    let (_postgres, _url, db) = common::postgres().await;
+    let config = application_config(smtp_port);
+    let accounts = DefaultAccountService::new(&config, db.clone()).unwrap();
+    let auth = DefaultAuthService::new(&config, db);
+
+    let user = accounts
+        .register("user", "user@localhost", "password")
+        .await
+        .unwrap();
+    assert!(!user.enabled);
+    let err = auth
+        .login("user", "password", "127.0.0.1")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AuthError::EmailNotVerified)
+    ));
+
+    let token = mailed_token(api_port, "user@localhost").await;
+    let verified = accounts.verify_email(&token).await.unwrap();
+    assert!(verified.enabled);
+    assert!(verified.email_verified_at.is_some());
+    assert!(auth.login("user", "password", "127.0.0.1").await.is_ok());
+    assert!(accounts.verify_email(&token).await.is_err());
+}
+
+#[test(tokio::test)]
+async fn keep_disabled_users_disabled_on_verification() {
+    let (_mailhog, smtp_port, api_port) = common::mailhog();
+    let (_postgres, _url, db) = common::postgres().await;
+    let config = application_config(smtp_port);
+    let accounts = DefaultAccountService::new(&config, db.clone()).unwrap();
+    let auth = DefaultAuthService::new(&config, db.clone());
+    let admin = UserRepository::new(db.clone())
+        .create(
+            User::default()
+                .with_name("admin")
+                .with_email("admin@localhost")
+                .with_role(Role::ADMIN)
+                .enable(true),
+        )
+        .await
+        .unwrap();
+    let admin = Claims {
+        sub: admin.id.unwrap(),
+        name: admin.name,
+        role: admin.role,
+        iat: 0,
+        exp: 0,
+    };
+
+    let user = accounts
+        .register("user", "user@localhost", "password")
+        .await
+        .unwrap();
+    DefaultUserService::new(db)
+        .set_enabled(&admin, user.id.unwrap(), false)
+        .await
+        .unwrap();
+    accounts
+        .resend_verification("user@localhost")
+        .await
+        .unwrap();
+
+    let token = mailed_token(api_port, "user@localhost").await;
+    let verified = accounts.verify_email(&token).await.unwrap();
+    assert!(!verified.enabled);
+    assert!(verified.email_verified_at.is_some());
+    let err = auth
+        .login("user", "password", "127.0.0.1")
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AuthError::AccountDisabled)
+    ));
+}
+
+/// Token of the link in the latest message MailHog received for the recipient.
+async fn mailed_token(api_port: u16, to: &str) -> String {
+    let messages: Value = reqwest::get(format!("http://127.0.0.1:{}/api/v2/messages", api_port))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    // MailHog lists the latest message first.
+    let message = messages["items"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|message| message["Raw"]["To"][0] == to)
+        .expect("No message to the recipient");
+    let body = message["Content"]["Body"]
+        .as_str()
+        .unwrap()
+        .replace("=\r\n", "")
+        .replace("=3D", "=");
+    let link = body
+        .split_whitespace()
+        .find(|word| word.starts_with("http://127.0.0.1:3000/verify-email?token="))
+        .expect("No verification link in the message");
+    link.split("token=").nth(1).unwrap().to_owned()
+}
+
+fn application_config(smtp_port: u16) -> ApplicationConfig {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    ApplicationConfig {
+        mail: config(smtp_port),
+        ..ApplicationConfig::default()
+    }
+}
+
+fn config(port: u16) -> MailConfig {
+    MailConfig {
+        transport: String::from("smtp"),
+        from: String::from("Assets <no-reply@localhost>"),
+        base_url: String::from("http://127.0.0.1:3000"),
+        smtp_host: String::from("127.0.0.1"),
+        smtp_port: port,
+        smtp_username: String::new(),
+        smtp_password: String::new(),
+        smtp_tls: false,
+    }
+}
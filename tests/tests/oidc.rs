@@ -0,0 +1,247 @@
+use app_config::OidcConfig;
+use axum::{
+    extract::{Extension, Form, Query},
+    http::{header::LOCATION, HeaderMap, StatusCode},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router, Server,
+};
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use remote::*;
+use reqwest::{redirect::Policy, Url};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use test_log::test;
+
+const CLIENT_ID: &str = "assets";
+const CLIENT_SECRET: &str = "secret";
+const REDIRECT_URL: &str = "http://localhost:3000/auth/oidc/callback";
+const KEY_ID: &str = "mock-key";
+const PRIVATE_KEY: &str = include_str!("../idp_key.pem");
+/// Modulus of `idp_key.pem`, base64url encoded.
+const MODULUS: &str = "yS625cmkkXKCQ6qHz83DgBGweLjrBl1ZWaXfgVCV3P2yqpXy2Xvqdhxoi6vFI1yq_SS7rqoWKZcqxnBsR0Z13DtidXsbbl_3w_ebcgovm2uqW--8NTCCxSMPhqiX-cyiRhSIM55w7ywKPoWTMOEcMXyPF_HIKErk6S5SFvqvYWpWRffRk67hIVD0foyEUwNA5bXt4sPzOD7AhpTKEHj2hWVJVzhoJmtxxKMu_Gh_TjOhTdflxvT_rF7mOgZ81094RIKrmBswZdJERzTxyZSHbV1vyvmJEqD6ltFDSFt81DFChoxRitc9Li3-Lf323sfZy6OI4IJkIUXhhSsE_uXMFw";
+
+/// Authorization requests of the mock identity provider by issued code.
+#[derive(Default)]
+struct MockIdp {
+    issuer: String,
+    requests: HashMap<String, AuthorizeQuery>,
+}
+
+type MockState = Arc<Mutex<MockIdp>>;
+
+#[derive(Clone, Debug, Deserialize)]
+struct AuthorizeQuery {
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: String,
+    code_challenge: String,
+    code_challenge_method: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+    code_verifier: String,
+}
+
+/// Starts the mock identity provider on a random port, returns its issuer.
+fn start_mock_idp() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let state: MockState = Arc::new(Mutex::new(MockIdp {
+        issuer: issuer.clone(),
+        ..MockIdp::default()
+    }));
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/jwks", get(jwks))
+        .route("/authorize", get(authorize))
+        .route("/token", post(token))
+        .layer(Extension(state));
+    tokio::spawn(async move {
+        Server::from_tcp(listener)
+            .unwrap()
+            .serve(app.into_make_service())
+            .await
+            .unwrap();
+    });
+    issuer
+}
+
+async fn discovery(Extension(state): Extension<MockState>) -> Json<serde_json::Value> {
+    let issuer = state.lock().unwrap().issuer.clone();
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{}/authorize", issuer),
+        "token_endpoint": format!("{}/token", issuer),
+        "jwks_uri": format!("{}/jwks", issuer),
+    }))
+}
+
+async fn jwks() -> Json<serde_json::Value> {
+    Json(json!({
+        "keys": [{
+            "kty": "RSA",
+            "kid": KEY_ID,
+            "use": "sig",
+            "alg": "RS256",
+            "n": MODULUS,
+            "e": "AQAB",
+        }]
+    }))
+}
+
+/// Logs the user in right away and redirects back with a code.
+async fn authorize(
+    Query(query): Query<AuthorizeQuery>,
+    Extension(state): Extension<MockState>,
+) -> impl IntoResponse {
+    let code = format!("code-{}", query.state);
+    let location = format!("{}?code={}&state={}", query.redirect_uri, code, query.state);
+    state.lock().unwrap().requests.insert(code, query);
+    (StatusCode::FOUND, [(LOCATION, location)])
+}
+
+async fn token(
+    headers: HeaderMap,
+    Form(form): Form<TokenForm>,
+    Extension(state): Extension<MockState>,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let state = state.lock().unwrap();
+    let request = state
+        .requests
+        .get(&form.code)
+        .ok_or(StatusCode::BAD_REQUEST)?;
+    let basic = format!(
+        "Basic {}",
+        base64::encode(format!("{}:{}", CLIENT_ID, CLIENT_SECRET))
+    );
+    if headers.get("authorization").and_then(|v| v.to_str().ok()) != Some(basic.as_str()) {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if form.grant_type != "authorization_code"
+        || request.code_challenge_method != "S256"
+        || code_challenge(&form.code_verifier) != request.code_challenge
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let claims = json!({
+        "iss": state.issuer,
+        "sub": "alice-subject",
+        "aud": request.client_id,
+        "iat": now,
+        "exp": now + 300,
+        "nonce": request.nonce,
+        "email": "alice@example.com",
+        "email_verified": true,
+        "preferred_username": "alice",
+        "groups": ["asset-admins"],
+    });
+    let header = Header {
+        kid: Some(KEY_ID.to_owned()),
+        ..Header::new(Algorithm::RS256)
+    };
+    let id_token = encode(
+        &header,
+        &claims,
+        &EncodingKey::from_rsa_pem(PRIVATE_KEY.as_bytes()).unwrap(),
+    )
+    .unwrap();
+    Ok(Json(json!({
+        "access_token": "access",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    })))
+}
+
+fn config(issuer: &str) -> OidcConfig {
+    OidcConfig {
+        enabled: true,
+        issuer: issuer.to_owned(),
+        client_id: CLIENT_ID.to_owned(),
+        client_secret: CLIENT_SECRET.to_owned(),
+        redirect_url: REDIRECT_URL.to_owned(),
+        scopes: "openid email profile".to_owned(),
+        role_claim: "groups".to_owned(),
+        role_mapping: HashMap::from([("asset-admins".to_owned(), "ADMIN".to_owned())]),
+        default_role: "USER".to_owned(),
+        login_state_secret: "state-secret".to_owned(),
+    }
+}
+
+/// Follows the authorization URL like a browser and returns the code and state of the callback.
+async fn authorize_at_idp(request: &AuthorizationRequest) -> (String, String) {
+    let response = reqwest::Client::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap()
+        .get(&request.url)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::FOUND);
+    let location = Url::parse(
+        response
+            .headers()
+            .get(LOCATION.as_str())
+            .unwrap()
+            .to_str()
+            .unwrap(),
+    )
+    .unwrap();
+    let query: HashMap<String, String> = location.query_pairs().into_owned().collect();
+    (query["code"].clone(), query["state"].clone())
+}
+
+#[test(tokio::test)]
+async fn login_with_authorization_code_and_pkce() {
+    let issuer = start_mock_idp();
+    let client = OidcClient::discover(config(&issuer)).await.unwrap();
+    let request = client.authorization_request().unwrap();
+
+    let (code, state) = authorize_at_idp(&request).await;
+    assert_eq!(state, request.state);
+
+    let claims = client
+        .exchange_code(&code, &request.code_verifier, &request.nonce)
+        .await
+        .unwrap();
+    assert_eq!(claims.iss, issuer);
+    assert_eq!(claims.sub, "alice-subject");
+    assert_eq!(claims.email.as_deref(), Some("alice@example.com"));
+    assert_eq!(claims.extra["groups"], json!(["asset-admins"]));
+}
+
+#[test(tokio::test)]
+async fn reject_wrong_code_verifier() {
+    let issuer = start_mock_idp();
+    let client = OidcClient::discover(config(&issuer)).await.unwrap();
+    let request = client.authorization_request().unwrap();
+    let (code, _) = authorize_at_idp(&request).await;
+
+    let other = client.authorization_request().unwrap();
+    assert!(client
+        .exchange_code(&code, &other.code_verifier, &request.nonce)
+        .await
+        .is_err());
+}
+
+#[test(tokio::test)]
+async fn reject_wrong_nonce() {
+    let issuer = start_mock_idp();
+    let client = OidcClient::discover(config(&issuer)).await.unwrap();
+    let request = client.authorization_request().unwrap();
+    let (code, _) = authorize_at_idp(&request).await;
+
+    assert!(client
+        .exchange_code(&code, &request.code_verifier, "other-nonce")
+        .await
+        .is_err());
+}
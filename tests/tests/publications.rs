@@ -0,0 +1,46 @@
+use chrono::{Duration, Utc};
+use domain::*;
+use repository::PublicationRepository;
+use test_log::test;
+use uuid::Uuid;
+
+mod common;
+
+#[test(tokio::test)]
+async fn start_only_the_current_schedule() {
+    let (_container, _url, db) = common::postgres().await;
+    let publications = PublicationRepository::new(db);
+    let created = publications
+        .create(Publication::new(Uuid::new_v4(), "vk", Utc::now(), 3))
+        .await
+        .unwrap();
+    let id = created.id.unwrap();
+    let later = created.scheduled_at + Duration::hours(1);
+    assert!(publications.reschedule(id, later).await.unwrap());
+    let later = publications.get_by_id(id).await.unwrap().scheduled_at;
+
+    // The job of the first schedule leaves the publication alone.
+    assert!(publications
+        .start(id, created.scheduled_at)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(!publications
+        .fail(id, created.scheduled_at, "stale")
+        .await
+        .unwrap());
+
+    let started = publications.start(id, later).await.unwrap().unwrap();
+    assert_eq!(started.status, PublicationStatus::Publishing);
+    assert_eq!(started.attempts, 1);
+    assert!(publications.retry(id, 1, "timeout").await.unwrap());
+    let started = publications.start(id, later).await.unwrap().unwrap();
+    assert_eq!(started.attempts, 2);
+    assert!(!publications.complete(id, 1, "post-1").await.unwrap());
+    assert!(publications.complete(id, 2, "post-1").await.unwrap());
+
+    let published = publications.get_by_id(id).await.unwrap();
+    assert_eq!(published.status, PublicationStatus::Published);
+    assert_eq!(published.remote_id.as_deref(), Some("post-1"));
+    assert!(publications.start(id, later).await.unwrap().is_none());
+}
@@ -0,0 +1,232 @@
+use chrono::{Duration, Utc};
+use domain::*;
+use repository::{is_unique_violation, PublicationRepository, ResourceRepository};
+use serde_json::{json, Map, Value};
+use test_log::test;
+use uuid::Uuid;
+
+mod common;
+
+fn tags(tags: Value) -> Map<String, Value> {
+    tags.as_object().unwrap().clone()
+}
+
+#[test(tokio::test)]
+async fn find_publishable_images() {
+    let (_container, _url, db) = common::postgres().await;
+    let resources = ResourceRepository::new(db.clone());
+    let publications = PublicationRepository::new(db);
+    let publish = tags(json!({ "publish": "vk" }));
+
+    let mut created = vec![];
+    for key in [
+        "unhashed.JPG",
+        "notes.pdf",
+        "queued.png",
+        "failed.png",
+        "other.png",
+    ] {
+        let tags = match key {
+            "other.png" => tags(json!({ "publish": "telegram" })),
+            _ => publish.clone(),
+        };
+        created.push(
+            resources
+                .create(Resource::default().with_key(key).with_tags(tags))
+                .await
+                .unwrap(),
+        );
+    }
+    for (resource, status) in [
+        (&created[2], PublicationStatus::Scheduled),
+        (&created[3], PublicationStatus::Failed),
+    ] {
+        publications
+            .create(Publication {
+                status,
+                ..Publication::new(resource.id.unwrap(), "vk", Utc::now(), 3)
+            })
+            .await
+            .unwrap();
+    }
+
+    let publishable = resources.find_publishable("vk", 10).await.unwrap();
+    let keys: Vec<&str> = publishable.iter().map(|r| r.key.as_str()).collect();
+    assert_eq!(keys, vec!["unhashed.JPG", "failed.png"]);
+}
+
+#[test(tokio::test)]
+async fn find_lifecycle_by_filter() {
+    let (_container, _url, db) = common::postgres().await;
+    let resources = ResourceRepository::new(db);
+    let now = Utc::now();
+    for (key, kind, days) in [
+        ("exports/a_1.csv", json!("report"), 10),
+        ("exports/ab.csv", json!("report"), 10),
+        ("exports/a_2.csv", json!("log"), 10),
+        ("exports/a_3.csv", json!("report"), 1),
+        ("exports/a_4.csv", json!(4), 10),
+        ("exports/a_/cold/5.csv", json!("report"), 10),
+        ("imports/a_6.csv", json!("report"), 10),
+    ] {
+        resources
+            .create(Resource {
+                created_at: now - Duration::days(days),
+                ..Resource::default()
+                    .with_key(key)
+                    .with_tags(tags(json!({ "kind": kind })))
+            })
+            .await
+            .unwrap();
+    }
+    let find = |filter: LifecycleFilter| {
+        let resources = &resources;
+        async move {
+            let found = resources.find_lifecycle(filter, 10).await.unwrap();
+            let mut keys: Vec<String> = found.into_iter().map(|r| r.key).collect();
+            keys.sort();
+            keys
+        }
+    };
+
+    // `_` is matched literally, not as a LIKE wildcard.
+    let keys = find(LifecycleFilter {
+        prefix: Some("exports/a_".to_owned()),
+        tag: Some("kind".to_owned()),
+        tag_value: Some("report".to_owned()),
+        created_before: Some(now - Duration::days(7)),
+        exclude_prefix: Some("exports/a_/cold/".to_owned()),
+    })
+    .await;
+    assert_eq!(keys, vec!["exports/a_1.csv"]);
+
+    let keys = find(LifecycleFilter {
+        tag: Some("kind".to_owned()),
+        tag_value: Some("4".to_owned()),
+        ..LifecycleFilter::default()
+    })
+    .await;
+    assert_eq!(keys, vec!["exports/a_4.csv"]);
+
+    let keys = find(LifecycleFilter {
+        prefix: Some("imports/".to_owned()),
+        tag: Some("kind".to_owned()),
+        ..LifecycleFilter::default()
+    })
+    .await;
+    assert_eq!(keys, vec!["imports/a_6.csv"]);
+}
+
+#[test(tokio::test)]
+async fn detect_taken_keys() {
+    let (_container, _url, db) = common::postgres().await;
+    let resources = ResourceRepository::new(db);
+    let photo = Resource::default().with_key("photo.png");
+    resources.create_with_event(photo.clone()).await.unwrap();
+
+    let err = resources.create_with_event(photo).await.unwrap_err();
+    assert!(is_unique_violation(&err));
+    let err = resources
+        .get_by_key("missing.png".to_owned())
+        .await
+        .unwrap_err();
+    assert!(!is_unique_violation(&err));
+}
+
+#[test(tokio::test)]
+async fn exclude_trashed_resources() {
+    let (_container, _url, db) = common::postgres().await;
+    let resources = ResourceRepository::new(db);
+    let mut created = vec![];
+    for key in ["kept.png", "trashed.png"] {
+        created.push(
+            resources
+                .create(
+                    Resource::default()
+                        .with_key(key)
+                        .with_perceptual_hash(Some(0b1010)),
+                )
+                .await
+                .unwrap(),
+        );
+    }
+    let trashed = &created[1];
+    resources
+        .trash_with_event(
+            trashed.id.unwrap(),
+            Resource {
+                key: format!(".trash/{}/trashed.png", trashed.id.unwrap()),
+                deleted_at: Some(Utc::now()),
+                ..trashed.clone()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert!(resources
+        .get_by_key("trashed.png".to_owned())
+        .await
+        .is_err());
+    assert!(resources
+        .get_by_key(format!(".trash/{}/trashed.png", trashed.id.unwrap()))
+        .await
+        .is_err());
+    assert!(resources.get_trashed(trashed.id.unwrap()).await.is_ok());
+
+    let found = resources
+        .find(ResourceFilter {
+            page_size: 10,
+            ..ResourceFilter::default()
+        })
+        .await
+        .unwrap();
+    let keys: Vec<&str> = found.items.iter().map(|r| r.key.as_str()).collect();
+    assert_eq!(keys, vec!["kept.png"]);
+    assert_eq!(found.total, 1);
+
+    let similar = resources.find_similar(0b1011, 4, None, 10).await.unwrap();
+    assert_eq!(similar.len(), 1);
+    assert_eq!(similar[0].resource.key, "kept.png");
+    let similar = resources
+        .find_similar(0b1011, 4, Some(Uuid::new_v4()), 10)
+        .await
+        .unwrap();
+    assert!(similar.is_empty());
+}
+
+#[test(tokio::test)]
+async fn find_unhashed_images() {
+    let (_container, _url, db) = common::postgres().await;
+    let resources = ResourceRepository::new(db);
+    for (key, perceptual_hash) in [
+        ("a.png", None),
+        ("b.JPG", None),
+        ("c.png", Some(7)),
+        ("notes.pdf", None),
+    ] {
+        resources
+            .create(
+                Resource::default()
+                    .with_key(key)
+                    .with_perceptual_hash(perceptual_hash),
+            )
+            .await
+            .unwrap();
+    }
+
+    let first = resources.find_unhashed(None, 1).await.unwrap();
+    assert_eq!(first.len(), 1);
+    let rest = resources.find_unhashed(first[0].id, 10).await.unwrap();
+    let mut keys: Vec<&str> = first.iter().chain(&rest).map(|r| r.key.as_str()).collect();
+    keys.sort_unstable();
+    assert_eq!(keys, vec!["a.png", "b.JPG"]);
+
+    let id = first[0].id.unwrap();
+    assert!(resources.set_perceptual_hash(id, 42).await.unwrap());
+    assert!(!resources.set_perceptual_hash(id, 43).await.unwrap());
+    assert_eq!(
+        resources.get_by_id(id).await.unwrap().perceptual_hash,
+        Some(42)
+    );
+    assert_eq!(resources.find_unhashed(None, 10).await.unwrap().len(), 1);
+}
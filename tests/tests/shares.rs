@@ -0,0 +1,117 @@
+use app_config::ApplicationConfig;
+use application::{
+    AuthError, Claims, DefaultShareService, ServiceError, ShareOptions, ShareService,
+};
+use chrono::Utc;
+use domain::*;
+use repository::ResourceRepository;
+use test_log::test;
+use uuid::Uuid;
+
+mod common;
+
+fn config() -> ApplicationConfig {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/..")).unwrap();
+    ApplicationConfig::default()
+}
+
+#[test(tokio::test)]
+async fn throttle_share_passwords() {
+    let (_container, _url, db) = common::postgres().await;
+    let owner = Uuid::new_v4();
+    let claims = Claims {
+        sub: owner,
+        name: "owner".to_owned(),
+        role: Role::USER,
+        iat: 0,
+        exp: 0,
+    };
+    ResourceRepository::new(db.clone())
+        .create(
+            Resource::default()
+                .with_key("photo.png")
+                .with_user_id(owner),
+        )
+        .await
+        .unwrap();
+    let service = DefaultShareService::new(&config(), db);
+    let created = service
+        .create(
+            &claims,
+            "photo.png",
+            ShareOptions {
+                password: Some("password".to_owned()),
+                ..ShareOptions::default()
+            },
+        )
+        .await
+        .unwrap();
+
+    let err = service
+        .open(&created.token, Some("wrong"), "10.0.0.1")
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AuthError::InvalidCredentials)
+    ));
+    let err = service
+        .open(&created.token, Some("password"), "10.0.0.1")
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(AuthError::TooManyAttempts)
+    ));
+}
+
+#[test(tokio::test)]
+async fn hide_shares_of_trashed_resources() {
+    let (_container, _url, db) = common::postgres().await;
+    let owner = Uuid::new_v4();
+    let claims = Claims {
+        sub: owner,
+        name: "owner".to_owned(),
+        role: Role::USER,
+        iat: 0,
+        exp: 0,
+    };
+    let resources = ResourceRepository::new(db.clone());
+    let resource = resources
+        .create(
+            Resource::default()
+                .with_key("photo.png")
+                .with_user_id(owner),
+        )
+        .await
+        .unwrap();
+    let service = DefaultShareService::new(&config(), db);
+    let created = service
+        .create(&claims, "photo.png", ShareOptions::default())
+        .await
+        .unwrap();
+    let id = resource.id.unwrap();
+    resources
+        .trash_with_event(
+            id,
+            Resource {
+                key: format!(".trash/{}/photo.png", id),
+                deleted_at: Some(Utc::now()),
+                ..resource
+            },
+        )
+        .await
+        .unwrap();
+
+    let err = service
+        .open(&created.token, None, "10.0.0.1")
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(ServiceError::NotFound(_))
+    ));
+}
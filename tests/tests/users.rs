@@ -0,0 +1,188 @@
+use application::{AuthError, Claims, DefaultUserService, ServiceError, UserService};
+use chrono::Duration;
+use domain::*;
+use repository::{RefreshTokenRepository, UserRepository};
+use test_log::test;
+use uuid::Uuid;
+
+mod common;
+
+fn claims(user: &User) -> Claims {
+    Claims {
+        sub: user.id.unwrap(),
+        name: user.name.clone(),
+        role: user.role.clone(),
+        iat: 0,
+        exp: 0,
+    }
+}
+
+async fn create_user(users: &UserRepository, name: &str, role: Role) -> User {
+    users
+        .create(
+            User::default()
+                .with_name(name)
+                .with_email(&format!("{}@example.com", name))
+                .with_password("secret")
+                .with_role(role)
+                .enable(true),
+        )
+        .await
+        .unwrap()
+}
+
+fn is_bad_request(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref(), Some(ServiceError::BadRequest(_)))
+}
+
+fn is_forbidden(err: &anyhow::Error) -> bool {
+    matches!(err.downcast_ref(), Some(AuthError::Forbidden))
+}
+
+#[test(tokio::test)]
+async fn list_and_create_users_as_admin() {
+    let (_container, _url, db) = common::postgres().await;
+    let users = UserRepository::new(db.clone());
+    let service = DefaultUserService::new(db);
+    let admin = create_user(&users, "admin", Role::ADMIN).await;
+    let user = create_user(&users, "user", Role::USER).await;
+    let filter = UserFilter {
+        page_size: 10,
+        ..UserFilter::default()
+    };
+    let created = || {
+        User::default()
+            .with_name("other")
+            .with_email("other@example.com")
+            .with_password("secret")
+    };
+
+    let err = service
+        .list(&claims(&user), filter.clone())
+        .await
+        .unwrap_err();
+    assert!(is_forbidden(&err));
+    let err = service.create(&claims(&user), created()).await.unwrap_err();
+    assert!(is_forbidden(&err));
+    assert!(users.get_by_key("other".to_owned()).await.is_err());
+
+    service.create(&claims(&admin), created()).await.unwrap();
+    let page = service.list(&claims(&admin), filter).await.unwrap();
+    assert_eq!(page.total, 3);
+}
+
+#[test(tokio::test)]
+async fn update_roles_of_other_users() {
+    let (_container, _url, db) = common::postgres().await;
+    let users = UserRepository::new(db.clone());
+    let service = DefaultUserService::new(db);
+    let admin = create_user(&users, "admin", Role::ADMIN).await;
+    let user = create_user(&users, "user", Role::USER).await;
+
+    let err = service
+        .update_role(&claims(&user), admin.id.unwrap(), Role::USER)
+        .await
+        .unwrap_err();
+    assert!(is_forbidden(&err));
+
+    let err = service
+        .update_role(&claims(&admin), admin.id.unwrap(), Role::USER)
+        .await
+        .unwrap_err();
+    assert!(is_bad_request(&err));
+    assert_eq!(
+        users.get_by_id(admin.id.unwrap()).await.unwrap().role,
+        Role::ADMIN
+    );
+
+    let err = service
+        .update_role(&claims(&admin), Uuid::new_v4(), Role::ADMIN)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(ServiceError::NotFound(_))
+    ));
+
+    let updated = service
+        .update_role(&claims(&admin), user.id.unwrap(), Role::ADMIN)
+        .await
+        .unwrap();
+    assert_eq!(updated.role, Role::ADMIN);
+}
+
+#[test(tokio::test)]
+async fn disable_other_users() {
+    let (_container, _url, db) = common::postgres().await;
+    let users = UserRepository::new(db.clone());
+    let tokens = RefreshTokenRepository::new(db.clone());
+    let service = DefaultUserService::new(db);
+    let admin = create_user(&users, "admin", Role::ADMIN).await;
+    let user = create_user(&users, "user", Role::USER).await;
+    let token = tokens
+        .create(RefreshToken::new(
+            user.id.unwrap(),
+            "hash",
+            Duration::days(1),
+        ))
+        .await
+        .unwrap();
+
+    let err = service
+        .set_enabled(&claims(&admin), admin.id.unwrap(), false)
+        .await
+        .unwrap_err();
+    assert!(is_bad_request(&err));
+    assert!(
+        service
+            .set_enabled(&claims(&admin), admin.id.unwrap(), true)
+            .await
+            .unwrap()
+            .enabled
+    );
+
+    let disabled = service
+        .set_enabled(&claims(&admin), user.id.unwrap(), false)
+        .await
+        .unwrap();
+    assert!(!disabled.enabled);
+    let revoked = tokens.get_by_id(token.id.unwrap()).await.unwrap();
+    assert!(revoked.is_revoked());
+}
+
+#[test(tokio::test)]
+async fn delete_other_users() {
+    let (_container, _url, db) = common::postgres().await;
+    let users = UserRepository::new(db.clone());
+    let service = DefaultUserService::new(db);
+    let admin = create_user(&users, "admin", Role::ADMIN).await;
+    let user = create_user(&users, "user", Role::USER).await;
+
+    let err = service
+        .delete(&claims(&user), user.id.unwrap())
+        .await
+        .unwrap_err();
+    assert!(is_forbidden(&err));
+
+    let err = service
+        .delete(&claims(&admin), admin.id.unwrap())
+        .await
+        .unwrap_err();
+    assert!(is_bad_request(&err));
+    assert!(users.get_by_id(admin.id.unwrap()).await.is_ok());
+
+    service
+        .delete(&claims(&admin), user.id.unwrap())
+        .await
+        .unwrap();
+    assert!(users.get_by_id(user.id.unwrap()).await.is_err());
+
+    let err = service
+        .delete(&claims(&admin), user.id.unwrap())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast_ref(),
+        Some(ServiceError::NotFound(_))
+    ));
+}
@@ -0,0 +1,62 @@
+use domain::*;
+use repository::{
+    JobRepository, ResourceRepository, WebhookDeliveryRepository, WebhookSubscriptionRepository,
+};
+use test_log::test;
+
+mod common;
+
+#[test(tokio::test)]
+async fn enqueue_deliveries_with_events() {
+    let (_container, _url, db) = common::postgres().await;
+    let subscriptions = WebhookSubscriptionRepository::new(db.clone());
+    let deliveries = WebhookDeliveryRepository::new(db.clone());
+    let jobs = JobRepository::new(db.clone());
+    subscriptions
+        .create(WebhookSubscription::new(
+            "http://localhost/hook",
+            "secret",
+            vec![ResourceEventType::Created],
+            3,
+        ))
+        .await
+        .unwrap();
+
+    ResourceRepository::new(db)
+        .create_with_event(Resource::default().with_key("photo.png"))
+        .await
+        .unwrap();
+
+    let created = deliveries.get_all().await.unwrap();
+    assert_eq!(created.len(), 1);
+    let id = created[0].id.unwrap();
+    let queued = jobs
+        .find(JobFilter {
+            kind: Some(DELIVER_WEBHOOK_JOB.to_owned()),
+            page_size: 10,
+            ..JobFilter::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(queued.total, 1);
+    assert_eq!(queued.items[0].max_attempts, 3);
+    assert_eq!(queued.items[0].payload["delivery_id"], id.to_string());
+
+    let started = deliveries.start(id).await.unwrap().unwrap();
+    assert_eq!(started.status, WebhookDeliveryStatus::Delivering);
+    assert_eq!(started.attempts, 1);
+    assert!(deliveries
+        .retry(id, 1, Some(500), "Responded with status 500")
+        .await
+        .unwrap());
+    let started = deliveries.start(id).await.unwrap().unwrap();
+    assert_eq!(started.attempts, 2);
+    assert!(!deliveries.complete(id, 1, 200).await.unwrap());
+    assert!(deliveries.complete(id, 2, 200).await.unwrap());
+
+    let delivered = deliveries.get_by_id(id).await.unwrap();
+    assert_eq!(delivered.status, WebhookDeliveryStatus::Delivered);
+    assert!(delivered.delivered_at.is_some());
+    assert!(deliveries.start(id).await.unwrap().is_none());
+    assert!(!deliveries.fail(id, "stale").await.unwrap());
+}
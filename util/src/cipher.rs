@@ -0,0 +1,60 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use anyhow::Result;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+const NONCE_LENGTH: usize = 12;
+
+/// AES-256-GCM encryption of secrets stored in the database. The key is derived from
+/// the configured passphrase, the random nonce is stored in front of the ciphertext.
+pub struct SecretCipher {
+    cipher: Aes256Gcm,
+}
+
+impl SecretCipher {
+    pub fn new(passphrase: &str) -> Self {
+        let key = Sha256::digest(passphrase.as_bytes());
+        SecretCipher {
+            cipher: Aes256Gcm::new(&key),
+        }
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), plaintext)
+            .map_err(|_| anyhow::Error::msg("Failed to encrypt secret"))?;
+        Ok(base64::encode(
+            [nonce.as_slice(), ciphertext.as_slice()].concat(),
+        ))
+    }
+
+    pub fn decrypt(&self, encrypted: &str) -> Result<Vec<u8>> {
+        let data = base64::decode(encrypted)?;
+        if data.len() < NONCE_LENGTH {
+            return Err(anyhow::Error::msg("Encrypted secret is too short"));
+        }
+        let (nonce, ciphertext) = data.split_at(NONCE_LENGTH);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow::Error::msg("Failed to decrypt secret"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SecretCipher;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let cipher = SecretCipher::new("passphrase");
+        let encrypted = cipher.encrypt(b"secret").unwrap();
+
+        assert_ne!(encrypted, cipher.encrypt(b"secret").unwrap());
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), b"secret");
+        assert!(SecretCipher::new("other").decrypt(&encrypted).is_err());
+    }
+}
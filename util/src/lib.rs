@@ -1,3 +1,13 @@
+pub mod cipher;
+pub mod password_encoder;
+pub mod signer;
+pub mod totp;
+
+pub use cipher::*;
+pub use password_encoder::*;
+pub use signer::*;
+pub use totp::*;
+
 #[cfg(test)]
 mod tests {
     #[test]
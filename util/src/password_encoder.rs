@@ -1,41 +1,81 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+
+/// Hashes passwords with Argon2id in the PHC string format, each hash with a random salt.
 pub struct PasswordEncoder {}
 
 impl PasswordEncoder {
     pub fn encode(raw_password: &str) -> String {
-        let digest = md5::compute(raw_password);
-        format!("{:x}", digest)
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(raw_password.as_bytes(), &salt)
+            .expect("Failed to hash the password")
+            .to_string()
     }
+
+    /// Verifies the password against an Argon2 hash or a legacy unsalted MD5 hash.
     pub fn verify(password: &str, raw_password: &str) -> bool {
-        if password.eq(raw_password) {
-            return true;
+        match PasswordHash::new(password) {
+            Ok(hash) => Argon2::default()
+                .verify_password(raw_password.as_bytes(), &hash)
+                .is_ok(),
+            Err(_) if is_legacy(password) => {
+                let hashed = format!("{:x}", md5::compute(raw_password));
+                constant_time_eq(password.as_bytes(), hashed.as_bytes())
+            }
+            Err(_) => false,
         }
-        let hashed = PasswordEncoder::encode(raw_password);
-        password.eq(&hashed)
+    }
+
+    /// Legacy hashes are replaced on the next successful login.
+    pub fn needs_rehash(password: &str) -> bool {
+        PasswordHash::new(password).is_err()
     }
 }
 
+/// MD5 hashes of the first versions, 32 lowercase hex digits.
+fn is_legacy(password: &str) -> bool {
+    password.len() == 32
+        && password
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
 #[cfg(test)]
 mod test {
-    use crate::util::password_encoder::PasswordEncoder;
+    use super::PasswordEncoder;
 
     #[test]
     fn test_encode() {
-        let s = PasswordEncoder::encode("123456");
-        println!("{}", s);
-        assert_eq!(
-            PasswordEncoder::encode("123456"),
-            PasswordEncoder::encode("123456")
-        )
+        let first = PasswordEncoder::encode("123456");
+        let second = PasswordEncoder::encode("123456");
+        assert!(first.starts_with("$argon2id$"));
+        assert_ne!(first, second);
     }
 
     #[test]
     fn test_verify() {
         let password = "12345";
-        let raw_password = "12345";
-
-        assert!(PasswordEncoder::verify(password, raw_password));
-
         let encode_password = PasswordEncoder::encode(password);
         assert!(PasswordEncoder::verify(&encode_password, password));
+        assert!(!PasswordEncoder::verify(&encode_password, "54321"));
+        assert!(!PasswordEncoder::needs_rehash(&encode_password));
+        // The stored value isn't a password.
+        assert!(!PasswordEncoder::verify(&encode_password, &encode_password));
+        assert!(!PasswordEncoder::verify(password, password));
+    }
+
+    #[test]
+    fn test_verify_legacy() {
+        let legacy = format!("{:x}", md5::compute("12345"));
+        assert!(PasswordEncoder::verify(&legacy, "12345"));
+        assert!(!PasswordEncoder::verify(&legacy, &legacy));
+        assert!(!PasswordEncoder::verify(&legacy, "54321"));
+        assert!(PasswordEncoder::needs_rehash(&legacy));
     }
 }
@@ -0,0 +1,54 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// HMAC-SHA256 signatures of messages, encoded as URL safe base64 without padding.
+pub struct Signer {
+    key: Vec<u8>,
+}
+
+impl Signer {
+    pub fn new(key: &str) -> Self {
+        Signer {
+            key: key.as_bytes().to_vec(),
+        }
+    }
+
+    pub fn sign(&self, message: &str) -> String {
+        base64::encode_config(
+            self.mac(message).finalize().into_bytes(),
+            base64::URL_SAFE_NO_PAD,
+        )
+    }
+
+    /// Compares the signature in constant time.
+    pub fn verify(&self, message: &str, signature: &str) -> bool {
+        match base64::decode_config(signature, base64::URL_SAFE_NO_PAD) {
+            Ok(signature) => self.mac(message).verify_slice(&signature).is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    fn mac(&self, message: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any size");
+        mac.update(message.as_bytes());
+        mac
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Signer;
+
+    #[test]
+    fn test_sign_verify() {
+        let signer = Signer::new("key");
+        let signature = signer.sign("message");
+
+        assert_eq!(signature, signer.sign("message"));
+        assert!(signer.verify("message", &signature));
+        assert!(!signer.verify("other", &signature));
+        assert!(!Signer::new("other").verify("message", &signature));
+        assert!(!signer.verify("message", "not base64!"));
+    }
+}
@@ -0,0 +1,120 @@
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha1::Sha1;
+
+/// RFC 6238 time-based one-time passwords with the defaults of authenticator apps:
+/// HMAC-SHA1, 6 digits and 30 seconds period.
+pub struct Totp {}
+
+const DIGITS: u32 = 6;
+const PERIOD: u64 = 30;
+const SECRET_LENGTH: usize = 20;
+/// Number of periods before and after the current one in which a code is still accepted.
+const SKEW: u64 = 1;
+
+impl Totp {
+    pub fn generate_secret() -> Vec<u8> {
+        let mut secret = vec![0u8; SECRET_LENGTH];
+        rand::thread_rng().fill_bytes(&mut secret);
+        secret
+    }
+
+    pub fn encode_secret(secret: &[u8]) -> String {
+        base32::encode(base32::Alphabet::RFC4648 { padding: false }, secret)
+    }
+
+    pub fn step(timestamp: u64) -> u64 {
+        timestamp / PERIOD
+    }
+
+    pub fn code(secret: &[u8], step: u64) -> String {
+        let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any size");
+        mac.update(&step.to_be_bytes());
+        let hash = mac.finalize().into_bytes();
+        let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+        let binary = u32::from_be_bytes([
+            hash[offset] & 0x7f,
+            hash[offset + 1],
+            hash[offset + 2],
+            hash[offset + 3],
+        ]);
+        format!(
+            "{:0width$}",
+            binary % 10u32.pow(DIGITS),
+            width = DIGITS as usize
+        )
+    }
+
+    /// Returns the time step the code belongs to, if the code is valid at the timestamp.
+    pub fn verify(secret: &[u8], code: &str, timestamp: u64) -> Option<u64> {
+        let code = code.trim();
+        if code.len() != DIGITS as usize {
+            return None;
+        }
+        let current = Self::step(timestamp);
+        (current.saturating_sub(SKEW)..=current + SKEW)
+            .find(|step| constant_time_eq(Self::code(secret, *step).as_bytes(), code.as_bytes()))
+    }
+
+    /// URI of the `otpauth` scheme, rendered as a QR code for authenticator apps.
+    pub fn provisioning_uri(secret: &[u8], issuer: &str, account: &str) -> String {
+        format!(
+            "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+            percent_encode(issuer),
+            percent_encode(account),
+            Self::encode_secret(secret),
+            percent_encode(issuer),
+            DIGITS,
+            PERIOD
+        )
+    }
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::Totp;
+
+    const SECRET: &[u8] = b"12345678901234567890";
+
+    #[test]
+    fn test_code() {
+        // RFC 6238 test vectors, truncated to 6 digits
+        assert_eq!(Totp::code(SECRET, Totp::step(59)), "287082");
+        assert_eq!(Totp::code(SECRET, Totp::step(1111111109)), "081804");
+        assert_eq!(Totp::code(SECRET, Totp::step(2000000000)), "279037");
+    }
+
+    #[test]
+    fn test_verify() {
+        assert_eq!(Totp::verify(SECRET, "287082", 59), Some(1));
+        assert_eq!(Totp::verify(SECRET, "287082", 89), Some(1));
+        assert_eq!(Totp::verify(SECRET, "287082", 150), None);
+        assert_eq!(Totp::verify(SECRET, "28708", 59), None);
+    }
+
+    #[test]
+    fn test_provisioning_uri() {
+        let uri = Totp::provisioning_uri(SECRET, "Assets", "user@example.com");
+        assert_eq!(
+            uri,
+            "otpauth://totp/Assets:user%40example.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=Assets&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}